@@ -0,0 +1,107 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tlv::errors::TLVError;
+use tlv::index::TLVIndex;
+use tlv::path::{self, TLVPath};
+use tlv::reader::TLVReader;
+use tlv::tags::TLVTag;
+use tlv::types::ContainerType;
+use tlv::writer::TLVWriter;
+
+const ELEMENTS: usize = 1000;
+
+// Array of small unsigned integers, so the walk is dominated by per element overhead
+fn flat_array() -> Vec<u8> {
+    let mut tlv_writer = TLVWriter::new();
+    tlv_writer
+        .start_container(TLVTag::Anonymous, ContainerType::Array)
+        .expect("Failed to start container");
+    for index in 0..ELEMENTS {
+        tlv_writer
+            .put_uint(TLVTag::Anonymous, index as u64)
+            .expect("Failed to write element");
+    }
+    tlv_writer.end_container().expect("Failed to end container");
+    tlv_writer.into_bytes()
+}
+
+// Top level sequence of context tagged unsigned integers
+fn tagged_sequence() -> Vec<u8> {
+    let mut tlv_writer = TLVWriter::new();
+    for index in 0..ELEMENTS {
+        tlv_writer
+            .put_uint(TLVTag::ContextSpecific(index as u8), index as u64)
+            .expect("Failed to write element");
+    }
+    tlv_writer.into_bytes()
+}
+
+fn walk_tagged(tlv_reader: &mut TLVReader) -> u64 {
+    let mut sum = 0;
+    loop {
+        if let TLVTag::ContextSpecific(tag_number) =
+            tlv_reader.read_tag().expect("Failed to read tag")
+        {
+            sum += tag_number as u64;
+        }
+        sum += tlv_reader.read_uint().expect("Failed to read uint");
+        match tlv_reader.next() {
+            Err(TLVError::EndOfTLV) => break,
+            result => result.expect("Failed to move to next element"),
+        }
+    }
+    sum
+}
+
+fn walk(tlv_reader: &mut TLVReader) -> u64 {
+    let mut sum = 0;
+    tlv_reader
+        .enter_container()
+        .expect("Failed to enter container");
+    loop {
+        sum += tlv_reader.read_uint().expect("Failed to read uint");
+        match tlv_reader.next() {
+            Err(TLVError::EndOfContainer) => break,
+            result => result.expect("Failed to move to next element"),
+        }
+    }
+    tlv_reader
+        .exit_container()
+        .expect("Failed to exit container");
+    sum
+}
+
+fn bench_read_elements(c: &mut Criterion) {
+    let bytes = flat_array();
+    c.bench_function("read_1000_uints", |b| {
+        b.iter(|| walk(&mut TLVReader::new(black_box(&bytes))))
+    });
+    let bytes = tagged_sequence();
+    c.bench_function("read_1000_tagged_uints", |b| {
+        b.iter(|| walk_tagged(&mut TLVReader::new(black_box(&bytes))))
+    });
+    let tlv_reader = TLVReader::new(&bytes);
+    c.bench_function("read_type", |b| {
+        b.iter(|| black_box(&tlv_reader).read_type())
+    });
+}
+
+// Lookups of the last member, walking up to it each time or in the index built once
+fn bench_path_lookup(c: &mut Criterion) {
+    let bytes = flat_array();
+    let path: TLVPath = format!("[{}]", ELEMENTS - 1)
+        .parse()
+        .expect("Failed to parse path");
+    c.bench_function("extract_path_1000", |b| {
+        b.iter(|| path::extract(black_box(&bytes), &path).map(|element| element.len()))
+    });
+    c.bench_function("build_index_1000", |b| {
+        b.iter(|| TLVIndex::build(black_box(&bytes)).map(|index| index.len()))
+    });
+    let index = TLVIndex::build(&bytes).expect("Failed to build index");
+    c.bench_function("index_lookup_1000", |b| {
+        b.iter(|| black_box(&index).lookup(&path))
+    });
+}
+
+criterion_group!(benches, bench_read_elements, bench_path_lookup);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+mod common;
+
+use common::allocations_during;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tlv::reader::TLVReader;
+use tlv::tags::TLVTag;
+use tlv::types::ContainerType;
+use tlv::value::{TLVValue, TLVValueRef};
+use tlv::writer::TLVWriter;
+
+const STRINGS: usize = 1000;
+
+// Report like structure with a label and an attribute path string per entry
+fn string_heavy_report() -> Vec<u8> {
+    let mut tlv_writer = TLVWriter::new();
+    tlv_writer
+        .start_container(TLVTag::Anonymous, ContainerType::Array)
+        .expect("Failed to start container");
+    for index in 0..STRINGS / 2 {
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(0), &format!("Endpoint {}", index))
+            .expect("Failed to write element");
+        tlv_writer
+            .put(
+                TLVTag::ContextSpecific(1),
+                &format!("/0/0x0028/0x{:04x}", index),
+            )
+            .expect("Failed to write element");
+        tlv_writer.end_container().expect("Failed to end container");
+    }
+    tlv_writer.end_container().expect("Failed to end container");
+    tlv_writer.into_bytes()
+}
+
+// Validation pass, checks every string is at most 32 octets
+fn validate(value: &TLVValue) -> bool {
+    match value {
+        TLVValue::Utf8String(value) => value.len() <= 32,
+        TLVValue::Array(members) => members.iter().all(validate),
+        TLVValue::Structure(members) | TLVValue::List(members) => {
+            members.iter().all(|(_, value)| validate(value))
+        }
+        _ => true,
+    }
+}
+
+fn validate_ref(value: &TLVValueRef) -> bool {
+    match value {
+        TLVValueRef::Utf8String(value) => value.len() <= 32,
+        TLVValueRef::Array(members) => members.iter().all(validate_ref),
+        TLVValueRef::Structure(members) | TLVValueRef::List(members) => {
+            members.iter().all(|(_, value)| validate_ref(value))
+        }
+        _ => true,
+    }
+}
+
+fn bench_validation(c: &mut Criterion) {
+    let tlv_reader = TLVReader::new(&string_heavy_report());
+    let (owned, _, _) = allocations_during(|| validate(&tlv_reader.read_value().unwrap()));
+    let (borrowed, _, _) =
+        allocations_during(|| validate_ref(&tlv_reader.read_value_ref().unwrap()));
+    println!(
+        "allocations for {} strings: owned {}, borrowed {}",
+        STRINGS, owned, borrowed
+    );
+    c.bench_function("validate_owned", |b| {
+        b.iter(|| validate(&black_box(&tlv_reader).read_value().unwrap()))
+    });
+    c.bench_function("validate_borrowed", |b| {
+        b.iter(|| validate_ref(&black_box(&tlv_reader).read_value_ref().unwrap()))
+    });
+}
+
+criterion_group!(benches, bench_validation);
+criterion_main!(benches);
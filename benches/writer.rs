@@ -0,0 +1,76 @@
+mod common;
+
+use bytes::Bytes;
+use common::allocations_during;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tlv::tags::TLVTag;
+use tlv::types::ContainerType;
+use tlv::writer::{TLVEncode, TLVWriter};
+
+const OCTETS: usize = 64 * 1024;
+const FIELDS: u8 = 200;
+
+fn write_octet_string(value: &Bytes) -> Vec<u8> {
+    let mut tlv_writer = TLVWriter::new();
+    tlv_writer
+        .put(TLVTag::ContextSpecific(1), value)
+        .expect("Failed to write element");
+    tlv_writer.into_bytes()
+}
+
+fn bench_write_octet_string(c: &mut Criterion) {
+    let value = Bytes::from(vec![0xa5; OCTETS]);
+    let (_, writer_bytes, _) = allocations_during(|| write_octet_string(&value));
+    let (_, encode_bytes, _) =
+        allocations_during(|| value.encode_tlv_with_tag(&TLVTag::ContextSpecific(1)));
+    println!(
+        "allocated bytes for a {} octet string: writer {}, encode_tlv_with_tag {}",
+        OCTETS, writer_bytes, encode_bytes
+    );
+    c.bench_function("write_64k_octet_string", |b| {
+        b.iter(|| write_octet_string(black_box(&value)))
+    });
+    c.bench_function("encode_64k_octet_string", |b| {
+        b.iter(|| black_box(&value).encode_tlv_with_tag(&TLVTag::ContextSpecific(1)))
+    });
+}
+
+// Structure of FIELDS members alternating integers and short strings
+fn write_structure(mut tlv_writer: TLVWriter, label: &str) -> Vec<u8> {
+    tlv_writer
+        .start_container(TLVTag::Anonymous, ContainerType::Structure)
+        .expect("Failed to start container");
+    for field in 0..FIELDS {
+        let tag = TLVTag::ContextSpecific(field);
+        if field % 2 == 0 {
+            tlv_writer.put(tag, &(field as u32 * 1000))
+        } else {
+            tlv_writer.put(tag, label)
+        }
+        .expect("Failed to write element");
+    }
+    tlv_writer.end_container().expect("Failed to end container");
+    tlv_writer.into_bytes()
+}
+
+fn bench_write_structure(c: &mut Criterion) {
+    let label = "temperature sensor";
+    let capacity = write_structure(TLVWriter::new(), label).len();
+    let (sized_allocations, _, _) =
+        allocations_during(|| write_structure(TLVWriter::with_capacity(capacity), label));
+    let (unsized_allocations, _, _) =
+        allocations_during(|| write_structure(TLVWriter::new(), label));
+    println!(
+        "allocations for a {} field structure: with_capacity {}, new {}",
+        FIELDS, sized_allocations, unsized_allocations
+    );
+    c.bench_function("write_structure_with_capacity", |b| {
+        b.iter(|| write_structure(TLVWriter::with_capacity(capacity), black_box(label)))
+    });
+    c.bench_function("write_structure", |b| {
+        b.iter(|| write_structure(TLVWriter::new(), black_box(label)))
+    });
+}
+
+criterion_group!(benches, bench_write_octet_string, bench_write_structure);
+criterion_main!(benches);
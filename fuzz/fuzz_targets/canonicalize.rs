@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tlv::canonical::canonicalize;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(canonical) = canonicalize(data) {
+        assert_eq!(
+            canonicalize(&canonical).expect("canonical form does not parse"),
+            canonical
+        );
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tlv::value::TLVDocument;
+
+// Encoding a parsed document is stable: parsing it back and encoding again gives the same bytes
+fuzz_target!(|data: &[u8]| {
+    if let Ok(document) = TLVDocument::parse(data) {
+        let encoded = document.encode();
+        let reparsed = TLVDocument::parse(&encoded).expect("encoded document does not parse");
+        assert_eq!(reparsed.encode(), encoded);
+    }
+});
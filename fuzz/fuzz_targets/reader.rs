@@ -0,0 +1,39 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tlv::errors::TLVError;
+use tlv::reader::TLVReader;
+use tlv::types::TLVType;
+
+// Every typed read on every element, entering containers depth first
+fuzz_target!(|data: &[u8]| {
+    let mut tlv_reader = TLVReader::new(data);
+    loop {
+        let _ = tlv_reader.read_tag();
+        let _ = tlv_reader.read_value();
+        let _ = tlv_reader.read_uint();
+        let _ = tlv_reader.read_int();
+        let _ = tlv_reader.read_f32();
+        let _ = tlv_reader.read_f64();
+        let _ = tlv_reader.read_bool();
+        let _ = tlv_reader.read_null();
+        let _ = tlv_reader.read_byte_str();
+        let _ = tlv_reader.read_char_str();
+        if let Ok(TLVType::Container(_)) = tlv_reader.read_type() {
+            if tlv_reader.enter_container().is_ok() {
+                continue;
+            }
+        }
+        loop {
+            match tlv_reader.next() {
+                Ok(()) => break,
+                Err(TLVError::EndOfContainer) => {
+                    if tlv_reader.exit_container().is_err() {
+                        return;
+                    }
+                }
+                Err(_) => return,
+            }
+        }
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tlv::value::TLVDocument;
+
+// Structure-aware: the input is turned into a document, which decodes back to itself once encoded
+fuzz_target!(|document: TLVDocument| {
+    let encoded = document.encode();
+    let decoded = TLVDocument::parse(&encoded).expect("encoded document does not parse");
+    assert_eq!(decoded, document);
+});
@@ -0,0 +1,159 @@
+// Arbitrary implementations for structure-aware fuzzing: fuzz targets take tags, values or documents
+// instead of raw bytes. Generated values encode to payloads the default reader configuration
+// accepts: containers nest at most MAX_DEPTH levels, Structure members are tagged and NaNs are
+// replaced by zero so values compare equal to themselves. Strings and member counts are bounded by
+// the data left.
+
+use crate::tags::{
+    CommonProfileLength, FullyQualifiedProfileLength, ImplicitProfileLength, TLVTag,
+};
+use crate::value::{TLVDocument, TLVElement, TLVValue};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+pub const MAX_DEPTH: usize = 4;
+const MAX_MEMBERS: usize = 8;
+
+impl<'a> Arbitrary<'a> for TLVTag {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        match u.int_in_range(0..=7)? {
+            0 => Ok(TLVTag::Anonymous),
+            _ => member_tag(u),
+        }
+    }
+}
+
+// Any tag form but Anonymous, widths are not always minimal
+fn member_tag(u: &mut Unstructured) -> Result<TLVTag> {
+    Ok(match u.int_in_range(0..=6)? {
+        0 => TLVTag::CommonProfile(CommonProfileLength::TwoOctets {
+            tag_number: u.arbitrary()?,
+        }),
+        1 => TLVTag::CommonProfile(CommonProfileLength::FourOctets {
+            tag_number: u.arbitrary()?,
+        }),
+        2 => TLVTag::ImplicitProfile(ImplicitProfileLength::TwoOctets {
+            tag_number: u.arbitrary()?,
+        }),
+        3 => TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets {
+            tag_number: u.arbitrary()?,
+        }),
+        4 => TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+            vendor_id: u.arbitrary()?,
+            profile_number: u.arbitrary()?,
+            tag_number: u.arbitrary()?,
+        }),
+        5 => TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+            vendor_id: u.arbitrary()?,
+            profile_number: u.arbitrary()?,
+            tag_number: u.arbitrary()?,
+        }),
+        _ => TLVTag::ContextSpecific(u.arbitrary()?),
+    })
+}
+
+impl<'a> Arbitrary<'a> for TLVValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        value(u, MAX_DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for TLVDocument {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let elements = members(u, MAX_DEPTH + 1, |u| u.arbitrary())?;
+        Ok(elements.into_iter().map(TLVElement::from).collect())
+    }
+}
+
+fn value(u: &mut Unstructured, depth: usize) -> Result<TLVValue> {
+    let kinds = if depth == 0 { 14 } else { 17 };
+    Ok(match u.choose_index(kinds)? {
+        0 => TLVValue::Int8(u.arbitrary()?),
+        1 => TLVValue::Int16(u.arbitrary()?),
+        2 => TLVValue::Int32(u.arbitrary()?),
+        3 => TLVValue::Int64(u.arbitrary()?),
+        4 => TLVValue::UInt8(u.arbitrary()?),
+        5 => TLVValue::UInt16(u.arbitrary()?),
+        6 => TLVValue::UInt32(u.arbitrary()?),
+        7 => TLVValue::UInt64(u.arbitrary()?),
+        8 => TLVValue::Bool(u.arbitrary()?),
+        9 => TLVValue::Float32(
+            Some(f32::arbitrary(u)?)
+                .filter(|v| !v.is_nan())
+                .unwrap_or(0.0),
+        ),
+        10 => TLVValue::Float64(
+            Some(f64::arbitrary(u)?)
+                .filter(|v| !v.is_nan())
+                .unwrap_or(0.0),
+        ),
+        11 => TLVValue::Utf8String(u.arbitrary()?),
+        12 => TLVValue::ByteString(u.arbitrary()?),
+        13 => TLVValue::Null,
+        14 => TLVValue::Structure(members(u, depth, member_tag)?),
+        15 => {
+            let members = members(u, depth, |_| Ok(TLVTag::Anonymous))?;
+            TLVValue::Array(members.into_iter().map(|(_, value)| value).collect())
+        }
+        _ => TLVValue::List(members(u, depth, |u| u.arbitrary())?),
+    })
+}
+
+fn members(
+    u: &mut Unstructured,
+    depth: usize,
+    mut tag: impl FnMut(&mut Unstructured) -> Result<TLVTag>,
+) -> Result<Vec<(TLVTag, TLVValue)>> {
+    let len = u.arbitrary_len::<(u8, u8)>()?.min(MAX_MEMBERS);
+    (0..len)
+        .map(|_| Ok((tag(u)?, value(u, depth - 1)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Xorshift, enough to fill the fuzzer input
+    fn random_bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn depth(value: &TLVValue) -> usize {
+        value
+            .into_iter()
+            .map(|(_, member)| depth(member) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_arbitrary_round_trip() {
+        for seed in 0..200 {
+            let data = random_bytes(seed, 64 + seed as usize * 16);
+            let mut u = Unstructured::new(&data);
+            let document = TLVDocument::arbitrary(&mut u).expect("Failed to generate document");
+            for element in &document.elements {
+                assert!(depth(&element.value) <= MAX_DEPTH);
+            }
+            let encoded = document.encode();
+            assert_eq!(
+                TLVDocument::parse(&encoded).expect("Failed to parse document"),
+                document
+            );
+        }
+
+        // Running out of data gives the smallest values rather than failing
+        let mut u = Unstructured::new(&[]);
+        assert_eq!(TLVValue::arbitrary(&mut u), Ok(TLVValue::Int8(0)));
+        let mut u = Unstructured::new(&[]);
+        assert_eq!(TLVDocument::arbitrary(&mut u), Ok(TLVDocument::default()));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::errors::TLVError;
+use ::base64::alphabet;
+use ::base64::engine::{DecodePaddingMode, Engine, GeneralPurpose, GeneralPurposeConfig};
+use ::base64::DecodeError;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// Padding is always emitted, but tooling frequently strips it, so unpadded input is accepted too.
+// Once padding is present it has to be complete.
+const PADDED: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::RequireCanonical);
+const UNPADDED: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::RequireNone);
+const STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, PADDED);
+const STANDARD_NO_PAD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, UNPADDED);
+const URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, PADDED);
+const URL_SAFE_NO_PAD: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, UNPADDED);
+const PADDING: char = '=';
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Base64Alphabet {
+    Standard, // RFC 4648 section 4, using '+' and '/'
+    UrlSafe,  // RFC 4648 section 5, using '-' and '_'
+}
+
+impl Base64Alphabet {
+    fn engine(&self, padded: bool) -> &'static GeneralPurpose {
+        match (self, padded) {
+            (Base64Alphabet::Standard, true) => &STANDARD,
+            (Base64Alphabet::Standard, false) => &STANDARD_NO_PAD,
+            (Base64Alphabet::UrlSafe, true) => &URL_SAFE,
+            (Base64Alphabet::UrlSafe, false) => &URL_SAFE_NO_PAD,
+        }
+    }
+
+    fn detect(base64: &str) -> Self {
+        if base64.contains(['-', '_']) {
+            Base64Alphabet::UrlSafe
+        } else {
+            Base64Alphabet::Standard
+        }
+    }
+}
+
+pub fn encode_base64(bytes: &[u8]) -> String {
+    encode_base64_with_alphabet(bytes, Base64Alphabet::Standard)
+}
+
+pub fn encode_base64_with_alphabet(bytes: &[u8], alphabet: Base64Alphabet) -> String {
+    alphabet.engine(true).encode(bytes)
+}
+
+// Alphabet is inferred from the input, surrounding whitespace is ignored
+pub fn decode_base64(base64: &str) -> Result<Vec<u8>, TLVError> {
+    let base64 = base64.trim();
+    decode_base64_with_alphabet(base64, Base64Alphabet::detect(base64))
+}
+
+pub fn decode_base64_with_alphabet(
+    base64: &str,
+    alphabet: Base64Alphabet,
+) -> Result<Vec<u8>, TLVError> {
+    let padded = base64.ends_with(PADDING);
+    alphabet.engine(padded).decode(base64).map_err(|e| match e {
+        DecodeError::InvalidByte(position, byte)
+        | DecodeError::InvalidLastSymbol(position, byte) => TLVError::InvalidBase64Character {
+            character: byte as char,
+            position,
+        },
+        DecodeError::InvalidLength(length) => TLVError::InvalidBase64Length(length),
+        DecodeError::InvalidPadding => TLVError::InvalidBase64Padding,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::TLVReader;
+
+    #[test]
+    fn test_encode_base64() {
+        // Octet String, 1-octet length specifying 5 octets fb ff fe fd fc
+        let test_input = &[0x10, 0x05, 0xfb, 0xff, 0xfe, 0xfd, 0xfc];
+        assert_eq!(encode_base64(test_input), "EAX7//79/A==");
+        assert_eq!(
+            encode_base64_with_alphabet(test_input, Base64Alphabet::UrlSafe),
+            "EAX7__79_A=="
+        );
+        assert_eq!(encode_base64(&[]), "");
+    }
+
+    #[test]
+    fn test_decode_base64() {
+        let test_output = vec![0x10, 0x05, 0xfb, 0xff, 0xfe, 0xfd, 0xfc];
+        assert_eq!(decode_base64("EAX7//79/A==").unwrap(), test_output);
+        assert_eq!(decode_base64("EAX7//79/A").unwrap(), test_output);
+        assert_eq!(decode_base64("EAX7__79_A==").unwrap(), test_output);
+        assert_eq!(decode_base64("EAX7__79_A").unwrap(), test_output);
+        assert_eq!(decode_base64(" EAX7//79/A==\n").unwrap(), test_output);
+        assert_eq!(
+            decode_base64_with_alphabet("EAX7//79/A==", Base64Alphabet::Standard).unwrap(),
+            test_output
+        );
+    }
+
+    #[test]
+    fn test_decode_base64_corrupted() {
+        // URL-safe characters in a standard alphabet payload
+        assert_eq!(
+            decode_base64_with_alphabet("EAX7__79/A==", Base64Alphabet::Standard)
+                .expect_err("Invalid character is expected"),
+            TLVError::InvalidBase64Character {
+                character: '_',
+                position: 4
+            }
+        );
+        // Mixed alphabets
+        assert_eq!(
+            decode_base64("EAX7/_79/A==").expect_err("Invalid character is expected"),
+            TLVError::InvalidBase64Character {
+                character: '/',
+                position: 4
+            }
+        );
+        assert_eq!(
+            decode_base64("EAX7//79/A=").expect_err("Invalid padding is expected"),
+            TLVError::InvalidBase64Padding
+        );
+        assert_eq!(
+            decode_base64("EAX7//79/").expect_err("Invalid length is expected"),
+            TLVError::InvalidBase64Length(9)
+        );
+        assert_eq!(
+            decode_base64("EA==EA==").expect_err("Invalid character is expected"),
+            TLVError::InvalidBase64Character {
+                character: '=',
+                position: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_reader_from_base64() {
+        // UTF-8 String, 1-octet length, "Hello!"
+        let test_bytes = &[0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21];
+        let base64 = encode_base64(test_bytes);
+        let tlv_reader = TLVReader::from_base64(&base64).expect("Failed to parse base64");
+        assert_eq!(
+            tlv_reader
+                .read_char_str()
+                .expect("Failed to read character string"),
+            "Hello!"
+        );
+
+        let base64 =
+            encode_base64_with_alphabet(&[0x10, 0x02, 0xff, 0xfe], Base64Alphabet::UrlSafe);
+        let tlv_reader = TLVReader::from_base64(&base64).expect("Failed to parse base64");
+        assert_eq!(
+            tlv_reader
+                .read_byte_str()
+                .expect("Failed to read byte string"),
+            [0xff, 0xfe]
+        );
+
+        assert!(TLVReader::from_base64("DAZIZWxsbyE*").is_err());
+    }
+}
@@ -0,0 +1,335 @@
+// Declarative construction of payloads on top of TLVWriter, the bytes are the ones the equivalent
+// writer calls give. Array members take no tag so they cannot be tagged, an anonymous Structure
+// member or a writer error fails the build.
+
+use crate::errors::TLVError;
+use crate::tags::TLVTag;
+use crate::types::ContainerType;
+use crate::value::TLVDocument;
+use crate::writer::{TLVEncode, TLVWriter};
+use alloc::vec::Vec;
+
+// Tags accepted for members, numbers are context tags
+pub trait MemberTag {
+    fn into_tag(self) -> TLVTag;
+}
+
+impl MemberTag for u8 {
+    fn into_tag(self) -> TLVTag {
+        TLVTag::ContextSpecific(self)
+    }
+}
+
+impl MemberTag for TLVTag {
+    fn into_tag(self) -> TLVTag {
+        self
+    }
+}
+
+// Writer shared by the nested builders, keeping the first error
+struct Output {
+    tlv_writer: TLVWriter,
+    error: Option<TLVError>,
+}
+
+impl Output {
+    fn record(&mut self, result: Result<(), TLVError>) {
+        if let (None, Err(e)) = (&self.error, result) {
+            self.error = Some(e);
+        }
+    }
+
+    fn put<T: TLVEncode + ?Sized>(&mut self, tag: TLVTag, value: &T) {
+        let result = self.tlv_writer.put(tag, value);
+        self.record(result);
+    }
+
+    fn put_null(&mut self, tag: TLVTag) {
+        let result = self.tlv_writer.put_null(tag);
+        self.record(result);
+    }
+
+    fn container(
+        mut self,
+        tag: TLVTag,
+        container_type: ContainerType,
+        build: impl FnOnce(Output) -> Output,
+    ) -> Output {
+        let result = self.tlv_writer.start_container(tag, container_type);
+        if result.is_err() {
+            self.record(result);
+            return self;
+        }
+        let mut output = build(self);
+        let result = output.tlv_writer.end_container();
+        output.record(result);
+        output
+    }
+}
+
+// Member methods of every builder, $tag gives the member tag from the optional tag parameter
+macro_rules! member_methods {
+    ($builder:ident, ($($param:ident: $param_type:ty)?), |$this:ident| $tag:expr) => {
+        impl $builder {
+            // Number with the width of its type
+            pub fn value<T: TLVEncode + ?Sized>(mut self, $($param: $param_type,)? value: &T) -> Self {
+                let tag = {
+                    let $this = &mut self;
+                    $tag
+                };
+                self.output.put(tag, value);
+                self
+            }
+
+            // Narrowest width able to hold the value, as TLVWriter::put_int
+            pub fn int(mut self, $($param: $param_type,)? value: i64) -> Self {
+                let tag = {
+                    let $this = &mut self;
+                    $tag
+                };
+                let result = self.output.tlv_writer.put_int(tag, value);
+                self.output.record(result);
+                self
+            }
+
+            pub fn uint(mut self, $($param: $param_type,)? value: u64) -> Self {
+                let tag = {
+                    let $this = &mut self;
+                    $tag
+                };
+                let result = self.output.tlv_writer.put_uint(tag, value);
+                self.output.record(result);
+                self
+            }
+
+            pub fn float(self, $($param: $param_type,)? value: f32) -> Self {
+                self.value($($param,)? &value)
+            }
+
+            pub fn double(self, $($param: $param_type,)? value: f64) -> Self {
+                self.value($($param,)? &value)
+            }
+
+            pub fn bool(self, $($param: $param_type,)? value: bool) -> Self {
+                self.value($($param,)? &value)
+            }
+
+            pub fn string(self, $($param: $param_type,)? value: &str) -> Self {
+                self.value($($param,)? value)
+            }
+
+            pub fn bytes(self, $($param: $param_type,)? value: &[u8]) -> Self {
+                self.value($($param,)? value)
+            }
+
+            pub fn null(mut self, $($param: $param_type)?) -> Self {
+                let tag = {
+                    let $this = &mut self;
+                    $tag
+                };
+                self.output.put_null(tag);
+                self
+            }
+
+            pub fn structure(
+                mut self,
+                $($param: $param_type,)?
+                build: impl FnOnce(StructureBuilder) -> StructureBuilder,
+            ) -> Self {
+                let tag = {
+                    let $this = &mut self;
+                    $tag
+                };
+                self.output = self.output.container(tag, ContainerType::Structure, |output| {
+                    build(StructureBuilder { output }).output
+                });
+                self
+            }
+
+            pub fn array(
+                mut self,
+                $($param: $param_type,)?
+                build: impl FnOnce(ArrayBuilder) -> ArrayBuilder,
+            ) -> Self {
+                let tag = {
+                    let $this = &mut self;
+                    $tag
+                };
+                self.output = self.output.container(tag, ContainerType::Array, |output| {
+                    build(ArrayBuilder { output }).output
+                });
+                self
+            }
+
+            pub fn list(
+                mut self,
+                $($param: $param_type,)?
+                build: impl FnOnce(ListBuilder) -> ListBuilder,
+            ) -> Self {
+                let tag = {
+                    let $this = &mut self;
+                    $tag
+                };
+                self.output = self.output.container(tag, ContainerType::List, |output| {
+                    build(ListBuilder { output }).output
+                });
+                self
+            }
+        }
+    };
+}
+
+// Top level elements
+pub struct DocumentBuilder {
+    output: Output,
+}
+
+impl DocumentBuilder {
+    pub fn new() -> Self {
+        Self::with_writer(TLVWriter::new())
+    }
+
+    // Keeps the writer configuration, e.g. its max_len
+    pub fn with_writer(tlv_writer: TLVWriter) -> Self {
+        Self {
+            output: Output {
+                tlv_writer,
+                error: None,
+            },
+        }
+    }
+
+    pub fn into_bytes(self) -> Result<Vec<u8>, TLVError> {
+        match self.output.error {
+            Some(e) => Err(e),
+            None => Ok(self.output.tlv_writer.into_bytes()),
+        }
+    }
+
+    pub fn build(self) -> Result<TLVDocument, TLVError> {
+        TLVDocument::parse(&self.into_bytes()?)
+    }
+}
+
+impl Default for DocumentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct StructureBuilder {
+    output: Output,
+}
+
+impl StructureBuilder {
+    fn member_tag(&mut self, tag: impl MemberTag) -> TLVTag {
+        let tag = tag.into_tag();
+        if tag == TLVTag::Anonymous {
+            self.output.record(Err(TLVError::InvalidTag));
+        }
+        tag
+    }
+}
+
+pub struct ArrayBuilder {
+    output: Output,
+}
+
+pub struct ListBuilder {
+    output: Output,
+}
+
+member_methods!(DocumentBuilder, (tag: impl MemberTag), |_builder| tag.into_tag());
+member_methods!(StructureBuilder, (tag: impl MemberTag), |builder| builder.member_tag(tag));
+member_methods!(ArrayBuilder, (), |_builder| TLVTag::Anonymous);
+member_methods!(ListBuilder, (tag: impl MemberTag), |_builder| tag.into_tag());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{TLVElement, TLVValue};
+
+    #[test]
+    fn test_build_document() {
+        let bytes = TLVDocument::builder()
+            .structure(TLVTag::Anonymous, |s| {
+                s.uint(1, 42)
+                    .string(2, "hi")
+                    .array(3, |a| a.uint(1).uint(2))
+                    .list(4, |l| l.null(TLVTag::Anonymous).bool(5, true))
+            })
+            .into_bytes()
+            .expect("Failed to build document");
+
+        // Same payload through the writer
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_uint(TLVTag::ContextSpecific(1), 42)
+            .expect("Failed to write element");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(2), "hi")
+            .expect("Failed to write element");
+        tlv_writer
+            .start_container(TLVTag::ContextSpecific(3), ContainerType::Array)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_uint(TLVTag::Anonymous, 1)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_uint(TLVTag::Anonymous, 2)
+            .expect("Failed to write element");
+        tlv_writer.end_container().expect("Failed to end container");
+        tlv_writer
+            .start_container(TLVTag::ContextSpecific(4), ContainerType::List)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_null(TLVTag::Anonymous)
+            .expect("Failed to write element");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(5), &true)
+            .expect("Failed to write element");
+        tlv_writer.end_container().expect("Failed to end container");
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(bytes, tlv_writer.into_bytes());
+
+        // Widths of the types given to value(), top level elements in order
+        let document = TLVDocument::builder()
+            .value(1, &-3i32)
+            .array(TLVTag::Anonymous, |a| a.value(&7u16).double(1.5))
+            .build()
+            .expect("Failed to build document");
+        assert_eq!(
+            document.elements,
+            vec![
+                TLVElement::new(TLVTag::ContextSpecific(1), TLVValue::Int32(-3)),
+                TLVElement::new(
+                    TLVTag::Anonymous,
+                    TLVValue::Array(vec![TLVValue::UInt16(7), TLVValue::Float64(1.5)])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        assert_eq!(
+            TLVDocument::builder()
+                .structure(TLVTag::Anonymous, |s| s
+                    .uint(0, 1)
+                    .uint(TLVTag::Anonymous, 2))
+                .into_bytes()
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        assert_eq!(
+            DocumentBuilder::with_writer(TLVWriter::with_max_len(4))
+                .structure(TLVTag::Anonymous, |s| s.string(0, "hello"))
+                .build()
+                .expect_err("Message too large is expected"),
+            TLVError::MessageTooLarge { limit: 4 }
+        );
+    }
+}
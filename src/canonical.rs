@@ -0,0 +1,421 @@
+// Canonical form of a payload: integers and tags use their narrowest encoding, structure members
+// follow the canonical tag order and NaNs are the quiet NaN without payload. Integer signedness,
+// float widths and the order of List and Array members are kept, as they carry meaning.
+
+use crate::errors::TLVError;
+use crate::tags::TLVTag;
+use crate::types::{
+    FloatingPoint, PredeterminedLenPrimitive, PrimitiveLengthType, SignedInteger, TLVFieldSize,
+    TLVType, UnsignedInteger,
+};
+use crate::value::{TLVDocument, TLVValue};
+use crate::wire;
+use alloc::vec::Vec;
+
+// Platforms differ in the NaN bit patterns they produce, infinities and signed zeros are kept
+pub const CANONICAL_NAN_F32: u32 = 0x7fc0_0000;
+pub const CANONICAL_NAN_F64: u64 = 0x7ff8_0000_0000_0000;
+
+pub fn canonical_f32(value: f32) -> f32 {
+    if value.is_nan() {
+        f32::from_bits(CANONICAL_NAN_F32)
+    } else {
+        value
+    }
+}
+
+pub fn canonical_f64(value: f64) -> f64 {
+    if value.is_nan() {
+        f64::from_bits(CANONICAL_NAN_F64)
+    } else {
+        value
+    }
+}
+
+pub fn canonicalize(bytes: &[u8]) -> Result<Vec<u8>, TLVError> {
+    let mut document = TLVDocument::parse(bytes)?;
+    document.canonicalize();
+    Ok(document.encode())
+}
+
+impl TLVDocument {
+    // Top level elements keep their order
+    pub fn canonicalize(&mut self) {
+        for element in &mut self.elements {
+            canonicalize_member(&mut element.tag, &mut element.value, true);
+        }
+    }
+
+    // Structure members in canonical tag order, encodings and everything else left as they are
+    pub fn canonical_sort(&mut self) {
+        self.elements
+            .iter_mut()
+            .for_each(|element| element.value.canonical_sort());
+    }
+}
+
+impl TLVValue {
+    pub fn canonicalize(&mut self) {
+        canonicalize_value(self, true);
+    }
+
+    // Members of Structures at any depth in canonical tag order, members with equal tags keep their
+    // order. Arrays and Lists keep theirs, the Structures within them are sorted.
+    pub fn canonical_sort(&mut self) {
+        match self {
+            TLVValue::Structure(members) => {
+                members
+                    .iter_mut()
+                    .for_each(|(_, value)| value.canonical_sort());
+                sort_members(members);
+            }
+            TLVValue::List(members) => {
+                members
+                    .iter_mut()
+                    .for_each(|(_, value)| value.canonical_sort());
+            }
+            TLVValue::Array(members) => members.iter_mut().for_each(TLVValue::canonical_sort),
+            _ => {}
+        }
+    }
+}
+
+// Stable, so members with equal tags keep their order
+fn sort_members(members: &mut [(TLVTag, TLVValue)]) {
+    members.sort_by(|(a, _), (b, _)| a.canonical_cmp(b));
+}
+
+// Canonical encoding of the value with structure members left in their order
+pub(crate) fn minimize(value: &mut TLVValue) {
+    canonicalize_value(value, false);
+}
+
+fn canonicalize_value(value: &mut TLVValue, sort_members: bool) {
+    match value {
+        TLVValue::Int16(number) => *value = minimal_int(*number as i64),
+        TLVValue::Int32(number) => *value = minimal_int(*number as i64),
+        TLVValue::Int64(number) => *value = minimal_int(*number),
+        TLVValue::UInt16(number) => *value = minimal_uint(*number as u64),
+        TLVValue::UInt32(number) => *value = minimal_uint(*number as u64),
+        TLVValue::UInt64(number) => *value = minimal_uint(*number),
+        TLVValue::Float32(number) => *number = canonical_f32(*number),
+        TLVValue::Float64(number) => *number = canonical_f64(*number),
+        TLVValue::Structure(members) => {
+            canonicalize_members(members, sort_members);
+            if sort_members {
+                self::sort_members(members);
+            }
+        }
+        TLVValue::List(members) => canonicalize_members(members, sort_members),
+        TLVValue::Array(members) => members
+            .iter_mut()
+            .for_each(|member| canonicalize_value(member, sort_members)),
+        _ => {}
+    }
+}
+
+fn canonicalize_members(members: &mut [(TLVTag, TLVValue)], sort_members: bool) {
+    for (tag, value) in members.iter_mut() {
+        canonicalize_member(tag, value, sort_members);
+    }
+}
+
+fn canonicalize_member(tag: &mut TLVTag, value: &mut TLVValue, sort_members: bool) {
+    *tag = tag.minimal();
+    canonicalize_value(value, sort_members);
+}
+
+// Whether the element is encoded the way canonicalize() encodes it: tag, integer and length field
+// widths are the narrowest able to hold them and NaNs are canonical. Takes the bytes following the
+// tag, values cut short are left to the length checks.
+pub(crate) fn is_minimal_encoding(tag: &TLVTag, tlv_type: TLVType, value_bytes: &[u8]) -> bool {
+    if *tag != tag.minimal() || is_non_canonical_nan(tlv_type, value_bytes) {
+        return false;
+    }
+    match tlv_type {
+        TLVType::Primitive(PrimitiveLengthType::Predetermined(
+            PredeterminedLenPrimitive::SignedInteger(width),
+        )) => signed_value(width, value_bytes)
+            .is_none_or(|value| SignedInteger::minimal_for(value) == width),
+        TLVType::Primitive(PrimitiveLengthType::Predetermined(
+            PredeterminedLenPrimitive::UnsignedInteger(width),
+        )) => unsigned_value(width, value_bytes)
+            .is_none_or(|value| UnsignedInteger::minimal_for(value) == width),
+        TLVType::Primitive(PrimitiveLengthType::Specified(specified)) => {
+            let field_size = specified.length_field_size();
+            field_size
+                .parse_field_size(value_bytes)
+                .map_or(true, |(_, len)| {
+                    TLVFieldSize::minimal_for(len) == field_size
+                })
+        }
+        _ => true,
+    }
+}
+
+pub(crate) fn is_non_canonical_nan(tlv_type: TLVType, value_bytes: &[u8]) -> bool {
+    let TLVType::Primitive(PrimitiveLengthType::Predetermined(
+        PredeterminedLenPrimitive::FloatingPointNumber(width),
+    )) = tlv_type
+    else {
+        return false;
+    };
+    match width {
+        FloatingPoint::FloatingPointNumber32 => wire::parse_u32(value_bytes)
+            .is_ok_and(|(_, bits)| f32::from_bits(bits).is_nan() && bits != CANONICAL_NAN_F32),
+        FloatingPoint::FloatingPointNumber64 => wire::parse_u64(value_bytes)
+            .is_ok_and(|(_, bits)| f64::from_bits(bits).is_nan() && bits != CANONICAL_NAN_F64),
+    }
+}
+
+fn signed_value(width: SignedInteger, bytes: &[u8]) -> Option<i64> {
+    match width {
+        SignedInteger::Int8 => wire::parse_i8(bytes).ok().map(|(_, value)| value as i64),
+        SignedInteger::Int16 => wire::parse_i16(bytes).ok().map(|(_, value)| value as i64),
+        SignedInteger::Int32 => wire::parse_i32(bytes).ok().map(|(_, value)| value as i64),
+        SignedInteger::Int64 => wire::parse_i64(bytes).ok().map(|(_, value)| value),
+    }
+}
+
+fn unsigned_value(width: UnsignedInteger, bytes: &[u8]) -> Option<u64> {
+    match width {
+        UnsignedInteger::UInt8 => wire::parse_u8(bytes).ok().map(|(_, value)| value as u64),
+        UnsignedInteger::UInt16 => wire::parse_u16(bytes).ok().map(|(_, value)| value as u64),
+        UnsignedInteger::UInt32 => wire::parse_u32(bytes).ok().map(|(_, value)| value as u64),
+        UnsignedInteger::UInt64 => wire::parse_u64(bytes).ok().map(|(_, value)| value),
+    }
+}
+
+fn minimal_int(value: i64) -> TLVValue {
+    match SignedInteger::minimal_for(value) {
+        SignedInteger::Int8 => TLVValue::Int8(value as i8),
+        SignedInteger::Int16 => TLVValue::Int16(value as i16),
+        SignedInteger::Int32 => TLVValue::Int32(value as i32),
+        SignedInteger::Int64 => TLVValue::Int64(value),
+    }
+}
+
+fn minimal_uint(value: u64) -> TLVValue {
+    match UnsignedInteger::minimal_for(value) {
+        UnsignedInteger::UInt8 => TLVValue::UInt8(value as u8),
+        UnsignedInteger::UInt16 => TLVValue::UInt16(value as u16),
+        UnsignedInteger::UInt32 => TLVValue::UInt32(value as u32),
+        UnsignedInteger::UInt64 => TLVValue::UInt64(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::TLVReaderConfig;
+    use crate::tags::{CommonProfileLength, FullyQualifiedProfileLength};
+    use crate::testing::assert_canonical;
+
+    #[test]
+    fn test_canonicalize_nans() {
+        // [Single precision signaling NaN, double precision NaN with a payload, -inf, -0.0]
+        let test_bytes = &[
+            0x16, 0x0a, 0x01, 0x00, 0x80, 0xff, 0x0b, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8,
+            0x7f, 0x0a, 0x00, 0x00, 0x80, 0xff, 0x0a, 0x00, 0x00, 0x00, 0x80, 0x18,
+        ];
+        let canonical = &[
+            0x16, 0x0a, 0x00, 0x00, 0xc0, 0x7f, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8,
+            0x7f, 0x0a, 0x00, 0x00, 0x80, 0xff, 0x0a, 0x00, 0x00, 0x00, 0x80, 0x18,
+        ];
+        assert_eq!(
+            canonicalize(test_bytes).expect("Failed to canonicalize"),
+            canonical
+        );
+        let config = TLVReaderConfig::default().require_minimal_encoding(true);
+        assert!(TLVDocument::parse_with_config(canonical, &config).is_ok());
+        assert_eq!(
+            TLVDocument::parse_with_config(test_bytes, &config)
+                .expect_err("Non minimal encoding is expected"),
+            TLVError::NonMinimalEncoding { offset: 1 }
+        );
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        // Structure {CHIP::1 = 1U as 4-octet tag, 2 = 300 as Int64, 1 = "a" with 2-octet length,
+        // 0 = List [[3 = 1U as UInt32, 2 = 1U]]}
+        let test_bytes = &[
+            0x15, 0x64, 0x01, 0x00, 0x00, 0x00, 0x01, 0x23, 0x02, 0x2c, 0x01, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x2d, 0x01, 0x01, 0x00, 0x61, 0x37, 0x00, 0x26, 0x03, 0x01, 0x00,
+            0x00, 0x00, 0x24, 0x02, 0x01, 0x18, 0x18,
+        ];
+        // Structure {0 = List [[3 = 1U, 2 = 1U]], 1 = "a", 2 = 300, CHIP::1 = 1U}
+        let canonical = &[
+            0x15, 0x37, 0x00, 0x24, 0x03, 0x01, 0x24, 0x02, 0x01, 0x18, 0x2c, 0x01, 0x01, 0x61,
+            0x21, 0x02, 0x2c, 0x01, 0x44, 0x01, 0x00, 0x01, 0x18,
+        ];
+        assert_eq!(
+            canonicalize(test_bytes).expect("Failed to canonicalize"),
+            canonical
+        );
+        assert_canonical(canonical);
+    }
+
+    #[test]
+    fn test_canonicalize_qualified_common_tags() {
+        // Structure {0::0:2 = 1U, 0::0:70000 = 2U, 0::1:1 = 3U}
+        let test_bytes = &[
+            0x15, 0xc4, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0xe4, 0x00, 0x00, 0x00, 0x00,
+            0x70, 0x11, 0x01, 0x00, 0x02, 0xc4, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x03, 0x18,
+        ];
+        // Structure {CHIP::2 = 1U, CHIP::70000 = 2U, 0::1:1 = 3U}
+        let canonical = &[
+            0x15, 0x44, 0x02, 0x00, 0x01, 0x64, 0x70, 0x11, 0x01, 0x00, 0x02, 0xc4, 0x00, 0x00,
+            0x01, 0x00, 0x01, 0x00, 0x03, 0x18,
+        ];
+        assert_eq!(
+            canonicalize(test_bytes).expect("Failed to canonicalize"),
+            canonical
+        );
+        assert_canonical(canonical);
+
+        let config = TLVReaderConfig::default().require_minimal_encoding(true);
+        assert_eq!(
+            TLVDocument::parse_with_config(test_bytes, &config)
+                .expect_err("Non minimal encoding is expected"),
+            TLVError::NonMinimalEncoding { offset: 1 }
+        );
+        let document = TLVDocument::parse(test_bytes).expect("Failed to parse document");
+        let canonical = TLVDocument::parse(canonical).expect("Failed to parse document");
+        assert_ne!(document, canonical);
+        assert!(document.elements[0]
+            .value
+            .semantic_eq(&canonical.elements[0].value));
+        let qualified = TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+            vendor_id: 0,
+            profile_number: 0,
+            tag_number: 2,
+        });
+        let common = TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 2 });
+        assert!(qualified.canonical_cmp(&common).is_eq());
+        assert_eq!(qualified.minimal(), common);
+        let standard = TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+            vendor_id: 0,
+            profile_number: 1,
+            tag_number: 2,
+        });
+        assert!(standard.canonical_cmp(&common).is_gt());
+        assert_eq!(standard.minimal(), standard);
+    }
+
+    #[test]
+    fn test_canonical_sort() {
+        use crate::writer::TLVEncode;
+
+        // Structure {2 = [{1 = 1U, 0 = 0U}], CHIP::1 = 1U as 4-octet tag, 0 = List [[3 = 1U, 2 = 1U]],
+        // 1 = "a"}
+        let test_bytes = &[
+            0x15, 0x36, 0x02, 0x15, 0x24, 0x01, 0x01, 0x24, 0x00, 0x00, 0x18, 0x18, 0x64, 0x01,
+            0x00, 0x00, 0x00, 0x01, 0x37, 0x00, 0x24, 0x03, 0x01, 0x24, 0x02, 0x01, 0x18, 0x2c,
+            0x01, 0x01, 0x61, 0x18,
+        ];
+        let mut document = TLVDocument::parse(test_bytes).expect("Failed to parse document");
+        document.canonical_sort();
+        // Structure {0 = List [[3 = 1U, 2 = 1U]], 1 = "a", 2 = [{0 = 0U, 1 = 1U}], CHIP::1 = 1U as
+        // 4-octet tag}
+        assert_eq!(
+            document.encode(),
+            [
+                0x15, 0x37, 0x00, 0x24, 0x03, 0x01, 0x24, 0x02, 0x01, 0x18, 0x2c, 0x01, 0x01, 0x61,
+                0x36, 0x02, 0x15, 0x24, 0x00, 0x00, 0x24, 0x01, 0x01, 0x18, 0x18, 0x64, 0x01, 0x00,
+                0x00, 0x00, 0x01, 0x18,
+            ]
+        );
+
+        // Same order as the byte level canonicalizer, which also narrows the tag
+        let mut value = TLVValue::try_from(&test_bytes[..]).expect("Failed to parse value");
+        value.canonical_sort();
+        let mut canonical = value.clone();
+        canonical.canonicalize();
+        assert_eq!(
+            canonical.encode_tlv(),
+            canonicalize(test_bytes).expect("Failed to canonicalize")
+        );
+        assert!(value.semantic_eq(&canonical));
+        assert_eq!(value.len(), 4);
+    }
+
+    #[test]
+    fn test_canonical_tag_order() {
+        use crate::tags::ImplicitProfileLength;
+        use core::cmp::Ordering;
+
+        let tags = [
+            TLVTag::Anonymous,
+            TLVTag::ContextSpecific(0),
+            TLVTag::ContextSpecific(255),
+            TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 1 }),
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 70000 }),
+            TLVTag::ImplicitProfile(ImplicitProfileLength::TwoOctets { tag_number: 0 }),
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                vendor_id: 1,
+                profile_number: 2,
+                tag_number: 3,
+            }),
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                vendor_id: 2,
+                profile_number: 1,
+                tag_number: 0,
+            }),
+        ];
+        for pair in tags.windows(2) {
+            assert_eq!(pair[0].canonical_cmp(&pair[1]), Ordering::Less);
+        }
+        assert_eq!(
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 1 })
+                .canonical_cmp(&tags[3]),
+            Ordering::Equal
+        );
+        assert_eq!(
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 1 }).minimal(),
+            tags[3]
+        );
+        assert_eq!(tags[4].minimal(), tags[4]);
+    }
+
+    #[test]
+    fn test_canonicalize_malformed() {
+        assert_eq!(
+            canonicalize(&[0x15, 0x24, 0x00, 0x01])
+                .expect_err("Unterminated container is expected"),
+            TLVError::UnterminatedContainer { offset: 0 }
+        );
+    }
+
+    #[test]
+    fn test_require_minimal_encoding() {
+        let config = TLVReaderConfig::default().require_minimal_encoding(true);
+        let test_vectors: &[(&[u8], usize)] = &[
+            // 5 as UInt32
+            (&[0x06, 0x05, 0x00, 0x00, 0x00], 0),
+            // -1 as Int16
+            (&[0x01, 0xff, 0xff], 0),
+            // "abc" with a 2-octet length
+            (&[0x0d, 0x03, 0x00, 0x61, 0x62, 0x63], 0),
+            // Structure {CHIP::1 = 1U as 4-octet tag}
+            (&[0x15, 0x64, 0x01, 0x00, 0x00, 0x00, 0x01, 0x18], 1),
+            // [1U, 300 as UInt32]
+            (&[0x16, 0x04, 0x01, 0x06, 0x2c, 0x01, 0x00, 0x00, 0x18], 3),
+        ];
+        for (test_bytes, offset) in test_vectors {
+            assert_eq!(
+                TLVDocument::parse_with_config(test_bytes, &config)
+                    .expect_err("Non minimal encoding is expected"),
+                TLVError::NonMinimalEncoding { offset: *offset }
+            );
+            TLVDocument::parse(test_bytes).expect("Failed to parse document");
+            let canonical = canonicalize(test_bytes).expect("Failed to canonicalize");
+            TLVDocument::parse_with_config(&canonical, &config)
+                .expect("Failed to parse canonical document");
+        }
+
+        // 300 as UInt16, "abc" with a 1-octet length
+        let test_bytes = &[0x05, 0x2c, 0x01, 0x0c, 0x03, 0x61, 0x62, 0x63];
+        TLVDocument::parse_with_config(test_bytes, &config).expect("Failed to parse document");
+    }
+}
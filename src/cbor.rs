@@ -0,0 +1,603 @@
+// Conversion between TLV and CBOR (RFC 8949). A TLV sequence of top level elements maps to a
+// CBOR sequence (RFC 8742). Structures become maps keyed by tag: context tags as integers,
+// profile tags as CBOR tagged values, anonymous members as null. Arrays become arrays.
+//
+// Lossy corners, reported per element by the conversion functions:
+// - CBOR integers and floats have no fixed width, so TLV widths are not carried over. The way
+//   back uses the narrowest integer width, and single precision for exactly representable floats.
+// - CBOR integers have no signedness beyond their value, non-negative signed integers come back
+//   as unsigned.
+// - Lists have no CBOR counterpart. Fully anonymous lists become arrays, others become maps
+//   (which keep order and duplicates), so both come back as Arrays or Structures.
+// - Top level tags have nowhere to go and are dropped.
+// - Profile tags come back in their narrowest form, foreign CBOR tags on values are dropped.
+
+use crate::errors::TLVError;
+use crate::reader::TLVReader;
+use crate::tags::{
+    CommonProfileLength, FullyQualifiedProfileLength, ImplicitProfileLength, TLVTag,
+};
+use crate::types::{
+    ContainerType, FloatingPoint, PredeterminedLenPrimitive, PrimitiveLengthType, SignedInteger,
+    SpecifiedLenPrimitive, TLVType, UnsignedInteger,
+};
+use crate::writer::TLVWriter;
+use bytes::Bytes;
+use ciborium::value::Value;
+
+// CBOR tags wrapping profile tag map keys
+pub const COMMON_PROFILE_CBOR_TAG: u64 = 0x4d54_0001; // Tag number
+pub const IMPLICIT_PROFILE_CBOR_TAG: u64 = 0x4d54_0002; // Tag number
+pub const FULLY_QUALIFIED_CBOR_TAG: u64 = 0x4d54_0003; // [Vendor ID, profile number, tag number]
+
+#[derive(Debug, PartialEq)]
+pub enum LossKind {
+    TopLevelTag(TLVTag),
+    IntegerWidth,
+    SignedAsUnsigned,
+    FloatWidth,
+    TagWidth(TLVTag),
+    ListAsArray,
+    ListAsMap,
+    CborTagDropped(u64),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LossyStep {
+    pub path: String, // Tag notation, e.g. 0/2[3]/1, empty for top level elements
+    pub kind: LossKind,
+}
+
+pub fn tlv_to_cbor(tlv: &[u8]) -> Result<(Vec<u8>, Vec<LossyStep>), TLVError> {
+    let mut cbor = Vec::new();
+    let mut lossy_steps = Vec::new();
+    if tlv.is_empty() {
+        return Ok((cbor, lossy_steps));
+    }
+    let mut tlv_reader = TLVReader::new(tlv);
+    loop {
+        let tag = tlv_reader.read_tag()?;
+        if tag != TLVTag::Anonymous {
+            lossy_steps.push(LossyStep {
+                path: String::new(),
+                kind: LossKind::TopLevelTag(tag),
+            });
+        }
+        let value = element_to_cbor(&mut tlv_reader, "", &mut lossy_steps)?;
+        ciborium::ser::into_writer(&value, &mut cbor).map_err(|_| TLVError::InvalidCbor)?;
+        match tlv_reader.next() {
+            Err(TLVError::EndOfTLV) => break,
+            result => result?,
+        }
+    }
+    Ok((cbor, lossy_steps))
+}
+
+pub fn cbor_to_tlv(cbor: &[u8]) -> Result<(Vec<u8>, Vec<LossyStep>), TLVError> {
+    let mut tlv_writer = TLVWriter::new();
+    let mut lossy_steps = Vec::new();
+    let mut remaining_bytes = cbor;
+    while !remaining_bytes.is_empty() {
+        let value: Value =
+            ciborium::de::from_reader(&mut remaining_bytes).map_err(|_| TLVError::InvalidCbor)?;
+        cbor_to_element(
+            &mut tlv_writer,
+            TLVTag::Anonymous,
+            &value,
+            "",
+            &mut lossy_steps,
+        )?;
+    }
+    Ok((tlv_writer.into_bytes(), lossy_steps))
+}
+
+fn member_path(path: &str, tag: &TLVTag) -> String {
+    if path.is_empty() {
+        tag.to_string()
+    } else {
+        format!("{}/{}", path, tag)
+    }
+}
+
+fn tag_to_cbor(tag: &TLVTag, path: &str, lossy_steps: &mut Vec<LossyStep>) -> Value {
+    let mut report_width = |wide: bool| {
+        if wide {
+            lossy_steps.push(LossyStep {
+                path: path.to_string(),
+                kind: LossKind::TagWidth(tag.clone()),
+            });
+        }
+    };
+    match tag {
+        TLVTag::Anonymous => Value::Null,
+        TLVTag::ContextSpecific(tag_number) => Value::from(*tag_number),
+        TLVTag::CommonProfile(profile) => {
+            let tag_number = match profile {
+                CommonProfileLength::TwoOctets { tag_number } => *tag_number as u32,
+                CommonProfileLength::FourOctets { tag_number } => {
+                    report_width(*tag_number <= u16::MAX as u32);
+                    *tag_number
+                }
+            };
+            Value::Tag(COMMON_PROFILE_CBOR_TAG, Box::new(Value::from(tag_number)))
+        }
+        TLVTag::ImplicitProfile(profile) => {
+            let tag_number = match profile {
+                ImplicitProfileLength::TwoOctets { tag_number } => *tag_number as u32,
+                ImplicitProfileLength::FourOctets { tag_number } => {
+                    report_width(*tag_number <= u16::MAX as u32);
+                    *tag_number
+                }
+            };
+            Value::Tag(IMPLICIT_PROFILE_CBOR_TAG, Box::new(Value::from(tag_number)))
+        }
+        TLVTag::FullyQualifiedProfile(profile) => {
+            let (vendor_id, profile_number, tag_number) = match profile {
+                FullyQualifiedProfileLength::SixOctets {
+                    vendor_id,
+                    profile_number,
+                    tag_number,
+                } => (*vendor_id, *profile_number, *tag_number as u32),
+                FullyQualifiedProfileLength::EightOctets {
+                    vendor_id,
+                    profile_number,
+                    tag_number,
+                } => {
+                    report_width(*tag_number <= u16::MAX as u32);
+                    (*vendor_id, *profile_number, *tag_number)
+                }
+            };
+            Value::Tag(
+                FULLY_QUALIFIED_CBOR_TAG,
+                Box::new(Value::Array(vec![
+                    Value::from(vendor_id),
+                    Value::from(profile_number),
+                    Value::from(tag_number),
+                ])),
+            )
+        }
+    }
+}
+
+fn cbor_to_tag(key: &Value) -> Result<TLVTag, TLVError> {
+    let tag_number = |value: &Value| -> Result<u32, TLVError> {
+        value
+            .as_integer()
+            .and_then(|integer| u32::try_from(integer).ok())
+            .ok_or(TLVError::InvalidTag)
+    };
+    Ok(match key {
+        Value::Null => TLVTag::Anonymous,
+        Value::Integer(integer) => {
+            TLVTag::ContextSpecific(u8::try_from(*integer).map_err(|_| TLVError::InvalidTag)?)
+        }
+        Value::Tag(COMMON_PROFILE_CBOR_TAG, value) => {
+            let tag_number = tag_number(value)?;
+            TLVTag::CommonProfile(match u16::try_from(tag_number) {
+                Ok(tag_number) => CommonProfileLength::TwoOctets { tag_number },
+                Err(_) => CommonProfileLength::FourOctets { tag_number },
+            })
+        }
+        Value::Tag(IMPLICIT_PROFILE_CBOR_TAG, value) => {
+            let tag_number = tag_number(value)?;
+            TLVTag::ImplicitProfile(match u16::try_from(tag_number) {
+                Ok(tag_number) => ImplicitProfileLength::TwoOctets { tag_number },
+                Err(_) => ImplicitProfileLength::FourOctets { tag_number },
+            })
+        }
+        Value::Tag(FULLY_QUALIFIED_CBOR_TAG, value) => match value.as_array().map(Vec::as_slice) {
+            Some([vendor_id, profile_number, tag_number_value]) => {
+                let vendor_id =
+                    u16::try_from(tag_number(vendor_id)?).map_err(|_| TLVError::InvalidTag)?;
+                let profile_number =
+                    u16::try_from(tag_number(profile_number)?).map_err(|_| TLVError::InvalidTag)?;
+                let tag_number = tag_number(tag_number_value)?;
+                TLVTag::FullyQualifiedProfile(match u16::try_from(tag_number) {
+                    Ok(tag_number) => FullyQualifiedProfileLength::SixOctets {
+                        vendor_id,
+                        profile_number,
+                        tag_number,
+                    },
+                    Err(_) => FullyQualifiedProfileLength::EightOctets {
+                        vendor_id,
+                        profile_number,
+                        tag_number,
+                    },
+                })
+            }
+            _ => return Err(TLVError::InvalidTag),
+        },
+        _ => return Err(TLVError::InvalidTag),
+    })
+}
+
+fn members_to_cbor(
+    tlv_reader: &mut TLVReader,
+    path: &str,
+    lossy_steps: &mut Vec<LossyStep>,
+) -> Result<Vec<(TLVTag, Value)>, TLVError> {
+    let mut members = Vec::new();
+    tlv_reader.enter_container()?;
+    loop {
+        let tag = match tlv_reader.read_tag() {
+            Err(TLVError::EndOfContainer) => break,
+            result => result?,
+        };
+        let member_path = match tag {
+            TLVTag::Anonymous => format!("{}[{}]", path, members.len()),
+            _ => member_path(path, &tag),
+        };
+        let value = element_to_cbor(tlv_reader, &member_path, lossy_steps)?;
+        members.push((tag, value));
+        match tlv_reader.next() {
+            Err(TLVError::EndOfContainer) => break,
+            result => result?,
+        }
+    }
+    tlv_reader.exit_container()?;
+    Ok(members)
+}
+
+fn members_to_map(
+    members: Vec<(TLVTag, Value)>,
+    path: &str,
+    lossy_steps: &mut Vec<LossyStep>,
+) -> Value {
+    Value::Map(
+        members
+            .into_iter()
+            .map(|(tag, value)| {
+                let key = tag_to_cbor(&tag, &member_path(path, &tag), lossy_steps);
+                (key, value)
+            })
+            .collect(),
+    )
+}
+
+fn element_to_cbor(
+    tlv_reader: &mut TLVReader,
+    path: &str,
+    lossy_steps: &mut Vec<LossyStep>,
+) -> Result<Value, TLVError> {
+    let mut report = |kind| {
+        lossy_steps.push(LossyStep {
+            path: path.to_string(),
+            kind,
+        })
+    };
+    Ok(match tlv_reader.read_type()? {
+        TLVType::Container(ContainerType::Structure) => {
+            let members = members_to_cbor(tlv_reader, path, lossy_steps)?;
+            members_to_map(members, path, lossy_steps)
+        }
+        TLVType::Container(ContainerType::Array) => Value::Array(
+            members_to_cbor(tlv_reader, path, lossy_steps)?
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect(),
+        ),
+        TLVType::Container(ContainerType::List) => {
+            let members = members_to_cbor(tlv_reader, path, lossy_steps)?;
+            if members.iter().all(|(tag, _)| *tag == TLVTag::Anonymous) {
+                lossy_steps.push(LossyStep {
+                    path: path.to_string(),
+                    kind: LossKind::ListAsArray,
+                });
+                Value::Array(members.into_iter().map(|(_, value)| value).collect())
+            } else {
+                lossy_steps.push(LossyStep {
+                    path: path.to_string(),
+                    kind: LossKind::ListAsMap,
+                });
+                members_to_map(members, path, lossy_steps)
+            }
+        }
+        TLVType::Primitive(PrimitiveLengthType::Predetermined(predetermined)) => {
+            match predetermined {
+                PredeterminedLenPrimitive::SignedInteger(width) => {
+                    let value = tlv_reader.read_int()?;
+                    if width != SignedInteger::minimal_for(value) {
+                        report(LossKind::IntegerWidth);
+                    }
+                    if value >= 0 {
+                        report(LossKind::SignedAsUnsigned);
+                    }
+                    Value::from(value)
+                }
+                PredeterminedLenPrimitive::UnsignedInteger(width) => {
+                    let value = tlv_reader.read_uint()?;
+                    if width != UnsignedInteger::minimal_for(value) {
+                        report(LossKind::IntegerWidth);
+                    }
+                    Value::from(value)
+                }
+                PredeterminedLenPrimitive::FloatingPointNumber(
+                    FloatingPoint::FloatingPointNumber32,
+                ) => Value::Float(tlv_reader.read_f32()? as f64),
+                PredeterminedLenPrimitive::FloatingPointNumber(
+                    FloatingPoint::FloatingPointNumber64,
+                ) => {
+                    let value = tlv_reader.read_f64()?;
+                    if value.is_nan() || value as f32 as f64 == value {
+                        report(LossKind::FloatWidth);
+                    }
+                    Value::Float(value)
+                }
+                PredeterminedLenPrimitive::Boolean(value) => Value::Bool(value),
+                PredeterminedLenPrimitive::Null => Value::Null,
+            }
+        }
+        TLVType::Primitive(PrimitiveLengthType::Specified(SpecifiedLenPrimitive::UTF8String(
+            _,
+        ))) => Value::Text(tlv_reader.read_char_str()?),
+        TLVType::Primitive(PrimitiveLengthType::Specified(SpecifiedLenPrimitive::ByteString(
+            _,
+        ))) => Value::Bytes(tlv_reader.read_byte_str()?),
+    })
+}
+
+fn cbor_to_element(
+    tlv_writer: &mut TLVWriter,
+    tag: TLVTag,
+    value: &Value,
+    path: &str,
+    lossy_steps: &mut Vec<LossyStep>,
+) -> Result<(), TLVError> {
+    match value {
+        Value::Integer(integer) => {
+            let integer = i128::from(*integer);
+            if integer >= 0 {
+                tlv_writer.put_uint(tag, integer as u64)?;
+            } else {
+                let integer = i64::try_from(integer).map_err(|_| TLVError::InvalidType)?;
+                tlv_writer.put_int(tag, integer)?;
+            }
+        }
+        Value::Float(float) => {
+            if float.is_nan() || *float as f32 as f64 == *float {
+                tlv_writer.put(tag, &(*float as f32))?;
+            } else {
+                tlv_writer.put(tag, float)?;
+            }
+        }
+        Value::Bool(boolean) => tlv_writer.put(tag, boolean)?,
+        Value::Null => tlv_writer.put_null(tag)?,
+        Value::Text(text) => tlv_writer.put(tag, text)?,
+        Value::Bytes(bytes) => tlv_writer.put(tag, &Bytes::copy_from_slice(bytes))?,
+        Value::Tag(cbor_tag, value) => {
+            lossy_steps.push(LossyStep {
+                path: path.to_string(),
+                kind: LossKind::CborTagDropped(*cbor_tag),
+            });
+            cbor_to_element(tlv_writer, tag, value, path, lossy_steps)?;
+        }
+        Value::Array(values) => {
+            tlv_writer.start_container(tag, ContainerType::Array)?;
+            for (index, value) in values.iter().enumerate() {
+                let element_path = format!("{}[{}]", path, index);
+                cbor_to_element(
+                    tlv_writer,
+                    TLVTag::Anonymous,
+                    value,
+                    &element_path,
+                    lossy_steps,
+                )?;
+            }
+            tlv_writer.end_container()?;
+        }
+        Value::Map(entries) => {
+            let member_tags = entries
+                .iter()
+                .map(|(key, _)| cbor_to_tag(key))
+                .collect::<Result<Vec<_>, _>>()?;
+            // Anonymous members only come from a List written as a map, Structure members have tags
+            let container_type = if member_tags.contains(&TLVTag::Anonymous) {
+                ContainerType::List
+            } else {
+                ContainerType::Structure
+            };
+            tlv_writer.start_container(tag, container_type)?;
+            for (member_tag, (_, value)) in member_tags.into_iter().zip(entries) {
+                let member_path = member_path(path, &member_tag);
+                cbor_to_element(tlv_writer, member_tag, value, &member_path, lossy_steps)?;
+            }
+            tlv_writer.end_container()?;
+        }
+        _ => return Err(TLVError::InvalidType),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::TLVDocument;
+
+    #[test]
+    fn test_tlv_to_cbor() {
+        // Structure {0 = 42U}
+        let (cbor, lossy_steps) =
+            tlv_to_cbor(&[0x15, 0x24, 0x00, 0x2a, 0x18]).expect("Failed to convert");
+        assert_eq!(cbor, [0xa1, 0x00, 0x18, 0x2a]);
+        assert_eq!(lossy_steps, vec![]);
+
+        // Array, mix of element types, [42, -170000, {}, 17.9, "Hello!"]
+        let test_bytes = &[
+            0x16, 0x00, 0x2a, 0x02, 0xf0, 0x67, 0xfd, 0xff, 0x15, 0x18, 0x0a, 0x33, 0x33, 0x8f,
+            0x41, 0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21, 0x18,
+        ];
+        let (cbor, lossy_steps) = tlv_to_cbor(test_bytes).expect("Failed to convert");
+        assert_eq!(
+            cbor,
+            [
+                0x85, 0x18, 0x2a, 0x3a, 0x00, 0x02, 0x98, 0x0f, 0xa0, 0xfa, 0x41, 0x8f, 0x33, 0x33,
+                0x66, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21
+            ]
+        );
+        assert_eq!(
+            lossy_steps,
+            vec![LossyStep {
+                path: String::from("[0]"),
+                kind: LossKind::SignedAsUnsigned
+            }]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tlv = TLVDocument::builder()
+            .structure(TLVTag::Anonymous, |s| {
+                s.value(0, &42u8)
+                    .value(1, &-17i8)
+                    .string(2, "Tschüs")
+                    .bytes(3, &[0x00, 0x01])
+                    .bool(4, true)
+                    .null(5)
+                    .array(6, |a| a.value(&40000000000u64).structure(|s| s))
+                    .float(7, 17.9)
+                    .double(8, 0.1)
+                    .value(
+                        TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 1 }),
+                        &65535u16,
+                    )
+                    .value(
+                        TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets {
+                            tag_number: 100000,
+                        }),
+                        &-40000000000i64,
+                    )
+                    .bool(
+                        TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+                            vendor_id: 65521,
+                            profile_number: 57069,
+                            tag_number: 2857762541,
+                        }),
+                        false,
+                    )
+            })
+            .into_bytes()
+            .expect("Failed to build document");
+
+        let (cbor, lossy_steps) = tlv_to_cbor(&tlv).expect("Failed to convert to CBOR");
+        assert_eq!(lossy_steps, vec![]);
+        let (round_trip, lossy_steps) = cbor_to_tlv(&cbor).expect("Failed to convert to TLV");
+        assert_eq!(lossy_steps, vec![]);
+        assert_eq!(round_trip, tlv);
+    }
+
+    #[test]
+    fn test_lossy_round_trip() {
+        // Context tag 1, Structure {0 = 42 as Int16, 1 = List [2 = 5U, 1.5 as double],
+        // CHIP::1 = [] with a 4-octet tag}
+        let tlv = TLVDocument::builder()
+            .structure(1, |s| {
+                s.value(0, &42i16)
+                    .list(1, |l| l.value(2, &5u8).double(TLVTag::Anonymous, 1.5))
+                    .array(
+                        TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 1 }),
+                        |a| a,
+                    )
+            })
+            .into_bytes()
+            .expect("Failed to build document");
+
+        let (cbor, lossy_steps) = tlv_to_cbor(&tlv).expect("Failed to convert");
+        assert_eq!(
+            lossy_steps,
+            vec![
+                LossyStep {
+                    path: String::new(),
+                    kind: LossKind::TopLevelTag(TLVTag::ContextSpecific(1))
+                },
+                LossyStep {
+                    path: String::from("0"),
+                    kind: LossKind::IntegerWidth
+                },
+                LossyStep {
+                    path: String::from("0"),
+                    kind: LossKind::SignedAsUnsigned
+                },
+                LossyStep {
+                    path: String::from("1[1]"),
+                    kind: LossKind::FloatWidth
+                },
+                LossyStep {
+                    path: String::from("1"),
+                    kind: LossKind::ListAsMap
+                },
+                LossyStep {
+                    path: String::from("CHIP::1"),
+                    kind: LossKind::TagWidth(TLVTag::CommonProfile(
+                        CommonProfileLength::FourOctets { tag_number: 1 }
+                    ))
+                },
+            ]
+        );
+
+        // Structure {0 = 42U, 1 = List [2 = 5U, 1.5], CHIP::1 = []}
+        let (tlv, lossy_steps) = cbor_to_tlv(&cbor).expect("Failed to convert");
+        assert_eq!(lossy_steps, vec![]);
+        assert_eq!(
+            tlv,
+            [
+                0x15, 0x24, 0x00, 0x2a, 0x37, 0x01, 0x24, 0x02, 0x05, 0x0a, 0x00, 0x00, 0xc0, 0x3f,
+                0x18, 0x56, 0x01, 0x00, 0x18, 0x18
+            ]
+        );
+
+        // Anonymous List [1, 2] comes back as an Array
+        let (cbor, lossy_steps) =
+            tlv_to_cbor(&[0x17, 0x04, 0x01, 0x04, 0x02, 0x18]).expect("Failed to convert");
+        assert_eq!(
+            lossy_steps,
+            vec![LossyStep {
+                path: String::new(),
+                kind: LossKind::ListAsArray
+            }]
+        );
+        let (tlv, _) = cbor_to_tlv(&cbor).expect("Failed to convert");
+        assert_eq!(tlv, [0x16, 0x04, 0x01, 0x04, 0x02, 0x18]);
+    }
+
+    #[test]
+    fn test_cbor_sequence() {
+        // true, 1(null), "a"
+        let cbor = &[0xf5, 0xc1, 0xf6, 0x61, 0x61];
+        let (tlv, lossy_steps) = cbor_to_tlv(cbor).expect("Failed to convert");
+        assert_eq!(tlv, [0x09, 0x14, 0x0c, 0x01, 0x61]);
+        assert_eq!(
+            lossy_steps,
+            vec![LossyStep {
+                path: String::new(),
+                kind: LossKind::CborTagDropped(1)
+            }]
+        );
+        let (round_trip, _) = tlv_to_cbor(&tlv).expect("Failed to convert");
+        assert_eq!(round_trip, [0xf5, 0xf6, 0x61, 0x61]);
+    }
+
+    #[test]
+    fn test_invalid_cbor() {
+        // Truncated map
+        assert_eq!(
+            cbor_to_tlv(&[0xa1, 0x00]).expect_err("Invalid CBOR is expected"),
+            TLVError::InvalidCbor
+        );
+        // Text map key
+        assert_eq!(
+            cbor_to_tlv(&[0xa1, 0x61, 0x61, 0x00]).expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        // Context tag out of range
+        assert_eq!(
+            cbor_to_tlv(&[0xa1, 0x19, 0x01, 0x00, 0x00]).expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        // Negative integer beyond i64
+        assert_eq!(
+            cbor_to_tlv(&[0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff])
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+    }
+}
@@ -0,0 +1,211 @@
+// Rust code writing a payload again with a TLVWriter, to turn captured traffic into regression
+// tests. Elements keep their encoding, integer and float widths, length field widths and tag forms,
+// so running the code gives back the exact bytes. The code is a list of statements expecting a
+// TLVWriter named w, a function returning Result<_, TLVError>, and TLVTag, CommonProfileLength,
+// ImplicitProfileLength, FullyQualifiedProfileLength, ContainerType and TLVFieldSize in scope.
+
+use crate::errors::TLVError;
+use crate::reader::TLVReader;
+use crate::tags::TLVTag;
+use crate::types::{PrimitiveLengthType, SpecifiedLenPrimitive, TLVFieldSize, TLVType};
+use crate::value::TLVValueRef;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+const INDENT: &str = "    ";
+
+pub fn generate_rust_code(bytes: &[u8]) -> Result<String, TLVError> {
+    let mut code = String::new();
+    if bytes.is_empty() {
+        return Ok(code);
+    }
+    let mut tlv_reader = TLVReader::new(bytes);
+    loop {
+        let indent = INDENT.repeat(tlv_reader.container_depth());
+        let mut at_end = match tlv_reader.read_tag() {
+            Ok(tag) => match tlv_reader.read_type()? {
+                TLVType::Container(container_type) => {
+                    let _ = writeln!(
+                        code,
+                        "{}w.start_container({}, ContainerType::{:?})?;",
+                        indent,
+                        rust_tag(&tag),
+                        container_type
+                    );
+                    tlv_reader.enter_container()?;
+                    continue; // Cursor on the first member or at the end of an empty container
+                }
+                TLVType::Primitive(primitive_length_type) => {
+                    let length_field_size = match primitive_length_type {
+                        PrimitiveLengthType::Specified(SpecifiedLenPrimitive::UTF8String(len)) => {
+                            Some(len.length_field_size())
+                        }
+                        PrimitiveLengthType::Specified(SpecifiedLenPrimitive::ByteString(len)) => {
+                            Some(len.length_field_size())
+                        }
+                        PrimitiveLengthType::Predetermined(_) => None,
+                    };
+                    let call = put_call(&tag, tlv_reader.read_value_ref()?, length_field_size);
+                    let _ = writeln!(code, "{}{}?;", indent, call);
+                    false
+                }
+            },
+            Err(TLVError::EndOfContainer) => true,
+            Err(e) => return Err(e),
+        };
+        // Ends every container ending here, then moves to the following sibling
+        loop {
+            if at_end {
+                tlv_reader.exit_container()?;
+                let indent = INDENT.repeat(tlv_reader.container_depth());
+                let _ = writeln!(code, "{}w.end_container()?;", indent);
+            }
+            match tlv_reader.next() {
+                Ok(()) => break,
+                Err(TLVError::EndOfContainer) => at_end = true,
+                Err(TLVError::EndOfTLV) => return Ok(code),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn rust_tag(tag: &TLVTag) -> String {
+    match tag {
+        TLVTag::Anonymous => String::from("TLVTag::Anonymous"),
+        TLVTag::ContextSpecific(tag_number) => format!("TLVTag::ContextSpecific({})", tag_number),
+        TLVTag::CommonProfile(length) => {
+            format!("TLVTag::CommonProfile(CommonProfileLength::{:?})", length)
+        }
+        TLVTag::ImplicitProfile(length) => {
+            format!(
+                "TLVTag::ImplicitProfile(ImplicitProfileLength::{:?})",
+                length
+            )
+        }
+        TLVTag::FullyQualifiedProfile(length) => format!(
+            "TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::{:?})",
+            length
+        ),
+    }
+}
+
+// Debug of finite floats reads back as the same value, NaNs keep their payload through their bits
+macro_rules! float_literal {
+    ($value:expr, $type:ident) => {{
+        let value = $value;
+        let type_name = stringify!($type);
+        if value.is_finite() {
+            format!("{:?}{}", value, type_name)
+        } else if value.is_nan() {
+            format!("{}::from_bits({:#x})", type_name, value.to_bits())
+        } else if value > 0.0 {
+            format!("{}::INFINITY", type_name)
+        } else {
+            format!("{}::NEG_INFINITY", type_name)
+        }
+    }};
+}
+
+// Strings with the narrowest length field use put, others give the width of theirs
+fn put_call(
+    tag: &TLVTag,
+    value: TLVValueRef<'_>,
+    length_field_size: Option<TLVFieldSize>,
+) -> String {
+    let tag = rust_tag(tag);
+    let value = match value {
+        TLVValueRef::Int8(value) => format!("&{}i8", value),
+        TLVValueRef::Int16(value) => format!("&{}i16", value),
+        TLVValueRef::Int32(value) => format!("&{}i32", value),
+        TLVValueRef::Int64(value) => format!("&{}i64", value),
+        TLVValueRef::UInt8(value) => format!("&{}u8", value),
+        TLVValueRef::UInt16(value) => format!("&{}u16", value),
+        TLVValueRef::UInt32(value) => format!("&{}u32", value),
+        TLVValueRef::UInt64(value) => format!("&{}u64", value),
+        TLVValueRef::Bool(value) => format!("&{}", value),
+        TLVValueRef::Float32(value) => format!("&{}", float_literal!(value, f32)),
+        TLVValueRef::Float64(value) => format!("&{}", float_literal!(value, f64)),
+        TLVValueRef::Null => return format!("w.put_null({})", tag),
+        TLVValueRef::Utf8String(value) => match length_field_size {
+            Some(field_size) if field_size != TLVFieldSize::minimal_for(value.len()) => {
+                return format!(
+                    "w.put_str_with_len_width({}, {:?}, TLVFieldSize::{:?})",
+                    tag, value, field_size
+                )
+            }
+            _ => format!("{:?}", value),
+        },
+        TLVValueRef::ByteString(value) => {
+            let octets: Vec<String> = value
+                .iter()
+                .map(|octet| format!("{:#04x}", octet))
+                .collect();
+            let octets = octets.join(", ");
+            match length_field_size {
+                Some(field_size) if field_size != TLVFieldSize::minimal_for(value.len()) => {
+                    return format!(
+                        "w.put_bytes_with_len_width({}, &[{}], TLVFieldSize::{:?})",
+                        tag, octets, field_size
+                    )
+                }
+                _ => format!("&[{}][..]", octets),
+            }
+        }
+        TLVValueRef::Structure(_) | TLVValueRef::Array(_) | TLVValueRef::List(_) => {
+            unreachable!("containers are written member by member")
+        }
+    };
+    format!("w.put({}, {})", tag, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::{CommonProfileLength, FullyQualifiedProfileLength, ImplicitProfileLength};
+    use crate::types::ContainerType;
+    use crate::writer::TLVWriter;
+
+    // Structure {0 = -17, 1 = 42 as Int16, 2 = -170000, 3 = i64::MIN, 4 = 255U, 5 = 1U as UInt16,
+    // 6 = 0U as UInt32, 7 = u64::MAX, 8 = true, 9 = false, 10 = 17.9f32, 11 = NaN with payload 1,
+    // 12 = -Inf f32, 13 = -0.0, 14 = Null, CHIP::1 = "Tschüs", Implicit::100000 = "a\"\n" with a
+    // 2-octet length, 65521::57069:1 = hex:000102, 65521::57069:2857762541 = hex: with a 4-octet
+    // length, 15 = [1U, {}, [[0 = 1U]]]}, Null
+    const TEST_BYTES: &[u8] = &[
+        0x15, 0x20, 0x00, 0xef, 0x21, 0x01, 0x2a, 0x00, 0x22, 0x02, 0xf0, 0x67, 0xfd, 0xff, 0x23,
+        0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x24, 0x04, 0xff, 0x25, 0x05, 0x01,
+        0x00, 0x26, 0x06, 0x00, 0x00, 0x00, 0x00, 0x27, 0x07, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0x29, 0x08, 0x28, 0x09, 0x2a, 0x0a, 0x33, 0x33, 0x8f, 0x41, 0x2b, 0x0b, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0xf8, 0x7f, 0x2a, 0x0c, 0x00, 0x00, 0x80, 0xff, 0x2b, 0x0d,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x34, 0x0e, 0x4c, 0x01, 0x00, 0x07, 0x54,
+        0x73, 0x63, 0x68, 0xc3, 0xbc, 0x73, 0xad, 0xa0, 0x86, 0x01, 0x00, 0x03, 0x00, 0x61, 0x22,
+        0x0a, 0xd0, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x03, 0x00, 0x01, 0x02, 0xf2, 0xf1, 0xff,
+        0xed, 0xde, 0xed, 0xfe, 0x55, 0xaa, 0x00, 0x00, 0x00, 0x00, 0x36, 0x0f, 0x04, 0x01, 0x15,
+        0x18, 0x17, 0x24, 0x00, 0x01, 0x18, 0x18, 0x18, 0x14,
+    ];
+
+    fn write_test_bytes() -> Result<Vec<u8>, TLVError> {
+        let mut w = TLVWriter::new();
+        include!("testdata/codegen_test_bytes.rs");
+        Ok(w.into_bytes())
+    }
+
+    #[test]
+    fn test_generate_rust_code() {
+        assert_eq!(write_test_bytes().as_deref(), Ok(TEST_BYTES));
+
+        let code = generate_rust_code(TEST_BYTES).expect("Failed to generate code");
+        let block: String = code.lines().map(|line| format!("    {}\n", line)).collect();
+        let included = include_str!("testdata/codegen_test_bytes.rs");
+        assert!(included.ends_with(&format!("{{\n{}}}\n", block)));
+
+        assert_eq!(generate_rust_code(&[]).as_deref(), Ok(""));
+        // Structure {0 = 42U cut short
+        assert_eq!(
+            generate_rust_code(&[0x15, 0x24, 0x00, 0x2a]),
+            Err(TLVError::UnterminatedContainer { offset: 0 })
+        );
+    }
+}
@@ -0,0 +1,287 @@
+// Differences between two payloads, located by paths in tag notation: 1/2 is member 2 of the
+// Structure or List member 1, [3] the fourth member of an Array or List. Top level elements are
+// compared in order, their paths are empty when both payloads hold a single element.
+
+use crate::schema;
+use crate::tags::TLVTag;
+use crate::value::{TLVDocument, TLVValue};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::discriminant;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Difference {
+    Added {
+        path: String,
+        value: TLVValue,
+    },
+    Removed {
+        path: String,
+        value: TLVValue,
+    },
+    Changed {
+        path: String,
+        old: TLVValue,
+        new: TLVValue,
+    },
+}
+
+impl Difference {
+    pub fn path(&self) -> &str {
+        match self {
+            Difference::Added { path, .. }
+            | Difference::Removed { path, .. }
+            | Difference::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = match self.path() {
+            "" => "<root>",
+            path => path,
+        };
+        match self {
+            Difference::Added { value, .. } => write!(f, "+ {}: {}", path, value),
+            Difference::Removed { value, .. } => write!(f, "- {}: {}", path, value),
+            Difference::Changed { old, new, .. } if old.semantic_eq(new) => {
+                write!(f, "~ {}: {} -> {} (width only)", path, old, new)
+            }
+            Difference::Changed { old, new, .. } => write!(f, "~ {}: {} -> {}", path, old, new),
+        }
+    }
+}
+
+// Semantic comparison ignores the width of integers, floats and tag fields, see
+// TLVValue::semantic_eq. Structure members are matched by tag whatever their order, List and Array
+// members by position.
+pub fn diff(old: &TLVDocument, new: &TLVDocument, semantic: bool) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    let single = old.elements.len() == 1 && new.elements.len() == 1;
+    let old_values = old.elements.iter().map(|element| &element.value);
+    let new_values = new.elements.iter().map(|element| &element.value);
+    let top_level_path = |index: usize| {
+        if single {
+            String::new()
+        } else {
+            format!("[{}]", index)
+        }
+    };
+    diff_positional(
+        old_values.collect(),
+        new_values.collect(),
+        top_level_path,
+        semantic,
+        &mut differences,
+    );
+    differences
+}
+
+fn diff_positional(
+    old: Vec<&TLVValue>,
+    new: Vec<&TLVValue>,
+    path: impl Fn(usize) -> String,
+    semantic: bool,
+    differences: &mut Vec<Difference>,
+) {
+    for index in 0..old.len().max(new.len()) {
+        match (old.get(index), new.get(index)) {
+            (Some(old), Some(new)) => diff_value(old, new, &path(index), semantic, differences),
+            (Some(old), None) => differences.push(Difference::Removed {
+                path: path(index),
+                value: (*old).clone(),
+            }),
+            (None, Some(new)) => differences.push(Difference::Added {
+                path: path(index),
+                value: (*new).clone(),
+            }),
+            (None, None) => unreachable!("index below the longest side"),
+        }
+    }
+}
+
+fn same_tag(a: &TLVTag, b: &TLVTag, semantic: bool) -> bool {
+    if semantic {
+        a.canonical_cmp(b).is_eq()
+    } else {
+        a == b
+    }
+}
+
+fn diff_value(
+    old: &TLVValue,
+    new: &TLVValue,
+    path: &str,
+    semantic: bool,
+    differences: &mut Vec<Difference>,
+) {
+    match (old, new) {
+        (TLVValue::Structure(old_members), TLVValue::Structure(new_members)) => {
+            for (tag, old_value) in old_members {
+                let member_path = schema::member_path(path, tag);
+                match new_members
+                    .iter()
+                    .find(|(new_tag, _)| same_tag(tag, new_tag, semantic))
+                {
+                    Some((_, new_value)) => {
+                        diff_value(old_value, new_value, &member_path, semantic, differences)
+                    }
+                    None => differences.push(Difference::Removed {
+                        path: member_path,
+                        value: old_value.clone(),
+                    }),
+                }
+            }
+            for (tag, new_value) in new_members {
+                if !old_members
+                    .iter()
+                    .any(|(old_tag, _)| same_tag(tag, old_tag, semantic))
+                {
+                    differences.push(Difference::Added {
+                        path: schema::member_path(path, tag),
+                        value: new_value.clone(),
+                    });
+                }
+            }
+        }
+        (TLVValue::List(old_members), TLVValue::List(new_members)) => {
+            // Members whose tags differ are replaced rather than compared
+            for index in 0..old_members.len().max(new_members.len()) {
+                let member_path = format!("{}[{}]", path, index);
+                match (old_members.get(index), new_members.get(index)) {
+                    (Some((old_tag, old_value)), Some((new_tag, new_value)))
+                        if same_tag(old_tag, new_tag, semantic) =>
+                    {
+                        diff_value(old_value, new_value, &member_path, semantic, differences)
+                    }
+                    (old_member, new_member) => {
+                        if let Some((_, value)) = old_member {
+                            differences.push(Difference::Removed {
+                                path: member_path.clone(),
+                                value: value.clone(),
+                            });
+                        }
+                        if let Some((_, value)) = new_member {
+                            differences.push(Difference::Added {
+                                path: member_path,
+                                value: value.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        (TLVValue::Array(old_members), TLVValue::Array(new_members)) => diff_positional(
+            old_members.iter().collect(),
+            new_members.iter().collect(),
+            |index| format!("{}[{}]", path, index),
+            semantic,
+            differences,
+        ),
+        _ => {
+            // Same variant and semantically equal differ at most by NaN payloads
+            let equal =
+                old.semantic_eq(new) && (semantic || discriminant(old) == discriminant(new));
+            if !equal {
+                differences.push(Difference::Changed {
+                    path: String::from(path),
+                    old: old.clone(),
+                    new: new.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> TLVDocument {
+        TLVDocument::parse(bytes).expect("Failed to parse document")
+    }
+
+    // Structure {0 = 42U, 1 = [true, -3], 2 = List [[Anonymous = "a", 3 = Null]]}
+    const TEST_BYTES: &[u8] = &[
+        0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x00, 0xfd, 0x18, 0x37, 0x02, 0x0c, 0x01, 0x61,
+        0x34, 0x03, 0x18, 0x18,
+    ];
+
+    #[test]
+    fn test_diff() {
+        assert!(diff(&parse(TEST_BYTES), &parse(TEST_BYTES), false).is_empty());
+
+        // Structure {2 = List [[Anonymous = "b"]], 1 = [true, -3, false], 0 = 43U, 4 = Null}
+        let test_bytes = &[
+            0x15, 0x37, 0x02, 0x0c, 0x01, 0x62, 0x18, 0x36, 0x01, 0x09, 0x00, 0xfd, 0x08, 0x18,
+            0x24, 0x00, 0x2b, 0x34, 0x04, 0x18,
+        ];
+        let differences = diff(&parse(TEST_BYTES), &parse(test_bytes), false);
+        let lines: Vec<String> = differences.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "~ 0: 42U -> 43U",
+                "+ 1[2]: false",
+                "~ 2[0]: \"a\" -> \"b\"",
+                "- 2[1]: Null",
+                "+ 4: Null",
+            ]
+        );
+        assert_eq!(differences[3].path(), "2[1]");
+
+        // Structure {0 = 42U} against Array [42U], then two top level elements against one
+        let differences = diff(
+            &parse(&[0x15, 0x24, 0x00, 0x2a, 0x18]),
+            &parse(&[0x16, 0x04, 0x2a, 0x18]),
+            false,
+        );
+        assert_eq!(
+            differences
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            vec!["~ <root>: {0 = 42U} -> [42U]"]
+        );
+        let differences = diff(
+            &parse(&[0x04, 0x01, 0x04, 0x02]),
+            &parse(&[0x04, 0x01]),
+            false,
+        );
+        assert_eq!(
+            differences,
+            vec![Difference::Removed {
+                path: String::from("[1]"),
+                value: TLVValue::UInt8(2)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_semantic() {
+        // Structure {CHIP::1 = 1U (2-octet tag), 0 = 1.5 (Float32)} against
+        // Structure {CHIP::1 = 1U (UInt16, 4-octet tag), 0 = 1.5 (Float64)}
+        let old = parse(&[
+            0x15, 0x44, 0x01, 0x00, 0x01, 0x2a, 0x00, 0x00, 0x00, 0xc0, 0x3f, 0x18,
+        ]);
+        let new = parse(&[
+            0x15, 0x65, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x2b, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xf8, 0x3f, 0x18,
+        ]);
+        assert!(diff(&old, &new, true).is_empty());
+        assert_eq!(
+            diff(&old, &new, false)
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "- CHIP::1: 1U",
+                "~ 0: 1.5 -> 1.5 (width only)",
+                "+ CHIP::1: 1U",
+            ]
+        );
+    }
+}
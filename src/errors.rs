@@ -1,9 +1,282 @@
-#[derive(Debug, PartialEq)]
+use crate::path::TLVPath;
+use crate::tags::{TLVTag, TagControl};
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt;
+
+// New variants may be added in minor releases, matches need a wildcard arm
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
 pub enum TLVError {
     UnderRun,
     EndOfTLV,
+    EndOfContainer,
+    NotInContainer,
+    NothingToRewind,
     InvalidTag,
     InvalidType,
     ParseError,
-    Internal(String),
+    MissingMember(TLVTag),
+    MissingField {
+        tag: u8,
+    },
+    UnknownMember(TLVTag),
+    DuplicateMember(TLVTag),
+    MemberOutOfOrder(TLVTag),
+    ValueOutOfRange,
+    InvalidHexCharacter {
+        character: char,
+        position: usize,
+    },
+    OddHexDigitCount(usize),
+    InvalidBase64Character {
+        character: char,
+        position: usize,
+    },
+    InvalidBase64Length(usize),
+    InvalidBase64Padding,
+    InvalidCbor,
+    DepthLimitExceeded(usize),
+    ElementTooLarge {
+        declared: usize,
+        limit: usize,
+    },
+    TooManyElements {
+        limit: usize,
+    },
+    TrailingBytes {
+        offset: usize,
+    },
+    NonMinimalEncoding {
+        offset: usize,
+    },
+    UnterminatedContainer {
+        offset: usize,
+    },
+    NulCharacter {
+        offset: usize,
+    },
+    NotNfcNormalized {
+        offset: usize,
+    },
+    UnknownElementType {
+        value: u8,
+        offset: usize,
+    },
+    TruncatedTag {
+        tag_control: TagControl,
+        missing: usize,
+    },
+    TruncatedValue {
+        expected: usize,
+        available: usize,
+    },
+    SourceLengthMismatch {
+        declared: u64,
+    },
+    MessageTooLarge {
+        limit: usize,
+    },
+    BufferTooSmall {
+        capacity: usize,
+    },
+    PathNotFound {
+        step: usize,
+    },
+    InvalidPath {
+        position: usize,
+    },
+    InvalidEnumValue {
+        value: u64,
+    },
+    UnknownFlags {
+        value: u64,
+    },
+    MergeConflict(TLVTag),
+    AtPath {
+        path: TLVPath,
+        error: Box<TLVError>,
+    },
+    // Stream of documents, see TLVDocument::documents
+    NeedMoreBytes {
+        document: usize,
+        offset: usize,
+    },
+    InDocument {
+        document: usize,
+        offset: usize,
+        error: Box<TLVError>,
+    },
+    Io(String),
+    Internal {
+        context: &'static str,
+    },
+}
+
+impl fmt::Display for TLVError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TLVError::UnderRun => write!(f, "not enough bytes for the element"),
+            TLVError::EndOfTLV => write!(f, "end of TLV reached"),
+            TLVError::EndOfContainer => write!(f, "end of container reached"),
+            TLVError::NotInContainer => write!(f, "not inside a container"),
+            TLVError::NothingToRewind => write!(f, "no element to rewind to"),
+            TLVError::InvalidTag => write!(f, "invalid tag"),
+            TLVError::InvalidType => write!(f, "invalid element type"),
+            TLVError::ParseError => write!(f, "failed to parse element"),
+            TLVError::MissingMember(tag) => write!(f, "missing structure member {}", tag),
+            TLVError::MissingField { tag } => write!(f, "missing structure field {}", tag),
+            TLVError::UnknownMember(tag) => write!(f, "unknown structure member {}", tag),
+            TLVError::DuplicateMember(tag) => write!(f, "duplicate structure member {}", tag),
+            TLVError::MemberOutOfOrder(tag) => {
+                write!(f, "structure member {} out of canonical order", tag)
+            }
+            TLVError::ValueOutOfRange => write!(f, "value out of range"),
+            TLVError::InvalidHexCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "invalid hex character {:?} at position {}",
+                character, position
+            ),
+            TLVError::OddHexDigitCount(count) => {
+                write!(f, "odd number of hex digits ({})", count)
+            }
+            TLVError::InvalidBase64Character {
+                character,
+                position,
+            } => write!(
+                f,
+                "invalid base64 character {:?} at position {}",
+                character, position
+            ),
+            TLVError::InvalidBase64Length(length) => {
+                write!(f, "invalid base64 length ({})", length)
+            }
+            TLVError::InvalidBase64Padding => write!(f, "invalid base64 padding"),
+            TLVError::InvalidCbor => write!(f, "invalid CBOR"),
+            TLVError::DepthLimitExceeded(limit) => {
+                write!(f, "containers nested deeper than {} levels", limit)
+            }
+            TLVError::ElementTooLarge { declared, limit } => write!(
+                f,
+                "element length {} exceeds the limit of {}",
+                declared, limit
+            ),
+            TLVError::TooManyElements { limit } => {
+                write!(f, "more than {} elements in the payload", limit)
+            }
+            TLVError::TrailingBytes { offset } => {
+                write!(f, "trailing bytes after the element at offset {}", offset)
+            }
+            TLVError::NonMinimalEncoding { offset } => {
+                write!(
+                    f,
+                    "non minimal encoding of the element at offset {}",
+                    offset
+                )
+            }
+            TLVError::UnterminatedContainer { offset } => {
+                write!(f, "container at offset {} is not terminated", offset)
+            }
+            TLVError::NulCharacter { offset } => {
+                write!(f, "NUL character in a string at offset {}", offset)
+            }
+            TLVError::NotNfcNormalized { offset } => {
+                write!(f, "string at offset {} is not NFC normalized", offset)
+            }
+            TLVError::UnknownElementType { value, offset } => write!(
+                f,
+                "reserved element type {:#04x} at offset {}",
+                value, offset
+            ),
+            TLVError::TruncatedTag {
+                tag_control,
+                missing,
+            } => write!(
+                f,
+                "{:?} tag truncated, {} more bytes needed",
+                tag_control, missing
+            ),
+            TLVError::TruncatedValue {
+                expected,
+                available,
+            } => write!(
+                f,
+                "value truncated, {} bytes expected, {} available",
+                expected, available
+            ),
+            TLVError::SourceLengthMismatch { declared } => write!(
+                f,
+                "source length differs from the declared {} octets",
+                declared
+            ),
+            TLVError::MessageTooLarge { limit } => {
+                write!(f, "encoding exceeds the limit of {} bytes", limit)
+            }
+            TLVError::BufferTooSmall { capacity } => {
+                write!(
+                    f,
+                    "encoding exceeds the {} bytes left in the buffer",
+                    capacity
+                )
+            }
+            TLVError::PathNotFound { step } => {
+                write!(f, "no element matches step {} of the path", step)
+            }
+            TLVError::InvalidPath { position } => {
+                write!(f, "malformed path at position {}", position)
+            }
+            TLVError::InvalidEnumValue { value } => write!(f, "unknown enum value {}", value),
+            TLVError::UnknownFlags { value } => {
+                write!(f, "bitmap {:#x} has unknown bits set", value)
+            }
+            TLVError::MergeConflict(tag) => {
+                write!(
+                    f,
+                    "structure member {} differs on both sides of the merge",
+                    tag
+                )
+            }
+            TLVError::AtPath { path, error } => write!(f, "{} at path {}", error, path),
+            TLVError::NeedMoreBytes { document, offset } => {
+                write!(f, "document {} at offset {} is cut short", document, offset)
+            }
+            TLVError::InDocument {
+                document,
+                offset,
+                error,
+            } => write!(f, "document {} at offset {}: {}", document, offset, error),
+            TLVError::Io(message) => write!(f, "I/O error: {}", message),
+            TLVError::Internal { context } => write!(f, "internal error: {}", context),
+        }
+    }
+}
+
+impl TLVError {
+    // The payload ends before the element does, so more bytes may complete it, see
+    // TLVReader::extend_payload. EndOfTLV is left out as it also ends complete payloads.
+    pub fn is_cut_short(&self) -> bool {
+        matches!(
+            self,
+            TLVError::UnderRun
+                | TLVError::TruncatedTag { .. }
+                | TLVError::TruncatedValue { .. }
+                | TLVError::UnterminatedContainer { .. }
+                | TLVError::NeedMoreBytes { .. }
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TLVError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TLVError::AtPath { error, .. } | TLVError::InDocument { error, .. } => {
+                Some(error.as_ref())
+            }
+            _ => None,
+        }
+    }
 }
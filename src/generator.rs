@@ -0,0 +1,358 @@
+// Random payloads conforming to a schema, to fuzz handlers with inputs they have to accept. Values
+// favor the bounds of their range, integers and floats take random widths, open ended lengths are
+// capped by the configuration. Schemas nothing conforms to, e.g. with an empty range, give
+// payloads failing validation.
+
+use crate::schema::TLVSchema;
+use crate::tags::TLVTag;
+use crate::value::TLVValue;
+use crate::writer::TLVEncode;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Bound;
+use rand_core::RngCore;
+
+#[derive(Debug, Copy, Clone)]
+pub struct GeneratorConfig {
+    pub optional_probability: f64, // Of an Optional Structure member being present
+    pub null_probability: f64,     // Of a Nullable value being Null
+    // Above the lower bound, for string lengths and member counts without an upper bound
+    pub max_len: usize,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            optional_probability: 0.5,
+            null_probability: 0.25,
+            max_len: 8,
+        }
+    }
+}
+
+impl GeneratorConfig {
+    pub fn optional_probability(mut self, probability: f64) -> Self {
+        self.optional_probability = probability;
+        self
+    }
+
+    pub fn null_probability(mut self, probability: f64) -> Self {
+        self.null_probability = probability;
+        self
+    }
+
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+}
+
+// Inclusive bounds of a range, empty ranges have the low bound above the high one
+macro_rules! inclusive {
+    (($start:expr, $end:expr), $min:expr, $max:expr) => {
+        (
+            match $start {
+                Bound::Included(low) => *low,
+                Bound::Excluded(low) => low.saturating_add(1),
+                Bound::Unbounded => $min,
+            },
+            match $end {
+                Bound::Included(high) => *high,
+                Bound::Excluded(high) => high.saturating_sub(1),
+                Bound::Unbounded => $max,
+            },
+        )
+    };
+}
+
+// Anonymous top level element
+pub fn generate<R: RngCore + ?Sized>(schema: &TLVSchema, rng: &mut R) -> Vec<u8> {
+    generate_with_config(schema, &GeneratorConfig::default(), rng)
+}
+
+pub fn generate_with_config<R: RngCore + ?Sized>(
+    schema: &TLVSchema,
+    config: &GeneratorConfig,
+    rng: &mut R,
+) -> Vec<u8> {
+    generate_value(schema, config, rng).encode_tlv()
+}
+
+pub fn generate_value<R: RngCore + ?Sized>(
+    schema: &TLVSchema,
+    config: &GeneratorConfig,
+    rng: &mut R,
+) -> TLVValue {
+    match schema {
+        TLVSchema::Any => any_value(rng),
+        TLVSchema::SignedInteger((start, end)) => {
+            let (low, high) = inclusive!((start, end), i64::MIN, i64::MAX);
+            // Offset so the order of signed values is kept by unsigned ones
+            let offset = |value: i64| (value as u64) ^ (1 << 63);
+            let value = (in_range(offset(low), offset(high), rng) ^ (1 << 63)) as i64;
+            int_with_random_width(value, rng)
+        }
+        TLVSchema::UnsignedInteger((start, end)) => {
+            let (low, high) = inclusive!((start, end), 0, u64::MAX);
+            uint_with_random_width(in_range(low, high, rng), rng)
+        }
+        TLVSchema::Boolean => TLVValue::Bool(rng.next_u32() & 1 == 1),
+        TLVSchema::FloatingPointNumber => random_float(rng),
+        TLVSchema::UTF8String(len) => {
+            let len = random_len(len, config, rng);
+            TLVValue::Utf8String(random_string(len, rng))
+        }
+        TLVSchema::ByteString(len) => {
+            let mut bytes = alloc::vec![0; random_len(len, config, rng)];
+            rng.fill_bytes(&mut bytes);
+            TLVValue::ByteString(bytes)
+        }
+        TLVSchema::Null => TLVValue::Null,
+        TLVSchema::Nullable(schema) => {
+            if happens(config.null_probability, rng) {
+                TLVValue::Null
+            } else {
+                generate_value(schema, config, rng)
+            }
+        }
+        TLVSchema::Optional(schema) => generate_value(schema, config, rng),
+        TLVSchema::Structure(members) => TLVValue::Structure(
+            members
+                .iter()
+                .filter_map(|(tag, member)| {
+                    if matches!(member, TLVSchema::Optional(_))
+                        && !happens(config.optional_probability, rng)
+                    {
+                        return None;
+                    }
+                    Some((tag.clone(), generate_value(member, config, rng)))
+                })
+                .collect(),
+        ),
+        TLVSchema::Array(element, len) => {
+            let count = random_len(len, config, rng);
+            TLVValue::Array(
+                (0..count)
+                    .map(|_| generate_value(element, config, rng))
+                    .collect(),
+            )
+        }
+        // List members are anonymous or context tagged
+        TLVSchema::List(element, len) => {
+            let count = random_len(len, config, rng);
+            TLVValue::List(
+                (0..count)
+                    .map(|_| {
+                        let tag = match rng.next_u32() % 3 {
+                            0 => TLVTag::Anonymous,
+                            _ => TLVTag::ContextSpecific(rng.next_u32() as u8),
+                        };
+                        (tag, generate_value(element, config, rng))
+                    })
+                    .collect(),
+            )
+        }
+    }
+}
+
+fn happens<R: RngCore + ?Sized>(probability: f64, rng: &mut R) -> bool {
+    ((rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+}
+
+// Either bound a quarter of the time, low when the range is empty
+fn in_range<R: RngCore + ?Sized>(low: u64, high: u64, rng: &mut R) -> u64 {
+    if high <= low {
+        return low;
+    }
+    match rng.next_u32() % 8 {
+        0 => low,
+        1 => high,
+        _ => match (high - low).checked_add(1) {
+            Some(span) => low + rng.next_u64() % span,
+            None => rng.next_u64(),
+        },
+    }
+}
+
+fn random_len<R: RngCore + ?Sized>(
+    (start, end): &(Bound<usize>, Bound<usize>),
+    config: &GeneratorConfig,
+    rng: &mut R,
+) -> usize {
+    let (low, high) = inclusive!((start, end), 0, usize::MAX);
+    let high = high.min(low.saturating_add(config.max_len));
+    in_range(low as u64, high as u64, rng) as usize
+}
+
+fn int_with_random_width<R: RngCore + ?Sized>(value: i64, rng: &mut R) -> TLVValue {
+    let widths = [
+        i8::try_from(value).ok().map(TLVValue::Int8),
+        i16::try_from(value).ok().map(TLVValue::Int16),
+        i32::try_from(value).ok().map(TLVValue::Int32),
+        Some(TLVValue::Int64(value)),
+    ];
+    pick(widths.into_iter().flatten().collect(), rng)
+}
+
+fn uint_with_random_width<R: RngCore + ?Sized>(value: u64, rng: &mut R) -> TLVValue {
+    let widths = [
+        u8::try_from(value).ok().map(TLVValue::UInt8),
+        u16::try_from(value).ok().map(TLVValue::UInt16),
+        u32::try_from(value).ok().map(TLVValue::UInt32),
+        Some(TLVValue::UInt64(value)),
+    ];
+    pick(widths.into_iter().flatten().collect(), rng)
+}
+
+fn pick<R: RngCore + ?Sized>(mut values: Vec<TLVValue>, rng: &mut R) -> TLVValue {
+    let choice = rng.next_u32() as usize % values.len();
+    values.swap_remove(choice)
+}
+
+// Finite, small numbers as often as any bit pattern
+fn random_float<R: RngCore + ?Sized>(rng: &mut R) -> TLVValue {
+    match rng.next_u32() % 4 {
+        0 => TLVValue::Float32(f32::from_bits(rng.next_u32())),
+        1 => TLVValue::Float64(f64::from_bits(rng.next_u64())),
+        2 => TLVValue::Float32(rng.next_u32() as i16 as f32 / 4.0),
+        _ => TLVValue::Float64(rng.next_u32() as i32 as f64 / 1000.0),
+    }
+}
+
+// Octets of 1 to 4 byte characters, ASCII filling what wider characters leave
+fn random_string<R: RngCore + ?Sized>(len: usize, rng: &mut R) -> String {
+    const CHARACTERS: [char; 6] = ['a', 'Z', '7', 'é', '€', '🦀'];
+    let mut string = String::with_capacity(len);
+    while string.len() < len {
+        let character = CHARACTERS[rng.next_u32() as usize % CHARACTERS.len()];
+        if string.len() + character.len_utf8() <= len {
+            string.push(character);
+        } else {
+            string.push(' ');
+        }
+    }
+    string
+}
+
+fn any_value<R: RngCore + ?Sized>(rng: &mut R) -> TLVValue {
+    match rng.next_u32() % 5 {
+        0 => int_with_random_width(rng.next_u64() as i64, rng),
+        1 => uint_with_random_width(rng.next_u64(), rng),
+        2 => TLVValue::Bool(rng.next_u32() & 1 == 1),
+        3 => random_float(rng),
+        _ => TLVValue::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::validate;
+    use crate::value::TLVDocument;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    fn sensor_schema() -> TLVSchema {
+        TLVSchema::structure([
+            (TLVTag::ContextSpecific(0), TLVSchema::uint(1..=254)),
+            (
+                TLVTag::ContextSpecific(1),
+                TLVSchema::optional(TLVSchema::utf8(..=32)),
+            ),
+            (
+                TLVTag::ContextSpecific(2),
+                TLVSchema::array(TLVSchema::nullable(TLVSchema::int(-100..100)), 1..4),
+            ),
+            (
+                TLVTag::ContextSpecific(3),
+                TLVSchema::optional(TLVSchema::list(
+                    TLVSchema::structure([
+                        (TLVTag::ContextSpecific(0), TLVSchema::bytes(16..=16)),
+                        (TLVTag::ContextSpecific(1), TLVSchema::float()),
+                        (TLVTag::ContextSpecific(2), TLVSchema::boolean()),
+                    ]),
+                    ..,
+                )),
+            ),
+            (TLVTag::ContextSpecific(4), TLVSchema::int(i64::MIN..)),
+            (TLVTag::ContextSpecific(5), TLVSchema::uint(..)),
+            (
+                TLVTag::ContextSpecific(6),
+                TLVSchema::nullable(TLVSchema::any()),
+            ),
+            (TLVTag::ContextSpecific(7), TLVSchema::utf8(3..)),
+            (TLVTag::ContextSpecific(8), TLVSchema::null()),
+        ])
+    }
+
+    #[test]
+    fn test_generate_validates() {
+        let schema = sensor_schema();
+        let mut rng = XorShiftRng::seed_from_u64(7);
+        let mut first_members = Vec::new();
+        for _ in 0..1000 {
+            let payload = generate(&schema, &mut rng);
+            assert_eq!(validate(&payload, &schema), vec![], "{:02x?}", payload);
+            let document = TLVDocument::parse(&payload).expect("Failed to parse");
+            let TLVValue::Structure(members) = &document.elements[0].value else {
+                panic!("Structure is expected")
+            };
+            first_members.push(members[0].1.as_i128());
+        }
+        // Bounds are favored
+        assert!(first_members.contains(&Some(1)));
+        assert!(first_members.contains(&Some(254)));
+        assert!(!first_members.contains(&Some(0)));
+
+        for schema in [
+            TLVSchema::int(-1..0),
+            TLVSchema::int(..=i64::MIN),
+            TLVSchema::uint(u64::MAX..),
+            TLVSchema::bytes(..1),
+            TLVSchema::utf8(1..=1),
+            TLVSchema::array(TLVSchema::array(TLVSchema::any(), ..), 2..),
+        ] {
+            for _ in 0..100 {
+                let payload = generate(&schema, &mut rng);
+                assert_eq!(validate(&payload, &schema), vec![], "{:02x?}", payload);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_with_config() {
+        let schema = TLVSchema::structure([
+            (
+                TLVTag::ContextSpecific(0),
+                TLVSchema::optional(TLVSchema::boolean()),
+            ),
+            (
+                TLVTag::ContextSpecific(1),
+                TLVSchema::nullable(TLVSchema::utf8(2..)),
+            ),
+        ]);
+        let mut rng = XorShiftRng::seed_from_u64(1);
+
+        let never = GeneratorConfig::default()
+            .optional_probability(0.0)
+            .null_probability(1.0);
+        let payload = generate_with_config(&schema, &never, &mut rng);
+        // Structure {1 = Null}
+        assert_eq!(payload, vec![0x15, 0x34, 0x01, 0x18]);
+
+        let always = GeneratorConfig::default()
+            .optional_probability(1.0)
+            .null_probability(0.0)
+            .max_len(0);
+        for _ in 0..100 {
+            let payload = generate_with_config(&schema, &always, &mut rng);
+            assert_eq!(validate(&payload, &schema), vec![]);
+            let value = generate_value(&schema, &always, &mut rng);
+            let TLVValue::Structure(members) = value else {
+                panic!("Structure is expected")
+            };
+            assert_eq!(members.len(), 2);
+            assert!(matches!(&members[1].1, TLVValue::Utf8String(s) if s.len() == 2));
+        }
+    }
+}
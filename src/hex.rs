@@ -0,0 +1,185 @@
+use crate::errors::TLVError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+const HEX_PREFIX: &str = "hex:";
+const HEX_SEPARATOR: char = ':';
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum HexFormat {
+    Plain,          // 1518
+    ColonSeparated, // 15:18
+    Prefixed,       // hex:1518
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    to_hex_with_format(bytes, HexFormat::Plain)
+}
+
+pub fn to_hex_with_format(bytes: &[u8], format: HexFormat) -> String {
+    let octets: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    match format {
+        HexFormat::Plain => octets.concat(),
+        HexFormat::ColonSeparated => octets.join(&HEX_SEPARATOR.to_string()),
+        HexFormat::Prefixed => format!("{}{}", HEX_PREFIX, octets.concat()),
+    }
+}
+
+// Accepts any of the HexFormat outputs, upper or lower case, ignoring whitespace and line breaks
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, TLVError> {
+    let leading_whitespace = hex.len() - hex.trim_start().len();
+    let digits_start = if hex[leading_whitespace..].starts_with(HEX_PREFIX) {
+        leading_whitespace + HEX_PREFIX.len()
+    } else {
+        leading_whitespace
+    };
+
+    let mut bytes = Vec::with_capacity(hex.len() / 2);
+    let mut high_nibble: Option<u8> = None;
+    for (position, character) in hex.char_indices().skip_while(|(i, _)| *i < digits_start) {
+        if character.is_whitespace() || character == HEX_SEPARATOR {
+            continue;
+        }
+        let nibble = character
+            .to_digit(16)
+            .ok_or(TLVError::InvalidHexCharacter {
+                character,
+                position,
+            })? as u8;
+        match high_nibble.take() {
+            Some(high) => bytes.push(high << 4 | nibble),
+            None => high_nibble = Some(nibble),
+        }
+    }
+
+    if high_nibble.is_some() {
+        return Err(TLVError::OddHexDigitCount(bytes.len() * 2 + 1));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::TLVReader;
+    use crate::tags::TLVTag;
+    use crate::writer::TLVWriter;
+
+    #[test]
+    fn test_to_hex() {
+        let test_input = &[0x15, 0x24, 0x01, 0x2a, 0x18];
+        assert_eq!(to_hex(test_input), "1524012a18");
+        assert_eq!(
+            to_hex_with_format(test_input, HexFormat::ColonSeparated),
+            "15:24:01:2a:18"
+        );
+        assert_eq!(
+            to_hex_with_format(test_input, HexFormat::Prefixed),
+            "hex:1524012a18"
+        );
+        assert_eq!(to_hex(&[]), "");
+        assert_eq!(to_hex_with_format(&[], HexFormat::ColonSeparated), "");
+    }
+
+    #[test]
+    fn test_from_hex() {
+        let test_output = vec![0x15, 0x24, 0x01, 0x2a, 0x18];
+        assert_eq!(from_hex("1524012a18").unwrap(), test_output);
+        assert_eq!(from_hex("1524012A18").unwrap(), test_output);
+        assert_eq!(from_hex("15:24:01:2a:18").unwrap(), test_output);
+        assert_eq!(from_hex("hex:1524012a18").unwrap(), test_output);
+        assert_eq!(
+            from_hex("  hex:15 24 01\n2a\r\n\t18\n").unwrap(),
+            test_output
+        );
+        assert_eq!(from_hex("").unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_from_hex_invalid() {
+        assert_eq!(
+            from_hex("15240g2a18").expect_err("Invalid hex character is expected"),
+            TLVError::InvalidHexCharacter {
+                character: 'g',
+                position: 5
+            }
+        );
+        // Position is relative to the original input, prefix included
+        assert_eq!(
+            from_hex("hex:15-24").expect_err("Invalid hex character is expected"),
+            TLVError::InvalidHexCharacter {
+                character: '-',
+                position: 6
+            }
+        );
+        // Prefix is only recognized at the start
+        assert_eq!(
+            from_hex("15hex:24").expect_err("Invalid hex character is expected"),
+            TLVError::InvalidHexCharacter {
+                character: 'h',
+                position: 2
+            }
+        );
+        assert_eq!(
+            from_hex("15:24:0").expect_err("Odd digit count is expected"),
+            TLVError::OddHexDigitCount(5)
+        );
+        assert_eq!(
+            TLVError::InvalidHexCharacter {
+                character: 'g',
+                position: 5
+            }
+            .to_string(),
+            "invalid hex character 'g' at position 5"
+        );
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        // Spec vectors: Unsigned Integer, 8-octet, value 40000000000
+        // + UTF-8 String, 1-octet length, "Tschüs"
+        // + Fully qualified tag 65521::57069:2857762541 = 42U
+        let vectors: [&[u8]; 3] = [
+            &[0x07, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00],
+            &[0x0c, 0x07, 0x54, 0x73, 0x63, 0x68, 0xc3, 0xbc, 0x73],
+            &[0xe4, 0xf1, 0xff, 0xed, 0xde, 0xed, 0xfe, 0x55, 0xaa, 0x2a],
+        ];
+        for vector in vectors {
+            for format in [
+                HexFormat::Plain,
+                HexFormat::ColonSeparated,
+                HexFormat::Prefixed,
+            ] {
+                let hex = to_hex_with_format(vector, format);
+                assert_eq!(from_hex(&hex).expect("Failed to parse hex"), vector);
+            }
+        }
+    }
+
+    #[test]
+    fn test_writer_reader_hex() {
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put(TLVTag::ContextSpecific(1), &42u8)
+            .expect("Failed to write element");
+        assert_eq!(tlv_writer.to_hex_string(), "24012a");
+
+        let tlv_reader =
+            TLVReader::from_hex(&tlv_writer.to_hex_string()).expect("Failed to parse hex");
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::ContextSpecific(1)
+        );
+        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+
+        let tlv_reader =
+            TLVReader::from_hex("hex:0c:06:48:65:6c:6c:6f:21").expect("Failed to parse hex");
+        assert_eq!(
+            tlv_reader
+                .read_char_str()
+                .expect("Failed to read character string"),
+            "Hello!"
+        );
+    }
+}
@@ -0,0 +1,265 @@
+// Hexdump annotated element by element: offset, the raw control byte, tag, length and value
+// groups, then what they decode to. Meant for finding where a payload a peer rejects goes wrong,
+// so everything decoded before an error is kept.
+
+use crate::errors::TLVError;
+use crate::hex;
+use crate::reader::TLVReader;
+use crate::tags::TLVTag;
+use crate::types::{ContainerType, PrimitiveLengthType, TLVType};
+use crate::value::TLVValueRef;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+const HEX_COLUMN_WIDTH: usize = 30;
+const INDENT: &str = "  ";
+// Longer values only show their first bytes followed by the count of elided ones
+const ELIDED_VALUE_LEN: usize = 16;
+
+pub fn annotated_hexdump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    if let Err(e) = write_annotated_hexdump(bytes, &mut out) {
+        let _ = writeln!(out, "error: {}", e);
+    }
+    out
+}
+
+// Appends the lines of every element decoded before an error to out
+pub fn write_annotated_hexdump(bytes: &[u8], out: &mut String) -> Result<(), TLVError> {
+    if bytes.is_empty() {
+        return Ok(());
+    }
+    let mut tlv_reader = TLVReader::new(bytes);
+    let mut end = 0; // Past the last dumped element, where an End of Container would be
+    loop {
+        let mut at_end = match tlv_reader.read_tag() {
+            Ok(tag) => {
+                let offset = tlv_reader.offset();
+                let depth = tlv_reader.container_depth();
+                let header_end = offset + 1 + tag.octets_count() as usize;
+                let control_and_tag = [
+                    hex::to_hex(&bytes[offset..offset + 1]),
+                    hex::to_hex(&bytes[offset + 1..header_end]),
+                ];
+                match tlv_reader.read_type()? {
+                    TLVType::Container(container_type) => {
+                        let (name, open, _) = brackets(container_type);
+                        let description = format!("{}{} {}", tag_label(&tag), name, open);
+                        write_line(out, offset, depth, &control_and_tag, &description);
+                        tlv_reader.enter_container()?;
+                        end = header_end;
+                        continue; // Cursor on the first member or at the end of an empty container
+                    }
+                    TLVType::Primitive(primitive) => {
+                        let value = tlv_reader.read_value_ref()?;
+                        let value_start = match primitive {
+                            PrimitiveLengthType::Specified(specified) => {
+                                header_end + specified.length_field_size() as usize
+                            }
+                            PrimitiveLengthType::Predetermined(_) => header_end,
+                        };
+                        end = value_start + value_len(primitive, &value);
+                        let groups = [
+                            control_and_tag[0].clone(),
+                            control_and_tag[1].clone(),
+                            hex::to_hex(&bytes[header_end..value_start]),
+                            value_hex(&bytes[value_start..end]),
+                        ];
+                        let description = format!("{}{}", tag_label(&tag), describe(&value));
+                        write_line(out, offset, depth, &groups, &description);
+                        false
+                    }
+                }
+            }
+            Err(TLVError::EndOfContainer) => true,
+            Err(e) => return Err(e),
+        };
+        // Dumps the End of Container of every container ending here, then moves to the sibling
+        loop {
+            if at_end {
+                let container_type = tlv_reader
+                    .container_type()
+                    .ok_or(TLVError::EndOfContainer)?;
+                tlv_reader.exit_container()?;
+                let (_, _, close) = brackets(container_type);
+                let description = format!("End of Container {}", close);
+                let control = [hex::to_hex(&bytes[end..end + 1])];
+                write_line(
+                    out,
+                    end,
+                    tlv_reader.container_depth(),
+                    &control,
+                    &description,
+                );
+                end += 1;
+            }
+            match tlv_reader.next() {
+                Ok(()) => break,
+                Err(TLVError::EndOfContainer) => at_end = true,
+                Err(TLVError::EndOfTLV) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn write_line(out: &mut String, offset: usize, depth: usize, groups: &[String], description: &str) {
+    let groups: Vec<&str> = groups
+        .iter()
+        .map(String::as_str)
+        .filter(|group| !group.is_empty())
+        .collect();
+    let hex_column = format!("{}{}", INDENT.repeat(depth), groups.join(" "));
+    let _ = writeln!(
+        out,
+        "{:04x}  {:<width$}  {}",
+        offset,
+        hex_column,
+        description,
+        width = HEX_COLUMN_WIDTH
+    );
+}
+
+fn value_hex(value: &[u8]) -> String {
+    if value.len() <= ELIDED_VALUE_LEN {
+        return hex::to_hex(value);
+    }
+    format!(
+        "{}.. (+{} bytes)",
+        hex::to_hex(&value[..ELIDED_VALUE_LEN]),
+        value.len() - ELIDED_VALUE_LEN
+    )
+}
+
+fn value_len(primitive: PrimitiveLengthType, value: &TLVValueRef) -> usize {
+    match (primitive, value) {
+        (_, TLVValueRef::Utf8String(value)) => value.len(),
+        (_, TLVValueRef::ByteString(value)) => value.len(),
+        (PrimitiveLengthType::Predetermined(predetermined), _) => {
+            predetermined.value_octets_count()
+        }
+        (PrimitiveLengthType::Specified(_), _) => 0,
+    }
+}
+
+fn tag_label(tag: &TLVTag) -> String {
+    match tag {
+        TLVTag::Anonymous => String::new(),
+        TLVTag::ContextSpecific(tag_number) => format!("ctx tag {}, ", tag_number),
+        tag => format!("tag {}, ", tag),
+    }
+}
+
+fn brackets(container_type: ContainerType) -> (&'static str, &'static str, &'static str) {
+    match container_type {
+        ContainerType::Structure => ("Structure", "{", "}"),
+        ContainerType::Array => ("Array", "[", "]"),
+        ContainerType::List => ("List", "[[", "]]"),
+    }
+}
+
+fn describe(value: &TLVValueRef) -> String {
+    match value {
+        TLVValueRef::Int8(value) => format!("Int8 = {}", value),
+        TLVValueRef::Int16(value) => format!("Int16 = {}", value),
+        TLVValueRef::Int32(value) => format!("Int32 = {}", value),
+        TLVValueRef::Int64(value) => format!("Int64 = {}", value),
+        TLVValueRef::UInt8(value) => format!("UInt8 = {}", value),
+        TLVValueRef::UInt16(value) => format!("UInt16 = {}", value),
+        TLVValueRef::UInt32(value) => format!("UInt32 = {}", value),
+        TLVValueRef::UInt64(value) => format!("UInt64 = {}", value),
+        TLVValueRef::Bool(value) => format!("Boolean = {}", value),
+        TLVValueRef::Float32(value) => format!("Float32 = {}", value),
+        TLVValueRef::Float64(value) => format!("Float64 = {}", value),
+        TLVValueRef::Null => String::from("Null"),
+        TLVValueRef::Utf8String(value) if value.len() <= ELIDED_VALUE_LEN => {
+            format!("UTF8String, {} bytes = {:?}", value.len(), value)
+        }
+        TLVValueRef::Utf8String(value) => {
+            let mut prefix_len = ELIDED_VALUE_LEN;
+            while !value.is_char_boundary(prefix_len) {
+                prefix_len -= 1;
+            }
+            format!(
+                "UTF8String, {} bytes = {:?}..",
+                value.len(),
+                &value[..prefix_len]
+            )
+        }
+        TLVValueRef::ByteString(value) => format!("ByteString, {} bytes", value.len()),
+        TLVValueRef::Structure(_) | TLVValueRef::Array(_) | TLVValueRef::List(_) => {
+            unreachable!("containers are entered, not read")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotated_hexdump() {
+        // Structure {0 = 42U, 1 = [true, "Hi"], 2 = List [[]], 3 = Null}
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x0c, 0x02, 0x48, 0x69, 0x18, 0x37, 0x02,
+            0x18, 0x34, 0x03, 0x18,
+        ];
+        assert_eq!(
+            annotated_hexdump(test_bytes),
+            "0000  15                              Structure {\n\
+             0001    24 00 2a                      ctx tag 0, UInt8 = 42\n\
+             0004    36 01                         ctx tag 1, Array [\n\
+             0006      09                          Boolean = true\n\
+             0007      0c 02 4869                  UTF8String, 2 bytes = \"Hi\"\n\
+             000b    18                            End of Container ]\n\
+             000c    37 02                         ctx tag 2, List [[\n\
+             000e    18                            End of Container ]]\n\
+             000f    34 03                         ctx tag 3, Null\n\
+             0011  18                              End of Container }\n"
+        );
+    }
+
+    #[test]
+    fn test_annotated_hexdump_spec_examples() {
+        // Signed Integer, 4-octet value, Matter common profile tag 1, 42
+        assert_eq!(
+            annotated_hexdump(&[0x42, 0x01, 0x00, 0x2a, 0x00, 0x00, 0x00]),
+            "0000  42 0100 2a000000                tag CHIP::1, Int32 = 42\n"
+        );
+        // Signed Integer, 1-octet value, fully qualified tag 65521::57069:1, 42
+        assert_eq!(
+            annotated_hexdump(&[0xc0, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x2a]),
+            "0000  c0 f1ffedde0100 2a              tag 65521::57069:1, Int8 = 42\n"
+        );
+        // Single precision floating point 1.5, then Byte String, 2-octet length, 20 bytes
+        let mut test_bytes = vec![0x0a, 0x00, 0x00, 0xc0, 0x3f, 0x11, 0x14, 0x00];
+        test_bytes.extend(0..20);
+        assert_eq!(
+            annotated_hexdump(&test_bytes),
+            "0000  0a 0000c03f                     Float32 = 1.5\n\
+             0005  11 1400 000102030405060708090a0b0c0d0e0f.. (+4 bytes)  ByteString, 20 bytes\n"
+        );
+        // UTF-8 String longer than the elision threshold
+        let mut test_bytes = vec![0x0c, 0x14];
+        test_bytes.extend(b"abcdefghijklmnopqrst");
+        assert_eq!(
+            annotated_hexdump(&test_bytes),
+            "0000  0c 14 6162636465666768696a6b6c6d6e6f70.. (+4 bytes)  \
+             UTF8String, 20 bytes = \"abcdefghijklmnop\"..\n"
+        );
+    }
+
+    #[test]
+    fn test_annotated_hexdump_error() {
+        // Structure {0 = 42, 1 = UInt16 missing its second value byte
+        assert_eq!(
+            annotated_hexdump(&[0x15, 0x20, 0x00, 0x2a, 0x25, 0x01, 0xff]),
+            "0000  15                              Structure {\n\
+             0001    20 00 2a                      ctx tag 0, Int8 = 42\n\
+             error: value truncated, 2 bytes expected, 1 available\n"
+        );
+        assert_eq!(annotated_hexdump(&[]), "");
+    }
+}
@@ -0,0 +1,380 @@
+// Push parser for payloads arriving in chunks of any size. Bytes of elements already reported are
+// dropped, only the element being assembled is buffered, a primitive is reported once all its
+// octets arrived.
+
+use crate::errors::TLVError;
+use crate::reader::{TLVReader, DEFAULT_MAX_ELEMENT_LEN};
+use crate::tags::{self, ControlByte, TLVTag, TagControl};
+use crate::types::{self, ContainerType, ElementType, PrimitiveLengthType, TLVType};
+use crate::value::TLVValue;
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event {
+    // Header of a primitive, its Value follows once the len octets of the value arrived
+    ElementStart {
+        tag: TLVTag,
+        tlv_type: TLVType,
+        len: usize,
+    },
+    Value(TLVValue),
+    ContainerStart {
+        tag: TLVTag,
+        container_type: ContainerType,
+    },
+    ContainerEnd,
+}
+
+#[derive(Debug)]
+pub struct IncrementalTLVParser {
+    buffer: Vec<u8>,
+    start: usize,                   // Buffered bytes already reported
+    dropped: usize,                 // Payload bytes reported and dropped before the buffer
+    containers: Vec<ContainerType>, // Started and not yet ended containers
+    pending_len: Option<usize>,     // Encoded size of the primitive whose ElementStart was reported
+    max_element_len: usize,         // Declared length of strings, checked before buffering them
+}
+
+impl IncrementalTLVParser {
+    pub fn new() -> Self {
+        Self::with_max_element_len(DEFAULT_MAX_ELEMENT_LEN)
+    }
+
+    pub fn with_max_element_len(max_element_len: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            start: 0,
+            dropped: 0,
+            containers: Vec::new(),
+            pending_len: None,
+            max_element_len,
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.buffer.drain(..self.start);
+        self.dropped += self.start;
+        self.start = 0;
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    // Next event, None until more bytes are fed
+    pub fn poll_element(&mut self) -> Result<Option<Event>, TLVError> {
+        let available = &self.buffer[self.start..];
+        if let Some(element_len) = self.pending_len {
+            if available.len() < element_len {
+                return Ok(None);
+            }
+            let value = TLVReader::new(&available[..element_len]).read_value()?;
+            self.pending_len = None;
+            self.start += element_len;
+            return Ok(Some(Event::Value(value)));
+        }
+
+        let control_byte = match available.first() {
+            Some(control_byte) => ControlByte::from(*control_byte),
+            None => return Ok(None),
+        };
+        if control_byte.element_type == ElementType::EndOfContainer as u8 {
+            if control_byte.tag_control != 0 {
+                return Err(TLVError::InvalidTag);
+            }
+            self.containers.pop().ok_or(TLVError::EndOfContainer)?;
+            self.start += 1;
+            return Ok(Some(Event::ContainerEnd));
+        }
+        let tag_control_byte = control_byte.tag_control << tags::CONTROL_BYTE_SHIFT;
+        let tag_len = TagControl::try_from(tag_control_byte)?.octets_count() as usize;
+        if types::is_reserved_element_type(control_byte.element_type) {
+            return Err(TLVError::UnknownElementType {
+                value: control_byte.element_type,
+                offset: self.dropped + self.start,
+            });
+        }
+        let tlv_type = TLVType::try_from(ElementType::try_from(control_byte.element_type)?)?;
+        if available.len() < 1 + tag_len {
+            return Ok(None);
+        }
+        let (remaining_bytes, tag) = tags::parse_tag(tag_control_byte, &available[1..])?;
+        if self.containers.last() == Some(&ContainerType::Array) && tag != TLVTag::Anonymous {
+            return Err(TLVError::InvalidTag);
+        }
+
+        let (header_len, len) = match tlv_type {
+            TLVType::Container(container_type) => {
+                self.containers.push(container_type);
+                self.start += 1 + tag_len;
+                return Ok(Some(Event::ContainerStart {
+                    tag,
+                    container_type,
+                }));
+            }
+            TLVType::Primitive(PrimitiveLengthType::Predetermined(predetermined)) => {
+                (1 + tag_len, predetermined.value_octets_count())
+            }
+            TLVType::Primitive(PrimitiveLengthType::Specified(specified)) => {
+                let field_size = specified.length_field_size();
+                if remaining_bytes.len() < field_size as usize {
+                    return Ok(None);
+                }
+                let (_, len) = field_size.parse_field_size(remaining_bytes)?;
+                if len > self.max_element_len {
+                    return Err(TLVError::ElementTooLarge {
+                        declared: len,
+                        limit: self.max_element_len,
+                    });
+                }
+                (1 + tag_len + field_size as usize, len)
+            }
+        };
+        self.pending_len = Some(header_len.checked_add(len).ok_or(TLVError::UnderRun)?);
+        Ok(Some(Event::ElementStart { tag, tlv_type, len }))
+    }
+
+    pub fn container_depth(&self) -> usize {
+        self.containers.len()
+    }
+
+    // Once polling returns None, fails with UnderRun if the input stopped inside an element or
+    // a container
+    pub fn finish(&self) -> Result<(), TLVError> {
+        if self.start < self.buffer.len() || !self.containers.is_empty() {
+            Err(TLVError::UnderRun)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for IncrementalTLVParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PredeterminedLenPrimitive, UnsignedInteger};
+    use crate::value::{TLVDocument, TLVElement};
+
+    fn poll_all(parser: &mut IncrementalTLVParser, events: &mut Vec<Event>) {
+        while let Some(event) = parser.poll_element().expect("Failed to poll element") {
+            events.push(event);
+        }
+    }
+
+    fn whole_buffer_events(bytes: &[u8]) -> Vec<Event> {
+        let mut parser = IncrementalTLVParser::new();
+        let mut events = Vec::new();
+        parser.feed(bytes);
+        poll_all(&mut parser, &mut events);
+        parser.finish().expect("Failed to finish");
+        events
+    }
+
+    fn byte_by_byte_events(bytes: &[u8]) -> Vec<Event> {
+        let mut parser = IncrementalTLVParser::new();
+        let mut events = Vec::new();
+        for byte in bytes {
+            parser.feed(&[*byte]);
+            poll_all(&mut parser, &mut events);
+            // Never more than the element being assembled
+            assert!(parser.buffer.len() - parser.start <= 16);
+        }
+        parser.finish().expect("Failed to finish");
+        events
+    }
+
+    // Rebuilds the top level elements from the event stream
+    fn document_from(events: &[Event]) -> TLVDocument {
+        let mut stack = vec![(TLVTag::Anonymous, ContainerType::List, Vec::new())];
+        let mut pending_tag = None;
+        for event in events {
+            let element = match event.clone() {
+                Event::ElementStart { tag, .. } => {
+                    pending_tag = Some(tag);
+                    continue;
+                }
+                Event::Value(value) => (pending_tag.take().expect("Value without start"), value),
+                Event::ContainerStart {
+                    tag,
+                    container_type,
+                } => {
+                    stack.push((tag, container_type, Vec::new()));
+                    continue;
+                }
+                Event::ContainerEnd => {
+                    let (tag, container_type, members) = stack.pop().expect("Stray end");
+                    let value = match container_type {
+                        ContainerType::Structure => TLVValue::Structure(members),
+                        ContainerType::List => TLVValue::List(members),
+                        ContainerType::Array => {
+                            TLVValue::Array(members.into_iter().map(|(_, value)| value).collect())
+                        }
+                    };
+                    (tag, value)
+                }
+            };
+            stack.last_mut().expect("Stray end").2.push(element);
+        }
+        let elements = stack.pop().expect("Missing top level").2;
+        elements.into_iter().map(TLVElement::from).collect()
+    }
+
+    #[test]
+    fn test_feed_byte_by_byte() {
+        let test_vectors: &[&[u8]] = &[
+            // Unsigned Integer, 8-octet, value 40000000000
+            &[0x07, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00],
+            // Fully qualified tag, Vendor ID 0xFFF1/65521, profile number 0xDEED/57069,
+            // 4-octet tag 0xAA55FEED/2857762541,
+            // Unsigned Integer, 1-octet value 42, 65521::57069:2857762541 = 42U
+            &[0xe4, 0xf1, 0xff, 0xed, 0xde, 0xed, 0xfe, 0x55, 0xaa, 0x2a],
+            // Common profile tag 100000, Unsigned Integer, 1-octet value, CHIP::100000 = 42U
+            &[0x64, 0xa0, 0x86, 0x01, 0x00, 0x2a],
+            // Double precision floating point negative infinity (-∞)
+            &[0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0xff],
+            // Boolean true + Null
+            &[0x09, 0x14],
+            // UTF-8 String, 1-octet length, "Tschüs"
+            &[0x0c, 0x07, 0x54, 0x73, 0x63, 0x68, 0xc3, 0xbc, 0x73],
+            // Octet String, 1-octet length specifying 5 octets 00 01 02 03 04
+            &[0x10, 0x05, 0x00, 0x01, 0x02, 0x03, 0x04],
+            // Octet String, 2-octet length specifying 0 octets
+            &[0x11, 0x00, 0x00],
+            // Structure, two context specific tags, Signed Integer, 1 octet values, {0 = 42, 1 = -17}
+            // + Boolean true
+            &[0x15, 0x20, 0x00, 0x2a, 0x20, 0x01, 0xef, 0x18, 0x09],
+            // Array, mix of element types, [42, -170000, {}, 17.9, "Hello!"]
+            &[
+                0x16, 0x00, 0x2a, 0x02, 0xf0, 0x67, 0xfd, 0xff, 0x15, 0x18, 0x0a, 0x33, 0x33, 0x8f,
+                0x41, 0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21, 0x18,
+            ],
+            // Structure {0 = 42U, 1 = [true, -3], 2 = List [[Anonymous = "a", 3 = Null]]}, 1.5
+            &[
+                0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x00, 0xfd, 0x18, 0x37, 0x02, 0x0c, 0x01,
+                0x61, 0x34, 0x03, 0x18, 0x18, 0x0a, 0x00, 0x00, 0xc0, 0x3f,
+            ],
+            // Empty payload
+            &[],
+        ];
+        for test_bytes in test_vectors {
+            let events = whole_buffer_events(test_bytes);
+            assert_eq!(byte_by_byte_events(test_bytes), events);
+            assert_eq!(
+                document_from(&events),
+                TLVDocument::parse(test_bytes).expect("Failed to parse document")
+            );
+        }
+    }
+
+    #[test]
+    fn test_poll_element() {
+        // Structure {1 = 42U}, split inside the tag and before the End of Container
+        let mut parser = IncrementalTLVParser::new();
+        parser.feed(&[0x15, 0x24]);
+        assert_eq!(
+            parser.poll_element().expect("Failed to poll element"),
+            Some(Event::ContainerStart {
+                tag: TLVTag::Anonymous,
+                container_type: ContainerType::Structure
+            })
+        );
+        assert_eq!(parser.poll_element().expect("Failed to poll element"), None);
+        parser.feed(&[0x01]);
+        assert_eq!(
+            parser.poll_element().expect("Failed to poll element"),
+            Some(Event::ElementStart {
+                tag: TLVTag::ContextSpecific(1),
+                tlv_type: TLVType::Primitive(PrimitiveLengthType::Predetermined(
+                    PredeterminedLenPrimitive::UnsignedInteger(UnsignedInteger::UInt8)
+                )),
+                len: 1
+            })
+        );
+        assert_eq!(parser.poll_element().expect("Failed to poll element"), None);
+        parser.feed(&[0x2a]);
+        assert_eq!(
+            parser.poll_element().expect("Failed to poll element"),
+            Some(Event::Value(TLVValue::UInt8(42)))
+        );
+        assert_eq!(parser.container_depth(), 1);
+        assert_eq!(
+            parser.finish().expect_err("Under run is expected"),
+            TLVError::UnderRun
+        );
+        parser.feed(&[0x18]);
+        assert_eq!(
+            parser.poll_element().expect("Failed to poll element"),
+            Some(Event::ContainerEnd)
+        );
+        assert_eq!(parser.container_depth(), 0);
+        parser.finish().expect("Failed to finish");
+    }
+
+    #[test]
+    fn test_poll_element_malformed() {
+        // Stray End of Container
+        let mut parser = IncrementalTLVParser::new();
+        parser.feed(&[0x18]);
+        assert_eq!(
+            parser
+                .poll_element()
+                .expect_err("End of container is expected"),
+            TLVError::EndOfContainer
+        );
+
+        // Structure {0 = 1U, reserved element type 0x1f}, in two chunks
+        let mut parser = IncrementalTLVParser::new();
+        parser.feed(&[0x15, 0x24, 0x00, 0x01]);
+        for _ in 0..3 {
+            parser.poll_element().expect("Failed to poll element");
+        }
+        parser.feed(&[0x3f, 0x01, 0x18]);
+        assert_eq!(
+            parser
+                .poll_element()
+                .expect_err("Unknown element type is expected"),
+            TLVError::UnknownElementType {
+                value: 0x1f,
+                offset: 4
+            }
+        );
+
+        // Array [0 = 1U]
+        let mut parser = IncrementalTLVParser::new();
+        parser.feed(&[0x16, 0x24, 0x00, 0x01, 0x18]);
+        parser.poll_element().expect("Failed to poll element");
+        assert_eq!(
+            parser.poll_element().expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+
+        // Octet String, 4-octet length of 0x01000000, past the limit before any value octet arrived
+        let mut parser = IncrementalTLVParser::new();
+        parser.feed(&[0x12, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(
+            parser
+                .poll_element()
+                .expect_err("Element too large is expected"),
+            TLVError::ElementTooLarge {
+                declared: 0x01000000,
+                limit: DEFAULT_MAX_ELEMENT_LEN
+            }
+        );
+        let mut parser = IncrementalTLVParser::with_max_element_len(usize::MAX);
+        parser.feed(&[0x12, 0x00, 0x00, 0x00, 0x01]);
+        parser.poll_element().expect("Failed to poll element");
+
+        // UTF-8 String, 1-octet length, "Hi" cut short
+        let mut parser = IncrementalTLVParser::new();
+        parser.feed(&[0x0c, 0x02, 0x48]);
+        parser.poll_element().expect("Failed to poll element");
+        assert_eq!(parser.poll_element().expect("Failed to poll element"), None);
+        assert_eq!(
+            parser.finish().expect_err("Under run is expected"),
+            TLVError::UnderRun
+        );
+    }
+}
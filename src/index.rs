@@ -0,0 +1,309 @@
+// Index of the elements a TLVPath can reach, to look up many paths in a large payload without
+// walking it for each. The payload is walked once, then each step of a lookup is a binary search,
+// whatever the size of the payload. Lookups find what TLVReader::descend does: the first member
+// with the tag in a Structure or List, the member at the index in an Array or List.
+//
+// Paths start from the first top level element, only its subtree is indexed. Each element takes
+// its offset, its length and at most two entries, its tag and its index in the container, packed
+// into integers.
+
+use crate::errors::TLVError;
+use crate::path::{PathStep, TLVPath};
+use crate::reader::{TLVReader, TLVReaderConfig};
+use crate::tags::TLVTag;
+use crate::types::ContainerType;
+use crate::value::TLVValueRef;
+use crate::visitor::{self, TLVVisitor};
+use alloc::vec::Vec;
+use bytes::Bytes;
+use core::ops::{ControlFlow, Range};
+
+// Step kind above the canonical tag key, whose kind ranks stay below it
+const INDEX_STEP: u128 = 0xff;
+
+#[derive(Debug, Clone)]
+pub struct TLVIndex {
+    bytes: Bytes,
+    elements: Vec<(u32, u32)>, // Offset and length, by element number in walk order
+    members: Vec<(u128, u32)>, // Container element number and step, sorted, to member number
+}
+
+// Container element number in the upper bits, the step in the lower 72
+fn member_key(container: u32, step: u128) -> u128 {
+    (container as u128) << 72 | step
+}
+
+fn tag_step(tag: &TLVTag) -> u128 {
+    let (kind, vendor_id, profile_number, tag_number) = tag.canonical_key();
+    (kind as u128) << 64
+        | (vendor_id as u128) << 48
+        | (profile_number as u128) << 32
+        | tag_number as u128
+}
+
+fn index_step(index: usize) -> u128 {
+    INDEX_STEP << 64 | index as u128
+}
+
+impl TLVIndex {
+    pub fn build(bytes: &[u8]) -> Result<Self, TLVError> {
+        Self::build_with_config(bytes, &TLVReaderConfig::default())
+    }
+
+    // Fails as walking the first top level element with the configuration does, or with
+    // ElementTooLarge for payloads of 4 GiB or more
+    pub fn build_with_config(bytes: &[u8], config: &TLVReaderConfig) -> Result<Self, TLVError> {
+        if u32::try_from(bytes.len()).is_err() {
+            return Err(TLVError::ElementTooLarge {
+                declared: bytes.len(),
+                limit: u32::MAX as usize,
+            });
+        }
+        let mut indexer = Indexer {
+            start: bytes.as_ptr() as usize,
+            elements: Vec::new(),
+            members: Vec::new(),
+            containers: Vec::new(),
+            last: 0,
+        };
+        let _ = visitor::walk_with_config(bytes, config, &mut indexer)?;
+        // Stable, so the first member with a tag comes first and is kept
+        indexer.members.sort_by_key(|(key, _)| *key);
+        indexer.members.dedup_by_key(|(key, _)| *key);
+        indexer.members.shrink_to_fit();
+        Ok(Self {
+            bytes: Bytes::copy_from_slice(bytes),
+            elements: indexer.elements,
+            members: indexer.members,
+        })
+    }
+
+    // Elements indexed
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    // Bytes of the element of the path with its whole subtree, None where descend would fail
+    pub fn lookup(&self, path: &TLVPath) -> Option<Range<usize>> {
+        if self.elements.is_empty() {
+            return None;
+        }
+        let mut element = 0;
+        for step in path.steps() {
+            let step = match step {
+                PathStep::ContextTag(tag_number) => tag_step(&TLVTag::ContextSpecific(*tag_number)),
+                PathStep::ProfileTag(tag) => tag_step(tag),
+                PathStep::Index(index) => index_step(*index),
+            };
+            let key = member_key(element, step);
+            let position = self
+                .members
+                .binary_search_by_key(&key, |(key, _)| *key)
+                .ok()?;
+            element = self.members[position].1;
+        }
+        let (offset, len) = self.elements[element as usize];
+        Some(offset as usize..(offset + len) as usize)
+    }
+
+    // Standalone reader over the element of the path, as TLVReader::fork gives
+    pub fn reader_at(&self, path: &TLVPath) -> Option<TLVReader> {
+        let range = self.lookup(path)?;
+        Some(TLVReader::from_bytes(self.bytes.slice(range)))
+    }
+}
+
+struct Indexer {
+    start: usize, // Address of the payload, element offsets are taken from their slices
+    elements: Vec<(u32, u32)>,
+    members: Vec<(u128, u32)>,
+    containers: Vec<(u32, ContainerType, usize)>, // Element numbers, types and members seen
+    last: u32,                                    // Element the next element_bytes call is about
+}
+
+impl Indexer {
+    // Numbers the element and records how its container reaches it
+    fn add(&mut self, tag: &TLVTag) -> u32 {
+        let element = self.elements.len() as u32;
+        self.elements.push((0, 0));
+        if let Some((container, container_type, members)) = self.containers.last_mut() {
+            if *container_type != ContainerType::Structure {
+                let key = member_key(*container, index_step(*members));
+                self.members.push((key, element));
+            }
+            if *container_type != ContainerType::Array && *tag != TLVTag::Anonymous {
+                self.members
+                    .push((member_key(*container, tag_step(tag)), element));
+            }
+            *members += 1;
+        }
+        element
+    }
+}
+
+impl TLVVisitor for Indexer {
+    fn primitive(&mut self, tag: &TLVTag, _value: TLVValueRef<'_>) -> ControlFlow<()> {
+        self.last = self.add(tag);
+        ControlFlow::Continue(())
+    }
+
+    fn enter_container(&mut self, tag: &TLVTag, container_type: ContainerType) -> ControlFlow<()> {
+        let element = self.add(tag);
+        self.containers.push((element, container_type, 0));
+        ControlFlow::Continue(())
+    }
+
+    fn exit_container(&mut self, _container_type: ContainerType) -> ControlFlow<()> {
+        if let Some((container, _, _)) = self.containers.pop() {
+            self.last = container;
+        }
+        ControlFlow::Continue(())
+    }
+
+    // Stops once the first top level element is done
+    fn element_bytes(&mut self, element: &[u8]) -> ControlFlow<()> {
+        let offset = element.as_ptr() as usize - self.start;
+        self.elements[self.last as usize] = (offset as u32, element.len() as u32);
+        if self.containers.is_empty() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path;
+    use crate::tags::{CommonProfileLength, FullyQualifiedProfileLength};
+    use crate::value::{TLVDocument, TLVValue};
+
+    // Paths to each member of the value, by tag and by index, and some reaching nothing
+    fn member_paths(value: &TLVValue, path: &TLVPath, paths: &mut Vec<TLVPath>) {
+        paths.push(path.clone());
+        paths.push(path.clone().index(value.len()));
+        paths.push(path.clone().tag(200));
+        for (index, (tag, member)) in value.into_iter().enumerate() {
+            member_paths(member, &path.clone().index(index), paths);
+            match tag {
+                TLVTag::Anonymous => {}
+                TLVTag::ContextSpecific(tag_number) => {
+                    member_paths(member, &path.clone().tag(*tag_number), paths)
+                }
+                tag => member_paths(member, &path.clone().profile_tag(tag.clone()), paths),
+            }
+        }
+    }
+
+    fn assert_agrees_with_descend(bytes: &[u8]) -> usize {
+        let index = TLVIndex::build(bytes).expect("Failed to build index");
+        let document = TLVDocument::parse(bytes).expect("Failed to parse");
+        let mut paths = Vec::new();
+        member_paths(&document.elements[0].value, &TLVPath::root(), &mut paths);
+        for path in &paths {
+            assert_eq!(
+                index.lookup(path),
+                path::element_range(bytes, path).ok(),
+                "{}",
+                path
+            );
+        }
+        paths.len()
+    }
+
+    #[test]
+    fn test_index_lookup() {
+        // Structure {0 = 42U, 1 = [true, "Hi"], 2 = List [[3 = Null, CHIP::1 = 7U, 3 = 1U, 4U]],
+        // 0 = {}, CHIP::65536 = [[]], 65521::57069:1 = -1}, true
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x0c, 0x02, 0x48, 0x69, 0x18, 0x37, 0x02,
+            0x34, 0x03, 0x44, 0x01, 0x00, 0x07, 0x24, 0x03, 0x01, 0x04, 0x04, 0x18, 0x35, 0x00,
+            0x18, 0x76, 0x00, 0x00, 0x01, 0x00, 0x16, 0x18, 0x18, 0xc0, 0xf1, 0xff, 0xed, 0xde,
+            0x01, 0x00, 0xff, 0x18, 0x09,
+        ];
+        assert_agrees_with_descend(test_bytes);
+        let index = TLVIndex::build(test_bytes).expect("Failed to build index");
+        assert_eq!(index.len(), 14);
+
+        // First member with a tag, profile tags whatever their width
+        assert_eq!(index.lookup(&TLVPath::root().tag(0)), Some(1..4));
+        assert_eq!(index.lookup(&TLVPath::root().tag(2).tag(3)), Some(14..16));
+        assert_eq!(index.lookup(&TLVPath::root().tag(2).index(2)), Some(20..23));
+        let chip_1 = TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 1 });
+        assert_eq!(
+            index.lookup(&TLVPath::root().tag(2).profile_tag(chip_1)),
+            Some(16..20)
+        );
+        let vendor_tag = TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+            vendor_id: 0xfff1,
+            profile_number: 0xdeed,
+            tag_number: 1,
+        });
+        assert_eq!(
+            index.lookup(&TLVPath::root().profile_tag(vendor_tag)),
+            Some(37..45)
+        );
+        // Indices in a Structure and tags in an Array reach nothing, as with descend
+        assert_eq!(index.lookup(&TLVPath::root().index(0)), None);
+        assert_eq!(index.lookup(&TLVPath::root().tag(1).tag(0)), None);
+        assert_eq!(index.lookup(&TLVPath::root().tag(0).tag(0)), None);
+
+        let mut tlv_reader = index
+            .reader_at(&"1[1]".parse().expect("Failed to parse path"))
+            .expect("Element is expected");
+        assert_eq!(tlv_reader.take_str(), Ok("Hi"));
+        assert_eq!(
+            tlv_reader.next().expect_err("Sequence End is expected"),
+            TLVError::EndOfTLV
+        );
+        assert!(index.reader_at(&TLVPath::root().tag(9)).is_none());
+
+        // Only the first top level element is walked
+        let index = TLVIndex::build(&[0x04, 0x01, 0x1f]).expect("Failed to build index");
+        assert_eq!(index.lookup(&TLVPath::root()), Some(0..2));
+        assert!(TLVIndex::build(&[])
+            .expect("Failed to build index")
+            .is_empty());
+        assert_eq!(
+            TLVIndex::build(&[])
+                .expect("Failed to build index")
+                .lookup(&TLVPath::root()),
+            None
+        );
+        // Structure {0 = 42U cut short
+        assert_eq!(
+            TLVIndex::build(&test_bytes[..4]).expect_err("Unterminated container is expected"),
+            TLVError::UnterminatedContainer { offset: 0 }
+        );
+    }
+
+    #[test]
+    fn test_index_large_payload() {
+        // [{0 = i, 1 = "sensor", 2 = [i as Int16, -1], 3 = List [4 = true, false]} for i in 0..100]
+        let test_bytes = TLVDocument::builder()
+            .array(TLVTag::Anonymous, |a| {
+                (0..100).fold(a, |a, i| {
+                    a.structure(|s| {
+                        s.uint(0, i)
+                            .string(1, "sensor")
+                            .array(2, |a| a.value(&(i as i16)).int(-1))
+                            .list(3, |l| l.bool(4, true).bool(TLVTag::Anonymous, false))
+                    })
+                })
+            })
+            .into_bytes()
+            .expect("Failed to build payload");
+        assert!(assert_agrees_with_descend(&test_bytes) > 2000);
+        let index = TLVIndex::build(&test_bytes).expect("Failed to build index");
+        assert_eq!(index.len(), 1 + 100 * 9);
+        let mut tlv_reader = index
+            .reader_at(&"[99]/2[0]".parse().expect("Failed to parse path"))
+            .expect("Element is expected");
+        assert_eq!(tlv_reader.take_i16(), Ok(99));
+    }
+}
@@ -0,0 +1,276 @@
+// Conversion between JSON and TLV values for tooling editing payloads as JSON. Objects map to
+// Structures keyed by tag notation (1, CHIP::1, Implicit::1, 65521::57069:1), arrays to Arrays,
+// other values to the matching primitives. Octet Strings are strings carrying a prefix, "hex:0001"
+// or "base64:AAE=", strings without it are UTF-8 Strings. Lists have no JSON counterpart.
+
+use crate::base64;
+use crate::errors::TLVError;
+use crate::hex::{self, HexFormat};
+use crate::tags::TLVTag;
+use crate::types::{SignedInteger, UnsignedInteger};
+use crate::value::TLVValue;
+use crate::writer::TLVWriter;
+use serde_json::{Map, Number, Value};
+
+const HEX_PREFIX: &str = "hex:";
+const BASE64_PREFIX: &str = "base64:";
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum JsonByteStrings {
+    Hex,    // hex:0001
+    Base64, // base64:AAE=
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct JsonEncodeOptions {
+    // Numbers written with a fraction or an exponent, e.g. 2.0, become integers when whole
+    pub whole_floats_as_integers: bool,
+    // Prefix of Octet Strings, which cannot be converted when None
+    pub byte_strings: Option<JsonByteStrings>,
+}
+
+impl Default for JsonEncodeOptions {
+    fn default() -> Self {
+        Self {
+            whole_floats_as_integers: false,
+            byte_strings: Some(JsonByteStrings::Hex),
+        }
+    }
+}
+
+impl JsonEncodeOptions {
+    pub fn whole_floats_as_integers(mut self, whole_floats_as_integers: bool) -> Self {
+        self.whole_floats_as_integers = whole_floats_as_integers;
+        self
+    }
+
+    pub fn byte_strings(mut self, byte_strings: Option<JsonByteStrings>) -> Self {
+        self.byte_strings = byte_strings;
+        self
+    }
+}
+
+impl TLVWriter {
+    // The document is converted before anything is written, nothing is written when it fails
+    pub fn put_json(
+        &mut self,
+        tag: TLVTag,
+        value: &Value,
+        options: &JsonEncodeOptions,
+    ) -> Result<(), TLVError> {
+        self.put_value(tag, &json_to_value(value, options)?)
+    }
+}
+
+// Integers take the narrowest width, other numbers are doubles
+pub fn json_to_value(value: &Value, options: &JsonEncodeOptions) -> Result<TLVValue, TLVError> {
+    Ok(match value {
+        Value::Null => TLVValue::Null,
+        Value::Bool(value) => TLVValue::Bool(*value),
+        Value::Number(number) => number_to_value(number, options)?,
+        Value::String(string) => match decode_byte_string(string, options)? {
+            Some(bytes) => TLVValue::ByteString(bytes),
+            None => TLVValue::Utf8String(string.clone()),
+        },
+        Value::Array(values) => TLVValue::Array(
+            values
+                .iter()
+                .map(|value| json_to_value(value, options))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(members) => TLVValue::Structure(
+            members
+                .iter()
+                .map(|(key, value)| Ok((key.parse()?, json_to_value(value, options)?)))
+                .collect::<Result<_, TLVError>>()?,
+        ),
+    })
+}
+
+fn number_to_value(number: &Number, options: &JsonEncodeOptions) -> Result<TLVValue, TLVError> {
+    if let Some(value) = number.as_u64() {
+        return Ok(uint_value(value));
+    }
+    if let Some(value) = number.as_i64() {
+        return Ok(int_value(value));
+    }
+    let value = number.as_f64().ok_or(TLVError::ValueOutOfRange)?;
+    if options.whole_floats_as_integers && value.fract() == 0.0 {
+        if value >= 0.0 && value <= u64::MAX as f64 {
+            return Ok(uint_value(value as u64));
+        }
+        if value >= i64::MIN as f64 {
+            return Ok(int_value(value as i64));
+        }
+    }
+    Ok(TLVValue::Float64(value))
+}
+
+fn uint_value(value: u64) -> TLVValue {
+    match UnsignedInteger::minimal_for(value) {
+        UnsignedInteger::UInt8 => TLVValue::UInt8(value as u8),
+        UnsignedInteger::UInt16 => TLVValue::UInt16(value as u16),
+        UnsignedInteger::UInt32 => TLVValue::UInt32(value as u32),
+        UnsignedInteger::UInt64 => TLVValue::UInt64(value),
+    }
+}
+
+fn int_value(value: i64) -> TLVValue {
+    match SignedInteger::minimal_for(value) {
+        SignedInteger::Int8 => TLVValue::Int8(value as i8),
+        SignedInteger::Int16 => TLVValue::Int16(value as i16),
+        SignedInteger::Int32 => TLVValue::Int32(value as i32),
+        SignedInteger::Int64 => TLVValue::Int64(value),
+    }
+}
+
+// Octets of a string carrying the Octet String prefix, None for UTF-8 Strings
+fn decode_byte_string(
+    string: &str,
+    options: &JsonEncodeOptions,
+) -> Result<Option<Vec<u8>>, TLVError> {
+    match options.byte_strings {
+        Some(JsonByteStrings::Hex) if string.starts_with(HEX_PREFIX) => {
+            hex::from_hex(string).map(Some)
+        }
+        Some(JsonByteStrings::Base64) => match string.strip_prefix(BASE64_PREFIX) {
+            Some(base64) => base64::decode_base64(base64).map(Some),
+            None => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+// Structure members are keyed by tag notation, floats which are not finite have no JSON number
+pub fn value_to_json(value: &TLVValue, options: &JsonEncodeOptions) -> Result<Value, TLVError> {
+    if let Some(integer) = value.as_i128() {
+        return Ok(match i64::try_from(integer) {
+            Ok(integer) => Value::from(integer),
+            Err(_) => Value::from(integer as u64),
+        });
+    }
+    if let Some(float) = value.as_f64() {
+        return Number::from_f64(float)
+            .map(Value::Number)
+            .ok_or(TLVError::ValueOutOfRange);
+    }
+    Ok(match value {
+        TLVValue::Bool(value) => Value::Bool(*value),
+        TLVValue::Null => Value::Null,
+        TLVValue::Utf8String(string) => Value::String(string.clone()),
+        TLVValue::ByteString(bytes) => Value::String(match options.byte_strings {
+            Some(JsonByteStrings::Hex) => hex::to_hex_with_format(bytes, HexFormat::Prefixed),
+            Some(JsonByteStrings::Base64) => {
+                format!("{}{}", BASE64_PREFIX, base64::encode_base64(bytes))
+            }
+            None => return Err(TLVError::InvalidType),
+        }),
+        TLVValue::Structure(members) => Value::Object(
+            members
+                .iter()
+                .map(|(tag, value)| Ok((tag.to_string(), value_to_json(value, options)?)))
+                .collect::<Result<Map<_, _>, TLVError>>()?,
+        ),
+        TLVValue::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| value_to_json(value, options))
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => return Err(TLVError::InvalidType),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::TLVReader;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_round_trip() {
+        let document = json!({
+            "0": 42,
+            "1": -170000,
+            "2": 17.5,
+            "3": [true, null, "Hello!"],
+            "4": {"CHIP::1": "hex:0001", "65521::57069:1": []},
+            "Implicit::100000": 18446744073709551615u64
+        });
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_json(TLVTag::Anonymous, &document, &JsonEncodeOptions::default())
+            .expect("Failed to write JSON");
+        // Structure {0 = 42U, 1 = -170000, 2 = 17.5 as double, 3 = [true, Null, "Hello!"],
+        // 4 = {CHIP::1 = 00 01, 65521::57069:1 = []}, Implicit::100000 = 2^64 - 1}
+        assert_eq!(
+            tlv_writer.bytes(),
+            [
+                0x15, 0x24, 0x00, 0x2a, 0x22, 0x01, 0xf0, 0x67, 0xfd, 0xff, 0x2b, 0x02, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x80, 0x31, 0x40, 0x36, 0x03, 0x09, 0x14, 0x0c, 0x06, 0x48, 0x65,
+                0x6c, 0x6c, 0x6f, 0x21, 0x18, 0x35, 0x04, 0x50, 0x01, 0x00, 0x02, 0x00, 0x01, 0xd6,
+                0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x18, 0x18, 0xa7, 0xa0, 0x86, 0x01, 0x00, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x18
+            ]
+        );
+        let value = TLVReader::new(tlv_writer.bytes())
+            .read_value()
+            .expect("Failed to read value");
+        assert_eq!(
+            value_to_json(&value, &JsonEncodeOptions::default()).expect("Failed to convert"),
+            document
+        );
+    }
+
+    #[test]
+    fn test_json_options() {
+        let options = JsonEncodeOptions::default()
+            .whole_floats_as_integers(true)
+            .byte_strings(Some(JsonByteStrings::Base64));
+        assert_eq!(
+            json_to_value(
+                &json!([2.0, -3e2, 2.5, "base64:AAE=", "hex:0001"]),
+                &options
+            )
+            .expect("Failed to convert"),
+            TLVValue::Array(vec![
+                TLVValue::UInt8(2),
+                TLVValue::Int16(-300),
+                TLVValue::Float64(2.5),
+                TLVValue::ByteString(vec![0x00, 0x01]),
+                TLVValue::Utf8String(String::from("hex:0001")),
+            ])
+        );
+        assert_eq!(
+            json_to_value(
+                &json!("hex:0001"),
+                &JsonEncodeOptions::default().byte_strings(None)
+            )
+            .expect("Failed to convert"),
+            TLVValue::Utf8String(String::from("hex:0001"))
+        );
+        assert_eq!(
+            json_to_value(&json!(2.0), &JsonEncodeOptions::default()).expect("Failed to convert"),
+            TLVValue::Float64(2.0)
+        );
+
+        // Keys which are not tags, nothing written
+        let mut tlv_writer = TLVWriter::new();
+        assert_eq!(
+            tlv_writer
+                .put_json(
+                    TLVTag::Anonymous,
+                    &json!({"0": 1, "name": 2}),
+                    &JsonEncodeOptions::default()
+                )
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        assert!(tlv_writer.is_empty());
+        assert_eq!(
+            value_to_json(&TLVValue::Float64(f64::NAN), &options)
+                .expect_err("Value out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+    }
+}
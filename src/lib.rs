@@ -1,7 +1,71 @@
+// Without the std feature the crate is no_std and only needs an allocator. Reading does not
+// allocate once the reader is built: navigation, scalar reads, read_tag, read_type and the borrowing
+// read_str and read_byte_slice work on the reader's buffer, only entering containers grows its stack.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("the alloc feature is required, enable it or std");
+
+extern crate alloc;
+
+// Lets code generated by the derive macros refer to ::tlv from within the crate
+extern crate self as tlv;
+
+// Compiled out unless the log feature is enabled
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($($arg:tt)*) => { log::trace!($($arg)*) };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {{
+        let _ = format_args!($($arg)*);
+    }};
+}
+
 pub mod reader;
+pub mod recovery;
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(test)]
+mod spec_vectors;
+pub mod stats;
+#[cfg(feature = "proptest")]
+pub mod strategies;
 pub mod writer;
 
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "base64")]
+pub mod base64;
+pub mod builder;
+pub mod canonical;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod codegen;
+pub mod diff;
 pub mod errors;
+#[cfg(feature = "rand")]
+pub mod generator;
+pub mod hex;
+pub mod hexdump;
+pub mod incremental;
+pub mod index;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod matter_types;
+pub mod patch;
+pub mod path;
+pub mod subset;
 pub mod tags;
+#[cfg(any(feature = "testing", test))]
+pub mod testing;
 pub mod types;
-mod util;
+#[cfg(feature = "unicode")]
+pub mod unicode;
+pub mod validation;
+pub mod value;
+pub mod visitor;
+pub mod wire;
@@ -0,0 +1,1108 @@
+// Decodes a TLV payload and prints its element tree with offsets, or compares two payloads.
+// Exit codes: 0 on success, 1 when the payload cannot be decoded, 2 on invalid arguments or input,
+// 3 when the compared payloads differ.
+
+use std::fmt::Write as _;
+use std::io::Read;
+use std::ops::Range;
+use std::process::ExitCode;
+use tlv::codegen;
+use tlv::diff::{self, Difference};
+use tlv::errors::TLVError;
+use tlv::hex;
+use tlv::hexdump;
+use tlv::path::TLVPath;
+use tlv::reader::{self, TLVReader};
+use tlv::tags::TLVTag;
+use tlv::types::{
+    ContainerType, FloatingPoint, PredeterminedLenPrimitive, PrimitiveLengthType, SignedInteger,
+    SpecifiedLenPrimitive, TLVType, UnsignedInteger,
+};
+use tlv::value::TLVDocument;
+
+const USAGE: &str = "Usage: tlv [OPTIONS] [INPUT]
+       tlv diff [DIFF OPTIONS] OLD NEW
+       tlv codegen [INPUT OPTIONS] [--path PATH] [INPUT]
+
+Decodes a TLV payload and prints its element tree.
+INPUT is the encoded payload, read from stdin when omitted or '-'.
+
+Options:
+  --hex              Input is hex, the default
+  --base64           Input is base64
+  --raw              Input is raw bytes, from --file or stdin only
+  --file PATH        Read the input from PATH
+  --strict           Enforce structure and array member tagging rules
+  --max-depth N      Collapse containers nested deeper than N
+  --json             Print the tree as JSON
+  --annotate         Print an annotated hexdump, one element per line
+  --recover          Print the elements still decoding in a corrupted payload,
+                     resuming after each error
+  --path PATH        Only decode the element at PATH, e.g. 0/2[3]/1 for tag 1 in
+                     the member at index 3 of the element tagged 2 in tag 0
+  -h, --help         Print this help
+
+Diff lists the members added (+), removed (-) and changed (~) from OLD to NEW.
+OLD and NEW are encoded payloads, '-' for stdin, or --file PATH in their place.
+
+Diff options:
+  --hex, --base64, --raw  Format of both inputs, hex by default
+  --file PATH             Read the next input from PATH
+  --semantic              Ignore differences in integer, float and tag widths
+
+Codegen prints the calls of a TLVWriter named w writing INPUT, or its element at
+--path, byte for byte. Its input options are --hex, --base64, --raw and --file.
+";
+
+const EXIT_DECODE_FAILURE: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_DIFFERENCES: u8 = 3;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum InputFormat {
+    Hex,
+    Base64,
+    Raw,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    format: InputFormat,
+    input: Option<String>,
+    file: Option<String>,
+    strict: bool,
+    max_depth: Option<usize>,
+    json: bool,
+    annotate: bool,
+    recover: bool,
+    path: Option<TLVPath>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Source {
+    Input(String),
+    File(String),
+}
+
+#[derive(Debug, PartialEq)]
+struct DiffOptions {
+    format: InputFormat,
+    sources: Vec<Source>, // Old then new
+    semantic: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Help,
+    Decode(Options),
+    Diff(DiffOptions),
+    Codegen(Options), // Input options and path only
+}
+
+fn parse_diff_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut options = DiffOptions {
+        format: InputFormat::Hex,
+        sources: Vec::new(),
+        semantic: false,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--hex" => options.format = InputFormat::Hex,
+            "--base64" => options.format = InputFormat::Base64,
+            "--raw" => options.format = InputFormat::Raw,
+            "--semantic" => options.semantic = true,
+            "--file" => {
+                let path = args.next().ok_or("--file requires a path")?;
+                options.sources.push(Source::File(path));
+            }
+            option if option.starts_with("--") => {
+                return Err(format!("unknown option '{}'", option));
+            }
+            _ => options.sources.push(Source::Input(arg)),
+        }
+    }
+
+    if options.sources.len() != 2 {
+        return Err(String::from("diff requires two inputs"));
+    }
+    let stdin_inputs = options
+        .sources
+        .iter()
+        .filter(|source| **source == Source::Input(String::from("-")))
+        .count();
+    if stdin_inputs > 1 {
+        return Err(String::from("only one input can come from stdin"));
+    }
+    if options.format == InputFormat::Raw
+        && options
+            .sources
+            .iter()
+            .any(|source| matches!(source, Source::Input(input) if input != "-"))
+    {
+        return Err(String::from("raw input must come from --file or stdin"));
+    }
+    if cfg!(not(feature = "base64")) && options.format == InputFormat::Base64 {
+        return Err(String::from("base64 input requires the base64 feature"));
+    }
+    Ok(Command::Diff(options))
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options {
+        format: InputFormat::Hex,
+        input: None,
+        file: None,
+        strict: false,
+        max_depth: None,
+        json: false,
+        annotate: false,
+        recover: false,
+        path: None,
+    };
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(String::as_str) == Some("diff") {
+        args.next();
+        return parse_diff_args(args);
+    }
+    let codegen = args.peek().map(String::as_str) == Some("codegen");
+    if codegen {
+        args.next();
+    }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--hex" => options.format = InputFormat::Hex,
+            "--base64" => options.format = InputFormat::Base64,
+            "--raw" => options.format = InputFormat::Raw,
+            "--strict" => options.strict = true,
+            "--json" => options.json = true,
+            "--annotate" => options.annotate = true,
+            "--recover" => options.recover = true,
+            "--file" => {
+                options.file = Some(args.next().ok_or("--file requires a path")?);
+            }
+            "--path" => {
+                let path = args.next().ok_or("--path requires a path")?;
+                options.path = Some(
+                    path.parse()
+                        .map_err(|e| format!("invalid --path value '{}': {}", path, e))?,
+                );
+            }
+            "--max-depth" => {
+                let depth = args.next().ok_or("--max-depth requires a number")?;
+                options.max_depth = Some(
+                    depth
+                        .parse()
+                        .map_err(|_| format!("invalid --max-depth value '{}'", depth))?,
+                );
+            }
+            option if option.starts_with("--") => {
+                return Err(format!("unknown option '{}'", option));
+            }
+            _ if options.input.is_some() => {
+                return Err(format!("unexpected argument '{}'", arg));
+            }
+            _ => options.input = Some(arg),
+        }
+    }
+
+    if codegen
+        && (options.strict
+            || options.max_depth.is_some()
+            || options.json
+            || options.annotate
+            || options.recover)
+    {
+        return Err(String::from("codegen only takes input options and --path"));
+    }
+    if options.json && options.annotate {
+        return Err(String::from("--json and --annotate are mutually exclusive"));
+    }
+    if options.recover
+        && (options.strict || options.max_depth.is_some() || options.json || options.annotate)
+    {
+        return Err(String::from(
+            "--recover only takes input options and --path",
+        ));
+    }
+    if options.file.is_some() && options.input.is_some() {
+        return Err(String::from("INPUT and --file are mutually exclusive"));
+    }
+    if options.format == InputFormat::Raw && options.input.as_deref().unwrap_or("-") != "-" {
+        return Err(String::from("raw input must come from --file or stdin"));
+    }
+    if cfg!(not(feature = "base64")) && options.format == InputFormat::Base64 {
+        return Err(String::from("base64 input requires the base64 feature"));
+    }
+    if codegen {
+        return Ok(Command::Codegen(options));
+    }
+    Ok(Command::Decode(options))
+}
+
+fn read_input(
+    file: Option<&String>,
+    input: Option<&str>,
+    format: InputFormat,
+) -> Result<Vec<u8>, String> {
+    let input = match (file, input) {
+        (Some(path), _) => {
+            std::fs::read(path).map_err(|e| format!("cannot read '{}': {}", path, e))?
+        }
+        (None, Some(input)) if input != "-" => input.as_bytes().to_vec(),
+        _ => {
+            let mut input = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut input)
+                .map_err(|e| format!("cannot read stdin: {}", e))?;
+            input
+        }
+    };
+    if format != InputFormat::Raw && std::str::from_utf8(&input).is_err() {
+        return Err(String::from(
+            "input is not text, use --raw for binary payloads",
+        ));
+    }
+    Ok(input)
+}
+
+fn decode_input(input: &[u8], format: InputFormat) -> Result<Vec<u8>, TLVError> {
+    let text = String::from_utf8_lossy(input);
+    match format {
+        InputFormat::Hex => hex::from_hex(&text),
+        #[cfg(feature = "base64")]
+        InputFormat::Base64 => tlv::base64::decode_base64(&text),
+        #[cfg(not(feature = "base64"))]
+        InputFormat::Base64 => unreachable!("rejected while parsing arguments"),
+        InputFormat::Raw => Ok(input.to_vec()),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    Int(i64, &'static str),
+    UInt(u64, &'static str),
+    Float(f64, &'static str),
+    Bool(bool),
+    Null,
+    Utf8(String),
+    Bytes(Vec<u8>),
+    Container(ContainerType, Option<Vec<Node>>), // No members when collapsed
+}
+
+#[derive(Debug, PartialEq)]
+struct Node {
+    offset: usize,
+    tag: TLVTag,
+    value: Value,
+}
+
+fn read_primitive(
+    tlv_reader: &TLVReader,
+    primitive: PrimitiveLengthType,
+) -> Result<Value, TLVError> {
+    Ok(match primitive {
+        PrimitiveLengthType::Predetermined(predetermined) => match predetermined {
+            PredeterminedLenPrimitive::SignedInteger(width) => Value::Int(
+                tlv_reader.read_int()?,
+                match width {
+                    SignedInteger::Int8 => "Int8",
+                    SignedInteger::Int16 => "Int16",
+                    SignedInteger::Int32 => "Int32",
+                    SignedInteger::Int64 => "Int64",
+                },
+            ),
+            PredeterminedLenPrimitive::UnsignedInteger(width) => Value::UInt(
+                tlv_reader.read_uint()?,
+                match width {
+                    UnsignedInteger::UInt8 => "UInt8",
+                    UnsignedInteger::UInt16 => "UInt16",
+                    UnsignedInteger::UInt32 => "UInt32",
+                    UnsignedInteger::UInt64 => "UInt64",
+                },
+            ),
+            PredeterminedLenPrimitive::FloatingPointNumber(
+                FloatingPoint::FloatingPointNumber32,
+            ) => Value::Float(tlv_reader.read_f32()? as f64, "Float32"),
+            PredeterminedLenPrimitive::FloatingPointNumber(
+                FloatingPoint::FloatingPointNumber64,
+            ) => Value::Float(tlv_reader.read_f64()?, "Float64"),
+            PredeterminedLenPrimitive::Boolean(value) => Value::Bool(value),
+            PredeterminedLenPrimitive::Null => Value::Null,
+        },
+        PrimitiveLengthType::Specified(SpecifiedLenPrimitive::UTF8String(_)) => {
+            Value::Utf8(tlv_reader.read_char_str()?)
+        }
+        PrimitiveLengthType::Specified(SpecifiedLenPrimitive::ByteString(_)) => {
+            Value::Bytes(tlv_reader.read_byte_str()?)
+        }
+    })
+}
+
+// Structure members carry unique tags, Array members are anonymous, List members are either and
+// may repeat tags
+fn check_member_tag(
+    container: Option<ContainerType>,
+    tag: &TLVTag,
+    seen_tags: &mut Vec<TLVTag>,
+) -> Result<(), TLVError> {
+    match container {
+        Some(ContainerType::Structure) => {
+            if *tag == TLVTag::Anonymous {
+                return Err(TLVError::InvalidTag);
+            }
+            if seen_tags.contains(tag) {
+                return Err(TLVError::DuplicateMember(tag.clone()));
+            }
+            seen_tags.push(tag.clone());
+        }
+        Some(ContainerType::Array) if *tag != TLVTag::Anonymous => {
+            return Err(TLVError::InvalidTag)
+        }
+        Some(ContainerType::Array) | Some(ContainerType::List) | None => {}
+    }
+    Ok(())
+}
+
+// Reads the elements at the cursor into nodes, which keep everything decoded before an error
+fn read_elements(
+    tlv_reader: &mut TLVReader,
+    container: Option<ContainerType>,
+    options: &Options,
+    nodes: &mut Vec<Node>,
+) -> Result<(), TLVError> {
+    let end = match container {
+        Some(_) => TLVError::EndOfContainer,
+        None => TLVError::EndOfTLV,
+    };
+    let mut seen_tags = Vec::new();
+    loop {
+        let tag = match tlv_reader.read_tag() {
+            Err(e) if e == end => break,
+            result => result?,
+        };
+        if options.strict {
+            check_member_tag(container, &tag, &mut seen_tags)?;
+        }
+        let value = match tlv_reader.read_type()? {
+            TLVType::Container(container_type) => {
+                let collapsed = options
+                    .max_depth
+                    .is_some_and(|max_depth| tlv_reader.container_depth() >= max_depth);
+                Value::Container(container_type, (!collapsed).then(Vec::new))
+            }
+            TLVType::Primitive(primitive) => read_primitive(tlv_reader, primitive)?,
+        };
+        nodes.push(Node {
+            offset: tlv_reader.offset(),
+            tag,
+            value,
+        });
+        if let Some(Node {
+            value: Value::Container(container_type, Some(members)),
+            ..
+        }) = nodes.last_mut()
+        {
+            tlv_reader.enter_container()?;
+            read_elements(tlv_reader, Some(*container_type), options, members)?;
+            tlv_reader.exit_container()?;
+        }
+        match tlv_reader.next() {
+            Err(e) if e == end => break,
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+fn brackets(container_type: ContainerType) -> (&'static str, &'static str, &'static str) {
+    match container_type {
+        ContainerType::Structure => ("Structure", "{", "}"),
+        ContainerType::Array => ("Array", "[", "]"),
+        ContainerType::List => ("List", "[[", "]]"),
+    }
+}
+
+fn render_text(nodes: &[Node], depth: usize, out: &mut String) {
+    for node in nodes {
+        let indent = "  ".repeat(depth);
+        let label = match node.tag {
+            TLVTag::Anonymous => String::new(),
+            ref tag => format!("{}: ", tag),
+        };
+        let _ = write!(out, "{:04x}  {}{}", node.offset, indent, label);
+        let _ = match &node.value {
+            Value::Int(value, name) => writeln!(out, "{} {}", name, value),
+            Value::UInt(value, name) => writeln!(out, "{} {}", name, value),
+            Value::Float(value, name) => writeln!(out, "{} {}", name, value),
+            Value::Bool(value) => writeln!(out, "Boolean {}", value),
+            Value::Null => writeln!(out, "Null"),
+            Value::Utf8(value) => writeln!(out, "UTF8String {:?}", value),
+            Value::Bytes(value) => writeln!(out, "ByteString {}", hex::to_hex(value)),
+            Value::Container(container_type, members) => {
+                let (name, open, close) = brackets(*container_type);
+                match members {
+                    None => writeln!(out, "{} {} ... {}", name, open, close),
+                    Some(members) if members.is_empty() => {
+                        writeln!(out, "{} {}{}", name, open, close)
+                    }
+                    Some(members) => {
+                        let _ = writeln!(out, "{} {}", name, open);
+                        render_text(members, depth + 1, out);
+                        writeln!(out, "      {}{}", indent, close)
+                    }
+                }
+            }
+        };
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for character in value.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", character as u32);
+            }
+            character => json.push(character),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn render_json(nodes: &[Node], out: &mut String) {
+    out.push('[');
+    for (index, node) in nodes.iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        let tag = match node.tag {
+            TLVTag::Anonymous => String::from("null"),
+            ref tag => json_string(&tag.to_string()),
+        };
+        let _ = write!(out, "{{\"offset\":{},\"tag\":{},", node.offset, tag);
+        let _ = match &node.value {
+            Value::Int(value, name) => write!(out, "\"type\":\"{}\",\"value\":{}", name, value),
+            Value::UInt(value, name) => write!(out, "\"type\":\"{}\",\"value\":{}", name, value),
+            Value::Float(value, name) if value.is_finite() => {
+                write!(out, "\"type\":\"{}\",\"value\":{}", name, value)
+            }
+            Value::Float(value, name) => write!(
+                out,
+                "\"type\":\"{}\",\"value\":{}",
+                name,
+                json_string(&value.to_string())
+            ),
+            Value::Bool(value) => write!(out, "\"type\":\"Boolean\",\"value\":{}", value),
+            Value::Null => write!(out, "\"type\":\"Null\",\"value\":null"),
+            Value::Utf8(value) => {
+                write!(
+                    out,
+                    "\"type\":\"UTF8String\",\"value\":{}",
+                    json_string(value)
+                )
+            }
+            Value::Bytes(value) => write!(
+                out,
+                "\"type\":\"ByteString\",\"value\":\"{}\"",
+                hex::to_hex(value)
+            ),
+            Value::Container(container_type, members) => {
+                let (name, _, _) = brackets(*container_type);
+                let _ = write!(out, "\"type\":\"{}\",\"members\":", name);
+                match members {
+                    Some(members) => {
+                        render_json(members, out);
+                        Ok(())
+                    }
+                    None => write!(out, "null"),
+                }
+            }
+        };
+        out.push('}');
+    }
+    out.push(']');
+}
+
+fn run(options: &Options) -> Result<String, (u8, String)> {
+    let input = read_input(
+        options.file.as_ref(),
+        options.input.as_deref(),
+        options.format,
+    )
+    .map_err(|e| (EXIT_USAGE, e))?;
+    let bytes = decode_input(&input, options.format)
+        .map_err(|e| (EXIT_DECODE_FAILURE, format!("invalid input: {}", e)))?;
+    let range = match &options.path {
+        Some(path) => element_range(&bytes, path).map_err(|e| {
+            (
+                EXIT_DECODE_FAILURE,
+                format!("error at path {}: {}", path, e),
+            )
+        })?,
+        None => 0..bytes.len(),
+    };
+
+    if options.annotate {
+        let mut out = String::new();
+        if let Err(e) = hexdump::write_annotated_hexdump(&bytes[range], &mut out) {
+            print!("{}", out);
+            return Err((EXIT_DECODE_FAILURE, format!("error: {}", e)));
+        }
+        return Ok(out);
+    }
+
+    if options.recover {
+        let (out, errors) = recover(&bytes[range.clone()], range.start);
+        if errors > 0 {
+            print!("{}", out);
+            let error = format!("{} errors, decoding resumed after each", errors);
+            return Err((EXIT_DECODE_FAILURE, error));
+        }
+        return Ok(out);
+    }
+
+    // Offsets stay those of the whole payload
+    let mut tlv_reader =
+        TLVReader::new_range(&bytes, range).map_err(|e| (EXIT_DECODE_FAILURE, e.to_string()))?;
+    let mut nodes = Vec::new();
+    let result = read_elements(&mut tlv_reader, None, options, &mut nodes);
+
+    let mut out = String::new();
+    if options.json {
+        if let Err(e) = result {
+            let error = format!("error at offset {}: {}", tlv_reader.offset(), e);
+            return Err((EXIT_DECODE_FAILURE, error));
+        }
+        render_json(&nodes, &mut out);
+        out.push('\n');
+    } else {
+        render_text(&nodes, 0, &mut out);
+        if let Err(e) = result {
+            print!("{}", out);
+            let error = format!("error at offset {}: {}", tlv_reader.offset(), e);
+            return Err((EXIT_DECODE_FAILURE, error));
+        }
+    }
+    Ok(out)
+}
+
+// Bytes of the element of the path with its subtree
+fn element_range(bytes: &[u8], path: &TLVPath) -> Result<Range<usize>, TLVError> {
+    let mut tlv_reader = TLVReader::new(bytes);
+    tlv_reader.descend(path)?;
+    let start = tlv_reader.offset();
+    Ok(start..start + reader::element_size(tlv_reader.remaining())?)
+}
+
+// Top level elements recovered one per line, then the errors with offsets in the whole payload
+fn recover(bytes: &[u8], start: usize) -> (String, usize) {
+    let recovered = TLVDocument::parse_recovering(bytes);
+    let mut out = String::new();
+    for element in &recovered.document.elements {
+        let _ = writeln!(out, "{}", element);
+    }
+    for error in &recovered.errors {
+        let _ = writeln!(
+            out,
+            "error at offset {}: {} ({} bytes skipped)",
+            start + error.offset,
+            error.error,
+            error.skipped
+        );
+    }
+    (out, recovered.errors.len())
+}
+
+fn run_codegen(options: &Options) -> Result<String, (u8, String)> {
+    let input = read_input(
+        options.file.as_ref(),
+        options.input.as_deref(),
+        options.format,
+    )
+    .map_err(|e| (EXIT_USAGE, e))?;
+    let bytes = decode_input(&input, options.format)
+        .map_err(|e| (EXIT_DECODE_FAILURE, format!("invalid input: {}", e)))?;
+    let range = match &options.path {
+        Some(path) => element_range(&bytes, path).map_err(|e| {
+            (
+                EXIT_DECODE_FAILURE,
+                format!("error at path {}: {}", path, e),
+            )
+        })?,
+        None => 0..bytes.len(),
+    };
+    codegen::generate_rust_code(&bytes[range])
+        .map_err(|e| (EXIT_DECODE_FAILURE, format!("cannot decode input: {}", e)))
+}
+
+fn render_diff(differences: &[Difference]) -> String {
+    let mut out = String::new();
+    for difference in differences {
+        let _ = writeln!(out, "{}", difference);
+    }
+    let _ = match differences.len() {
+        0 => writeln!(out, "no differences"),
+        1 => writeln!(out, "1 difference"),
+        count => writeln!(out, "{} differences", count),
+    };
+    out
+}
+
+// Prints the differences, exiting with EXIT_DIFFERENCES when there are any
+fn run_diff(options: &DiffOptions) -> Result<(String, u8), (u8, String)> {
+    let mut documents = Vec::new();
+    for (source, name) in options.sources.iter().zip(["old", "new"]) {
+        let input = match source {
+            Source::Input(input) => read_input(None, Some(input), options.format),
+            Source::File(path) => read_input(Some(path), None, options.format),
+        }
+        .map_err(|e| (EXIT_USAGE, e))?;
+        let bytes = decode_input(&input, options.format).map_err(|e| {
+            (
+                EXIT_DECODE_FAILURE,
+                format!("invalid {} input: {}", name, e),
+            )
+        })?;
+        let document = TLVDocument::parse(&bytes).map_err(|e| {
+            (
+                EXIT_DECODE_FAILURE,
+                format!("cannot decode {} input: {}", name, e),
+            )
+        })?;
+        documents.push(document);
+    }
+    let differences = diff::diff(&documents[0], &documents[1], options.semantic);
+    let code = if differences.is_empty() {
+        0
+    } else {
+        EXIT_DIFFERENCES
+    };
+    Ok((render_diff(&differences), code))
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Decode(options)) => options,
+        Ok(Command::Codegen(options)) => {
+            return match run_codegen(&options) {
+                Ok(out) => {
+                    print!("{}", out);
+                    ExitCode::SUCCESS
+                }
+                Err((code, e)) => {
+                    eprintln!("tlv: {}", e);
+                    ExitCode::from(code)
+                }
+            }
+        }
+        Ok(Command::Diff(options)) => {
+            return match run_diff(&options) {
+                Ok((out, code)) => {
+                    print!("{}", out);
+                    ExitCode::from(code)
+                }
+                Err((code, e)) => {
+                    eprintln!("tlv: {}", e);
+                    ExitCode::from(code)
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("tlv: {}\n\n{}", e, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    match run(&options) {
+        Ok(out) => {
+            print!("{}", out);
+            ExitCode::SUCCESS
+        }
+        Err((code, e)) => {
+            eprintln!("tlv: {}", e);
+            ExitCode::from(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn options(strict: bool, max_depth: Option<usize>) -> Options {
+        Options {
+            format: InputFormat::Hex,
+            input: None,
+            file: None,
+            strict,
+            max_depth,
+            json: false,
+            annotate: false,
+            recover: false,
+            path: None,
+        }
+    }
+
+    fn decode(bytes: &[u8], options: &Options) -> (Vec<Node>, Result<(), TLVError>) {
+        let mut tlv_reader = TLVReader::new(bytes);
+        let mut nodes = Vec::new();
+        let result = read_elements(&mut tlv_reader, None, options, &mut nodes);
+        (nodes, result)
+    }
+
+    // Structure {0 = 42U, 1 = [true, "Hi"], 2 = Structure {}}
+    const TEST_BYTES: &[u8] = &[
+        0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x0c, 0x02, 0x48, 0x69, 0x18, 0x35, 0x02, 0x18,
+        0x18,
+    ];
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(args(&["--strict", "--max-depth", "2", "--json", "1518"])),
+            Ok(Command::Decode(Options {
+                input: Some(String::from("1518")),
+                json: true,
+                ..options(true, Some(2))
+            }))
+        );
+        assert_eq!(
+            parse_args(args(&["--raw", "--file", "payload.bin"])),
+            Ok(Command::Decode(Options {
+                format: InputFormat::Raw,
+                file: Some(String::from("payload.bin")),
+                ..options(false, None)
+            }))
+        );
+        assert_eq!(parse_args(args(&["1518", "--help"])), Ok(Command::Help));
+        assert!(parse_args(args(&["--max-depth", "deep"])).is_err());
+        assert!(parse_args(args(&["--max-depth"])).is_err());
+        assert!(parse_args(args(&["--pretty"])).is_err());
+        assert!(parse_args(args(&["1518", "1518"])).is_err());
+        assert!(parse_args(args(&["--raw", "1518"])).is_err());
+        assert!(parse_args(args(&["--file", "payload.hex", "1518"])).is_err());
+        assert!(parse_args(args(&["--json", "--annotate", "1518"])).is_err());
+    }
+
+    #[test]
+    fn test_render_text() {
+        let (nodes, result) = decode(TEST_BYTES, &options(false, None));
+        result.expect("Failed to decode");
+        let mut out = String::new();
+        render_text(&nodes, 0, &mut out);
+        assert_eq!(
+            out,
+            "0000  Structure {\n\
+             0001    0: UInt8 42\n\
+             0004    1: Array [\n\
+             0006      Boolean true\n\
+             0007      UTF8String \"Hi\"\n\
+             \x20       ]\n\
+             000c    2: Structure {}\n\
+             \x20     }\n"
+        );
+
+        let (nodes, result) = decode(TEST_BYTES, &options(false, Some(1)));
+        result.expect("Failed to decode");
+        let mut out = String::new();
+        render_text(&nodes, 0, &mut out);
+        assert!(out.contains("0004    1: Array [ ... ]\n"));
+    }
+
+    #[test]
+    fn test_render_json() {
+        let (nodes, result) = decode(TEST_BYTES, &options(false, None));
+        result.expect("Failed to decode");
+        let mut out = String::new();
+        render_json(&nodes, &mut out);
+        assert_eq!(
+            out,
+            "[{\"offset\":0,\"tag\":null,\"type\":\"Structure\",\"members\":[\
+             {\"offset\":1,\"tag\":\"0\",\"type\":\"UInt8\",\"value\":42},\
+             {\"offset\":4,\"tag\":\"1\",\"type\":\"Array\",\"members\":[\
+             {\"offset\":6,\"tag\":null,\"type\":\"Boolean\",\"value\":true},\
+             {\"offset\":7,\"tag\":null,\"type\":\"UTF8String\",\"value\":\"Hi\"}]},\
+             {\"offset\":12,\"tag\":\"2\",\"type\":\"Structure\",\"members\":[]}]}]"
+        );
+        assert_eq!(json_string("a\"b\\\n\u{1}"), "\"a\\\"b\\\\\\n\\u0001\"");
+    }
+
+    #[test]
+    fn test_run_annotate() {
+        let options = Options {
+            input: Some(String::from("1524002a18")),
+            annotate: true,
+            ..options(false, None)
+        };
+        assert_eq!(
+            run(&options).expect("Failed to dump"),
+            "0000  15                              Structure {\n\
+             0001    24 00 2a                      ctx tag 0, UInt8 = 42\n\
+             0004  18                              End of Container }\n"
+        );
+        let options = Options {
+            input: Some(String::from("1524002a")),
+            ..options
+        };
+        assert_eq!(
+            run(&options).expect_err("Decode failure is expected").0,
+            EXIT_DECODE_FAILURE
+        );
+    }
+
+    #[test]
+    fn test_run_recover() {
+        assert!(parse_args(args(&["--recover", "--json", "1518"])).is_err());
+        assert!(parse_args(args(&["codegen", "--recover", "1518"])).is_err());
+
+        // Structure {0 = 42U, reserved element type 1f, 1f, 1f, 2 = "a"}
+        let input = "1524002a3f1f1f2c02016118";
+        let options = Options {
+            input: Some(String::from(input)),
+            recover: true,
+            ..options(false, None)
+        };
+        let Ok(Command::Decode(parsed)) = parse_args(args(&["--recover", input])) else {
+            panic!("Failed to parse arguments");
+        };
+        assert_eq!(parsed, options);
+        assert_eq!(
+            recover(&hex::from_hex(input).expect("Failed to decode hex"), 0),
+            (
+                String::from(
+                    "{0 = 42U, 2 = \"a\"}\n\
+                     error at offset 4: reserved element type 0x1f at offset 4 (3 bytes skipped)\n"
+                ),
+                1
+            )
+        );
+        assert_eq!(
+            run(&options).expect_err("Decode failure is expected").0,
+            EXIT_DECODE_FAILURE
+        );
+        let options = Options {
+            input: Some(String::from("1524002a18")),
+            ..options
+        };
+        assert_eq!(run(&options).expect("Failed to decode"), "{0 = 42U}\n");
+    }
+
+    #[test]
+    fn test_run_path() {
+        assert_eq!(
+            parse_args(args(&["--path", "1[1]", "1518"])),
+            Ok(Command::Decode(Options {
+                input: Some(String::from("1518")),
+                path: Some(TLVPath::root().tag(1).index(1)),
+                ..options(false, None)
+            }))
+        );
+        assert!(parse_args(args(&["--path", "1[x]"])).is_err());
+        assert!(parse_args(args(&["--path"])).is_err());
+
+        let options = Options {
+            input: Some(hex::to_hex(TEST_BYTES)),
+            path: Some(TLVPath::root().tag(1)),
+            ..options(false, None)
+        };
+        assert_eq!(
+            run(&options).expect("Failed to decode"),
+            "0004  1: Array [\n\
+             0006    Boolean true\n\
+             0007    UTF8String \"Hi\"\n\
+             \x20     ]\n"
+        );
+        let options = Options {
+            path: Some(TLVPath::root().tag(1).index(2)),
+            ..options
+        };
+        assert_eq!(
+            run(&options).expect_err("Decode failure is expected"),
+            (
+                EXIT_DECODE_FAILURE,
+                String::from("error at path 1[2]: no element matches step 1 of the path")
+            )
+        );
+    }
+
+    fn diff_options(old: &str, new: &str, semantic: bool) -> DiffOptions {
+        DiffOptions {
+            format: InputFormat::Hex,
+            sources: vec![
+                Source::Input(String::from(old)),
+                Source::Input(String::from(new)),
+            ],
+            semantic,
+        }
+    }
+
+    #[test]
+    fn test_parse_diff_args() {
+        assert_eq!(
+            parse_args(args(&["diff", "--semantic", "1518", "--file", "new.hex"])),
+            Ok(Command::Diff(DiffOptions {
+                format: InputFormat::Hex,
+                sources: vec![
+                    Source::Input(String::from("1518")),
+                    Source::File(String::from("new.hex")),
+                ],
+                semantic: true,
+            }))
+        );
+        assert!(parse_args(args(&["diff", "1518"])).is_err());
+        assert!(parse_args(args(&["diff", "1518", "1518", "1518"])).is_err());
+        assert!(parse_args(args(&["diff", "-", "-"])).is_err());
+        assert!(parse_args(args(&["diff", "--raw", "1518", "-"])).is_err());
+        assert!(parse_args(args(&["diff", "--strict", "1518", "1518"])).is_err());
+    }
+
+    #[test]
+    fn test_run_diff() {
+        // Structure {0 = 42U, 1 = "a"} against Structure {0 = 43U, 2 = Null}
+        assert_eq!(
+            run_diff(&diff_options("1524002a2c01016118", "1524002b340218", false)),
+            Ok((
+                String::from("~ 0: 42U -> 43U\n- 1: \"a\"\n+ 2: Null\n3 differences\n"),
+                EXIT_DIFFERENCES
+            ))
+        );
+        assert_eq!(
+            run_diff(&diff_options("1524002a18", "1524002a18", false)),
+            Ok((String::from("no differences\n"), 0))
+        );
+
+        // Structure {0 = 42U} against the same member as UInt16
+        let options = diff_options("1524002a18", "1525002a0018", false);
+        assert_eq!(
+            run_diff(&options),
+            Ok((
+                String::from("~ 0: 42U -> 42U (width only)\n1 difference\n"),
+                EXIT_DIFFERENCES
+            ))
+        );
+        let options = DiffOptions {
+            semantic: true,
+            ..options
+        };
+        assert_eq!(
+            run_diff(&options),
+            Ok((String::from("no differences\n"), 0))
+        );
+
+        let options = diff_options("1524002a18", "1524002a", false);
+        let (code, error) = run_diff(&options).expect_err("Decode failure is expected");
+        assert_eq!(code, EXIT_DECODE_FAILURE);
+        assert!(error.starts_with("cannot decode new input"));
+    }
+
+    #[test]
+    fn test_run_codegen() {
+        assert_eq!(
+            parse_args(args(&["codegen", "--path", "1", "1518"])),
+            Ok(Command::Codegen(Options {
+                input: Some(String::from("1518")),
+                path: Some(TLVPath::root().tag(1)),
+                ..options(false, None)
+            }))
+        );
+        assert_eq!(parse_args(args(&["codegen", "--help"])), Ok(Command::Help));
+        assert!(parse_args(args(&["codegen", "--json", "1518"])).is_err());
+        assert!(parse_args(args(&["codegen", "--strict", "1518"])).is_err());
+
+        let options = Options {
+            input: Some(hex::to_hex(TEST_BYTES)),
+            path: Some(TLVPath::root().tag(1)),
+            ..options(false, None)
+        };
+        assert_eq!(
+            run_codegen(&options).expect("Failed to generate code"),
+            "w.start_container(TLVTag::ContextSpecific(1), ContainerType::Array)?;\n\
+             \x20   w.put(TLVTag::Anonymous, &true)?;\n\
+             \x20   w.put(TLVTag::Anonymous, \"Hi\")?;\n\
+             w.end_container()?;\n"
+        );
+        let options = Options {
+            input: Some(String::from("1524002a")),
+            path: None,
+            ..options
+        };
+        assert_eq!(
+            run_codegen(&options)
+                .expect_err("Decode failure is expected")
+                .0,
+            EXIT_DECODE_FAILURE
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        // Structure {0 = 1U, 0 = 2U}
+        let test_bytes = &[0x15, 0x24, 0x00, 0x01, 0x24, 0x00, 0x02, 0x18];
+        let (nodes, result) = decode(test_bytes, &options(false, None));
+        assert_eq!(result, Ok(()));
+        assert_eq!(nodes.len(), 1);
+        let (nodes, result) = decode(test_bytes, &options(true, None));
+        assert_eq!(
+            result.expect_err("Duplicate member is expected"),
+            TLVError::DuplicateMember(TLVTag::ContextSpecific(0))
+        );
+        match &nodes[0].value {
+            Value::Container(ContainerType::Structure, Some(members)) => {
+                assert_eq!(members.len(), 1)
+            }
+            value => panic!("Unexpected value {:?}", value),
+        }
+
+        // Array [0 = 1U]
+        let test_bytes = &[0x16, 0x24, 0x00, 0x01, 0x18];
+        let (_, result) = decode(test_bytes, &options(true, None));
+        assert_eq!(
+            result.expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+    }
+
+    #[test]
+    fn test_run_exit_codes() {
+        let options = Options {
+            input: Some(String::from("1524002a")),
+            ..options(false, None)
+        };
+        assert_eq!(
+            run(&options).expect_err("Decode failure is expected").0,
+            EXIT_DECODE_FAILURE
+        );
+        let options = Options {
+            input: Some(String::from("zz")),
+            ..options
+        };
+        assert_eq!(
+            run(&options).expect_err("Decode failure is expected").0,
+            EXIT_DECODE_FAILURE
+        );
+        let options = Options {
+            input: None,
+            file: Some(String::from("/nonexistent/payload.hex")),
+            ..options
+        };
+        assert_eq!(
+            run(&options).expect_err("Usage error is expected").0,
+            EXIT_USAGE
+        );
+    }
+}
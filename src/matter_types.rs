@@ -0,0 +1,395 @@
+// Derived data types of the Matter specification (section 7.19), wrapping the base TLV types.
+// Values are range checked on construction and on decode, so an encoded value is always valid.
+
+use crate::errors::TLVError;
+use crate::reader::{TLVDecode, TLVReader};
+use crate::tags::TLVTag;
+use crate::writer::TLVEncode;
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+#[cfg(feature = "system-time")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Seconds from the Unix epoch to the Matter epoch, 2000-01-01 00:00:00 UTC
+pub const MATTER_EPOCH_UNIX_SECONDS: u64 = 946684800;
+
+macro_rules! matter_type {
+    ($name:ident, $type:ty) => {
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+        pub struct $name($type);
+
+        impl $name {
+            pub const fn new(value: $type) -> Self {
+                Self(value)
+            }
+
+            pub const fn value(&self) -> $type {
+                self.0
+            }
+        }
+
+        impl From<$type> for $name {
+            fn from(value: $type) -> Self {
+                Self(value)
+            }
+        }
+
+        impl TLVDecode for $name {
+            fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+                Ok(Self(<$type>::decode_tlv(tlv_reader)?))
+            }
+        }
+
+        matter_type!(@common $name, $type);
+    };
+    ($name:ident, $type:ty, $range:expr) => {
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+        pub struct $name($type);
+
+        impl $name {
+            pub const RANGE: RangeInclusive<$type> = $range;
+
+            pub fn new(value: $type) -> Result<Self, TLVError> {
+                if Self::RANGE.contains(&value) {
+                    Ok(Self(value))
+                } else {
+                    Err(TLVError::ValueOutOfRange)
+                }
+            }
+
+            pub const fn value(&self) -> $type {
+                self.0
+            }
+        }
+
+        impl TryFrom<$type> for $name {
+            type Error = TLVError;
+
+            fn try_from(value: $type) -> Result<Self, Self::Error> {
+                Self::new(value)
+            }
+        }
+
+        impl TLVDecode for $name {
+            fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+                Self::new(<$type>::decode_tlv(tlv_reader)?)
+            }
+        }
+
+        matter_type!(@common $name, $type);
+    };
+    (@common $name:ident, $type:ty) => {
+        impl From<$name> for $type {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl TLVEncode for $name {
+            fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+                self.0.encode_tlv_with_tag(tag)
+            }
+        }
+    };
+}
+
+matter_type!(EpochS, u32); // Seconds since the Matter epoch
+matter_type!(EpochUs, u64); // Microseconds since the Matter epoch
+matter_type!(PosixMs, u64); // Milliseconds since the Unix epoch
+matter_type!(SystimeUs, u64); // Microseconds since boot
+matter_type!(Percent, u8, 0..=100);
+matter_type!(Percent100ths, u16, 0..=10000);
+matter_type!(Temperature, i16, -27315..=32767); // Hundredths of a degree Celsius
+matter_type!(FabricId, u64);
+matter_type!(FabricIndex, u8, 1..=254);
+matter_type!(NodeId, u64);
+matter_type!(VendorId, u16);
+matter_type!(GroupId, u16);
+matter_type!(EndpointNumber, u16);
+matter_type!(ClusterId, u32);
+matter_type!(AttributeId, u32);
+matter_type!(CommandId, u32);
+matter_type!(EventId, u32);
+
+impl Temperature {
+    pub fn celsius(&self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+}
+
+#[cfg(feature = "system-time")]
+impl From<EpochS> for SystemTime {
+    fn from(epoch: EpochS) -> Self {
+        UNIX_EPOCH + Duration::from_secs(MATTER_EPOCH_UNIX_SECONDS + epoch.0 as u64)
+    }
+}
+
+#[cfg(feature = "system-time")]
+impl TryFrom<SystemTime> for EpochS {
+    type Error = TLVError;
+
+    // Fails before the Matter epoch and after 2136
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        let seconds = since_matter_epoch(time)?.as_secs();
+        u32::try_from(seconds)
+            .map(EpochS)
+            .map_err(|_| TLVError::ValueOutOfRange)
+    }
+}
+
+#[cfg(feature = "system-time")]
+impl TryFrom<EpochUs> for SystemTime {
+    type Error = TLVError;
+
+    // Fails on platforms whose SystemTime ends before the largest epoch-us values, e.g. Windows
+    fn try_from(epoch: EpochUs) -> Result<Self, Self::Error> {
+        after_matter_epoch(Duration::from_micros(epoch.0))
+    }
+}
+
+#[cfg(feature = "system-time")]
+impl TryFrom<SystemTime> for EpochUs {
+    type Error = TLVError;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        let micros = since_matter_epoch(time)?.as_micros();
+        u64::try_from(micros)
+            .map(EpochUs)
+            .map_err(|_| TLVError::ValueOutOfRange)
+    }
+}
+
+#[cfg(feature = "system-time")]
+fn since_matter_epoch(time: SystemTime) -> Result<Duration, TLVError> {
+    time.duration_since(UNIX_EPOCH + Duration::from_secs(MATTER_EPOCH_UNIX_SECONDS))
+        .map_err(|_| TLVError::ValueOutOfRange)
+}
+
+// Decoded times the platform cannot represent fail with ValueOutOfRange
+#[cfg(feature = "system-time")]
+fn after_matter_epoch(duration: Duration) -> Result<SystemTime, TLVError> {
+    UNIX_EPOCH
+        .checked_add(Duration::from_secs(MATTER_EPOCH_UNIX_SECONDS))
+        .and_then(|matter_epoch| matter_epoch.checked_add(duration))
+        .ok_or(TLVError::ValueOutOfRange)
+}
+
+// SystemTime fields encoded as epoch-s and epoch-us values. Times before the Matter epoch or
+// beyond the range of the encoding fail with ValueOutOfRange on construction, precision finer
+// than the encoding is dropped.
+#[cfg(feature = "system-time")]
+macro_rules! epoch_time {
+    ($name:ident, $epoch:ident, $duration:path) => {
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+        pub struct $name($epoch);
+
+        impl $name {
+            pub fn new(time: SystemTime) -> Result<Self, TLVError> {
+                $epoch::try_from(time).map(Self)
+            }
+
+            pub fn time(&self) -> SystemTime {
+                after_matter_epoch($duration(self.0.value().into()))
+                    .unwrap_or_else(|_| unreachable!("checked when built or decoded"))
+            }
+        }
+
+        impl TryFrom<SystemTime> for $name {
+            type Error = TLVError;
+
+            fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+                Self::new(time)
+            }
+        }
+
+        impl From<$name> for SystemTime {
+            fn from(time: $name) -> Self {
+                time.time()
+            }
+        }
+
+        impl TLVEncode for $name {
+            fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+                self.0.encode_tlv_with_tag(tag)
+            }
+        }
+
+        impl TLVDecode for $name {
+            fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+                let epoch = $epoch::decode_tlv(tlv_reader)?;
+                after_matter_epoch($duration(epoch.value().into()))?;
+                Ok(Self(epoch))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "system-time")]
+epoch_time!(EpochSeconds, EpochS, Duration::from_secs);
+#[cfg(feature = "system-time")]
+epoch_time!(EpochMicros, EpochUs, Duration::from_micros);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_roundtrip;
+
+    #[test]
+    fn test_range_validation() {
+        assert_eq!(
+            Percent::new(0).expect("Failed to create percent").value(),
+            0
+        );
+        assert_eq!(
+            Percent::new(100).expect("Failed to create percent").value(),
+            100
+        );
+        assert_eq!(
+            Percent::new(101).expect_err("Out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+        assert!(Percent100ths::try_from(10000).is_ok());
+        assert!(Percent100ths::try_from(10001).is_err());
+        assert_eq!(
+            Temperature::new(-27315)
+                .expect("Failed to create temperature")
+                .celsius(),
+            -273.15
+        );
+        assert!(Temperature::new(-27316).is_err());
+        assert!(FabricIndex::new(0).is_err());
+        assert!(FabricIndex::new(254).is_ok());
+        assert!(FabricIndex::new(255).is_err());
+        assert_eq!(u64::from(NodeId::from(0x0102)), 0x0102);
+    }
+
+    #[test]
+    fn test_encode() {
+        // Context tag 1, Unsigned Integer, 1-octet, value 100
+        assert_eq!(
+            Percent::new(100)
+                .unwrap()
+                .encode_tlv_with_tag(&TLVTag::ContextSpecific(1)),
+            [0x24, 0x01, 0x64]
+        );
+        // Signed Integer, 2-octet, value 2150
+        assert_roundtrip(&Temperature::new(2150).unwrap(), Some(&[0x01, 0x66, 0x08]));
+        // Unsigned Integer, 4-octet, value 1
+        assert_roundtrip(&EpochS::new(1), Some(&[0x06, 0x01, 0x00, 0x00, 0x00]));
+        // Unsigned Integer, 8-octet, value 1
+        assert_roundtrip(
+            &NodeId::new(1),
+            Some(&[0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        // Unsigned Integer, 1-octet, value 100
+        let mut tlv_reader = TLVReader::new(&[0x04, 0x64]);
+        assert_eq!(
+            Percent::decode_tlv(&mut tlv_reader).expect("Failed to decode percent"),
+            Percent::new(100).unwrap()
+        );
+        assert_eq!(
+            EpochS::decode_tlv(&mut tlv_reader).expect("Failed to decode epoch"),
+            EpochS::new(100)
+        );
+
+        // Unsigned Integer, 1-octet, value 101
+        let mut tlv_reader = TLVReader::new(&[0x04, 0x65]);
+        assert_eq!(
+            Percent::decode_tlv(&mut tlv_reader).expect_err("Out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+
+        // Unsigned Integer, 2-octet, value 300
+        let mut tlv_reader = TLVReader::new(&[0x05, 0x2c, 0x01]);
+        assert_eq!(
+            Percent::decode_tlv(&mut tlv_reader).expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+
+        // Signed Integer, 2-octet, value -27316
+        let mut tlv_reader = TLVReader::new(&[0x01, 0x4c, 0x95]);
+        assert_eq!(
+            Temperature::decode_tlv(&mut tlv_reader).expect_err("Out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+    }
+
+    #[cfg(feature = "system-time")]
+    #[test]
+    fn test_system_time() {
+        let matter_epoch = UNIX_EPOCH + Duration::from_secs(MATTER_EPOCH_UNIX_SECONDS);
+        assert_eq!(SystemTime::from(EpochS::new(0)), matter_epoch);
+        assert_eq!(
+            EpochS::try_from(matter_epoch + Duration::from_secs(u32::MAX as u64))
+                .expect("Failed to convert time"),
+            EpochS::new(u32::MAX)
+        );
+        assert_eq!(
+            EpochS::try_from(matter_epoch + Duration::from_secs(u32::MAX as u64 + 1))
+                .expect_err("Out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+        assert_eq!(
+            EpochUs::try_from(UNIX_EPOCH).expect_err("Out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+        let time = matter_epoch + Duration::from_micros(1_500_000);
+        assert_eq!(
+            EpochUs::try_from(time).expect("Failed to convert time"),
+            EpochUs::new(1_500_000)
+        );
+        assert_eq!(SystemTime::try_from(EpochUs::new(1_500_000)), Ok(time));
+        // Past the end of SystemTime on some platforms, which fails rather than panics
+        assert_eq!(
+            SystemTime::try_from(EpochUs::new(u64::MAX)),
+            matter_epoch
+                .checked_add(Duration::from_micros(u64::MAX))
+                .ok_or(TLVError::ValueOutOfRange)
+        );
+    }
+
+    #[cfg(feature = "system-time")]
+    #[test]
+    fn test_epoch_time() {
+        // 2021-01-01 00:00:00 UTC, Unsigned Integer, 4-octet, value 662774400
+        let time = UNIX_EPOCH + Duration::from_secs(1609459200);
+        let epoch_seconds = EpochSeconds::new(time).expect("Failed to convert time");
+        assert_roundtrip(&epoch_seconds, Some(&[0x06, 0x80, 0x22, 0x81, 0x27]));
+        assert_eq!(SystemTime::from(epoch_seconds), time);
+        // Fractions of a second are dropped
+        assert_eq!(
+            EpochSeconds::new(time + Duration::from_millis(999)),
+            Ok(epoch_seconds)
+        );
+
+        // 2023-11-14 22:13:20.25 UTC, Unsigned Integer, 8-octet, value 753315200250000
+        let time = UNIX_EPOCH + Duration::from_micros(1_700_000_000_250_000);
+        let epoch_micros = EpochMicros::new(time).expect("Failed to convert time");
+        assert_roundtrip(
+            &epoch_micros,
+            Some(&[0x07, 0x90, 0x30, 0xea, 0xdc, 0x22, 0xad, 0x02, 0x00]),
+        );
+        assert_eq!(epoch_micros.time(), time);
+
+        // Before the Matter epoch and past 2136 for epoch-s
+        let matter_epoch = UNIX_EPOCH + Duration::from_secs(MATTER_EPOCH_UNIX_SECONDS);
+        assert_eq!(
+            EpochMicros::new(matter_epoch - Duration::from_micros(1))
+                .expect_err("Out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+        assert_eq!(
+            EpochSeconds::try_from(matter_epoch + Duration::from_secs(1 << 32))
+                .expect_err("Out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+        assert_eq!(
+            EpochSeconds::new(matter_epoch).map(|epoch| epoch.encode_tlv()),
+            Ok(vec![0x06, 0x00, 0x00, 0x00, 0x00])
+        );
+    }
+}
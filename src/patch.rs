@@ -0,0 +1,365 @@
+// Editing encoded payloads in place. Only the bytes of the element edited or added change, its siblings and
+// ancestors are kept as they are since containers have no length field to update.
+
+use crate::canonical;
+use crate::errors::TLVError;
+use crate::path::{self, TLVPath};
+use crate::reader::{element_size, TLVReader};
+use crate::tags::TLVTag;
+use crate::types::{
+    ContainerType, FloatingPoint, PredeterminedLenPrimitive, PrimitiveLengthType, SignedInteger,
+    SpecifiedLenPrimitive, TLVType, UnsignedInteger,
+};
+use crate::value::TLVValue;
+use crate::writer::TLVWriter;
+use alloc::vec::Vec;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PatchOptions {
+    preserve_width: bool,
+}
+
+impl PatchOptions {
+    // Keeps the tag field, integer or float width and string length field of the element replaced
+    // when the new value is of the same kind, failing with ValueOutOfRange when it does not fit
+    pub fn preserve_width(mut self, preserve_width: bool) -> Self {
+        self.preserve_width = preserve_width;
+        self
+    }
+}
+
+// Replaces the element of the path, keeping its tag. The new value and the tag use their narrowest
+// encoding, structure members keep their order. Nothing changes when it fails.
+pub fn patch_element(
+    bytes: &mut Vec<u8>,
+    path: &TLVPath,
+    new_value: &TLVValue,
+) -> Result<(), TLVError> {
+    patch_element_with_options(bytes, path, new_value, &PatchOptions::default())
+}
+
+pub fn patch_element_with_options(
+    bytes: &mut Vec<u8>,
+    path: &TLVPath,
+    new_value: &TLVValue,
+    options: &PatchOptions,
+) -> Result<(), TLVError> {
+    let range = path::element_range(bytes, path)?;
+    let tlv_reader = TLVReader::new(&bytes[range.clone()]);
+    let tag = tlv_reader.read_tag()?;
+    let mut tlv_writer = TLVWriter::new();
+    if options.preserve_width {
+        put_with_width(&mut tlv_writer, tag, tlv_reader.read_type()?, new_value)?;
+    } else {
+        let mut new_value = new_value.clone();
+        canonical::minimize(&mut new_value);
+        tlv_writer.put_value(tag.minimal(), &new_value)?;
+    }
+    bytes.splice(range, tlv_writer.into_bytes());
+    Ok(())
+}
+
+// Adds the value at the end of the Array of the path, numbers are written with the width of their
+// variant as TLVWriter::put_value does
+pub fn append_to_array(
+    bytes: &mut Vec<u8>,
+    path: &TLVPath,
+    element: &TLVValue,
+) -> Result<(), TLVError> {
+    let mut tlv_writer = TLVWriter::new();
+    tlv_writer.put_value(TLVTag::Anonymous, element)?;
+    insert_into_array(bytes, path, &tlv_writer.into_bytes())
+}
+
+// Same with an element already encoded, which has to be a single anonymous element
+pub fn append_encoded_to_array(
+    bytes: &mut Vec<u8>,
+    path: &TLVPath,
+    element: &[u8],
+) -> Result<(), TLVError> {
+    let tlv_reader = TLVReader::new(element);
+    if tlv_reader.read_tag()? != TLVTag::Anonymous {
+        return Err(TLVError::InvalidTag);
+    }
+    let element_len = element_size(element)?;
+    if element_len != element.len() {
+        return Err(TLVError::TrailingBytes {
+            offset: element_len,
+        });
+    }
+    insert_into_array(bytes, path, element)
+}
+
+// Inserts before the End of Container of the Array, the last byte of its range
+fn insert_into_array(bytes: &mut Vec<u8>, path: &TLVPath, element: &[u8]) -> Result<(), TLVError> {
+    let range = path::element_range(bytes, path)?;
+    let tlv_reader = TLVReader::new(&bytes[range.clone()]);
+    if tlv_reader.read_type()? != TLVType::Container(ContainerType::Array) {
+        return Err(TLVError::InvalidType);
+    }
+    let end_of_container = range.end - 1;
+    bytes.splice(end_of_container..end_of_container, element.iter().copied());
+    Ok(())
+}
+
+fn put_with_width(
+    tlv_writer: &mut TLVWriter,
+    tag: TLVTag,
+    original: TLVType,
+    value: &TLVValue,
+) -> Result<(), TLVError> {
+    let out_of_range = |_| TLVError::ValueOutOfRange;
+    let predetermined = match original {
+        TLVType::Primitive(PrimitiveLengthType::Predetermined(predetermined)) => predetermined,
+        TLVType::Primitive(PrimitiveLengthType::Specified(specified)) => {
+            let width = specified.length_field_size();
+            return match (specified, value) {
+                (SpecifiedLenPrimitive::UTF8String(_), TLVValue::Utf8String(value)) => {
+                    tlv_writer.put_str_with_len_width(tag, value, width)
+                }
+                (SpecifiedLenPrimitive::ByteString(_), TLVValue::ByteString(value)) => {
+                    tlv_writer.put_bytes_with_len_width(tag, value, width)
+                }
+                _ => tlv_writer.put_value(tag, value),
+            };
+        }
+        TLVType::Container(_) => return tlv_writer.put_value(tag, value),
+    };
+    match (predetermined, unsigned(value), signed(value), value) {
+        (PredeterminedLenPrimitive::UnsignedInteger(width), Some(value), _, _) => match width {
+            UnsignedInteger::UInt8 => {
+                tlv_writer.put(tag, &u8::try_from(value).map_err(out_of_range)?)
+            }
+            UnsignedInteger::UInt16 => {
+                tlv_writer.put(tag, &u16::try_from(value).map_err(out_of_range)?)
+            }
+            UnsignedInteger::UInt32 => {
+                tlv_writer.put(tag, &u32::try_from(value).map_err(out_of_range)?)
+            }
+            UnsignedInteger::UInt64 => tlv_writer.put(tag, &value),
+        },
+        (PredeterminedLenPrimitive::SignedInteger(width), _, Some(value), _) => match width {
+            SignedInteger::Int8 => tlv_writer.put(tag, &i8::try_from(value).map_err(out_of_range)?),
+            SignedInteger::Int16 => {
+                tlv_writer.put(tag, &i16::try_from(value).map_err(out_of_range)?)
+            }
+            SignedInteger::Int32 => {
+                tlv_writer.put(tag, &i32::try_from(value).map_err(out_of_range)?)
+            }
+            SignedInteger::Int64 => tlv_writer.put(tag, &value),
+        },
+        (
+            PredeterminedLenPrimitive::FloatingPointNumber(FloatingPoint::FloatingPointNumber64),
+            _,
+            _,
+            TLVValue::Float32(value),
+        ) => tlv_writer.put(tag, &(*value as f64)),
+        (
+            PredeterminedLenPrimitive::FloatingPointNumber(FloatingPoint::FloatingPointNumber32),
+            _,
+            _,
+            TLVValue::Float64(value),
+        ) => {
+            let narrowed = *value as f32;
+            if narrowed as f64 != *value && !value.is_nan() {
+                return Err(TLVError::ValueOutOfRange);
+            }
+            tlv_writer.put(tag, &narrowed)
+        }
+        _ => tlv_writer.put_value(tag, value),
+    }
+}
+
+fn unsigned(value: &TLVValue) -> Option<u64> {
+    match value {
+        TLVValue::UInt8(value) => Some(*value as u64),
+        TLVValue::UInt16(value) => Some(*value as u64),
+        TLVValue::UInt32(value) => Some(*value as u64),
+        TLVValue::UInt64(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn signed(value: &TLVValue) -> Option<i64> {
+    match value {
+        TLVValue::Int8(value) => Some(*value as i64),
+        TLVValue::Int16(value) => Some(*value as i64),
+        TLVValue::Int32(value) => Some(*value as i64),
+        TLVValue::Int64(value) => Some(*value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::PathStep;
+    use crate::tags::well_known;
+    use alloc::string::String;
+    use alloc::vec;
+
+    // Structure {0 = 42U, 1 = [true, "Hi"], CHIP::1 = 7U with a 4-octet tag}
+    const TEST_BYTES: &[u8] = &[
+        0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x0c, 0x02, 0x48, 0x69, 0x18, 0x64, 0x01, 0x00,
+        0x00, 0x00, 0x07, 0x18,
+    ];
+
+    #[test]
+    fn test_patch_element() {
+        // Same size
+        let mut bytes = TEST_BYTES.to_vec();
+        patch_element(&mut bytes, &TLVPath::root().tag(0), &TLVValue::UInt32(43))
+            .expect("Failed to patch");
+        assert_eq!(bytes[..4], [0x15, 0x24, 0x00, 0x2b]);
+        assert_eq!(bytes[4..], TEST_BYTES[4..]);
+
+        // Grows, 1 = [true, "Hello"]
+        let mut bytes = TEST_BYTES.to_vec();
+        let path = TLVPath::root().tag(1).index(1);
+        let new_value = TLVValue::Utf8String(String::from("Hello"));
+        patch_element(&mut bytes, &path, &new_value).expect("Failed to patch");
+        assert_eq!(bytes[..7], TEST_BYTES[..7]);
+        assert_eq!(bytes[7..14], [0x0c, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f]);
+        assert_eq!(bytes[14..], TEST_BYTES[11..]);
+
+        // Shrinks, 1 = Null
+        let mut bytes = TEST_BYTES.to_vec();
+        patch_element(&mut bytes, &TLVPath::root().tag(1), &TLVValue::Null)
+            .expect("Failed to patch");
+        assert_eq!(bytes[..4], TEST_BYTES[..4]);
+        assert_eq!(bytes[4..6], [0x34, 0x01]);
+        assert_eq!(bytes[6..], TEST_BYTES[12..]);
+
+        // The tag is narrowed, CHIP::1 = [1, 2] with a 2-octet tag
+        let mut bytes = TEST_BYTES.to_vec();
+        let path = TLVPath::new([PathStep::Index(2)]);
+        let new_value = TLVValue::Array(vec![TLVValue::UInt16(1), TLVValue::UInt16(2)]);
+        assert_eq!(
+            patch_element(&mut bytes, &path, &new_value),
+            Err(TLVError::InvalidType)
+        );
+        let path = TLVPath::root().profile_tag(well_known::matter_core_tag(1));
+        patch_element(&mut bytes, &path, &new_value).expect("Failed to patch");
+        assert_eq!(bytes[..12], TEST_BYTES[..12]);
+        assert_eq!(
+            bytes[12..],
+            [0x56, 0x01, 0x00, 0x04, 0x01, 0x04, 0x02, 0x18, 0x18]
+        );
+
+        // Failures leave the payload as it was
+        let mut bytes = TEST_BYTES.to_vec();
+        assert_eq!(
+            patch_element(&mut bytes, &TLVPath::root().tag(3), &TLVValue::Null),
+            Err(TLVError::PathNotFound { step: 0 })
+        );
+        assert_eq!(bytes, TEST_BYTES);
+    }
+
+    #[test]
+    fn test_patch_element_preserving_width() {
+        let options = PatchOptions::default().preserve_width(true);
+        let mut bytes = TEST_BYTES.to_vec();
+        let path = TLVPath::root().profile_tag(well_known::matter_core_tag(1));
+        patch_element_with_options(&mut bytes, &path, &TLVValue::UInt64(255), &options)
+            .expect("Failed to patch");
+        assert_eq!(bytes, [&TEST_BYTES[..17], &[0xff, 0x18]].concat());
+        assert_eq!(
+            patch_element_with_options(&mut bytes, &path, &TLVValue::UInt64(256), &options),
+            Err(TLVError::ValueOutOfRange)
+        );
+
+        // Other kinds of values use the width of their variant
+        patch_element_with_options(&mut bytes, &path, &TLVValue::Int16(-1), &options)
+            .expect("Failed to patch");
+        assert_eq!(
+            bytes[12..],
+            [0x61, 0x01, 0x00, 0x00, 0x00, 0xff, 0xff, 0x18]
+        );
+
+        // "Hi" becomes "Hey" with a 1-octet length field
+        let mut bytes = TEST_BYTES.to_vec();
+        let path = TLVPath::root().tag(1).index(1);
+        let new_value = TLVValue::Utf8String(String::from("Hey"));
+        patch_element_with_options(&mut bytes, &path, &new_value, &options)
+            .expect("Failed to patch");
+        assert_eq!(bytes[7..12], [0x0c, 0x03, 0x48, 0x65, 0x79]);
+
+        // Float64 1.5 written as the Float32 it replaces
+        let mut bytes = vec![0x0a, 0x00, 0x00, 0x80, 0x3f];
+        patch_element_with_options(
+            &mut bytes,
+            &TLVPath::root(),
+            &TLVValue::Float64(1.5),
+            &options,
+        )
+        .expect("Failed to patch");
+        assert_eq!(bytes, [0x0a, 0x00, 0x00, 0xc0, 0x3f]);
+        assert_eq!(
+            patch_element_with_options(
+                &mut bytes,
+                &TLVPath::root(),
+                &TLVValue::Float64(0.1),
+                &options
+            ),
+            Err(TLVError::ValueOutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_append_to_array() {
+        // Structure {0 = [], 1 = Structure {2 = [1U]}}
+        let mut bytes = vec![
+            0x15, 0x36, 0x00, 0x18, 0x35, 0x01, 0x36, 0x02, 0x04, 0x01, 0x18, 0x18, 0x18,
+        ];
+        append_to_array(&mut bytes, &TLVPath::root().tag(0), &TLVValue::Bool(true))
+            .expect("Failed to append");
+        append_to_array(
+            &mut bytes,
+            &TLVPath::root().tag(1).tag(2),
+            &TLVValue::Structure(vec![(TLVTag::ContextSpecific(0), TLVValue::UInt16(2))]),
+        )
+        .expect("Failed to append");
+        // 0 = [true, "a"], 1 = Structure {2 = [1U, {0 = 2U}, Null]}
+        append_encoded_to_array(&mut bytes, &TLVPath::root().tag(0), &[0x0c, 0x01, 0x61])
+            .expect("Failed to append");
+        append_encoded_to_array(&mut bytes, &TLVPath::root().tag(1).tag(2), &[0x14])
+            .expect("Failed to append");
+        assert_eq!(
+            bytes,
+            [
+                0x15, 0x36, 0x00, 0x09, 0x0c, 0x01, 0x61, 0x18, 0x35, 0x01, 0x36, 0x02, 0x04, 0x01,
+                0x15, 0x25, 0x00, 0x02, 0x00, 0x18, 0x14, 0x18, 0x18, 0x18
+            ]
+        );
+        // Appending to the top level Array [1U]
+        let mut bytes = vec![0x16, 0x04, 0x01, 0x18];
+        append_to_array(&mut bytes, &TLVPath::root(), &TLVValue::UInt8(2))
+            .expect("Failed to append");
+        assert_eq!(bytes, [0x16, 0x04, 0x01, 0x04, 0x02, 0x18]);
+    }
+
+    #[test]
+    fn test_append_to_array_invalid() {
+        let mut bytes = TEST_BYTES.to_vec();
+        assert_eq!(
+            append_to_array(&mut bytes, &TLVPath::root(), &TLVValue::Null),
+            Err(TLVError::InvalidType)
+        );
+        assert_eq!(
+            append_to_array(&mut bytes, &TLVPath::root().tag(0), &TLVValue::Null),
+            Err(TLVError::InvalidType)
+        );
+        // Context tag 0 = Null, Null followed by Null, then Array with its End of Container missing
+        let path = TLVPath::root().tag(1);
+        assert_eq!(
+            append_encoded_to_array(&mut bytes, &path, &[0x34, 0x00]),
+            Err(TLVError::InvalidTag)
+        );
+        assert_eq!(
+            append_encoded_to_array(&mut bytes, &path, &[0x14, 0x14]),
+            Err(TLVError::TrailingBytes { offset: 1 })
+        );
+        assert!(append_encoded_to_array(&mut bytes, &path, &[0x16, 0x14]).is_err());
+        assert_eq!(bytes, TEST_BYTES);
+    }
+}
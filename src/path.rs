@@ -0,0 +1,439 @@
+// Location of an element inside a payload. Steps start from the first top level element: a tag
+// selects the first member of a Structure or List with that tag, an index the member of an Array or
+// List at that position. The empty path is the first top level element itself.
+//
+// The notation separates tags with '/' and appends indices in brackets: "0/2[3]/1" is context tag
+// 0, then the member at index 3 of the element tagged 2, then tag 1. Profile tags use the TLVTag
+// notation, e.g. "CHIP::1", and the empty path is "/".
+
+use crate::errors::TLVError;
+use crate::reader::TLVReader;
+use crate::tags::TLVTag;
+use crate::types::{ContainerType, TLVType};
+use crate::value::{TLVElement, TLVValue};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PathStep {
+    ContextTag(u8),
+    // Matched whatever the width of its tag field, see TLVTag::canonical_cmp
+    ProfileTag(TLVTag),
+    Index(usize),
+}
+
+impl PathStep {
+    fn matches(&self, tag: &TLVTag, index: usize) -> bool {
+        match self {
+            PathStep::ContextTag(tag_number) => *tag == TLVTag::ContextSpecific(*tag_number),
+            PathStep::ProfileTag(profile_tag) => profile_tag.canonical_cmp(tag).is_eq(),
+            PathStep::Index(step_index) => *step_index == index,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TLVPath {
+    steps: Vec<PathStep>,
+}
+
+impl TLVPath {
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn new(steps: impl IntoIterator<Item = PathStep>) -> Self {
+        Self {
+            steps: steps.into_iter().collect(),
+        }
+    }
+
+    pub fn tag(mut self, tag_number: u8) -> Self {
+        self.steps.push(PathStep::ContextTag(tag_number));
+        self
+    }
+
+    pub fn profile_tag(mut self, tag: TLVTag) -> Self {
+        self.steps.push(PathStep::ProfileTag(tag));
+        self
+    }
+
+    pub fn index(mut self, index: usize) -> Self {
+        self.steps.push(PathStep::Index(index));
+        self
+    }
+
+    pub fn steps(&self) -> &[PathStep] {
+        &self.steps
+    }
+}
+
+impl fmt::Display for PathStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathStep::ContextTag(tag_number) => write!(f, "{}", tag_number),
+            PathStep::ProfileTag(tag) => write!(f, "{}", tag),
+            PathStep::Index(index) => write!(f, "[{}]", index),
+        }
+    }
+}
+
+impl fmt::Display for TLVPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.steps.is_empty() {
+            return write!(f, "/");
+        }
+        for (position, step) in self.steps.iter().enumerate() {
+            if position > 0 && !matches!(step, PathStep::Index(_)) {
+                write!(f, "/")?;
+            }
+            write!(f, "{}", step)?;
+        }
+        Ok(())
+    }
+}
+
+// A leading '/' is accepted. Malformed notations fail with InvalidPath at the byte position of the
+// malformed tag or index.
+impl FromStr for TLVPath {
+    type Err = TLVError;
+
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        let segments = notation.strip_prefix('/').unwrap_or(notation);
+        let mut path = TLVPath::root();
+        if segments.is_empty() {
+            return Ok(path);
+        }
+        let mut position = notation.len() - segments.len();
+        for segment in segments.split('/') {
+            let (tag, mut indices) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+            // A segment of indices only, e.g. "0/[1]", indexes the element of the previous step
+            if !tag.is_empty() || indices.is_empty() {
+                let invalid_tag = TLVError::InvalidPath { position };
+                path = match tag.parse::<TLVTag>().map_err(|_| invalid_tag.clone())? {
+                    TLVTag::ContextSpecific(tag_number) => path.tag(tag_number),
+                    TLVTag::Anonymous => return Err(invalid_tag),
+                    tag => path.profile_tag(tag),
+                };
+            }
+            while !indices.is_empty() {
+                let invalid_index = TLVError::InvalidPath {
+                    position: position + segment.len() - indices.len(),
+                };
+                let (index, rest) = indices
+                    .strip_prefix('[')
+                    .and_then(|indices| indices.split_once(']'))
+                    .filter(|(index, _)| index.bytes().all(|digit| digit.is_ascii_digit()))
+                    .ok_or(invalid_index.clone())?;
+                path = path.index(index.parse().map_err(|_| invalid_index)?);
+                indices = rest;
+            }
+            position += segment.len() + 1;
+        }
+        Ok(path)
+    }
+}
+
+// Moves the cursor of a reader to the element of the path, steps starting from the element under
+// the cursor. Steps which cannot apply to the element reached, e.g. a tag in an Array, fail with
+// InvalidType.
+pub(crate) fn seek(tlv_reader: &mut TLVReader, path: &TLVPath) -> Result<(), TLVError> {
+    for (step_number, step) in path.steps().iter().enumerate() {
+        let container_type = match tlv_reader.read_type()? {
+            TLVType::Container(container_type) => container_type,
+            TLVType::Primitive(_) => return Err(TLVError::InvalidType),
+        };
+        match (container_type, step) {
+            (ContainerType::Structure, PathStep::Index(_)) => return Err(TLVError::InvalidType),
+            (ContainerType::Array, PathStep::ContextTag(_) | PathStep::ProfileTag(_)) => {
+                return Err(TLVError::InvalidType)
+            }
+            _ => {}
+        }
+        let not_found = TLVError::PathNotFound { step: step_number };
+        tlv_reader.enter_container()?;
+        let mut index = 0;
+        loop {
+            match tlv_reader.read_tag() {
+                Ok(tag) if step.matches(&tag, index) => break,
+                Ok(_) => {}
+                Err(TLVError::EndOfContainer) => return Err(not_found),
+                Err(e) => return Err(e),
+            }
+            match tlv_reader.next() {
+                Ok(()) => index += 1,
+                Err(TLVError::EndOfContainer) => return Err(not_found),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    Ok(())
+}
+
+// Value of the path in decoded elements, failing as seek does on the encoded ones
+pub(crate) fn lookup<'a>(
+    elements: &'a [TLVElement],
+    path: &TLVPath,
+) -> Result<&'a TLVValue, TLVError> {
+    let mut value = &elements.first().ok_or(TLVError::EndOfTLV)?.value;
+    for (step_number, step) in path.steps().iter().enumerate() {
+        match (value, step) {
+            (TLVValue::Structure(_), PathStep::Index(_)) => return Err(TLVError::InvalidType),
+            (TLVValue::Array(_), PathStep::ContextTag(_) | PathStep::ProfileTag(_)) => {
+                return Err(TLVError::InvalidType)
+            }
+            (TLVValue::Structure(_) | TLVValue::Array(_) | TLVValue::List(_), _) => {}
+            _ => return Err(TLVError::InvalidType),
+        }
+        value = value
+            .into_iter()
+            .enumerate()
+            .find(|(index, (tag, _))| step.matches(tag, *index))
+            .map(|(_, (_, member))| member)
+            .ok_or(TLVError::PathNotFound { step: step_number })?;
+    }
+    Ok(value)
+}
+
+// Bytes of the element of the path, with its whole subtree
+pub(crate) fn element_range(bytes: &[u8], path: &TLVPath) -> Result<Range<usize>, TLVError> {
+    let mut tlv_reader = TLVReader::new(bytes);
+    seek(&mut tlv_reader, path)?;
+    let start = tlv_reader.offset();
+    Ok(start..start + tlv_reader.current_element_len()?)
+}
+
+// Encoded element of the path with its whole subtree, a payload of its own. Signatures over part of
+// a payload, e.g. the TBS portion of a certificate, are computed over these bytes.
+pub fn extract<'a>(bytes: &'a [u8], path: &TLVPath) -> Result<&'a [u8], TLVError> {
+    Ok(&bytes[element_range(bytes, path)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::TLVReaderConfig;
+    use crate::tags::{well_known, CommonProfileLength};
+    use crate::value::TLVDocument;
+    use alloc::vec;
+
+    // Structure {0 = 42U, 1 = [true, "Hi"], 2 = List [[3 = Null, CHIP::1 = 7U]]}
+    const TEST_BYTES: &[u8] = &[
+        0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x0c, 0x02, 0x48, 0x69, 0x18, 0x37, 0x02, 0x34,
+        0x03, 0x44, 0x01, 0x00, 0x07, 0x18, 0x18,
+    ];
+
+    #[test]
+    fn test_path_notation() {
+        let chip_1 = TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 1 });
+        let test_vectors = [
+            ("/", TLVPath::root()),
+            ("0", TLVPath::root().tag(0)),
+            ("0/2[3]/1", TLVPath::root().tag(0).tag(2).index(3).tag(1)),
+            ("[0][12]", TLVPath::root().index(0).index(12)),
+            ("[1]/255", TLVPath::root().index(1).tag(255)),
+            (
+                "2/CHIP::1",
+                TLVPath::root().tag(2).profile_tag(chip_1.clone()),
+            ),
+            (
+                "Implicit::70000[0]/65521::1:2",
+                TLVPath::root()
+                    .profile_tag("Implicit::70000".parse().expect("Failed to parse tag"))
+                    .index(0)
+                    .profile_tag("65521::1:2".parse().expect("Failed to parse tag")),
+            ),
+        ];
+        for (notation, path) in test_vectors {
+            assert_eq!(
+                notation.parse::<TLVPath>().as_ref(),
+                Ok(&path),
+                "{}",
+                notation
+            );
+            assert_eq!(path.to_string(), notation);
+        }
+
+        // Leading '/', indices in a segment of their own
+        assert_eq!("".parse(), Ok(TLVPath::root()));
+        assert_eq!(
+            "/0/2[3]".parse(),
+            Ok(TLVPath::root().tag(0).tag(2).index(3))
+        );
+        assert_eq!(
+            "2/[3][4]".parse(),
+            Ok(TLVPath::root().tag(2).index(3).index(4))
+        );
+        // Profile tags of any width print in their notation, matching the same members
+        let chip_1_wide = TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 1 });
+        assert_eq!(
+            TLVPath::root().profile_tag(chip_1_wide).to_string(),
+            "CHIP::1"
+        );
+    }
+
+    #[test]
+    fn test_path_notation_errors() {
+        let test_vectors = [
+            ("0/", 2),
+            ("0//1", 2),
+            ("//", 1),
+            ("256", 0),
+            ("0/-1", 2),
+            ("x", 0),
+            ("Anonymous", 0),
+            ("0/CHIP::", 2),
+            ("0[", 1),
+            ("0[]", 1),
+            ("0[1", 1),
+            ("0[+1]", 1),
+            ("0[1]x", 4),
+            ("1/2[0][a]", 6),
+            ("[99999999999999999999999]", 0),
+        ];
+        for (notation, position) in test_vectors {
+            assert_eq!(
+                notation.parse::<TLVPath>(),
+                Err(TLVError::InvalidPath { position }),
+                "{}",
+                notation
+            );
+        }
+    }
+
+    #[test]
+    fn test_element_range() {
+        assert_eq!(element_range(TEST_BYTES, &TLVPath::root()), Ok(0..22));
+        assert_eq!(element_range(TEST_BYTES, &TLVPath::root().tag(0)), Ok(1..4));
+        assert_eq!(
+            element_range(TEST_BYTES, &TLVPath::root().tag(1)),
+            Ok(4..12)
+        );
+        assert_eq!(
+            element_range(TEST_BYTES, &TLVPath::root().tag(1).index(1)),
+            Ok(7..11)
+        );
+        assert_eq!(
+            element_range(TEST_BYTES, &TLVPath::root().tag(2).index(0)),
+            Ok(14..16)
+        );
+        // Four-octet form of the two-octet tag in the payload
+        let chip_1 = TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 1 });
+        assert_eq!(
+            element_range(TEST_BYTES, &TLVPath::root().tag(2).profile_tag(chip_1)),
+            Ok(16..20)
+        );
+        assert_eq!(
+            element_range(
+                TEST_BYTES,
+                &TLVPath::new([PathStep::ContextTag(2), PathStep::Index(1)])
+            ),
+            Ok(16..20)
+        );
+    }
+
+    #[test]
+    fn test_element_range_errors() {
+        assert_eq!(
+            element_range(TEST_BYTES, &TLVPath::root().tag(4)),
+            Err(TLVError::PathNotFound { step: 0 })
+        );
+        assert_eq!(
+            element_range(TEST_BYTES, &TLVPath::root().tag(1).index(2)),
+            Err(TLVError::PathNotFound { step: 1 })
+        );
+        assert_eq!(
+            element_range(TEST_BYTES, &TLVPath::root().index(0)),
+            Err(TLVError::InvalidType)
+        );
+        assert_eq!(
+            element_range(TEST_BYTES, &TLVPath::root().tag(1).tag(0)),
+            Err(TLVError::InvalidType)
+        );
+        assert_eq!(
+            element_range(TEST_BYTES, &TLVPath::root().tag(0).tag(0)),
+            Err(TLVError::InvalidType)
+        );
+        // Structure {} and Structure {0 = 42U cut short
+        assert_eq!(
+            element_range(&[0x15, 0x18], &TLVPath::root().tag(0)),
+            Err(TLVError::PathNotFound { step: 0 })
+        );
+        assert_eq!(
+            element_range(&[0x15, 0x24, 0x00], &TLVPath::root().tag(1)),
+            Err(TLVError::TruncatedValue {
+                expected: 1,
+                available: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_extract() {
+        // Nested containers of every kind around the elements extracted, with members after them
+        let bytes = TLVDocument::builder()
+            .structure(TLVTag::Anonymous, |s| {
+                s.bytes(1, &[0x01, 0x02])
+                    .list(3, |l| l.uint(17, 1).structure(TLVTag::Anonymous, |s| s))
+                    .structure(6, |s| {
+                        s.array(1, |a| {
+                            a.structure(|s| s.uint(0, 1).string(1, "a"))
+                                .structure(|s| s)
+                                .array(|a| a.array(|a| a))
+                        })
+                        .string(2, "x")
+                        .value(well_known::matter_core_tag(5), &-1i32)
+                    })
+                    .bytes(11, &[0xff; 64])
+            })
+            .into_bytes()
+            .expect("Failed to build document");
+        let document = TLVDocument::parse(&bytes).expect("Failed to parse document");
+        let paths = [
+            TLVPath::root(),
+            TLVPath::root().tag(1),
+            TLVPath::root().tag(3),
+            TLVPath::root().tag(3).index(1),
+            TLVPath::root().tag(6),
+            TLVPath::root().tag(6).tag(1),
+            TLVPath::root().tag(6).tag(1).index(0),
+            TLVPath::root().tag(6).tag(1).index(0).tag(1),
+            TLVPath::root().tag(6).tag(1).index(1),
+            TLVPath::root().tag(6).tag(1).index(2).index(0),
+            TLVPath::root()
+                .tag(6)
+                .profile_tag(well_known::matter_core_tag(5)),
+            TLVPath::root().tag(11),
+        ];
+        let config = TLVReaderConfig::default().single_element(true);
+        for path in &paths {
+            let element = extract(&bytes, path).expect("Failed to extract");
+            let extracted =
+                TLVDocument::parse_with_config(element, &config).expect("Failed to parse element");
+            assert_eq!(extracted.encode(), element, "{:?}", path);
+
+            // Same element found in the decoded tree
+            let mut expected = document.elements[0].clone();
+            for step in path.steps() {
+                expected = (&expected.value)
+                    .into_iter()
+                    .enumerate()
+                    .find(|(index, (tag, _))| step.matches(tag, *index))
+                    .map(|(_, (tag, value))| TLVElement::new(tag.clone(), value.clone()))
+                    .expect("Failed to find the element in the tree");
+            }
+            let reencoded = TLVDocument {
+                elements: vec![expected],
+            }
+            .encode();
+            assert_eq!(element, reencoded, "{:?}", path);
+        }
+        assert!(extract(&bytes, &TLVPath::root()).is_ok_and(|element| element == bytes));
+        assert_eq!(
+            extract(&bytes, &TLVPath::root().tag(6).tag(3)),
+            Err(TLVError::PathNotFound { step: 1 })
+        );
+    }
+}
@@ -1,46 +1,565 @@
 #![allow(dead_code)] // Until the Library is used
 
 use crate::errors::TLVError;
-use crate::tags::TLVTag;
-use crate::types::{ElementType, PrimitiveLengthType, SpecifiedLenPrimitive, TLVType};
-use crate::{tags, util};
-use log::error;
-use nom::Finish;
-use std::cmp::Ordering;
-
-struct TLVReader {
-    bytes: Vec<u8>,
+use crate::path::{self, TLVPath};
+use crate::tags::{ControlByteInfo, TLVTag};
+use crate::types::{
+    ContainerType, ElementType, FloatingPoint, PredeterminedLenPrimitive, PrimitiveLengthType,
+    SignedInteger, SpecifiedLenPrimitive, TLVFieldSize, TLVType, UnsignedInteger,
+};
+use crate::value::{TLVValue, TLVValueRef};
+use crate::{canonical, hex, tags, value, wire};
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use bytes::{Buf, Bytes};
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Deref, Range};
+use num::FromPrimitive;
+
+#[cfg(feature = "derive")]
+pub use tlv_derive::TLVDecode;
+
+// Encoded size of the first element of the bytes: control byte, tag, length field and value, or
+// the whole subtree of a container up to its End of Container. Values are not decoded, so it is
+// also where the reader finds the next element.
+pub fn element_size(bytes: &[u8]) -> Result<usize, TLVError> {
+    TLVReader::element_size_at(bytes, 0, usize::MAX)
+}
+
+// Decodes the element at the cursor, the cursor is left on that element
+pub trait TLVDecode: Sized {
+    fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError>;
+    // Value of an absent structure member, None if the member is required
+    fn decode_absent() -> Option<Self> {
+        None
+    }
+}
+
+macro_rules! decode_integer {
+    ($type:ty, $read:ident) => {
+        impl TLVDecode for $type {
+            fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+                <$type>::try_from(tlv_reader.$read()?).map_err(|_| TLVError::InvalidType)
+            }
+        }
+    };
+}
+
+decode_integer!(u8, read_uint);
+decode_integer!(u16, read_uint);
+decode_integer!(u32, read_uint);
+decode_integer!(u64, read_uint);
+decode_integer!(i8, read_int);
+decode_integer!(i16, read_int);
+decode_integer!(i32, read_int);
+decode_integer!(i64, read_int);
+
+impl TLVDecode for f32 {
+    fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+        tlv_reader.read_f32()
+    }
+}
+
+impl TLVDecode for f64 {
+    fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+        tlv_reader.read_f64()
+    }
+}
+
+impl TLVDecode for bool {
+    fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+        tlv_reader.read_bool()
+    }
+}
+
+impl TLVDecode for String {
+    fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+        tlv_reader.read_char_str()
+    }
+}
+
+impl TLVDecode for Bytes {
+    fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+        tlv_reader.read_bytes()
+    }
+}
+
+// Null or absent
+impl<T: TLVDecode> TLVDecode for Option<T> {
+    fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+        match tlv_reader.read_null() {
+            Ok(()) => Ok(None),
+            Err(TLVError::InvalidType) => Ok(Some(T::decode_tlv(tlv_reader)?)),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn decode_absent() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: TLVDecode> TLVDecode for Vec<T> {
+    fn decode_tlv(tlv_reader: &mut TLVReader) -> Result<Self, TLVError> {
+        if tlv_reader.read_type()? != TLVType::Container(ContainerType::Array) {
+            return Err(TLVError::InvalidType);
+        }
+        let mut elements = Vec::new();
+        tlv_reader.enter_container()?;
+        loop {
+            match tlv_reader.read_type() {
+                Err(TLVError::EndOfContainer) => break,
+                result => result?,
+            };
+            elements.push(T::decode_tlv(tlv_reader)?);
+            match tlv_reader.next() {
+                Err(TLVError::EndOfContainer) => break,
+                result => result?,
+            }
+        }
+        tlv_reader.exit_container()?;
+        Ok(elements)
+    }
+}
+
+// Tag and type of the element under the cursor, parsed once whenever the cursor moves
+struct ElementHeader {
+    tag: TLVTag,
+    tlv_type: TLVType,
+    value_offset: usize, // Past the control byte and tag
+}
+
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+pub const DEFAULT_MAX_ELEMENT_LEN: usize = 1 << 20;
+pub const DEFAULT_MAX_ELEMENTS: usize = 1 << 16;
+pub const DEFAULT_REWIND_DEPTH: usize = 4;
+
+// Limits and strictness of the reader, shared by TLVDocument::parse_with_config and validate()
+#[derive(Debug, Copy, Clone)]
+pub struct TLVReaderConfig {
+    pub max_depth: usize,
+    // Declared length of strings, checked before reading their value
+    pub max_element_len: usize,
+    // Control bytes processed in one payload, End of Container markers included
+    pub max_elements: usize,
+    // Elements next() left that rewind_element() can go back to, the oldest are forgotten first
+    pub rewind_depth: usize,
+    // Structure members sharing a tag, List members may share theirs
+    pub reject_duplicate_tags: bool,
+    // Structure members are tagged, Array members anonymous, List members either
+    pub enforce_tag_rules: bool,
+    // Otherwise invalid sequences are replaced when decoding strings
+    pub check_utf8: bool,
+    // Anything after the first top level element is trailing
+    pub single_element: bool,
+    // Tags, integers and length fields as narrow as canonicalize() writes them
+    pub require_minimal_encoding: bool,
+    // Structure members in canonical tag order, as canonicalize() sorts them
+    pub require_canonical_order: bool,
+    // UTF-8 strings without NUL characters, as the spec requires
+    pub strict_strings: bool,
+    // UTF-8 strings in Normalization Form C
+    #[cfg(feature = "unicode")]
+    pub require_nfc: bool,
+}
+
+impl Default for TLVReaderConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_element_len: DEFAULT_MAX_ELEMENT_LEN,
+            max_elements: DEFAULT_MAX_ELEMENTS,
+            rewind_depth: DEFAULT_REWIND_DEPTH,
+            reject_duplicate_tags: false,
+            enforce_tag_rules: true,
+            check_utf8: true,
+            single_element: false,
+            require_minimal_encoding: false,
+            require_canonical_order: false,
+            strict_strings: false,
+            #[cfg(feature = "unicode")]
+            require_nfc: false,
+        }
+    }
+}
+
+impl TLVReaderConfig {
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn max_element_len(mut self, max_element_len: usize) -> Self {
+        self.max_element_len = max_element_len;
+        self
+    }
+
+    pub fn max_elements(mut self, max_elements: usize) -> Self {
+        self.max_elements = max_elements;
+        self
+    }
+
+    pub fn rewind_depth(mut self, rewind_depth: usize) -> Self {
+        self.rewind_depth = rewind_depth;
+        self
+    }
+
+    pub fn reject_duplicate_tags(mut self, reject_duplicate_tags: bool) -> Self {
+        self.reject_duplicate_tags = reject_duplicate_tags;
+        self
+    }
+
+    pub fn enforce_tag_rules(mut self, enforce_tag_rules: bool) -> Self {
+        self.enforce_tag_rules = enforce_tag_rules;
+        self
+    }
+
+    pub fn check_utf8(mut self, check_utf8: bool) -> Self {
+        self.check_utf8 = check_utf8;
+        self
+    }
+
+    pub fn single_element(mut self, single_element: bool) -> Self {
+        self.single_element = single_element;
+        self
+    }
+
+    pub fn require_minimal_encoding(mut self, require_minimal_encoding: bool) -> Self {
+        self.require_minimal_encoding = require_minimal_encoding;
+        self
+    }
+
+    pub fn require_canonical_order(mut self, require_canonical_order: bool) -> Self {
+        self.require_canonical_order = require_canonical_order;
+        self
+    }
+
+    pub fn strict_strings(mut self, strict_strings: bool) -> Self {
+        self.strict_strings = strict_strings;
+        self
+    }
+
+    #[cfg(feature = "unicode")]
+    pub fn require_nfc(mut self, require_nfc: bool) -> Self {
+        self.require_nfc = require_nfc;
+        self
+    }
+
+    // Spec constraints on the value of a UTF-8 string beyond its validity, offsets are the payload's
+    pub(crate) fn check_string(&self, value: &[u8], value_offset: usize) -> Result<(), TLVError> {
+        // U+0000 is the only character whose encoding contains a zero byte
+        if let (true, Some(position)) = (
+            self.strict_strings,
+            value.iter().position(|byte| *byte == 0),
+        ) {
+            return Err(TLVError::NulCharacter {
+                offset: value_offset + position,
+            });
+        }
+        // Invalid UTF-8 is either rejected already or replaced, it is not normalized
+        #[cfg(feature = "unicode")]
+        if self.require_nfc
+            && core::str::from_utf8(value).is_ok_and(|value| !crate::unicode::is_nfc(value))
+        {
+            return Err(TLVError::NotNfcNormalized {
+                offset: value_offset,
+            });
+        }
+        Ok(())
+    }
+}
+
+// Container opened by element_ref_at, with its tag and the members read so far
+type OpenContainerRef<'a> = (ContainerType, TLVTag, Vec<(TLVTag, TLVValueRef<'a>)>);
+
+pub struct TLVReader {
+    bytes: Bytes,
     bytes_read: usize,
+    containers: Vec<(ContainerType, usize)>, // Entered containers and offsets of their control bytes
+    header: Result<ElementHeader, TLVError>,
+    config: TLVReaderConfig,
+    elements: usize, // Control bytes the cursor went through, the one under it included
+    start: usize,    // Offset of the first top level element
+    history: VecDeque<(usize, usize)>, // Depths and offsets of the elements next() left, see rewind_element
+}
+
+// Bytes shown on each side of the cursor in Debug output
+const DEBUG_WINDOW: usize = 8;
+
+// Position of the cursor and a preview of the element under it rather than the whole buffer
+impl fmt::Debug for TLVReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cursor = self.bytes_read.min(self.bytes.len());
+        let start = cursor.saturating_sub(DEBUG_WINDOW);
+        let end = (cursor + DEBUG_WINDOW + 1).min(self.bytes.len());
+        let mut window = String::new();
+        if start > 0 {
+            window.push_str("..");
+        }
+        for (offset, byte) in self.bytes[start..end].iter().enumerate() {
+            if start + offset == cursor {
+                window.push_str(&format!("[{:02x}]", byte));
+            } else {
+                window.push_str(&format!("{:02x}", byte));
+            }
+        }
+        if cursor == self.bytes.len() {
+            window.push_str("[]");
+        }
+        if end < self.bytes.len() {
+            window.push_str("..");
+        }
+        let mut debug_struct = f.debug_struct("TLVReader");
+        debug_struct
+            .field("len", &self.bytes.len())
+            .field("bytes_read", &self.bytes_read)
+            .field("depth", &self.containers.len())
+            .field("container", &self.container_type());
+        match (&self.header, self.bytes.get(cursor)) {
+            (Ok(header), Some(control_byte)) => debug_struct.field(
+                "next",
+                &format_args!(
+                    "{:#04x} {:?} {:?}",
+                    control_byte, header.tag, header.tlv_type
+                ),
+            ),
+            (Err(e), _) => debug_struct.field("next", e),
+            (Ok(_), None) => debug_struct.field("next", &TLVError::EndOfTLV),
+        };
+        debug_struct
+            .field("window", &format_args!("{}", window))
+            .finish()
+    }
 }
 
 impl TLVReader {
-    fn new(bytes: &[u8]) -> Self {
+    pub fn new(bytes: &[u8]) -> Self {
+        Self::with_config(bytes, TLVReaderConfig::default())
+    }
+
+    pub fn with_config(bytes: &[u8], config: TLVReaderConfig) -> Self {
+        Self::from_bytes_with_config(Bytes::copy_from_slice(bytes), config)
+    }
+
+    // Shares the buffer rather than copying it
+    pub fn from_bytes(bytes: Bytes) -> Self {
+        Self::from_bytes_with_config(bytes, TLVReaderConfig::default())
+    }
+
+    pub fn from_bytes_with_config(bytes: Bytes, config: TLVReaderConfig) -> Self {
+        Self::from_bytes_at(bytes, 0, config)
+    }
+
+    // Payload starting at the offset, e.g. past the header of a packet. Offsets in errors and
+    // position queries are those of the whole buffer, so they line up with packet captures.
+    pub fn new_at(bytes: &[u8], offset: usize) -> Result<Self, TLVError> {
+        Self::new_range(bytes, offset..bytes.len())
+    }
+
+    // Payload in the range of the buffer, the bytes after it are never read. The buffer is copied
+    // up to the end of the range as with new, from_bytes_range shares it.
+    pub fn new_range(bytes: &[u8], range: Range<usize>) -> Result<Self, TLVError> {
+        if range.end > bytes.len() {
+            return Err(TLVError::ValueOutOfRange);
+        }
+        Self::from_bytes_range(Bytes::copy_from_slice(&bytes[..range.end]), range)
+    }
+
+    // Payload in the range of the buffer without copying it, offsets are those of the whole buffer
+    pub fn from_bytes_range(bytes: Bytes, range: Range<usize>) -> Result<Self, TLVError> {
+        if range.start > range.end || range.end > bytes.len() {
+            return Err(TLVError::ValueOutOfRange);
+        }
+        Ok(Self::from_bytes_at(
+            bytes.slice(..range.end),
+            range.start,
+            TLVReaderConfig::default(),
+        ))
+    }
+
+    pub(crate) fn from_bytes_at(bytes: Bytes, start: usize, config: TLVReaderConfig) -> Self {
+        let header = Self::parse_header(&bytes, start, &config);
+        let elements = usize::from(start < bytes.len());
         Self {
-            bytes: bytes.to_owned(),
-            bytes_read: 0,
+            bytes,
+            bytes_read: start,
+            containers: Vec::new(),
+            header,
+            config,
+            elements,
+            start,
+            history: VecDeque::new(),
+        }
+    }
+
+    // A Bytes buffer is shared, buffers made of several chunks are gathered into one
+    pub fn from_buf(mut buf: impl Buf) -> Self {
+        Self::from_bytes(buf.copy_to_bytes(buf.remaining()))
+    }
+
+    pub fn from_hex(hex: &str) -> Result<Self, TLVError> {
+        Ok(Self::from_bytes(Bytes::from(hex::from_hex(hex)?)))
+    }
+
+    #[cfg(feature = "base64")]
+    pub fn from_base64(base64: &str) -> Result<Self, TLVError> {
+        Ok(Self::from_bytes(Bytes::from(crate::base64::decode_base64(
+            base64,
+        )?)))
+    }
+
+    // Moves the cursor down to the element of the path, steps starting from the element under the
+    // cursor, see TLVPath. The containers on the way are entered, exit_container goes back up.
+    pub fn descend(&mut self, path: &TLVPath) -> Result<(), TLVError> {
+        path::seek(self, path)
+    }
+
+    // Appends bytes to the payload, e.g. the next segment of a message being reassembled. The cursor,
+    // entered containers and rewind history are kept and the element under the cursor is parsed
+    // again, so a read that failed for lack of bytes, see TLVError::is_cut_short, or with EndOfTLV
+    // can be retried. The buffer is copied into a new one holding both.
+    pub fn extend_payload(&mut self, more: &[u8]) {
+        if more.is_empty() {
+            return;
+        }
+        let at_end = self.bytes_read == self.bytes.len();
+        let mut bytes = Vec::with_capacity(self.bytes.len() + more.len());
+        bytes.extend_from_slice(&self.bytes);
+        bytes.extend_from_slice(more);
+        self.bytes = Bytes::from(bytes);
+        // Elements are counted once the cursor is on them, one past the end was not
+        let counted = if at_end && !Self::is_end_of_container(more) {
+            self.count_element()
+        } else {
+            Ok(())
+        };
+        self.move_to(self.bytes_read);
+        if let Err(e) = counted {
+            self.header = Err(e);
+        }
+    }
+
+    // Reader over the element under the cursor and its subtree, sharing the buffer
+    pub fn fork(&self) -> Result<Self, TLVError> {
+        let element_len = self.current_element_len()?;
+        let element = self.bytes_read..self.bytes_read + element_len;
+        Ok(Self::from_bytes_with_config(
+            self.bytes.slice(element),
+            self.config,
+        ))
+    }
+
+    // Standalone reader over the container under the cursor, from its control byte through its End
+    // of Container, then moves past the container as take does. The buffer is shared, the reader
+    // can be handed to another thread and reads the container whatever the parent does.
+    pub fn to_owned_subtree(&mut self) -> Result<Self, TLVError> {
+        let tlv_type = self.read_type()?;
+        if !matches!(tlv_type, TLVType::Container(_)) {
+            return Err(self.type_mismatch("a container", tlv_type));
         }
+        let subtree = self.fork()?;
+        self.advance()?;
+        Ok(subtree)
     }
 
     fn current_element(&self) -> &[u8] {
         self.bytes[(self.bytes_read)..].as_ref()
     }
 
-    fn parse_control_byte(&self) -> Result<(&[u8], (u8, u8)), TLVError> {
-        util::split_byte_into_2_parts(self.current_element(), (3usize, 5usize))
-            .finish()
-            .map_err(|e| {
-                error!("Cannot parse control byte {}", e.code.description());
-                TLVError::ParseError
-            })
+    fn parse_control_byte(&self) -> Result<(&[u8], ControlByteInfo), TLVError> {
+        Self::split_control_byte(self.current_element(), self.bytes_read)
     }
 
-    fn parse_control(&self) -> Result<(&[u8], TLVTag, TLVType), TLVError> {
-        let (remaining_bytes, (tag_control_byte, element_type_byte)) = self.parse_control_byte()?;
-        let (remaining_bytes, tlv_tag) = tags::parse_tag(
-            tag_control_byte << tags::CONTROL_BYTE_SHIFT,
-            remaining_bytes,
-        )?;
-        let tlv_type = Self::tlv_type(element_type_byte)?;
+    // Reserved element types are reported with the offset of the element
+    fn split_control_byte(
+        element: &[u8],
+        offset: usize,
+    ) -> Result<(&[u8], ControlByteInfo), TLVError> {
+        let (remaining_bytes, control_byte) = wire::parse_control_byte(element)?;
+        match ControlByteInfo::parse(element[0]) {
+            Ok(control_byte_info) => Ok((remaining_bytes, control_byte_info)),
+            Err(TLVError::InvalidType) => {
+                trace!(
+                    "Reserved element type {:#04x} at offset {}",
+                    control_byte.element_type,
+                    offset
+                );
+                Err(TLVError::UnknownElementType {
+                    value: control_byte.element_type,
+                    offset,
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn parse_header(
+        bytes: &[u8],
+        offset: usize,
+        config: &TLVReaderConfig,
+    ) -> Result<ElementHeader, TLVError> {
+        let (remaining_bytes, tag, tlv_type) = Self::parse_element_control(bytes, offset)?;
+        if config.require_minimal_encoding
+            && !canonical::is_minimal_encoding(&tag, tlv_type, remaining_bytes)
+        {
+            trace!("Non minimal encoding of the element at offset {}", offset);
+            return Err(TLVError::NonMinimalEncoding { offset });
+        }
+        Ok(ElementHeader {
+            tag,
+            tlv_type,
+            value_offset: bytes.len() - remaining_bytes.len(),
+        })
+    }
+
+    fn move_to(&mut self, offset: usize) {
+        self.bytes_read = offset;
+        self.header = match Self::parse_header(&self.bytes, offset, &self.config) {
+            Err(TLVError::EndOfTLV) if !self.containers.is_empty() => {
+                Err(self.unterminated(offset))
+            }
+            header => header,
+        };
+    }
+
+    // The payload ends before the End of Container of the outermost entered container, or of the
+    // container at the offset when none is entered
+    fn unterminated(&self, container_start: usize) -> TLVError {
+        let offset = self
+            .containers
+            .first()
+            .map_or(container_start, |(_, offset)| *offset);
+        trace!("Unterminated container at offset {}", offset);
+        TLVError::UnterminatedContainer { offset }
+    }
+
+    #[inline]
+    fn parse_control(&self) -> Result<(&[u8], &TLVTag, TLVType), TLVError> {
+        match &self.header {
+            Ok(header) => Ok((
+                &self.bytes[header.value_offset..],
+                &header.tag,
+                header.tlv_type,
+            )),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    // Element at the offset of the buffer
+    fn parse_element_control(
+        bytes: &[u8],
+        offset: usize,
+    ) -> Result<(&[u8], TLVTag, TLVType), TLVError> {
+        let (remaining_bytes, control_byte) = Self::split_control_byte(&bytes[offset..], offset)?;
+        if control_byte.element_type == ElementType::EndOfContainer {
+            return Err(TLVError::EndOfContainer);
+        }
+        let (remaining_bytes, tlv_tag) =
+            tags::parse_tag(control_byte.tag_control as u8, remaining_bytes)?;
+        let tlv_type = TLVType::try_from(control_byte.element_type)?;
         Ok((remaining_bytes, tlv_tag, tlv_type))
     }
 
@@ -63,516 +582,3789 @@ impl TLVReader {
         })
     }
 
-    fn tlv_type(element_type_byte: u8) -> Result<TLVType, TLVError> {
-        let element_type = ElementType::try_from(element_type_byte)?;
-        let tlv_type = TLVType::try_from(element_type)?;
-        Ok(tlv_type)
+    fn is_end_of_container(element: &[u8]) -> bool {
+        element.first() == Some(&(ElementType::EndOfContainer as u8))
     }
 
-    fn next(&mut self) -> Result<(), TLVError> {
-        let (remaining_bytes, tlv_tag, tlv_type) = self.parse_control()?;
-        let length_and_value_octets_count = match tlv_type {
-            TLVType::Container(_) => todo!("Skip to the End of Container"),
-            TLVType::Primitive(primitive_length_type) => {
-                let (_, length_octets_count, value_octets_count) =
-                    Self::parse_primitive_len(primitive_length_type, remaining_bytes)?;
-                length_octets_count + value_octets_count
+    // Encoded size of the element at the offset of the buffer, see element_size. More than
+    // max_elements elements fail with TooManyElements, reaching the end of the buffer inside a
+    // container with UnterminatedContainer at the offset.
+    fn element_size_at(
+        bytes: &[u8],
+        element_offset: usize,
+        max_elements: usize,
+    ) -> Result<usize, TLVError> {
+        let element = &bytes[element_offset..];
+        let mut offset = 0;
+        let mut depth = 0;
+        let mut elements = 0;
+        loop {
+            elements += 1;
+            if elements > max_elements {
+                trace!(
+                    "More than {} elements in the element at offset {}",
+                    max_elements,
+                    element_offset
+                );
+                return Err(TLVError::TooManyElements {
+                    limit: max_elements,
+                });
             }
-        };
-        let element_len = length_and_value_octets_count + tlv_tag.octets_count() as usize + 1; // +1 for control byte
+            let current = &element[offset..];
+            if depth > 0 && Self::is_end_of_container(current) {
+                offset += 1;
+                depth -= 1;
+            } else {
+                let (remaining_bytes, _, tlv_type) =
+                    match Self::parse_element_control(bytes, element_offset + offset) {
+                        Err(TLVError::EndOfTLV) if depth > 0 => {
+                            return Err(TLVError::UnterminatedContainer {
+                                offset: element_offset,
+                            })
+                        }
+                        result => result?,
+                    };
+                offset += current.len() - remaining_bytes.len(); // Control byte and tag
+                match tlv_type {
+                    TLVType::Container(_) => depth += 1,
+                    TLVType::Primitive(primitive_length_type) => {
+                        let (_, length_octets_count, value_octets_count) =
+                            Self::parse_primitive_len(primitive_length_type, remaining_bytes)?;
+                        offset += length_octets_count;
+                        let available = element.len() - offset;
+                        if let PrimitiveLengthType::Predetermined(_) = primitive_length_type {
+                            if value_octets_count > available {
+                                return Err(TLVError::TruncatedValue {
+                                    expected: value_octets_count,
+                                    available,
+                                });
+                            }
+                        }
+                        offset = offset
+                            .checked_add(value_octets_count)
+                            .filter(|end| *end <= element.len())
+                            .ok_or(TLVError::UnderRun)?;
+                    }
+                }
+            }
+            if depth == 0 {
+                return Ok(offset);
+            }
+        }
+    }
+
+    // Size of the element at the offset, an unterminated container is reported at the outermost
+    // container entered
+    fn element_len(&self, element_offset: usize) -> Result<usize, TLVError> {
+        Self::element_size_at(&self.bytes, element_offset, self.config.max_elements).map_err(|e| {
+            match e {
+                TLVError::UnterminatedContainer { offset } => self.unterminated(offset),
+                e => e,
+            }
+        })
+    }
+
+    // Size of the element under the cursor, errors of its header come first
+    pub(crate) fn current_element_len(&self) -> Result<usize, TLVError> {
+        self.parse_control()?;
+        self.element_len(self.bytes_read)
+    }
+
+    #[allow(clippy::should_implement_trait)] // Advances the cursor, does not yield elements
+    pub fn next(&mut self) -> Result<(), TLVError> {
+        let element_len = self.current_element_len()?;
         let next_element = self.bytes_read + element_len;
         match next_element.cmp(&self.bytes.len()) {
             Ordering::Greater => Err(TLVError::UnderRun),
+            Ordering::Equal if !self.containers.is_empty() => Err(self.unterminated(next_element)),
             Ordering::Equal => Err(TLVError::EndOfTLV),
+            Ordering::Less if Self::is_end_of_container(&self.bytes[next_element..]) => {
+                Err(TLVError::EndOfContainer)
+            }
             Ordering::Less => {
-                self.bytes_read = next_element;
+                self.count_element()?;
+                self.remember(self.bytes_read);
+                self.move_to(next_element);
                 Ok(())
             }
         }
     }
 
-    fn read_tag(&self) -> Result<TLVTag, TLVError> {
-        let (_, tlv_tag, _) = self.parse_control()?;
-        Ok(tlv_tag)
-    }
-
-    fn read_u8(&self) -> Result<u8, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::UInt8)? {
-            let (_, value) = util::parse_u8(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
+    // Moves the cursor back to the element the last next() left, in the container the cursor is
+    // in. Entering a container starts with no history, exiting it goes back to the history of the
+    // parent, so rewinding after exit_container() moves to the sibling before the container.
+    // Elements read again count again against max_elements.
+    pub fn rewind_element(&mut self) -> Result<(), TLVError> {
+        match self.history.back() {
+            Some(&(depth, offset)) if depth == self.containers.len() => {
+                self.history.pop_back();
+                self.move_to(offset);
+                Ok(())
+            }
+            _ => Err(TLVError::NothingToRewind),
         }
     }
 
-    fn read_u16(&self) -> Result<u16, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::UInt16)? {
-            let (_, value) = util::parse_u16(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
+    fn remember(&mut self, offset: usize) {
+        if self.config.rewind_depth == 0 {
+            return;
         }
-    }
-
-    fn read_u32(&self) -> Result<u32, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::UInt32)? {
-            let (_, value) = util::parse_u32(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
+        if self.history.len() == self.config.rewind_depth {
+            self.history.pop_front();
         }
+        self.history.push_back((self.containers.len(), offset));
     }
 
-    fn read_u64(&self) -> Result<u64, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::UInt64)? {
-            let (_, value) = util::parse_u64(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
-        }
+    // Offsets in containers left are of no use anymore
+    fn forget_exited(&mut self) {
+        let depth = self.containers.len();
+        self.history
+            .retain(|(element_depth, _)| *element_depth <= depth);
     }
 
-    fn read_i8(&self) -> Result<i8, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::Int8)? {
-            let (_, value) = util::parse_i8(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
+    // Decodes the member of the entered structure with the context tag, wherever it is among the
+    // members, failing with MissingField without one. The cursor is left where it was.
+    pub fn read_field<T: TLVDecode>(&mut self, tag: u8) -> Result<T, TLVError> {
+        match self.find_field(tag)? {
+            Some(offset) => self.decode_at(offset),
+            None => Err(TLVError::MissingField { tag }),
         }
     }
 
-    fn read_i16(&self) -> Result<i16, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::Int16)? {
-            let (_, value) = util::parse_i16(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
+    // Absent and Null members are both None, the member is decoded as an Option<T> would be
+    pub fn try_read_field<T: TLVDecode>(&mut self, tag: u8) -> Result<Option<T>, TLVError> {
+        match self.find_field(tag)? {
+            Some(offset) => self.decode_at(offset),
+            None => Ok(None),
         }
     }
 
-    fn read_i32(&self) -> Result<i32, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::Int32)? {
-            let (_, value) = util::parse_i32(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
+    // Offset of the member of the entered structure with the context tag
+    fn find_field(&self, tag: u8) -> Result<Option<usize>, TLVError> {
+        let wanted = TLVTag::ContextSpecific(tag);
+        let (container_type, container_start) =
+            *self.containers.last().ok_or(TLVError::NotInContainer)?;
+        if container_type != ContainerType::Structure {
+            return Err(TLVError::InvalidType);
+        }
+        let mut offset =
+            Self::parse_header(&self.bytes, container_start, &self.config)?.value_offset;
+        loop {
+            let element = &self.bytes[offset..];
+            if element.is_empty() {
+                return Err(self.unterminated(offset));
+            }
+            if Self::is_end_of_container(element) {
+                return Ok(None);
+            }
+            let (_, member_tag, _) = Self::parse_element_control(&self.bytes, offset)?;
+            if member_tag == wanted {
+                return Ok(Some(offset));
+            }
+            offset += self.element_len(offset)?;
         }
     }
 
-    fn read_i64(&self) -> Result<i64, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::Int64)? {
-            let (_, value) = util::parse_i64(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
-        }
+    // View of the members of the container under the cursor, see ScopedReader. The cursor stays on
+    // the container.
+    pub fn enter_scoped(&self) -> Result<ScopedReader, TLVError> {
+        let mut reader = self.fork()?;
+        reader.enter_container()?;
+        Ok(ScopedReader { reader })
     }
 
-    fn read_f32(&self) -> Result<f32, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::FloatingPointNumber32)? {
-            let (_, value) = util::parse_f32(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
+    // Enters the Array under the cursor, the parent is borrowed until the ArrayReader is dropped
+    pub fn enter_array(&mut self) -> Result<ArrayReader<'_>, TLVError> {
+        let tlv_type = self.read_type()?;
+        if tlv_type != TLVType::Container(ContainerType::Array) {
+            return Err(self.type_mismatch("an Array", tlv_type));
         }
+        self.enter_container()?;
+        let at_end = matches!(self.header, Err(TLVError::EndOfContainer));
+        Ok(ArrayReader {
+            depth: self.containers.len(),
+            parent: self,
+            at_end,
+            finished: false,
+        })
     }
 
-    fn read_f64(&self) -> Result<f64, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::FloatingPointNumber64)? {
-            let (_, value) = util::parse_f64(remaining_bytes)?;
-            Ok(value)
-        } else {
-            Err(TLVError::InvalidType)
-        }
+    // Decodes the element at the offset then moves the cursor back where it was
+    fn decode_at<T: TLVDecode>(&mut self, offset: usize) -> Result<T, TLVError> {
+        let (cursor, depth) = (self.bytes_read, self.containers.len());
+        self.move_to(offset);
+        let result = T::decode_tlv(self);
+        self.containers.truncate(depth);
+        self.forget_exited();
+        self.move_to(cursor);
+        result
     }
 
-    fn read_bool(&self) -> Result<bool, TLVError> {
-        let (_, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::BooleanTrue)? {
-            Ok(true)
-        } else if tlv_type == TLVType::try_from(ElementType::BooleanFalse)? {
-            Ok(false)
-        } else {
-            Err(TLVError::InvalidType)
+    // Moves the cursor up to n siblings further, containers count as one element. Returns how many
+    // were skipped, fewer than n when the container or the payload ends first.
+    pub fn skip_n(&mut self, n: usize) -> Result<usize, TLVError> {
+        for skipped in 0..n {
+            match self.next() {
+                Ok(()) => {}
+                Err(TLVError::EndOfContainer | TLVError::EndOfTLV) => return Ok(skipped),
+                Err(e) => return Err(e),
+            }
         }
+        Ok(n)
     }
 
-    fn read_null(&self) -> Result<(), TLVError> {
-        let (_, _, tlv_type) = self.parse_control()?;
-        if tlv_type == TLVType::try_from(ElementType::Null)? {
-            Ok(())
-        } else {
-            Err(TLVError::InvalidType)
+    fn count_element(&mut self) -> Result<(), TLVError> {
+        let limit = self.config.max_elements;
+        if self.elements == limit {
+            trace!("More than {} elements in the payload", limit);
+            return Err(TLVError::TooManyElements { limit });
         }
+        self.elements += 1;
+        Ok(())
     }
 
-    fn read_byte_str(&self) -> Result<Vec<u8>, TLVError> {
+    // Moves the cursor to the first member of the container under the cursor.
+    // An empty container leaves the cursor at its end, where reads fail with EndOfContainer.
+    pub fn enter_container(&mut self) -> Result<ContainerType, TLVError> {
         let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        let field_size = match tlv_type {
-            TLVType::Primitive(PrimitiveLengthType::Specified(
-                SpecifiedLenPrimitive::ByteString(string),
-            )) => string.length_field_size(),
+        let container_type = match tlv_type {
+            TLVType::Container(container_type) => container_type,
             _ => return Err(TLVError::InvalidType),
         };
-        Ok(field_size
-            .extract_field_sized_bytes(remaining_bytes)?
-            .to_vec())
+        if self.containers.len() == self.config.max_depth {
+            return Err(TLVError::DepthLimitExceeded(self.config.max_depth));
+        }
+        let first_member = self.bytes.len() - remaining_bytes.len();
+        if first_member < self.bytes.len()
+            && !Self::is_end_of_container(&self.bytes[first_member..])
+        {
+            self.count_element()?;
+        }
+        self.containers.push((container_type, self.bytes_read));
+        self.move_to(first_member);
+        Ok(container_type)
     }
 
-    fn read_char_str(&self) -> Result<String, TLVError> {
-        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
-        let field_size = match tlv_type {
-            TLVType::Primitive(PrimitiveLengthType::Specified(
-                SpecifiedLenPrimitive::UTF8String(string),
-            )) => string.length_field_size(),
-            _ => return Err(TLVError::InvalidType),
-        };
-        let value = field_size.extract_field_sized_bytes(remaining_bytes)?;
-        Ok(util::parse_str(value)?.to_string())
+    // Moves the cursor back to the innermost entered container, so next() continues with its sibling
+    pub fn exit_container(&mut self) -> Result<(), TLVError> {
+        let (_, container_start) = *self.containers.last().ok_or(TLVError::NotInContainer)?;
+        self.count_element()?; // Its End of Container, even when members are skipped
+        self.containers.pop();
+        self.forget_exited();
+        self.move_to(container_start);
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
+    pub fn config(&self) -> &TLVReaderConfig {
+        &self.config
+    }
+
+    pub fn container_depth(&self) -> usize {
+        self.containers.len()
+    }
+
+    pub fn container_type(&self) -> Option<ContainerType> {
+        self.containers
+            .last()
+            .map(|(container_type, _)| *container_type)
+    }
+
+    pub fn offset(&self) -> usize {
+        self.bytes_read
+    }
+
+    // Bytes from the cursor to the end of the buffer, whatever containers are entered: inside one
+    // they start with its members left and its End of Container. Past the last top level element,
+    // e.g. after next() moved onto a trailing MIC, they are the bytes following the payload.
+    pub fn remaining(&self) -> &[u8] {
+        &self.bytes[self.bytes_read..]
+    }
+
+    pub fn remaining_len(&self) -> usize {
+        self.bytes.len() - self.bytes_read
+    }
+
+    // Probing an element with the wrong read function is expected, so mismatches are only traced
+    fn type_mismatch(&self, expected: &str, found: TLVType) -> TLVError {
+        trace!(
+            "expected {} at offset {}, found {:?}",
+            expected,
+            self.bytes_read,
+            found
+        );
+        TLVError::InvalidType
+    }
+
+    // Element under the cursor with its whole subtree
+    pub fn read_value(&self) -> Result<TLVValue, TLVError> {
+        value::read_value(&mut self.fork()?)
+    }
+
+    // Top level elements of the payload wherever the cursor is, see TLVElements
+    pub fn elements(&self) -> TLVElements<'_> {
+        TLVElements {
+            tlv_reader: self,
+            offset: self.start,
+            outermost: None,
+            depth: 0,
+            done: false,
+        }
+    }
+
+    // Members of the structure under the cursor in wire order, duplicate tags are kept unless the
+    // configuration rejects them
+    pub fn read_structure_fields(&self) -> Result<Vec<(TLVTag, TLVValue)>, TLVError> {
+        let tlv_type = self.read_type()?;
+        if tlv_type != TLVType::Container(ContainerType::Structure) {
+            return Err(self.type_mismatch("a Structure", tlv_type));
+        }
+        match self.read_value()? {
+            TLVValue::Structure(members) => Ok(members),
+            _ => Err(TLVError::Internal {
+                context: "Structure read as another type",
+            }),
+        }
+    }
+
+    // Element under the cursor with its whole subtree, strings are borrowed from the reader's buffer
+    pub fn read_value_ref(&self) -> Result<TLVValueRef<'_>, TLVError> {
+        self.current_element_len()?; // Validates the subtree, so walking it cannot run out of bytes
+        let (_, value, _) = self.element_ref_at(self.bytes_read, 0)?;
+        Ok(value)
+    }
+
+    // Tag and value of the element at the offset, with the offset of the element following it.
+    // Containers are read with a stack on the heap as value::read_value does, those nested deeper
+    // than max_depth, counting the depth the element is at, fail with DepthLimitExceeded.
+    fn element_ref_at(
+        &self,
+        mut offset: usize,
+        depth: usize,
+    ) -> Result<(TLVTag, TLVValueRef<'_>, usize), TLVError> {
+        let mut containers: Vec<OpenContainerRef> = Vec::new();
+        loop {
+            let (tag, value) =
+                if !containers.is_empty() && Self::is_end_of_container(&self.bytes[offset..]) {
+                    offset += 1; // Past the End of Container
+                    let (container_type, tag, members) =
+                        containers.pop().ok_or(TLVError::Internal {
+                            context: "no container to close",
+                        })?;
+                    (tag, self.container_ref(container_type, members)?)
+                } else {
+                    let (remaining_bytes, tag, tlv_type) =
+                        Self::parse_element_control(&self.bytes, offset)?;
+                    match tlv_type {
+                        TLVType::Container(container_type) => {
+                            if depth + containers.len() >= self.config.max_depth {
+                                return Err(TLVError::DepthLimitExceeded(self.config.max_depth));
+                            }
+                            containers.push((container_type, tag, Vec::new()));
+                            offset = self.bytes.len() - remaining_bytes.len();
+                            continue;
+                        }
+                        TLVType::Primitive(primitive_length_type) => {
+                            let (value, next_element) =
+                                self.primitive_ref(remaining_bytes, primitive_length_type)?;
+                            offset = next_element;
+                            (tag, value)
+                        }
+                    }
+                };
+            match containers.last_mut() {
+                Some((_, _, members)) => members.push((tag, value)),
+                None => return Ok((tag, value, offset)),
+            }
+        }
+    }
+
+    // Value of the primitive whose control byte and tag precede the bytes, with the offset of the
+    // element following it
+    fn primitive_ref<'a>(
+        &'a self,
+        remaining_bytes: &'a [u8],
+        primitive_length_type: PrimitiveLengthType,
+    ) -> Result<(TLVValueRef<'a>, usize), TLVError> {
+        let (value_start, _, value_octets_count) =
+            Self::parse_primitive_len(primitive_length_type, remaining_bytes)?;
+        if let PrimitiveLengthType::Specified(_) = primitive_length_type {
+            self.check_element_len(value_octets_count)?;
+        }
+        let value_bytes = &value_start[..value_octets_count];
+        let next_element = self.bytes.len() - value_start.len() + value_octets_count;
+        let value = match primitive_length_type {
+            PrimitiveLengthType::Predetermined(predetermined) => match predetermined {
+                PredeterminedLenPrimitive::SignedInteger(width) => match width {
+                    SignedInteger::Int8 => TLVValueRef::Int8(wire::parse_i8(value_bytes)?.1),
+                    SignedInteger::Int16 => TLVValueRef::Int16(wire::parse_i16(value_bytes)?.1),
+                    SignedInteger::Int32 => TLVValueRef::Int32(wire::parse_i32(value_bytes)?.1),
+                    SignedInteger::Int64 => TLVValueRef::Int64(wire::parse_i64(value_bytes)?.1),
+                },
+                PredeterminedLenPrimitive::UnsignedInteger(width) => match width {
+                    UnsignedInteger::UInt8 => TLVValueRef::UInt8(wire::parse_u8(value_bytes)?.1),
+                    UnsignedInteger::UInt16 => TLVValueRef::UInt16(wire::parse_u16(value_bytes)?.1),
+                    UnsignedInteger::UInt32 => TLVValueRef::UInt32(wire::parse_u32(value_bytes)?.1),
+                    UnsignedInteger::UInt64 => TLVValueRef::UInt64(wire::parse_u64(value_bytes)?.1),
+                },
+                PredeterminedLenPrimitive::FloatingPointNumber(
+                    FloatingPoint::FloatingPointNumber32,
+                ) => TLVValueRef::Float32(wire::parse_f32(value_bytes)?.1),
+                PredeterminedLenPrimitive::FloatingPointNumber(
+                    FloatingPoint::FloatingPointNumber64,
+                ) => TLVValueRef::Float64(wire::parse_f64(value_bytes)?.1),
+                PredeterminedLenPrimitive::Boolean(value) => TLVValueRef::Bool(value),
+                PredeterminedLenPrimitive::Null => TLVValueRef::Null,
+            },
+            PrimitiveLengthType::Specified(SpecifiedLenPrimitive::UTF8String(_)) => {
+                let value_offset = self.bytes.len() - value_start.len();
+                self.config.check_string(value_bytes, value_offset)?;
+                TLVValueRef::Utf8String(wire::parse_str(value_bytes)?)
+            }
+            PrimitiveLengthType::Specified(SpecifiedLenPrimitive::ByteString(_)) => {
+                TLVValueRef::ByteString(value_bytes)
+            }
+        };
+        Ok((value, next_element))
+    }
+
+    fn container_ref<'a>(
+        &self,
+        container_type: ContainerType,
+        members: Vec<(TLVTag, TLVValueRef<'a>)>,
+    ) -> Result<TLVValueRef<'a>, TLVError> {
+        Ok(match container_type {
+            ContainerType::Structure => {
+                value::check_structure_members(&members, &self.config)?;
+                TLVValueRef::Structure(members)
+            }
+            ContainerType::List => TLVValueRef::List(members),
+            ContainerType::Array => TLVValueRef::Array(
+                members
+                    .into_iter()
+                    .map(|(tag, value)| match tag {
+                        TLVTag::Anonymous => Ok(value),
+                        _ => Err(TLVError::InvalidTag),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+
+    pub fn read_tag(&self) -> Result<TLVTag, TLVError> {
+        let (_, tlv_tag, _) = self.parse_control()?;
+        Ok(tlv_tag.clone())
+    }
+
+    pub fn read_type(&self) -> Result<TLVType, TLVError> {
+        let (_, _, tlv_type) = self.parse_control()?;
+        Ok(tlv_type)
+    }
+
+    // Unsigned integer of any width
+    pub fn read_uint(&self) -> Result<u64, TLVError> {
+        let tlv_type = self.read_type()?;
+        match tlv_type {
+            TLVType::Primitive(PrimitiveLengthType::Predetermined(
+                PredeterminedLenPrimitive::UnsignedInteger(width),
+            )) => Ok(match width {
+                UnsignedInteger::UInt8 => self.read_u8()? as u64,
+                UnsignedInteger::UInt16 => self.read_u16()? as u64,
+                UnsignedInteger::UInt32 => self.read_u32()? as u64,
+                UnsignedInteger::UInt64 => self.read_u64()?,
+            }),
+            _ => Err(self.type_mismatch("an unsigned integer", tlv_type)),
+        }
+    }
+
+    // Signed integer of any width
+    pub fn read_int(&self) -> Result<i64, TLVError> {
+        let tlv_type = self.read_type()?;
+        match tlv_type {
+            TLVType::Primitive(PrimitiveLengthType::Predetermined(
+                PredeterminedLenPrimitive::SignedInteger(width),
+            )) => Ok(match width {
+                SignedInteger::Int8 => self.read_i8()? as i64,
+                SignedInteger::Int16 => self.read_i16()? as i64,
+                SignedInteger::Int32 => self.read_i32()? as i64,
+                SignedInteger::Int64 => self.read_i64()?,
+            }),
+            _ => Err(self.type_mismatch("a signed integer", tlv_type)),
+        }
+    }
+
+    // Enumeration of a cluster, e.g. an enum8 or enum16, encoded as an unsigned integer of any width
+    pub fn read_enum<E: FromPrimitive>(&self) -> Result<E, TLVError> {
+        let value = self.read_uint()?;
+        E::from_u64(value).ok_or(TLVError::InvalidEnumValue { value })
+    }
+
+    // Bitmap of a cluster, e.g. a map8 or map16, encoded as an unsigned integer of any width. Bits
+    // the type does not define fail with UnknownFlags, values wider than the type with
+    // ValueOutOfRange.
+    #[cfg(feature = "bitflags")]
+    pub fn read_flags<F: bitflags::Flags>(&self) -> Result<F, TLVError>
+    where
+        F::Bits: TryFrom<u64>,
+    {
+        let value = self.read_uint()?;
+        let bits = F::Bits::try_from(value).map_err(|_| TLVError::ValueOutOfRange)?;
+        F::from_bits(bits).ok_or(TLVError::UnknownFlags { value })
+    }
+
+    // Drops the bits the type does not define, for bitmaps of newer revisions of a cluster
+    #[cfg(feature = "bitflags")]
+    pub fn read_flags_truncate<F: bitflags::Flags>(&self) -> Result<F, TLVError>
+    where
+        F::Bits: TryFrom<u64>,
+    {
+        let value = self.read_uint()?;
+        let bits = F::Bits::try_from(value).map_err(|_| TLVError::ValueOutOfRange)?;
+        Ok(F::from_bits_truncate(bits))
+    }
+
+    pub fn read_u8(&self) -> Result<u8, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::UInt8)? {
+            let (_, value) = wire::parse_u8(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("UInt8", tlv_type))
+        }
+    }
+
+    pub fn read_u16(&self) -> Result<u16, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::UInt16)? {
+            let (_, value) = wire::parse_u16(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("UInt16", tlv_type))
+        }
+    }
+
+    pub fn read_u32(&self) -> Result<u32, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::UInt32)? {
+            let (_, value) = wire::parse_u32(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("UInt32", tlv_type))
+        }
+    }
+
+    pub fn read_u64(&self) -> Result<u64, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::UInt64)? {
+            let (_, value) = wire::parse_u64(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("UInt64", tlv_type))
+        }
+    }
+
+    pub fn read_i8(&self) -> Result<i8, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::Int8)? {
+            let (_, value) = wire::parse_i8(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("Int8", tlv_type))
+        }
+    }
+
+    pub fn read_i16(&self) -> Result<i16, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::Int16)? {
+            let (_, value) = wire::parse_i16(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("Int16", tlv_type))
+        }
+    }
+
+    pub fn read_i32(&self) -> Result<i32, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::Int32)? {
+            let (_, value) = wire::parse_i32(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("Int32", tlv_type))
+        }
+    }
+
+    pub fn read_i64(&self) -> Result<i64, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::Int64)? {
+            let (_, value) = wire::parse_i64(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("Int64", tlv_type))
+        }
+    }
+
+    pub fn read_f32(&self) -> Result<f32, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::FloatingPointNumber32)? {
+            let (_, value) = wire::parse_f32(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("Float32", tlv_type))
+        }
+    }
+
+    pub fn read_f64(&self) -> Result<f64, TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::FloatingPointNumber64)? {
+            let (_, value) = wire::parse_f64(remaining_bytes)?;
+            Ok(value)
+        } else {
+            Err(self.type_mismatch("Float64", tlv_type))
+        }
+    }
+
+    pub fn read_bool(&self) -> Result<bool, TLVError> {
+        let (_, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::BooleanTrue)? {
+            Ok(true)
+        } else if tlv_type == TLVType::try_from(ElementType::BooleanFalse)? {
+            Ok(false)
+        } else {
+            Err(self.type_mismatch("Bool", tlv_type))
+        }
+    }
+
+    pub fn read_null(&self) -> Result<(), TLVError> {
+        let (_, _, tlv_type) = self.parse_control()?;
+        if tlv_type == TLVType::try_from(ElementType::Null)? {
+            Ok(())
+        } else {
+            Err(self.type_mismatch("Null", tlv_type))
+        }
+    }
+
+    fn check_element_len(&self, declared: usize) -> Result<(), TLVError> {
+        let limit = self.config.max_element_len;
+        if declared > limit {
+            trace!(
+                "Declared length {} exceeds the limit of {}",
+                declared,
+                limit
+            );
+            return Err(TLVError::ElementTooLarge { declared, limit });
+        }
+        Ok(())
+    }
+
+    // Value of a string, its declared length is checked against the limit first
+    fn string_bytes<'a>(
+        &self,
+        field_size: TLVFieldSize,
+        remaining_bytes: &'a [u8],
+    ) -> Result<&'a [u8], TLVError> {
+        let (_, declared) = field_size.parse_field_size(remaining_bytes)?;
+        self.check_element_len(declared)?;
+        field_size.extract_field_sized_bytes(remaining_bytes)
+    }
+
+    // Declared length of a string value and the width of its length field, the value is not
+    // extracted nor checked against max_element_len
+    pub fn peek_str_len(&self) -> Result<(usize, TLVFieldSize), TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        let field_size = match tlv_type {
+            TLVType::Primitive(PrimitiveLengthType::Specified(specified)) => {
+                specified.length_field_size()
+            }
+            _ => return Err(self.type_mismatch("a string", tlv_type)),
+        };
+        let (_, declared) = field_size.parse_field_size(remaining_bytes)?;
+        Ok((declared, field_size))
+    }
+
+    // Borrows the value from the reader's buffer, without allocating
+    pub fn read_byte_slice(&self) -> Result<&[u8], TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        let field_size = match tlv_type {
+            TLVType::Primitive(PrimitiveLengthType::Specified(
+                SpecifiedLenPrimitive::ByteString(string),
+            )) => string.length_field_size(),
+            _ => return Err(self.type_mismatch("an octet string", tlv_type)),
+        };
+        self.string_bytes(field_size, remaining_bytes)
+    }
+
+    // Whether the Octet String under the cursor holds the expected octets, compared in constant
+    // time, see value::constant_time_eq. Meant for secrets read in authentication paths.
+    pub fn read_byte_str_ct_eq(&self, expected: &[u8]) -> Result<bool, TLVError> {
+        Ok(value::constant_time_eq(self.read_byte_slice()?, expected))
+    }
+
+    fn read_utf8_bytes(&self) -> Result<&[u8], TLVError> {
+        let (remaining_bytes, _, tlv_type) = self.parse_control()?;
+        let field_size = match tlv_type {
+            TLVType::Primitive(PrimitiveLengthType::Specified(
+                SpecifiedLenPrimitive::UTF8String(string),
+            )) => string.length_field_size(),
+            _ => return Err(self.type_mismatch("a UTF-8 string", tlv_type)),
+        };
+        let value = self.string_bytes(field_size, remaining_bytes)?;
+        let value_offset = self.bytes.len() - remaining_bytes.len() + field_size as usize;
+        self.config.check_string(value, value_offset)?;
+        Ok(value)
+    }
+
+    // Borrows the value from the reader's buffer, without allocating
+    pub fn read_str(&self) -> Result<&str, TLVError> {
+        wire::parse_str(self.read_utf8_bytes()?)
+    }
+
+    // Shares the value with the reader's buffer
+    pub fn read_bytes(&self) -> Result<Bytes, TLVError> {
+        Ok(self.bytes.slice_ref(self.read_byte_slice()?))
+    }
+
+    // Shares the value with the reader's buffer, it is valid UTF-8 as read_str checks it
+    pub fn read_str_bytes(&self) -> Result<Bytes, TLVError> {
+        Ok(self.bytes.slice_ref(self.read_str()?.as_bytes()))
+    }
+
+    pub fn read_byte_str(&self) -> Result<Vec<u8>, TLVError> {
+        Ok(self.read_byte_slice()?.to_vec())
+    }
+
+    // Invalid UTF-8 is replaced rather than rejected unless the configuration checks it
+    pub fn read_char_str(&self) -> Result<String, TLVError> {
+        if self.config.check_utf8 {
+            Ok(self.read_str()?.to_string())
+        } else {
+            Ok(String::from_utf8_lossy(self.read_utf8_bytes()?).into_owned())
+        }
+    }
+
+    // Two styles of reading. The read_* functions peek at the element under the cursor, which only
+    // next() moves, so the same element can be read again. The take_* functions read it the same
+    // way and move the cursor past it on success, also past the last element of the container or
+    // payload, where reads then fail with EndOfContainer or EndOfTLV. A failed take leaves the
+    // cursor where it was.
+
+    // Moves the cursor past the element under it, with the length math of next()
+    fn advance(&mut self) -> Result<(), TLVError> {
+        let next_element = self.bytes_read + self.current_element_len()?;
+        if next_element > self.bytes.len() {
+            return Err(TLVError::UnderRun);
+        }
+        if next_element < self.bytes.len()
+            && !Self::is_end_of_container(&self.bytes[next_element..])
+        {
+            self.count_element()?;
+        }
+        self.remember(self.bytes_read);
+        self.move_to(next_element);
+        Ok(())
+    }
+
+    fn take_with<T>(&mut self, read: fn(&Self) -> Result<T, TLVError>) -> Result<T, TLVError> {
+        let value = read(self)?;
+        self.advance()?;
+        Ok(value)
+    }
+
+    // Position of a value borrowed from the buffer, to borrow it again once the cursor moved
+    fn range_of(&self, value: &[u8]) -> Range<usize> {
+        let start = value.as_ptr() as usize - self.bytes.as_ptr() as usize;
+        start..start + value.len()
+    }
+
+    // Decodes the element under the cursor, containers included, then moves past it
+    pub fn take<T: TLVDecode>(&mut self) -> Result<T, TLVError> {
+        let value = self.decode_at(self.bytes_read)?;
+        self.advance()?;
+        Ok(value)
+    }
+
+    pub fn take_value(&mut self) -> Result<TLVValue, TLVError> {
+        self.take_with(Self::read_value)
+    }
+
+    pub fn take_uint(&mut self) -> Result<u64, TLVError> {
+        self.take_with(Self::read_uint)
+    }
+
+    pub fn take_int(&mut self) -> Result<i64, TLVError> {
+        self.take_with(Self::read_int)
+    }
+
+    pub fn take_u8(&mut self) -> Result<u8, TLVError> {
+        self.take_with(Self::read_u8)
+    }
+
+    pub fn take_u16(&mut self) -> Result<u16, TLVError> {
+        self.take_with(Self::read_u16)
+    }
+
+    pub fn take_u32(&mut self) -> Result<u32, TLVError> {
+        self.take_with(Self::read_u32)
+    }
+
+    pub fn take_u64(&mut self) -> Result<u64, TLVError> {
+        self.take_with(Self::read_u64)
+    }
+
+    pub fn take_i8(&mut self) -> Result<i8, TLVError> {
+        self.take_with(Self::read_i8)
+    }
+
+    pub fn take_i16(&mut self) -> Result<i16, TLVError> {
+        self.take_with(Self::read_i16)
+    }
+
+    pub fn take_i32(&mut self) -> Result<i32, TLVError> {
+        self.take_with(Self::read_i32)
+    }
+
+    pub fn take_i64(&mut self) -> Result<i64, TLVError> {
+        self.take_with(Self::read_i64)
+    }
+
+    pub fn take_f32(&mut self) -> Result<f32, TLVError> {
+        self.take_with(Self::read_f32)
+    }
+
+    pub fn take_f64(&mut self) -> Result<f64, TLVError> {
+        self.take_with(Self::read_f64)
+    }
+
+    pub fn take_bool(&mut self) -> Result<bool, TLVError> {
+        self.take_with(Self::read_bool)
+    }
+
+    pub fn take_null(&mut self) -> Result<(), TLVError> {
+        self.take_with(Self::read_null)
+    }
+
+    pub fn take_byte_slice(&mut self) -> Result<&[u8], TLVError> {
+        let range = self.range_of(self.read_byte_slice()?);
+        self.advance()?;
+        Ok(&self.bytes[range])
+    }
+
+    // The value was checked by read_str before moving, so parsing it again cannot fail
+    pub fn take_str(&mut self) -> Result<&str, TLVError> {
+        let range = self.range_of(self.read_str()?.as_bytes());
+        self.advance()?;
+        wire::parse_str(&self.bytes[range])
+    }
+
+    pub fn take_bytes(&mut self) -> Result<Bytes, TLVError> {
+        self.take_with(Self::read_bytes)
+    }
+
+    pub fn take_str_bytes(&mut self) -> Result<Bytes, TLVError> {
+        self.take_with(Self::read_str_bytes)
+    }
+
+    pub fn take_byte_str(&mut self) -> Result<Vec<u8>, TLVError> {
+        self.take_with(Self::read_byte_str)
+    }
+
+    pub fn take_char_str(&mut self) -> Result<String, TLVError> {
+        self.take_with(Self::read_char_str)
+    }
+
+    // Value of the Octet String under the cursor as a std::io::Read, e.g. to hash it or copy it to
+    // a file. Fails as read_byte_slice does, a value cut short included, so the Read yields exactly
+    // the declared length. The cursor moves past the element as take_* do once the value is read
+    // to its end, or on ByteStrReader::finish.
+    #[cfg(feature = "std")]
+    pub fn byte_str_reader(&mut self) -> Result<ByteStrReader<'_>, TLVError> {
+        let value = self.range_of(self.read_byte_slice()?);
+        Ok(ByteStrReader {
+            tlv_reader: self,
+            value,
+            finished: false,
+        })
+    }
+}
+
+// Reader over the members of one container. Its buffer ends with the container's End of Container,
+// and the container itself cannot be exited, so decoders handed a scope cannot reach the data
+// around it. Reads are those of TLVReader.
+pub struct ScopedReader {
+    reader: TLVReader,
+}
+
+impl Deref for ScopedReader {
+    type Target = TLVReader;
+
+    fn deref(&self) -> &TLVReader {
+        &self.reader
+    }
+}
+
+impl ScopedReader {
+    #[allow(clippy::should_implement_trait)] // Advances the cursor, does not yield elements
+    pub fn next(&mut self) -> Result<(), TLVError> {
+        self.reader.next()
+    }
+
+    pub fn skip_n(&mut self, n: usize) -> Result<usize, TLVError> {
+        self.reader.skip_n(n)
+    }
+
+    pub fn rewind_element(&mut self) -> Result<(), TLVError> {
+        self.reader.rewind_element()
+    }
+
+    pub fn enter_container(&mut self) -> Result<ContainerType, TLVError> {
+        self.reader.enter_container()
+    }
+
+    // Only containers entered within the scope can be exited
+    pub fn exit_container(&mut self) -> Result<(), TLVError> {
+        if self.reader.container_depth() == 1 {
+            return Err(TLVError::NotInContainer);
+        }
+        self.reader.exit_container()
+    }
+
+    pub fn enter_array(&mut self) -> Result<ArrayReader<'_>, TLVError> {
+        self.reader.enter_array()
+    }
+
+    // Decodes the element under the cursor, leaving the cursor on it
+    pub fn decode<T: TLVDecode>(&mut self) -> Result<T, TLVError> {
+        let cursor = self.reader.bytes_read;
+        self.reader.decode_at(cursor)
+    }
+
+    pub fn take<T: TLVDecode>(&mut self) -> Result<T, TLVError> {
+        self.reader.take()
+    }
+
+    pub fn read_field<T: TLVDecode>(&mut self, tag: u8) -> Result<T, TLVError> {
+        self.reader.read_field(tag)
+    }
+
+    pub fn try_read_field<T: TLVDecode>(&mut self, tag: u8) -> Result<Option<T>, TLVError> {
+        self.reader.try_read_field(tag)
+    }
+}
+
+// Members of an entered Array, one at a time. Finishing or dropping it leaves the parent on the
+// Array as exit_container() does, however many members were read.
+pub struct ArrayReader<'a> {
+    parent: &'a mut TLVReader,
+    depth: usize, // Of the parent once in the Array
+    at_end: bool,
+    finished: bool,
+}
+
+impl ArrayReader<'_> {
+    pub fn is_at_end(&self) -> bool {
+        self.at_end
+    }
+
+    // Decodes the next member, None once the End of Container is reached
+    pub fn next_value<T: TLVDecode>(&mut self) -> Result<Option<T>, TLVError> {
+        if !self.at_member()? {
+            return Ok(None);
+        }
+        let value = self.parent.decode_at(self.parent.bytes_read)?;
+        self.advance()?;
+        Ok(Some(value))
+    }
+
+    // Encoded next member with its whole subtree, sharing the parent's buffer
+    pub fn next_raw(&mut self) -> Result<Option<Bytes>, TLVError> {
+        if !self.at_member()? {
+            return Ok(None);
+        }
+        let start = self.parent.bytes_read;
+        let element = start..start + self.parent.current_element_len()?;
+        let raw = self.parent.bytes.slice(element);
+        self.advance()?;
+        Ok(Some(raw))
+    }
+
+    pub fn finish(mut self) -> Result<(), TLVError> {
+        self.exit()
+    }
+
+    // Array members are anonymous
+    fn at_member(&self) -> Result<bool, TLVError> {
+        if self.at_end || self.finished {
+            return Ok(false);
+        }
+        match self.parent.read_tag()? {
+            TLVTag::Anonymous => Ok(true),
+            _ => Err(TLVError::InvalidTag),
+        }
+    }
+
+    fn advance(&mut self) -> Result<(), TLVError> {
+        match self.parent.next() {
+            Err(TLVError::EndOfContainer) => {
+                self.at_end = true;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn exit(&mut self) -> Result<(), TLVError> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.parent.containers.truncate(self.depth);
+        self.parent.exit_container()
+    }
+}
+
+impl Drop for ArrayReader<'_> {
+    fn drop(&mut self) {
+        let _ = self.exit();
+    }
+}
+
+// See TLVReader::byte_str_reader. Dropping it before the end of the value leaves the cursor on the
+// element.
+#[cfg(feature = "std")]
+pub struct ByteStrReader<'a> {
+    tlv_reader: &'a mut TLVReader,
+    value: Range<usize>, // Part of the value not read yet
+    finished: bool,
+}
+
+#[cfg(feature = "std")]
+impl ByteStrReader<'_> {
+    // Octets of the value not read yet
+    pub fn remaining_len(&self) -> usize {
+        self.value.len()
+    }
+
+    // Moves the cursor past the element, whatever was read of the value
+    pub fn finish(mut self) -> Result<(), TLVError> {
+        self.advance()
+    }
+
+    fn advance(&mut self) -> Result<(), TLVError> {
+        if !self.finished {
+            self.tlv_reader.advance()?;
+            self.finished = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for ByteStrReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.value.len());
+        let end = self.value.start + len;
+        buf[..len].copy_from_slice(&self.tlv_reader.bytes[self.value.start..end]);
+        self.value.start = end;
+        if self.value.is_empty() {
+            match self.advance() {
+                Ok(()) => {}
+                Err(_) if len > 0 => {} // Returned by the following read
+                Err(e) => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+            }
+        }
+        Ok(len)
+    }
+}
+
+// Elements at one level of the payload. Only the header of each element is parsed to reach the next
+// one, values are decoded by the getters of TLVElementRef when asked for.
+pub struct TLVElements<'a> {
+    tlv_reader: &'a TLVReader,
+    offset: usize,
+    outermost: Option<usize>, // Control byte of the outermost entered container, None at top level
+    depth: usize,
+    done: bool,
+}
+
+impl<'a> TLVElements<'a> {
+    fn element_at(&self, offset: usize) -> Result<(TLVElementRef<'a>, usize), TLVError> {
+        let tlv_reader = self.tlv_reader;
+        let header = TLVReader::parse_header(&tlv_reader.bytes, offset, &tlv_reader.config)?;
+        let outermost = self.outermost.unwrap_or(offset);
+        // Regardless of the containers entered by the reader's cursor
+        let element_len = tlv_reader.element_len(offset).map_err(|e| match e {
+            TLVError::UnterminatedContainer { .. } => {
+                TLVError::UnterminatedContainer { offset: outermost }
+            }
+            e => e,
+        })?;
+        let element = TLVElementRef {
+            tlv_reader,
+            offset,
+            header,
+            outermost,
+            depth: self.depth,
+        };
+        Ok((element, offset + element_len))
+    }
+}
+
+impl<'a> Iterator for TLVElements<'a> {
+    type Item = Result<TLVElementRef<'a>, TLVError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = &self.tlv_reader.bytes[self.offset..];
+        let result = match self.outermost {
+            _ if self.done => return None,
+            None if bytes.is_empty() => return None,
+            Some(offset) if bytes.is_empty() => Err(TLVError::UnterminatedContainer { offset }),
+            Some(_) if TLVReader::is_end_of_container(bytes) => return None,
+            _ => self.element_at(self.offset),
+        };
+        match result {
+            Ok((element, next_element)) => {
+                self.offset = next_element;
+                Some(Ok(element))
+            }
+            Err(e) => {
+                self.done = true; // The following elements cannot be located
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+// Element of the reader's buffer whose tag and type are known, its value is not decoded yet
+pub struct TLVElementRef<'a> {
+    tlv_reader: &'a TLVReader,
+    offset: usize,
+    header: ElementHeader,
+    outermost: usize,
+    depth: usize, // Of the containers enclosing the element
+}
+
+impl<'a> TLVElementRef<'a> {
+    pub fn tag(&self) -> &TLVTag {
+        &self.header.tag
+    }
+
+    pub fn tlv_type(&self) -> TLVType {
+        self.header.tlv_type
+    }
+
+    // Of the control byte in the reader's buffer
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    // Value with its whole subtree, whose containers count towards max_depth as with enter
+    pub fn value(&self) -> Result<TLVValueRef<'a>, TLVError> {
+        let (_, value, _) = self.tlv_reader.element_ref_at(self.offset, self.depth)?;
+        Ok(value)
+    }
+
+    // Decodes the value only once its type is known to be the expected one
+    fn primitive(
+        &self,
+        expected: &str,
+        is_expected: fn(&PrimitiveLengthType) -> bool,
+    ) -> Result<TLVValueRef<'a>, TLVError> {
+        match self.header.tlv_type {
+            TLVType::Primitive(primitive_length_type) if is_expected(&primitive_length_type) => {
+                self.value()
+            }
+            _ => Err(self.type_mismatch(expected)),
+        }
+    }
+
+    fn type_mismatch(&self, expected: &str) -> TLVError {
+        trace!(
+            "Expected {} at offset {}, found {:?}",
+            expected,
+            self.offset,
+            self.header.tlv_type
+        );
+        TLVError::InvalidType
+    }
+
+    // Unsigned integer of any width
+    pub fn as_u64(&self) -> Result<u64, TLVError> {
+        let expected = "an unsigned integer";
+        match self.primitive(expected, |primitive_length_type| {
+            matches!(
+                primitive_length_type,
+                PrimitiveLengthType::Predetermined(PredeterminedLenPrimitive::UnsignedInteger(_))
+            )
+        })? {
+            TLVValueRef::UInt8(value) => Ok(value as u64),
+            TLVValueRef::UInt16(value) => Ok(value as u64),
+            TLVValueRef::UInt32(value) => Ok(value as u64),
+            TLVValueRef::UInt64(value) => Ok(value),
+            _ => Err(self.type_mismatch(expected)),
+        }
+    }
+
+    // Signed integer of any width
+    pub fn as_i64(&self) -> Result<i64, TLVError> {
+        let expected = "a signed integer";
+        match self.primitive(expected, |primitive_length_type| {
+            matches!(
+                primitive_length_type,
+                PrimitiveLengthType::Predetermined(PredeterminedLenPrimitive::SignedInteger(_))
+            )
+        })? {
+            TLVValueRef::Int8(value) => Ok(value as i64),
+            TLVValueRef::Int16(value) => Ok(value as i64),
+            TLVValueRef::Int32(value) => Ok(value as i64),
+            TLVValueRef::Int64(value) => Ok(value),
+            _ => Err(self.type_mismatch(expected)),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, TLVError> {
+        let expected = "Bool";
+        match self.primitive(expected, |primitive_length_type| {
+            matches!(
+                primitive_length_type,
+                PrimitiveLengthType::Predetermined(PredeterminedLenPrimitive::Boolean(_))
+            )
+        })? {
+            TLVValueRef::Bool(value) => Ok(value),
+            _ => Err(self.type_mismatch(expected)),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&'a str, TLVError> {
+        let expected = "a UTF-8 string";
+        match self.primitive(expected, |primitive_length_type| {
+            matches!(
+                primitive_length_type,
+                PrimitiveLengthType::Specified(SpecifiedLenPrimitive::UTF8String(_))
+            )
+        })? {
+            TLVValueRef::Utf8String(value) => Ok(value),
+            _ => Err(self.type_mismatch(expected)),
+        }
+    }
+
+    pub fn as_bytes(&self) -> Result<&'a [u8], TLVError> {
+        let expected = "an octet string";
+        match self.primitive(expected, |primitive_length_type| {
+            matches!(
+                primitive_length_type,
+                PrimitiveLengthType::Specified(SpecifiedLenPrimitive::ByteString(_))
+            )
+        })? {
+            TLVValueRef::ByteString(value) => Ok(value),
+            _ => Err(self.type_mismatch(expected)),
+        }
+    }
+
+    // Members of the container, as lazy as the elements they were found among
+    pub fn enter(&self) -> Result<TLVElements<'a>, TLVError> {
+        if let TLVType::Primitive(_) = self.header.tlv_type {
+            return Err(self.type_mismatch("a container"));
+        }
+        let max_depth = self.tlv_reader.config.max_depth;
+        if self.depth == max_depth {
+            return Err(TLVError::DepthLimitExceeded(max_depth));
+        }
+        Ok(TLVElements {
+            tlv_reader: self.tlv_reader,
+            offset: self.header.value_offset,
+            outermost: Some(self.outermost),
+            depth: self.depth + 1,
+            done: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
-    use crate::tags::{CommonProfileLength, FullyQualifiedProfileLength, TagControl};
+    use crate::tags::{CommonProfileLength, ControlByte, FullyQualifiedProfileLength, TagControl};
+
+    #[test]
+    fn test_parse_control_byte() {
+        // Unsigned Integer, 8-octet, value 40000000000
+        let test_bytes = &[0x07, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00];
+        let tlv_reader = TLVReader::new(test_bytes);
+        let (remaining_bytes, control_byte) = tlv_reader
+            .parse_control_byte()
+            .expect("Cannot parse control byte");
+        assert_eq!(control_byte.tag_control, TagControl::Anonymous);
+        assert_eq!(control_byte.element_type, ElementType::UInt64);
+        assert_eq!(
+            remaining_bytes,
+            [0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00]
+        );
+
+        // Fully qualified tag, 6-octet, Structure
+        assert_eq!(
+            ControlByte::from(0xd5),
+            ControlByte {
+                tag_control: 0x06,
+                element_type: ElementType::Structure as u8
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_u8() {
+        let test_bytes = &[0x04, 0xFF]; // Unsigned Integer, 1-octet, value 255
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 255);
+    }
+
+    #[test]
+    fn test_read_u8_tagged() {
+        // Anonymous tag, Unsigned Integer, 1-octet value, 42U
+        let test_bytes = &[0x04, 0x2a];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::Anonymous
+        );
+        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+
+        // Context tag 1, Unsigned Integer, 1-octet value, 1 = 42U
+        let test_bytes = &[0x24, 0x01, 0x2a];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::ContextSpecific(1)
+        );
+        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+
+        // Common profile tag 1, Unsigned Integer, 1-octet value, CHIP::1 = 42U
+        let test_bytes = &[0x44, 0x01, 0x00, 0x2a];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 1 })
+        );
+        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+
+        // Common profile tag 100000, Unsigned Integer, 1-octet value, CHIP::100000 = 42U
+        let test_bytes = &[0x64, 0xa0, 0x86, 0x01, 0x00, 0x2a];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 100000 })
+        );
+        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+
+        // Fully qualified tag, Vendor ID 0xFFF1/65521, profile number 0xDEED/57069,
+        // 2-octet tag 1, Unsigned Integer, 1-octet value 42, 65521::57069:1 = 42U
+        let test_bytes = &[0xc4, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x2a];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                vendor_id: 65521,
+                profile_number: 57069,
+                tag_number: 1
+            })
+        );
+        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+
+        // Fully qualified tag, Vendor ID 0xFFF1/65521, profile number 0xDEED/57069,
+        // 4-octet tag 0xAA55FEED/2857762541,
+        // Unsigned Integer, 1-octet value 42, 65521::57069:2857762541 = 42U
+        let test_bytes = &[0xe4, 0xf1, 0xff, 0xed, 0xde, 0xed, 0xfe, 0x55, 0xaa, 0x2a];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+                vendor_id: 65521,
+                profile_number: 57069,
+                tag_number: 2857762541
+            })
+        );
+        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+    }
+
+    #[test]
+    fn test_read_u16() {
+        let test_bytes = &[0x05, 0xFF, 0xFF]; // Unsigned Integer, 2-octet, value 65535
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(tlv_reader.read_u16().expect("Failed to read u16"), 65535);
+    }
+
+    #[test]
+    fn test_read_u32() {
+        // Unsigned Integer, 4-octet, value 237998115
+        let test_bytes = &[0x06, 0x23, 0x90, 0x2f, 0x0E];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_u32().expect("Failed to read u32"),
+            237998115
+        );
+    }
+
+    #[test]
+    fn test_read_u64() {
+        // Unsigned Integer, 8-octet, value 40000000000
+        let test_bytes = &[0x07, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_u64().expect("Failed to read u64"),
+            40000000000
+        );
+    }
+
+    #[test]
+    fn test_read_i8() {
+        let test_bytes = &[0x00, 0xFF]; // Signed Integer, 1-octet, value -1
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(tlv_reader.read_i8().expect("Failed to read i8"), -1);
+    }
+
+    #[test]
+    fn test_read_i16() {
+        let test_bytes = &[0x01, 0x0F, 0xFF]; // Signed Integer, 2-octet, value -241
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(tlv_reader.read_i16().expect("Failed to read i16"), -241);
+    }
+
+    #[test]
+    fn test_read_i32() {
+        // Signed Integer, 4-octet, value 237998115
+        let test_bytes = &[0x02, 0x23, 0x90, 0x2f, 0x0E];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_i32().expect("Failed to read i32"),
+            237998115
+        );
+    }
+
+    #[test]
+    fn test_read_i64() {
+        // Signed Integer, 8-octet, value 40000000000
+        let test_bytes = &[0x03, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_i64().expect("Failed to read i64"),
+            40000000000
+        );
+    }
+
+    #[test]
+    fn test_read_f32() {
+        // Single precision floating point 17.9
+        let test_bytes = &[0x0a, 0x33, 0x33, 0x8f, 0x41];
+        let tlv_reader = TLVReader::new(test_bytes);
+        let expected: f32 = 17.9;
+        let actual = tlv_reader.read_f32().expect("Failed to read f32");
+        assert!((expected - actual).abs() < f32::EPSILON);
+
+        // Single precision floating point infinity (∞)
+        let test_bytes = &[0x0a, 0x00, 0x00, 0x80, 0x7f];
+        let tlv_reader = TLVReader::new(test_bytes);
+        let infinity = tlv_reader.read_f32().expect("Failed to read f32");
+        assert!(infinity.is_sign_positive());
+        assert!(infinity.is_infinite());
+
+        // Single precision floating point negative infinity (-∞)
+        let test_bytes = &[0x0a, 0x00, 0x00, 0x80, 0xff];
+        let tlv_reader = TLVReader::new(test_bytes);
+        let infinity = tlv_reader.read_f32().expect("Failed to read f32");
+        assert!(infinity.is_sign_negative());
+        assert!(infinity.is_infinite());
+    }
+
+    #[test]
+    fn test_read_f64() {
+        // Double precision floating point 17.9
+        let test_bytes = &[0x0b, 0x66, 0x66, 0x66, 0x66, 0x66, 0xe6, 0x31, 0x40];
+        let tlv_reader = TLVReader::new(test_bytes);
+        let expected: f64 = 17.9;
+        let actual = tlv_reader.read_f64().expect("Failed to read f64");
+        assert!((expected - actual).abs() < f64::EPSILON);
+
+        // Double precision floating point infinity (∞)
+        let test_bytes = &[0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x7f];
+        let tlv_reader = TLVReader::new(test_bytes);
+        let infinity = tlv_reader.read_f64().expect("Failed to read f64");
+        assert!(infinity.is_sign_positive());
+        assert!(infinity.is_infinite());
+
+        // Double precision floating point negative infinity (-∞)
+        let test_bytes = &[0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0xff];
+        let tlv_reader = TLVReader::new(test_bytes);
+        let infinity = tlv_reader.read_f64().expect("Failed to read f64");
+        assert!(infinity.is_sign_negative());
+        assert!(infinity.is_infinite());
+    }
+
+    #[test]
+    fn test_read_bool() {
+        let test_bytes = &[0x08]; // Boolean false
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert!(!tlv_reader.read_bool().expect("Failed to read bool"));
+
+        let test_bytes = &[0x09]; // Boolean true
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert!(tlv_reader.read_bool().expect("Failed to read bool"));
+    }
+
+    #[test]
+    fn test_read_null() {
+        let test_bytes = &[0x14]; // Null
+        let tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader.read_null().expect("Failed to read null");
+    }
+
+    #[test]
+    fn test_read_byte_str() {
+        // Octet String, 1-octet length specifying 5 octets 00 01 02 03 04
+        let test_bytes = &[0x10, 0x05, 0x00, 0x01, 0x02, 0x03, 0x04];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader
+                .read_byte_str()
+                .expect("Failed to read byte string"),
+            [0x00, 0x01, 0x02, 0x03, 0x04]
+        );
+    }
+
+    #[test]
+    fn test_read_byte_str_ct_eq() {
+        // Structure {1 = hex:00010203, 2 = "abcd"}
+        let test_bytes = &[
+            0x15, 0x30, 0x01, 0x04, 0x00, 0x01, 0x02, 0x03, 0x2c, 0x02, 0x04, 0x61, 0x62, 0x63,
+            0x64, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(
+            tlv_reader.read_byte_str_ct_eq(&[0x00, 0x01, 0x02, 0x03]),
+            Ok(true)
+        );
+        assert_eq!(
+            tlv_reader.read_byte_str_ct_eq(&[0x00, 0x01, 0x02, 0x04]),
+            Ok(false)
+        );
+        assert_eq!(
+            tlv_reader.read_byte_str_ct_eq(&[0x00, 0x01, 0x02]),
+            Ok(false)
+        );
+        assert_eq!(tlv_reader.read_byte_str_ct_eq(&[]), Ok(false));
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(
+            tlv_reader
+                .read_byte_str_ct_eq(b"abcd")
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        // Octet String of 4 octets cut short
+        assert_eq!(
+            TLVReader::new(&[0x10, 0x04, 0x00, 0x01]).read_byte_str_ct_eq(&[0x00, 0x01]),
+            Err(TLVError::UnderRun)
+        );
+    }
+
+    #[test]
+    fn test_read_char_str() {
+        // UTF-8 String, 1-octet length, "Hello!"
+        let test_bytes = &[0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21];
+        let tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader
+                .read_char_str()
+                .expect("Failed to read character string"),
+            "Hello!"
+        );
+
+        // UTF-8 String, 1-octet length, "Tschüs"
+        let test_bytes = &[0x0c, 0x07, 0x54, 0x73, 0x63, 0x68, 0xc3, 0xbc, 0x73];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader
+                .read_char_str()
+                .expect("Failed to read character string"),
+            "Tschüs"
+        );
+        assert_eq!(
+            tlv_reader.next().expect_err("Sequence End is expected"),
+            TLVError::EndOfTLV
+        );
+    }
+
+    #[test]
+    fn test_read_borrowed_str() {
+        // UTF-8 String, 1-octet length, "Hi" + Octet String, 1-octet length, 00 01
+        let test_bytes = &[0x0c, 0x02, 0x48, 0x69, 0x10, 0x02, 0x00, 0x01];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader
+                .read_str()
+                .expect("Failed to read character string"),
+            "Hi"
+        );
+        assert_eq!(
+            tlv_reader
+                .read_byte_slice()
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(
+            tlv_reader
+                .read_byte_slice()
+                .expect("Failed to read byte string"),
+            [0x00, 0x01]
+        );
+
+        // UTF-8 String, 1-octet length, invalid UTF-8 byte ff
+        let tlv_reader = TLVReader::new(&[0x0c, 0x01, 0xff]);
+        assert_eq!(
+            tlv_reader.read_str().expect_err("Parse error is expected"),
+            TLVError::ParseError
+        );
+    }
+
+    #[test]
+    fn test_read_sequence() {
+        // Unsigned Integer, 8-octet, value 40000000000
+        // + Unsigned Integer, 1-octet, value 255
+        // + Signed Integer, 4-octet, value -904534
+        // + Boolean true
+        // + Null
+        // + Double precision floating point negative infinity (-∞)
+        // + UTF-8 String, 1-octet length, "The End."
+        let test_bytes = &[
+            0x07, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00, 0x04, 0xFF, 0x02, 0xAA, 0x32,
+            0xF2, 0xFF, 0x09, 0x14, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0xff, 0x0c,
+            0x08, 0x54, 0x68, 0x65, 0x20, 0x45, 0x6e, 0x64, 0x2e,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+
+        assert_eq!(
+            tlv_reader.take_u64().expect("Failed to take u64"),
+            40000000000
+        );
+        // A failed take leaves the cursor on the element
+        assert_eq!(
+            tlv_reader.take_i8().expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        assert_eq!(tlv_reader.take_u8().expect("Failed to take u8"), 255);
+        assert_eq!(tlv_reader.take_i32().expect("Failed to take i32"), -904534);
+        assert!(tlv_reader.take_bool().expect("Failed to take bool"));
+        tlv_reader.take_null().expect("Failed to take null byte");
+        let infinity = tlv_reader.take_f64().expect("Failed to take f64");
+        assert!(infinity.is_sign_negative());
+        assert!(infinity.is_infinite());
+        assert_eq!(
+            tlv_reader.take_str().expect("Failed to take str"),
+            "The End."
+        );
+
+        assert_eq!(
+            tlv_reader.take_u8().expect_err("Sequence End is expected"),
+            TLVError::EndOfTLV
+        );
+        assert_eq!(tlv_reader.offset(), test_bytes.len());
+
+        // The last element taken can be read again
+        tlv_reader
+            .rewind_element()
+            .expect("Failed to rewind element");
+        assert_eq!(
+            tlv_reader
+                .take_char_str()
+                .expect("Failed to take character string"),
+            "The End."
+        );
+    }
+
+    #[test]
+    fn test_peek_sequence() {
+        // Unsigned Integer, 1-octet, value 255
+        // + Boolean true
+        let test_bytes = &[0x04, 0xFF, 0x09];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+
+        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 255);
+        assert_eq!(tlv_reader.read_uint().expect("Failed to read uint"), 255);
+        tlv_reader
+            .next()
+            .expect("Failed to move pointer to next element");
+        assert!(tlv_reader.read_bool().expect("Failed to read bool"));
+        assert_eq!(
+            tlv_reader.next().expect_err("Sequence End is expected"),
+            TLVError::EndOfTLV
+        );
+        assert!(tlv_reader.read_bool().expect("Failed to read bool"));
+    }
+
+    #[test]
+    fn test_read_uint_int() {
+        // Unsigned Integer, 2-octet, value 65535
+        let tlv_reader = TLVReader::new(&[0x05, 0xFF, 0xFF]);
+        assert_eq!(tlv_reader.read_uint().expect("Failed to read uint"), 65535);
+        assert_eq!(
+            tlv_reader.read_int().expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+
+        // Signed Integer, 4-octet, value -904534
+        let tlv_reader = TLVReader::new(&[0x02, 0xAA, 0x32, 0xF2, 0xFF]);
+        assert_eq!(tlv_reader.read_int().expect("Failed to read int"), -904534);
+        assert_eq!(
+            tlv_reader
+                .read_uint()
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+    }
+
+    #[derive(Debug, PartialEq, num_derive::FromPrimitive)]
+    enum StatusCode {
+        Success = 0,
+        Busy = 0x1c,
+        Timeout = 0x0194,
+    }
+
+    #[test]
+    fn test_read_enum() {
+        // Unsigned Integer, 1-octet, value 0x1c
+        let tlv_reader = TLVReader::new(&[0x04, 0x1c]);
+        assert_eq!(tlv_reader.read_enum(), Ok(StatusCode::Busy));
+
+        // Wider encodings of a value are accepted
+        // Unsigned Integer, 8-octet, value 0
+        let tlv_reader = TLVReader::new(&[0x07, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(tlv_reader.read_enum(), Ok(StatusCode::Success));
+        // Unsigned Integer, 2-octet, value 0x0194
+        let tlv_reader = TLVReader::new(&[0x05, 0x94, 0x01]);
+        assert_eq!(tlv_reader.read_enum(), Ok(StatusCode::Timeout));
+
+        // Unsigned Integer, 2-octet, value 0x0195
+        let tlv_reader = TLVReader::new(&[0x05, 0x95, 0x01]);
+        assert_eq!(
+            tlv_reader
+                .read_enum::<StatusCode>()
+                .expect_err("Invalid enum value is expected"),
+            TLVError::InvalidEnumValue { value: 0x0195 }
+        );
+        // Signed Integer, 1-octet, value 0
+        let tlv_reader = TLVReader::new(&[0x00, 0x00]);
+        assert_eq!(
+            tlv_reader
+                .read_enum::<StatusCode>()
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+    }
+
+    #[test]
+    fn test_read_field() {
+        // Structure {0 = 42U, 1 = "Hi", 2 = [300U, 1U]}
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x2c, 0x01, 0x02, 0x48, 0x69, 0x36, 0x02, 0x05, 0x2c, 0x01,
+            0x04, 0x01, 0x18, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader
+                .read_field::<u8>(0)
+                .expect_err("Not in container is expected"),
+            TLVError::NotInContainer
+        );
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(
+            tlv_reader
+                .read_field::<Vec<u32>>(2)
+                .expect("Failed to read field"),
+            vec![300, 1]
+        );
+        assert_eq!(
+            tlv_reader
+                .read_field::<String>(1)
+                .expect("Failed to read field"),
+            "Hi"
+        );
+        assert_eq!(
+            tlv_reader
+                .read_field::<u64>(0)
+                .expect("Failed to read field"),
+            42
+        );
+        assert_eq!(
+            tlv_reader
+                .read_field::<u8>(3)
+                .expect_err("Missing field is expected"),
+            TLVError::MissingField { tag: 3 }
+        );
+        assert_eq!(
+            tlv_reader
+                .read_field::<u8>(1)
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        // Where the cursor was, with the structure still entered
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::ContextSpecific(1)
+        );
+        assert_eq!(tlv_reader.container_depth(), 1);
+        tlv_reader.next().expect("Failed to move to next element");
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(
+            tlv_reader
+                .read_field::<u8>(0)
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+    }
+
+    #[test]
+    fn test_try_read_field() {
+        // Structure {0 = 42U, 1 = Null, 2 = "Hi"}
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x34, 0x01, 0x2c, 0x02, 0x02, 0x48, 0x69, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(
+            tlv_reader
+                .try_read_field::<u16>(0)
+                .expect("Failed to read field"),
+            Some(42)
+        );
+        assert_eq!(
+            tlv_reader
+                .try_read_field::<u16>(1)
+                .expect("Failed to read field"),
+            None
+        );
+        assert_eq!(
+            tlv_reader
+                .try_read_field::<u16>(3)
+                .expect("Failed to read field"),
+            None
+        );
+        assert_eq!(
+            tlv_reader
+                .try_read_field::<u16>(2)
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        assert_eq!(
+            tlv_reader
+                .try_read_field::<String>(2)
+                .expect("Failed to read field")
+                .as_deref(),
+            Some("Hi")
+        );
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::ContextSpecific(0)
+        );
+    }
+
+    #[test]
+    fn test_read_structure_fields() {
+        // Structure {2 = 1U, 65521::57069:1 = 42U, 1 = {}, 2 = true}
+        let test_bytes = &[
+            0x15, 0x24, 0x02, 0x01, 0xc4, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x2a, 0x35, 0x01,
+            0x18, 0x29, 0x02, 0x18,
+        ];
+        let tlv_reader = TLVReader::new(test_bytes);
+        let fully_qualified =
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                vendor_id: 65521,
+                profile_number: 57069,
+                tag_number: 1,
+            });
+        assert_eq!(
+            tlv_reader
+                .read_structure_fields()
+                .expect("Failed to read structure fields"),
+            vec![
+                (TLVTag::ContextSpecific(2), TLVValue::UInt8(1)),
+                (fully_qualified, TLVValue::UInt8(42)),
+                (TLVTag::ContextSpecific(1), TLVValue::Structure(vec![])),
+                (TLVTag::ContextSpecific(2), TLVValue::Bool(true)),
+            ]
+        );
+        assert_eq!(tlv_reader.offset(), 0);
+
+        let tlv_reader = TLVReader::with_config(
+            test_bytes,
+            TLVReaderConfig::default().reject_duplicate_tags(true),
+        );
+        assert_eq!(
+            tlv_reader
+                .read_structure_fields()
+                .expect_err("Duplicate member is expected"),
+            TLVError::DuplicateMember(TLVTag::ContextSpecific(2))
+        );
+        let tlv_reader =
+            TLVReader::with_config(test_bytes, TLVReaderConfig::default().max_depth(1));
+        assert_eq!(
+            tlv_reader
+                .read_structure_fields()
+                .expect_err("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(1)
+        );
+
+        // Array [1U]
+        assert_eq!(
+            TLVReader::new(&[0x16, 0x04, 0x01, 0x18])
+                .read_structure_fields()
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+    }
+
+    #[test]
+    fn test_enter_array() {
+        // Structure {0 = [1U, 300U, [true], 2U], 1 = "Hi"}
+        let test_bytes = &[
+            0x15, 0x36, 0x00, 0x04, 0x01, 0x05, 0x2c, 0x01, 0x16, 0x09, 0x18, 0x04, 0x02, 0x18,
+            0x2c, 0x01, 0x02, 0x48, 0x69, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        let mut array_reader = tlv_reader.enter_array().expect("Failed to enter array");
+        assert!(!array_reader.is_at_end());
+        assert_eq!(
+            array_reader
+                .next_value::<u16>()
+                .expect("Failed to read value"),
+            Some(1)
+        );
+        assert_eq!(
+            array_reader
+                .next_value::<u16>()
+                .expect("Failed to read value"),
+            Some(300)
+        );
+        // A failed decode leaves the member to be read again
+        assert_eq!(
+            array_reader
+                .next_value::<u16>()
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        assert_eq!(
+            array_reader
+                .next_raw()
+                .expect("Failed to read raw member")
+                .expect("Member is expected"),
+            [0x16, 0x09, 0x18].as_ref()
+        );
+        assert_eq!(
+            array_reader
+                .next_value::<u8>()
+                .expect("Failed to read value"),
+            Some(2)
+        );
+        assert!(array_reader.is_at_end());
+        assert_eq!(
+            array_reader
+                .next_value::<u8>()
+                .expect("Failed to read value"),
+            None
+        );
+        array_reader.finish().expect("Failed to finish array");
+        assert_eq!(tlv_reader.offset(), 1);
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(tlv_reader.read_str().expect("Failed to read string"), "Hi");
+
+        // Abandoned after the first member
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        {
+            let mut array_reader = tlv_reader.enter_array().expect("Failed to enter array");
+            assert_eq!(
+                array_reader
+                    .next_value::<u8>()
+                    .expect("Failed to read value"),
+                Some(1)
+            );
+        }
+        assert_eq!(tlv_reader.container_depth(), 1);
+        assert_eq!(tlv_reader.offset(), 1);
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(tlv_reader.read_str().expect("Failed to read string"), "Hi");
+
+        // Array [] then Array [1 = 1U]
+        let mut tlv_reader = TLVReader::new(&[0x16, 0x18, 0x16, 0x24, 0x01, 0x01, 0x18]);
+        let array_reader = tlv_reader.enter_array().expect("Failed to enter array");
+        assert!(array_reader.is_at_end());
+        drop(array_reader);
+        tlv_reader.next().expect("Failed to move to next element");
+        let mut array_reader = tlv_reader.enter_array().expect("Failed to enter array");
+        assert_eq!(
+            array_reader
+                .next_value::<u8>()
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        drop(array_reader);
+        assert_eq!(tlv_reader.offset(), 2);
+        // Unsigned Integer, 1-octet, value 1
+        assert_eq!(
+            TLVReader::new(&[0x04, 0x01])
+                .enter_array()
+                .err()
+                .expect("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+    }
+
+    #[test]
+    fn test_extend_payload() {
+        // Structure {0 = 42U, 1 = "abc"} cut short in the string
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x2c, 0x01, 0x03, 0x61, 0x62, 0x63, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(&test_bytes[..8]);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        tlv_reader.next().expect("Failed to move to next element");
+        assert!(tlv_reader
+            .read_str()
+            .expect_err("Cut short string is expected")
+            .is_cut_short());
+        tlv_reader.extend_payload(&test_bytes[8..]);
+        assert_eq!(tlv_reader.read_str(), Ok("abc"));
+        assert_eq!(tlv_reader.offset(), 4);
+        assert_eq!(tlv_reader.container_depth(), 1);
+        tlv_reader
+            .rewind_element()
+            .expect("Failed to rewind element");
+        assert_eq!(tlv_reader.take_u8(), Ok(42));
+        assert_eq!(
+            tlv_reader.take_value(),
+            Ok(TLVValue::Utf8String(String::from("abc")))
+        );
+        assert_eq!(tlv_reader.read_tag(), Err(TLVError::EndOfContainer));
+
+        // Past the end of the payload, the elements appended count against max_elements as they
+        // would have in the whole payload
+        let config = TLVReaderConfig::default().max_elements(1);
+        let mut tlv_reader = TLVReader::with_config(&[], config);
+        assert_eq!(tlv_reader.read_tag(), Err(TLVError::EndOfTLV));
+        tlv_reader.extend_payload(&[0x04, 0x01, 0x04, 0x02]);
+        assert_eq!(tlv_reader.read_u8(), Ok(1));
+        assert_eq!(
+            tlv_reader.take_u8(),
+            TLVReader::with_config(&[0x04, 0x01, 0x04, 0x02], config).take_u8()
+        );
+        let mut tlv_reader = TLVReader::new(&[0x04, 0x01]);
+        assert_eq!(tlv_reader.take_u8(), Ok(1));
+        assert_eq!(tlv_reader.read_tag(), Err(TLVError::EndOfTLV));
+        tlv_reader.extend_payload(&[0x04, 0x02]);
+        assert_eq!(tlv_reader.take_u8(), Ok(2));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_byte_str_reader() {
+        use crate::writer::TLVWriter;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+        use std::io::{Read, Write};
+
+        // Hash of the octets written, whatever the chunks they come in
+        struct HashingWriter(DefaultHasher);
+
+        impl Write for HashingWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.write(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        // Structure {1 = 100000 octets counting up, 2 = true}
+        let value: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(1), value.as_slice())
+            .expect("Failed to write element");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(2), &true)
+            .expect("Failed to write element");
+        tlv_writer.end_container().expect("Failed to end container");
+        let test_bytes = tlv_writer.into_bytes();
+        let config = TLVReaderConfig::default().max_element_len(value.len());
+
+        let mut tlv_reader = TLVReader::with_config(&test_bytes, config);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        let mut byte_str_reader = tlv_reader
+            .byte_str_reader()
+            .expect("Failed to read byte string");
+        assert_eq!(byte_str_reader.remaining_len(), value.len());
+        let mut hashing_writer = HashingWriter(DefaultHasher::new());
+        assert_eq!(
+            std::io::copy(&mut byte_str_reader, &mut hashing_writer).expect("Failed to copy value"),
+            value.len() as u64
+        );
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&value);
+        assert_eq!(hashing_writer.0.finish(), hasher.finish());
+        // Read to its end, the cursor is past the element
+        assert_eq!(tlv_reader.read_tag(), Ok(TLVTag::ContextSpecific(2)));
+
+        // Dropped partway the cursor stays on the element, finish moves it past
+        let mut tlv_reader = TLVReader::with_config(&test_bytes, config);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        let mut prefix = [0; 4];
+        {
+            let mut byte_str_reader = tlv_reader
+                .byte_str_reader()
+                .expect("Failed to read byte string");
+            byte_str_reader
+                .read_exact(&mut prefix)
+                .expect("Failed to read value");
+            assert_eq!(prefix, [0x00, 0x01, 0x02, 0x03]);
+        }
+        assert_eq!(tlv_reader.read_tag(), Ok(TLVTag::ContextSpecific(1)));
+        let mut byte_str_reader = tlv_reader
+            .byte_str_reader()
+            .expect("Failed to read byte string");
+        byte_str_reader
+            .read_exact(&mut prefix)
+            .expect("Failed to read value");
+        byte_str_reader.finish().expect("Failed to finish value");
+        assert_eq!(tlv_reader.read_bool(), Ok(true));
+
+        // Empty Octet String ending the payload
+        let mut tlv_reader = TLVReader::new(&[0x10, 0x00]);
+        let mut read = Vec::new();
+        tlv_reader
+            .byte_str_reader()
+            .expect("Failed to read byte string")
+            .read_to_end(&mut read)
+            .expect("Failed to read value");
+        assert!(read.is_empty());
+        assert_eq!(tlv_reader.read_tag(), Err(TLVError::EndOfTLV));
+
+        // Octet String of 5 octets cut short after 2, UTF-8 String
+        assert_eq!(
+            TLVReader::new(&[0x10, 0x05, 0x00, 0x01])
+                .byte_str_reader()
+                .err(),
+            Some(TLVError::UnderRun)
+        );
+        assert_eq!(
+            TLVReader::new(&[0x0c, 0x01, 0x61]).byte_str_reader().err(),
+            Some(TLVError::InvalidType)
+        );
+    }
+
+    // Reads every member it can reach, then tries to leave the scope
+    fn greedy_decoder(scope: &mut ScopedReader) -> Vec<TLVTag> {
+        let mut tags = Vec::new();
+        while let Ok(tag) = scope.read_tag() {
+            tags.push(tag);
+            if scope.next().is_err() {
+                break;
+            }
+        }
+        assert_eq!(
+            scope.next().expect_err("End of container is expected"),
+            TLVError::EndOfContainer
+        );
+        assert_eq!(scope.skip_n(5).expect("Failed to skip"), 0);
+        assert_eq!(
+            scope
+                .exit_container()
+                .expect_err("Not in container is expected"),
+            TLVError::NotInContainer
+        );
+        tags
+    }
+
+    #[test]
+    fn test_enter_scoped() {
+        // Structure {0 = {1 = 1U, 2 = {3 = true}}, 4 = "secret"} + Boolean true
+        let test_bytes = &[
+            0x15, 0x35, 0x00, 0x24, 0x01, 0x01, 0x35, 0x02, 0x29, 0x03, 0x18, 0x18, 0x2c, 0x04,
+            0x06, 0x73, 0x65, 0x63, 0x72, 0x65, 0x74, 0x18, 0x09,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        let mut scope = tlv_reader.enter_scoped().expect("Failed to enter scope");
+        assert_eq!(
+            greedy_decoder(&mut scope),
+            vec![TLVTag::ContextSpecific(1), TLVTag::ContextSpecific(2)]
+        );
+        assert_eq!(scope.read_field::<u8>(1).expect("Failed to read field"), 1);
+        assert_eq!(
+            scope
+                .read_field::<String>(4)
+                .expect_err("Missing field is expected"),
+            TLVError::MissingField { tag: 4 }
+        );
+
+        // Nested scope, on the member tagged 2
+        let mut inner_scope = scope.enter_scoped().expect("Failed to enter scope");
+        assert_eq!(
+            greedy_decoder(&mut inner_scope),
+            vec![TLVTag::ContextSpecific(3)]
+        );
+        assert!(inner_scope.decode::<bool>().expect("Failed to decode"));
+
+        // Containers entered within the scope can be exited
+        scope.enter_container().expect("Failed to enter container");
+        scope.exit_container().expect("Failed to exit container");
+        assert_eq!(
+            scope.read_tag().expect("Failed to read tag"),
+            TLVTag::ContextSpecific(2)
+        );
+
+        // The parent did not move
+        assert_eq!(tlv_reader.offset(), 1);
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(
+            tlv_reader.read_str().expect("Failed to read string"),
+            "secret"
+        );
+        assert_eq!(
+            tlv_reader
+                .enter_scoped()
+                .err()
+                .expect("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+    }
+
+    #[test]
+    fn test_read_reserved_element_types() {
+        for value in 0x19..=0x1f {
+            let unknown = TLVError::UnknownElementType { value, offset: 0 };
+            assert_eq!(
+                TLVReader::new(&[value])
+                    .read_type()
+                    .expect_err("Unknown element type is expected"),
+                unknown
+            );
+            // Context tag 1
+            assert_eq!(
+                TLVReader::new(&[0x20 | value, 0x01])
+                    .read_tag()
+                    .expect_err("Unknown element type is expected"),
+                unknown
+            );
+
+            // Structure {0 = 1U, reserved} + Boolean true
+            let test_bytes = &[0x15, 0x24, 0x00, 0x01, value, 0x18, 0x09];
+            let unknown = TLVError::UnknownElementType { value, offset: 4 };
+            assert_eq!(
+                TLVReader::new(test_bytes)
+                    .next()
+                    .expect_err("Unknown element type is expected"),
+                unknown
+            );
+            let mut tlv_reader = TLVReader::new(test_bytes);
+            tlv_reader
+                .enter_container()
+                .expect("Failed to enter container");
+            tlv_reader.next().expect("Failed to move to next element");
+            assert_eq!(
+                tlv_reader
+                    .read_type()
+                    .expect_err("Unknown element type is expected"),
+                unknown
+            );
+            assert_eq!(
+                tlv_reader
+                    .next()
+                    .expect_err("Unknown element type is expected"),
+                unknown
+            );
+        }
+    }
+
+    #[test]
+    fn test_reader_debug() {
+        // Structure {0 = [1U, 2U], 1 = "abcdefghij"}
+        let test_bytes = &[
+            0x15, 0x36, 0x00, 0x04, 0x01, 0x04, 0x02, 0x18, 0x2c, 0x01, 0x0a, 0x61, 0x62, 0x63,
+            0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            format!("{:?}", tlv_reader),
+            "TLVReader { len: 22, bytes_read: 0, depth: 0, container: None, \
+             next: 0x15 Anonymous Container(Structure), window: [15]360004010402182c.. }"
+        );
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(
+            format!("{:?}", tlv_reader),
+            "TLVReader { len: 22, bytes_read: 8, depth: 1, container: Some(Structure), \
+             next: 0x2c ContextSpecific(1) Primitive(Specified(UTF8String(OneOctet))), \
+             window: 1536000401040218[2c]010a616263646566.. }"
+        );
+        tlv_reader.next().expect_err("End of container is expected");
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        tlv_reader.next().expect_err("End of TLV is expected");
+        assert_eq!(
+            format!("{:?}", tlv_reader),
+            "TLVReader { len: 22, bytes_read: 0, depth: 0, container: None, \
+             next: 0x15 Anonymous Container(Structure), window: [15]360004010402182c.. }"
+        );
+
+        // Malformed and exhausted input
+        assert_eq!(
+            format!("{:?}", TLVReader::new(&[0x0c, 0x05, 0x61])),
+            "TLVReader { len: 3, bytes_read: 0, depth: 0, container: None, \
+             next: 0x0c Anonymous Primitive(Specified(UTF8String(OneOctet))), window: [0c]0561 }"
+        );
+        // Context tag missing
+        assert_eq!(
+            format!("{:?}", TLVReader::new(&[0x24])),
+            "TLVReader { len: 1, bytes_read: 0, depth: 0, container: None, \
+             next: TruncatedTag { tag_control: ContextSpecific, missing: 1 }, window: [24] }"
+        );
+        assert_eq!(
+            format!("{:?}", TLVReader::new(&[])),
+            "TLVReader { len: 0, bytes_read: 0, depth: 0, container: None, \
+             next: EndOfTLV, window: [] }"
+        );
+    }
+
+    #[test]
+    fn test_remaining() {
+        // Structure {0 = 42U} followed by a 4-byte MIC
+        let test_bytes = &[0x15, 0x24, 0x00, 0x2a, 0x18, 0xde, 0xad, 0xbe, 0xef];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(tlv_reader.remaining(), test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(tlv_reader.read_u8(), Ok(42));
+        assert_eq!(tlv_reader.remaining(), &test_bytes[1..]);
+        assert_eq!(tlv_reader.next(), Err(TLVError::EndOfContainer));
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        assert_eq!(tlv_reader.remaining_len(), test_bytes.len());
+
+        // Not an element, but positioned on it all the same
+        tlv_reader
+            .next()
+            .expect("Failed to move past the structure");
+        assert_eq!(tlv_reader.remaining(), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(tlv_reader.remaining_len(), 4);
+        assert!(tlv_reader.read_tag().is_err());
+    }
+
+    #[test]
+    fn test_new_range() {
+        // Junk, Structure {0 = 42U, 1 = "Hi"}, Unsigned Integer 7 then junk
+        let test_bytes = &[
+            0xde, 0xad, 0x15, 0x24, 0x00, 0x2a, 0x2c, 0x01, 0x02, 0x48, 0x69, 0x18, 0x04, 0x07,
+            0xbe, 0xef,
+        ];
+        let mut tlv_reader =
+            TLVReader::new_range(test_bytes, 2..14).expect("Failed to create reader");
+        assert_eq!(tlv_reader.offset(), 2);
+        assert_eq!(tlv_reader.remaining_len(), 12);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(tlv_reader.read_u8(), Ok(42));
+        tlv_reader
+            .next()
+            .expect("Failed to move to the next element");
+        assert_eq!(tlv_reader.offset(), 6);
+        assert_eq!(tlv_reader.read_u8(), Err(TLVError::InvalidType));
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        tlv_reader
+            .next()
+            .expect("Failed to move to the next element");
+        assert_eq!(tlv_reader.read_u8(), Ok(7));
+        assert_eq!(tlv_reader.next(), Err(TLVError::EndOfTLV));
+        let elements: Vec<_> = tlv_reader
+            .elements()
+            .map(|element| element.map(|element| element.offset()))
+            .collect();
+        assert_eq!(elements, vec![Ok(2), Ok(12)]);
+
+        // Errors give offsets in the whole buffer
+        let mut tlv_reader =
+            TLVReader::new_range(test_bytes, 2..11).expect("Failed to create reader");
+        assert_eq!(
+            tlv_reader.next(),
+            Err(TLVError::UnterminatedContainer { offset: 2 })
+        );
+        let tlv_reader = TLVReader::new_at(test_bytes, 12).expect("Failed to create reader");
+        assert_eq!(tlv_reader.read_u8(), Ok(7));
+        assert_eq!(tlv_reader.remaining(), [0x04, 0x07, 0xbe, 0xef]);
+        let tlv_reader = TLVReader::new_at(test_bytes, 16).expect("Failed to create reader");
+        assert_eq!(tlv_reader.read_tag(), Err(TLVError::EndOfTLV));
+        assert!(tlv_reader.elements().next().is_none());
+
+        assert!(TLVReader::new_at(test_bytes, 17).is_err());
+        assert!(TLVReader::new_range(test_bytes, Range { start: 4, end: 2 }).is_err());
+        assert!(TLVReader::new_range(test_bytes, 2..17).is_err());
+
+        // Sharing the buffer, with the same offsets
+        let buffer = Bytes::copy_from_slice(test_bytes);
+        let mut tlv_reader =
+            TLVReader::from_bytes_range(buffer.clone(), 12..14).expect("Failed to create reader");
+        assert_eq!(tlv_reader.offset(), 12);
+        assert_eq!(tlv_reader.remaining().as_ptr(), buffer[12..].as_ptr());
+        assert_eq!(tlv_reader.take_u8(), Ok(7));
+        assert_eq!(tlv_reader.next(), Err(TLVError::EndOfTLV));
+        assert_eq!(tlv_reader.offset(), 14);
+        let mut tlv_reader =
+            TLVReader::from_bytes_range(buffer.clone(), 2..11).expect("Failed to create reader");
+        assert_eq!(
+            tlv_reader.next(),
+            Err(TLVError::UnterminatedContainer { offset: 2 })
+        );
+        assert!(TLVReader::from_bytes_range(buffer.clone(), Range { start: 4, end: 2 }).is_err());
+        assert!(TLVReader::from_bytes_range(buffer, 2..17).is_err());
+    }
+
+    #[test]
+    fn test_rewind_element() {
+        // Unsigned Integer 1, Structure {0 = 2U, 1 = 3U, 2 = 4U}, Unsigned Integer 5
+        let test_bytes = &[
+            0x04, 0x01, 0x15, 0x24, 0x00, 0x02, 0x24, 0x01, 0x03, 0x24, 0x02, 0x04, 0x18, 0x04,
+            0x05,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(tlv_reader.rewind_element(), Err(TLVError::NothingToRewind));
+        tlv_reader
+            .next()
+            .expect("Failed to move to the next element");
+        tlv_reader
+            .rewind_element()
+            .expect("Failed to rewind element");
+        assert_eq!(tlv_reader.read_u8(), Ok(1));
+        assert_eq!(tlv_reader.skip_n(2), Ok(2));
+        assert_eq!(tlv_reader.read_u8(), Ok(5));
+        tlv_reader
+            .rewind_element()
+            .expect("Failed to rewind element");
+        assert_eq!(tlv_reader.offset(), 2);
+
+        // Members have their own history, the parent's comes back on exit
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(tlv_reader.rewind_element(), Err(TLVError::NothingToRewind));
+        assert_eq!(tlv_reader.skip_n(2), Ok(2));
+        assert_eq!(tlv_reader.read_u8(), Ok(4));
+        tlv_reader
+            .rewind_element()
+            .expect("Failed to rewind element");
+        assert_eq!(tlv_reader.read_u8(), Ok(3));
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        tlv_reader
+            .rewind_element()
+            .expect("Failed to rewind element");
+        assert_eq!(tlv_reader.read_u8(), Ok(1));
+        assert_eq!(tlv_reader.rewind_element(), Err(TLVError::NothingToRewind));
+
+        // Members left behind by exit_container cannot be rewound to from a sibling
+        tlv_reader
+            .next()
+            .expect("Failed to move to the next element");
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        tlv_reader
+            .next()
+            .expect("Failed to move to the next element");
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        tlv_reader
+            .next()
+            .expect("Failed to move to the next element");
+        tlv_reader
+            .rewind_element()
+            .expect("Failed to rewind element");
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(tlv_reader.rewind_element(), Err(TLVError::NothingToRewind));
+
+        // The history is bounded, the oldest elements are forgotten
+        let config = TLVReaderConfig::default().rewind_depth(1);
+        let mut tlv_reader = TLVReader::with_config(test_bytes, config);
+        assert_eq!(tlv_reader.skip_n(2), Ok(2));
+        tlv_reader
+            .rewind_element()
+            .expect("Failed to rewind element");
+        assert_eq!(tlv_reader.offset(), 2);
+        assert_eq!(tlv_reader.rewind_element(), Err(TLVError::NothingToRewind));
+        let config = TLVReaderConfig::default().rewind_depth(0);
+        let mut tlv_reader = TLVReader::with_config(test_bytes, config);
+        tlv_reader
+            .next()
+            .expect("Failed to move to the next element");
+        assert_eq!(tlv_reader.rewind_element(), Err(TLVError::NothingToRewind));
+    }
+
+    #[test]
+    fn test_descend() {
+        // Structure {0 = 42U, 1 = [true, "Hi"], 2 = List [[3 = Null]]}
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x0c, 0x02, 0x48, 0x69, 0x18, 0x37, 0x02,
+            0x34, 0x03, 0x18, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        let path = "1[1]".parse().expect("Failed to parse path");
+        tlv_reader.descend(&path).expect("Failed to descend");
+        assert_eq!(tlv_reader.read_char_str(), Ok(String::from("Hi")));
+        assert_eq!(tlv_reader.container_depth(), 2);
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        assert_eq!(tlv_reader.offset(), 4);
+
+        // Relative to the element under the cursor
+        tlv_reader
+            .next()
+            .expect("Failed to move to the next element");
+        tlv_reader
+            .descend(&TLVPath::root().tag(3))
+            .expect("Failed to descend");
+        assert_eq!(tlv_reader.read_null(), Ok(()));
+        assert_eq!(
+            tlv_reader.descend(&TLVPath::root().tag(0)),
+            Err(TLVError::InvalidType)
+        );
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.descend(&TLVPath::root().tag(2).tag(4)),
+            Err(TLVError::PathNotFound { step: 1 })
+        );
+    }
+
+    #[test]
+    fn test_skip_n() {
+        // Structure {0 = 42U, 1 = [true, -3], 2 = List [[Anonymous = "a", 3 = Null]], 3 = 1.5}
+        // + Boolean true
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x00, 0xfd, 0x18, 0x37, 0x02, 0x0c, 0x01,
+            0x61, 0x34, 0x03, 0x18, 0x2a, 0x03, 0x00, 0x00, 0xc0, 0x3f, 0x18, 0x09,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(tlv_reader.skip_n(0).expect("Failed to skip"), 0);
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::ContextSpecific(0)
+        );
+        assert_eq!(tlv_reader.skip_n(2).expect("Failed to skip"), 2);
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::ContextSpecific(2)
+        );
+        // Only the last member is left
+        assert_eq!(tlv_reader.skip_n(3).expect("Failed to skip"), 1);
+        assert_eq!(tlv_reader.read_f32().expect("Failed to read f32"), 1.5);
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        assert_eq!(tlv_reader.skip_n(5).expect("Failed to skip"), 1);
+        assert!(tlv_reader.read_bool().expect("Failed to read bool"));
+        assert_eq!(tlv_reader.skip_n(1).expect("Failed to skip"), 0);
+
+        // Structure {0 = 42, 1 = -17} without its End of Container
+        let mut tlv_reader = TLVReader::new(&[0x15, 0x20, 0x00, 0x2a, 0x20, 0x01, 0xef]);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(
+            tlv_reader
+                .skip_n(2)
+                .expect_err("Unterminated container is expected"),
+            TLVError::UnterminatedContainer { offset: 0 }
+        );
+    }
+
+    #[test]
+    fn test_read_structure() {
+        // Structure, two context specific tags, Signed Integer, 1 octet values, {0 = 42, 1 = -17}
+        // + Boolean true
+        let test_bytes = &[0x15, 0x20, 0x00, 0x2a, 0x20, 0x01, 0xef, 0x18, 0x09];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.read_type().expect("Failed to read type"),
+            TLVType::Container(ContainerType::Structure)
+        );
+        assert_eq!(
+            tlv_reader
+                .enter_container()
+                .expect("Failed to enter container"),
+            ContainerType::Structure
+        );
+        assert_eq!(tlv_reader.container_depth(), 1);
+        assert_eq!(tlv_reader.container_type(), Some(ContainerType::Structure));
+        assert_eq!(tlv_reader.offset(), 1);
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::ContextSpecific(0)
+        );
+        assert_eq!(tlv_reader.read_i8().expect("Failed to read i8"), 42);
+
+        tlv_reader
+            .next()
+            .expect("Failed to move pointer to next element");
+        assert_eq!(
+            tlv_reader.read_tag().expect("Failed to read tag"),
+            TLVTag::ContextSpecific(1)
+        );
+        assert_eq!(tlv_reader.read_i8().expect("Failed to read i8"), -17);
+        assert_eq!(
+            tlv_reader.next().expect_err("Container End is expected"),
+            TLVError::EndOfContainer
+        );
+
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        assert_eq!(tlv_reader.container_depth(), 0);
+        assert_eq!(tlv_reader.offset(), 0);
+        tlv_reader
+            .next()
+            .expect("Failed to move pointer to next element");
+        assert!(tlv_reader.read_bool().expect("Failed to read bool"));
+        assert_eq!(
+            tlv_reader.next().expect_err("Sequence End is expected"),
+            TLVError::EndOfTLV
+        );
+        assert_eq!(
+            tlv_reader
+                .exit_container()
+                .expect_err("Not in container is expected"),
+            TLVError::NotInContainer
+        );
+    }
 
     #[test]
-    fn test_parse_control_byte() {
-        // Unsigned Integer, 8-octet, value 40000000000
-        let test_bytes = &[0x07, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00];
-        let tlv_reader = TLVReader::new(test_bytes);
-        let (remaining_bytes, (tag_control, element_type)) = tlv_reader
-            .parse_control_byte()
-            .expect("Cannot parse control byte");
+    fn test_read_nested_containers() {
+        // Array, mix of element types, [42, -170000, {}, 17.9, "Hello!"]
+        // + Structure {0 = [1, 2]}
+        let test_bytes = &[
+            0x16, 0x00, 0x2a, 0x02, 0xf0, 0x67, 0xfd, 0xff, 0x15, 0x18, 0x0a, 0x33, 0x33, 0x8f,
+            0x41, 0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21, 0x18, 0x15, 0x36, 0x00, 0x04,
+            0x01, 0x04, 0x02, 0x18, 0x18,
+        ];
+        // The Array alone, nothing follows it
+        let mut tlv_reader = TLVReader::new(&test_bytes[..24]);
+        assert_eq!(
+            tlv_reader.next().expect_err("Sequence End is expected"),
+            TLVError::EndOfTLV
+        );
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(tlv_reader.take_i8().expect("Failed to take i8"), 42);
+        assert_eq!(tlv_reader.take_i32().expect("Failed to take i32"), -170000);
+
+        // Empty structure
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(tlv_reader.container_depth(), 2);
         assert_eq!(
-            tag_control << tags::CONTROL_BYTE_SHIFT,
-            TagControl::Anonymous as u8
+            tlv_reader.take_u8().expect_err("Container End is expected"),
+            TLVError::EndOfContainer
         );
-        assert_eq!(element_type, ElementType::UInt64 as u8);
         assert_eq!(
-            remaining_bytes,
-            [0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00]
+            tlv_reader
+                .read_tag()
+                .expect_err("Container End is expected"),
+            TLVError::EndOfContainer
+        );
+        assert_eq!(
+            tlv_reader.next().expect_err("Container End is expected"),
+            TLVError::EndOfContainer
+        );
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        tlv_reader
+            .next()
+            .expect("Failed to move pointer to next element");
+
+        let expected: f32 = 17.9;
+        let actual = tlv_reader.take_f32().expect("Failed to take f32");
+        assert!((expected - actual).abs() < f32::EPSILON);
+        assert_eq!(
+            tlv_reader
+                .take_char_str()
+                .expect("Failed to take character string"),
+            "Hello!"
+        );
+        assert_eq!(
+            tlv_reader
+                .take_char_str()
+                .expect_err("Container End is expected"),
+            TLVError::EndOfContainer
+        );
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        tlv_reader
+            .next()
+            .expect("Failed to move pointer to next element");
+
+        // Containers are taken whole
+        assert_eq!(
+            tlv_reader.take_value().expect("Failed to take structure"),
+            TLVValue::Structure(vec![(
+                TLVTag::ContextSpecific(0),
+                TLVValue::Array(vec![TLVValue::UInt8(1), TLVValue::UInt8(2)])
+            )])
+        );
+        assert_eq!(
+            tlv_reader
+                .take_value()
+                .expect_err("Sequence End is expected"),
+            TLVError::EndOfTLV
+        );
+        tlv_reader
+            .rewind_element()
+            .expect("Failed to rewind element");
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(
+            tlv_reader.take::<Vec<u8>>().expect("Failed to take array"),
+            vec![1, 2]
+        );
+        assert_eq!(
+            tlv_reader
+                .take::<u8>()
+                .expect_err("Container End is expected"),
+            TLVError::EndOfContainer
         );
     }
 
     #[test]
-    fn test_read_u8() {
-        let test_bytes = &[0x04, 0xFF]; // Unsigned Integer, 1-octet, value 255
-        let tlv_reader = TLVReader::new(test_bytes);
-        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 255);
+    fn test_read_truncated_container() {
+        // Structure {0 = 42, 1 = -17} without its End of Container
+        let test_bytes = &[0x15, 0x20, 0x00, 0x2a, 0x20, 0x01, 0xef];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        let unterminated = TLVError::UnterminatedContainer { offset: 0 };
+        assert_eq!(
+            tlv_reader
+                .next()
+                .expect_err("Unterminated container is expected"),
+            unterminated
+        );
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(
+            tlv_reader
+                .next()
+                .expect_err("Unterminated container is expected"),
+            unterminated
+        );
+
+        // Structure {0 = {}} ending right after the control byte of the inner structure
+        let mut tlv_reader = TLVReader::new(&[0x15, 0x35, 0x00]);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(
+            tlv_reader
+                .read_tag()
+                .expect_err("Unterminated container is expected"),
+            unterminated
+        );
+
+        // Element type of End of Container with a non anonymous tag
+        let test_bytes = &[0x15, 0x38, 0x01];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            tlv_reader.next().expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
     }
 
     #[test]
-    fn test_read_u8_tagged() {
-        // Anonymous tag, Unsigned Integer, 1-octet value, 42U
-        let test_bytes = &[0x04, 0x2a];
-        let tlv_reader = TLVReader::new(test_bytes);
+    fn test_read_unterminated_containers() {
+        // Boolean true + Array, mix of element types, [42, -170000, {}, 17.9, "Hello!"]
+        let test_bytes = &[
+            0x09, 0x16, 0x00, 0x2a, 0x02, 0xf0, 0x67, 0xfd, 0xff, 0x15, 0x18, 0x0a, 0x33, 0x33,
+            0x8f, 0x41, 0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21, 0x18,
+        ];
+        // Truncated right before each End of Container, the Array is the outermost open container
+        let unterminated = TLVError::UnterminatedContainer { offset: 1 };
+        for end in [10, 24] {
+            let mut tlv_reader = TLVReader::new(&test_bytes[..end]);
+            tlv_reader.next().expect("Failed to move to next element");
+            assert_eq!(
+                tlv_reader
+                    .read_value()
+                    .expect_err("Unterminated container is expected"),
+                unterminated
+            );
+            assert_eq!(
+                tlv_reader
+                    .read_value_ref()
+                    .expect_err("Unterminated container is expected"),
+                unterminated
+            );
+            tlv_reader
+                .enter_container()
+                .expect("Failed to enter container");
+            let error = loop {
+                if let Err(e) = tlv_reader.next() {
+                    break e;
+                }
+            };
+            assert_eq!(error, unterminated);
+        }
+
+        // Cursor on the empty Structure, the payload ends before its End of Container
+        let mut tlv_reader = TLVReader::new(&test_bytes[..10]);
+        tlv_reader.next().expect("Failed to move to next element");
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        tlv_reader.next().expect("Failed to move to next element");
+        tlv_reader.next().expect("Failed to move to next element");
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
         assert_eq!(
-            tlv_reader.read_tag().expect("Failed to read tag"),
-            TLVTag::Anonymous
+            tlv_reader
+                .read_tag()
+                .expect_err("Unterminated container is expected"),
+            unterminated
         );
-        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+    }
 
-        // Context tag 1, Unsigned Integer, 1-octet value, 1 = 42U
-        let test_bytes = &[0x24, 0x01, 0x2a];
-        let tlv_reader = TLVReader::new(test_bytes);
+    #[test]
+    fn test_next_huge_length() {
+        // Octet String, 8-octet length of u64::MAX, found by fuzzing
+        let test_bytes = &[
+            0x13, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x04, 0x01,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
         assert_eq!(
-            tlv_reader.read_tag().expect("Failed to read tag"),
-            TLVTag::ContextSpecific(1)
+            tlv_reader.next().expect_err("Under run is expected"),
+            TLVError::UnderRun
         );
-        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+        assert_eq!(
+            tlv_reader.read_value().expect_err("Under run is expected"),
+            TLVError::UnderRun
+        );
+    }
 
-        // Common profile tag 1, Unsigned Integer, 1-octet value, CHIP::1 = 42U
-        let test_bytes = &[0x44, 0x01, 0x00, 0x2a];
-        let tlv_reader = TLVReader::new(test_bytes);
+    #[test]
+    fn test_peek_str_len() {
+        let test_vectors: &[(&[u8], TLVFieldSize)] = &[
+            // Octet String, 1-octet length specifying 3 octets 00 01 02
+            (&[0x10, 0x03, 0x00, 0x01, 0x02], TLVFieldSize::OneOctet),
+            // UTF-8 String, 2-octet length, "abc"
+            (
+                &[0x0d, 0x03, 0x00, 0x61, 0x62, 0x63],
+                TLVFieldSize::TwoOctets,
+            ),
+            // Octet String, 4-octet length specifying 3 octets 00 01 02
+            (
+                &[0x12, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02],
+                TLVFieldSize::FourOctets,
+            ),
+            // UTF-8 String, 8-octet length, "abc"
+            (
+                &[
+                    0x0f, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x61, 0x62, 0x63,
+                ],
+                TLVFieldSize::EightOctets,
+            ),
+        ];
+        for (test_bytes, field_size) in test_vectors {
+            let tlv_reader = TLVReader::new(test_bytes);
+            assert_eq!(
+                tlv_reader
+                    .peek_str_len()
+                    .expect("Failed to peek string length"),
+                (3, *field_size)
+            );
+            let value_len = match tlv_reader.read_byte_str() {
+                Ok(value) => value.len(),
+                Err(_) => tlv_reader.read_str().expect("Failed to read string").len(),
+            };
+            assert_eq!(value_len, 3);
+            assert_eq!(tlv_reader.offset(), 0);
+        }
+
+        // Declared past the end of the payload and past max_element_len
+        let tlv_reader = TLVReader::with_config(
+            &[0x10, 0xff, 0x00],
+            TLVReaderConfig::default().max_element_len(4),
+        );
         assert_eq!(
-            tlv_reader.read_tag().expect("Failed to read tag"),
-            TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 1 })
+            tlv_reader
+                .peek_str_len()
+                .expect("Failed to peek string length"),
+            (255, TLVFieldSize::OneOctet)
         );
-        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+        // Unsigned Integer, 1-octet, value 1
+        assert_eq!(
+            TLVReader::new(&[0x04, 0x01])
+                .peek_str_len()
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        // UTF-8 String, 2-octet length field cut short
+        assert_eq!(
+            TLVReader::new(&[0x0d, 0x01])
+                .peek_str_len()
+                .expect_err("Truncated value is expected"),
+            TLVError::TruncatedValue {
+                expected: 2,
+                available: 1
+            }
+        );
+    }
 
-        // Common profile tag 100000, Unsigned Integer, 1-octet value, CHIP::100000 = 42U
-        let test_bytes = &[0x64, 0xa0, 0x86, 0x01, 0x00, 0x2a];
-        let tlv_reader = TLVReader::new(test_bytes);
+    #[test]
+    fn test_read_shared_bytes() {
+        // Structure {1 = Octet String 00 01 02, 2 = "Hi"}
+        let test_bytes = Bytes::from(vec![
+            0x15, 0x30, 0x01, 0x03, 0x00, 0x01, 0x02, 0x2c, 0x02, 0x02, 0x48, 0x69, 0x18,
+        ]);
+        let storage = test_bytes.as_ptr();
+        let mut tlv_reader = TLVReader::from_bytes(test_bytes.clone());
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        let value = tlv_reader.read_bytes().expect("Failed to read bytes");
+        assert_eq!(value, [0x00, 0x01, 0x02].as_ref());
+        assert_eq!(value.as_ptr(), storage.wrapping_add(4));
         assert_eq!(
-            tlv_reader.read_tag().expect("Failed to read tag"),
-            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 100000 })
+            Bytes::decode_tlv(&mut tlv_reader)
+                .expect("Failed to decode bytes")
+                .as_ptr(),
+            storage.wrapping_add(4)
         );
-        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
+        tlv_reader.next().expect("Failed to move to next element");
+        let value = tlv_reader.read_str_bytes().expect("Failed to read string");
+        assert_eq!(value, "Hi".as_bytes());
+        assert_eq!(value.as_ptr(), storage.wrapping_add(10));
 
-        // Fully qualified tag, Vendor ID 0xFFF1/65521, profile number 0xDEED/57069,
-        // 2-octet tag 1, Unsigned Integer, 1-octet value 42, 65521::57069:1 = 42U
-        let test_bytes = &[0xc4, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x2a];
-        let tlv_reader = TLVReader::new(test_bytes);
+        // The fork covers the element under the cursor only
+        let mut fork = tlv_reader.fork().expect("Failed to fork");
         assert_eq!(
-            tlv_reader.read_tag().expect("Failed to read tag"),
-            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
-                vendor_id: 65521,
-                profile_number: 57069,
-                tag_number: 1
-            })
+            fork.read_byte_slice()
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        assert_eq!(fork.read_str().expect("Failed to read string"), "Hi");
+        assert_eq!(
+            fork.read_str_bytes()
+                .expect("Failed to read string")
+                .as_ptr(),
+            storage.wrapping_add(10)
+        );
+        assert_eq!(
+            fork.next().expect_err("Sequence End is expected"),
+            TLVError::EndOfTLV
         );
-        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
 
-        // Fully qualified tag, Vendor ID 0xFFF1/65521, profile number 0xDEED/57069,
-        // 4-octet tag 0xAA55FEED/2857762541,
-        // Unsigned Integer, 1-octet value 42, 65521::57069:2857762541 = 42U
-        let test_bytes = &[0xe4, 0xf1, 0xff, 0xed, 0xde, 0xed, 0xfe, 0x55, 0xaa, 0x2a];
-        let tlv_reader = TLVReader::new(test_bytes);
+        // A Bytes buffer is shared as well when read as an impl Buf
+        let tlv_reader = TLVReader::from_buf(test_bytes.clone());
+        let fork = tlv_reader.fork().expect("Failed to fork");
+        assert_eq!(fork.bytes.as_ptr(), storage);
+        assert_eq!(fork.bytes.len(), test_bytes.len());
+        // Chunks are gathered
+        let chained = (&test_bytes[..5]).chain(&test_bytes[5..]);
         assert_eq!(
-            tlv_reader.read_tag().expect("Failed to read tag"),
-            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
-                vendor_id: 65521,
-                profile_number: 57069,
-                tag_number: 2857762541
-            })
+            TLVReader::from_buf(chained)
+                .read_value()
+                .expect("Failed to read value"),
+            TLVReader::new(&test_bytes)
+                .read_value()
+                .expect("Failed to read value")
         );
-        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 42);
     }
 
     #[test]
-    fn test_read_u16() {
-        let test_bytes = &[0x05, 0xFF, 0xFF]; // Unsigned Integer, 2-octet, value 65535
-        let tlv_reader = TLVReader::new(test_bytes);
-        assert_eq!(tlv_reader.read_u16().expect("Failed to read u16"), 65535);
+    fn test_to_owned_subtree() {
+        // Structure {0 = {1 = [1U, [2U]]}, CHIP::2 = List [true], CHIP::65536 = [], 1 = 42U,
+        // Implicit::3 = {Implicit::100000 = {}}, 65521::57069:4 = [{}],
+        // 65521::57069:100000 = List [5 = Null]} + Array [] + Null
+        let test_bytes = &[
+            0x15, 0x35, 0x00, 0x36, 0x01, 0x04, 0x01, 0x16, 0x04, 0x02, 0x18, 0x18, 0x18, 0x57,
+            0x02, 0x00, 0x09, 0x18, 0x76, 0x00, 0x00, 0x01, 0x00, 0x18, 0x24, 0x01, 0x2a, 0x95,
+            0x03, 0x00, 0xb5, 0xa0, 0x86, 0x01, 0x00, 0x18, 0x18, 0xd6, 0xf1, 0xff, 0xed, 0xde,
+            0x04, 0x00, 0x15, 0x18, 0x18, 0xf7, 0xf1, 0xff, 0xed, 0xde, 0xa0, 0x86, 0x01, 0x00,
+            0x34, 0x05, 0x18, 0x18, 0x16, 0x18, 0x14,
+        ];
+        let in_place = |offset: usize| {
+            let tlv_reader =
+                TLVReader::new_at(test_bytes, offset).expect("Failed to create reader");
+            (
+                tlv_reader.read_tag().expect("Failed to read tag"),
+                tlv_reader.read_value().expect("Failed to read value"),
+            )
+        };
+
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        let mut subtrees = Vec::new();
+        loop {
+            let offset = tlv_reader.offset();
+            match tlv_reader.to_owned_subtree() {
+                Ok(subtree) => subtrees.push((offset, subtree)),
+                Err(TLVError::InvalidType) => {
+                    assert_eq!(tlv_reader.take_u8(), Ok(42));
+                }
+                Err(TLVError::EndOfContainer) => break,
+                Err(e) => panic!("Failed to take subtree: {}", e),
+            }
+        }
+        assert_eq!(subtrees.len(), 6);
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+        tlv_reader.next().expect("Failed to move to next element");
+        // Top level container, the parent ends past it
+        let offset = tlv_reader.offset();
+        subtrees.push((
+            offset,
+            tlv_reader
+                .to_owned_subtree()
+                .expect("Failed to take subtree"),
+        ));
+        assert_eq!(tlv_reader.take_null(), Ok(()));
+        assert_eq!(
+            tlv_reader
+                .to_owned_subtree()
+                .expect_err("Sequence End is expected"),
+            TLVError::EndOfTLV
+        );
+
+        // Each subtree decodes on another thread as it does in place, with nothing after it
+        let decoded = std::thread::spawn(move || {
+            subtrees
+                .into_iter()
+                .map(|(offset, mut subtree)| {
+                    let decoded = (
+                        subtree.read_tag().expect("Failed to read tag"),
+                        subtree.read_value().expect("Failed to read value"),
+                    );
+                    let len = subtree.remaining_len();
+                    assert_eq!(
+                        subtree.next().expect_err("Sequence End is expected"),
+                        TLVError::EndOfTLV
+                    );
+                    (offset, len, decoded)
+                })
+                .collect::<Vec<_>>()
+        })
+        .join()
+        .expect("Failed to decode subtrees");
+        for (offset, len, decoded) in &decoded {
+            assert_eq!(*decoded, in_place(*offset));
+            assert_eq!(
+                element_size(&test_bytes[*offset..]).expect("Failed to size element"),
+                *len
+            );
+        }
+        let ends: Vec<_> = decoded
+            .iter()
+            .map(|(offset, len, _)| offset + len)
+            .collect();
+        assert_eq!(ends, vec![13, 18, 24, 37, 47, 59, 62]);
+
+        // A container cut short is not taken, the parent stays on it
+        let mut tlv_reader = TLVReader::new(&test_bytes[..10]);
+        assert_eq!(
+            tlv_reader
+                .to_owned_subtree()
+                .expect_err("Unterminated container is expected"),
+            TLVError::UnterminatedContainer { offset: 0 }
+        );
+        assert_eq!(tlv_reader.offset(), 0);
     }
 
     #[test]
-    fn test_read_u32() {
-        // Unsigned Integer, 4-octet, value 237998115
-        let test_bytes = &[0x06, 0x23, 0x90, 0x2f, 0x0E];
+    fn test_read_max_element_len() {
+        // Octet String, 8-octet length of u64::MAX, far past the buffer
+        let test_bytes = &[
+            0x13, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x04, 0x01,
+        ];
         let tlv_reader = TLVReader::new(test_bytes);
         assert_eq!(
-            tlv_reader.read_u32().expect("Failed to read u32"),
-            237998115
+            tlv_reader
+                .read_byte_str()
+                .expect_err("Element too large is expected"),
+            TLVError::ElementTooLarge {
+                declared: usize::MAX,
+                limit: DEFAULT_MAX_ELEMENT_LEN
+            }
+        );
+
+        // UTF-8 String, 1-octet length, "Hello!", within the buffer but past a limit of 4
+        let test_bytes = &[0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21];
+        let tlv_reader =
+            TLVReader::with_config(test_bytes, TLVReaderConfig::default().max_element_len(4));
+        let too_large = TLVError::ElementTooLarge {
+            declared: 6,
+            limit: 4,
+        };
+        assert_eq!(
+            tlv_reader
+                .read_char_str()
+                .expect_err("Element too large is expected"),
+            too_large
+        );
+        assert_eq!(
+            tlv_reader
+                .read_value()
+                .expect_err("Element too large is expected"),
+            too_large
+        );
+        assert_eq!(
+            tlv_reader
+                .read_value_ref()
+                .expect_err("Element too large is expected"),
+            too_large
+        );
+        let tlv_reader =
+            TLVReader::with_config(test_bytes, TLVReaderConfig::default().max_element_len(6));
+        assert_eq!(
+            tlv_reader.read_char_str().expect("Failed to read string"),
+            "Hello!"
+        );
+    }
+
+    #[test]
+    fn test_read_strict_strings() {
+        // Array [1U, "Hi" followed by a NUL character then "!"]
+        let test_bytes = &[0x16, 0x04, 0x01, 0x0c, 0x04, 0x48, 0x69, 0x00, 0x21, 0x18];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(
+            tlv_reader.read_char_str().expect("Failed to read string"),
+            "Hi\0!"
+        );
+
+        let mut tlv_reader =
+            TLVReader::with_config(test_bytes, TLVReaderConfig::default().strict_strings(true));
+        let nul_character = TLVError::NulCharacter { offset: 7 };
+        assert_eq!(
+            tlv_reader
+                .read_value()
+                .expect_err("NUL character is expected"),
+            nul_character
+        );
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(
+            tlv_reader
+                .read_char_str()
+                .expect_err("NUL character is expected"),
+            nul_character
+        );
+        assert_eq!(
+            tlv_reader
+                .read_str()
+                .expect_err("NUL character is expected"),
+            nul_character
         );
     }
 
     #[test]
-    fn test_read_u64() {
-        // Unsigned Integer, 8-octet, value 40000000000
-        let test_bytes = &[0x07, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00];
+    fn test_elements() {
+        // 1 = invalid UTF-8 byte ff, 2 = Structure {5 = 42U, 6 = [true, -3]}, 5 = "Hi"
+        let test_bytes = &[
+            0x2c, 0x01, 0x01, 0xff, 0x35, 0x02, 0x24, 0x05, 0x2a, 0x36, 0x06, 0x09, 0x00, 0xfd,
+            0x18, 0x18, 0x2c, 0x05, 0x02, 0x48, 0x69,
+        ];
         let tlv_reader = TLVReader::new(test_bytes);
+        let elements = tlv_reader
+            .elements()
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Failed to iterate elements");
         assert_eq!(
-            tlv_reader.read_u64().expect("Failed to read u64"),
-            40000000000
+            elements
+                .iter()
+                .map(|element| (element.tag().clone(), element.offset()))
+                .collect::<Vec<_>>(),
+            vec![
+                (TLVTag::ContextSpecific(1), 0),
+                (TLVTag::ContextSpecific(2), 4),
+                (TLVTag::ContextSpecific(5), 16)
+            ]
+        );
+        // The corrupt value is only decoded when asked for
+        let element = tlv_reader
+            .elements()
+            .find_map(|element| {
+                element
+                    .ok()
+                    .filter(|e| *e.tag() == TLVTag::ContextSpecific(5))
+            })
+            .expect("Failed to find element");
+        assert_eq!(element.as_str().expect("Failed to read string"), "Hi");
+        assert_eq!(
+            elements[0].as_str().expect_err("Parse error is expected"),
+            TLVError::ParseError
+        );
+        assert_eq!(
+            elements[0].as_u64().expect_err("Invalid type is expected"),
+            TLVError::InvalidType
         );
-    }
 
-    #[test]
-    fn test_read_i8() {
-        let test_bytes = &[0x00, 0xFF]; // Signed Integer, 1-octet, value -1
-        let tlv_reader = TLVReader::new(test_bytes);
-        assert_eq!(tlv_reader.read_i8().expect("Failed to read i8"), -1);
+        let structure = &elements[1];
+        assert_eq!(
+            structure.tlv_type(),
+            TLVType::Container(ContainerType::Structure)
+        );
+        assert_eq!(
+            structure.as_u64().expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        let members = structure
+            .enter()
+            .expect("Failed to enter structure")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Failed to iterate members");
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].as_u64().expect("Failed to read u64"), 42);
+        let array = members[1]
+            .enter()
+            .expect("Failed to enter array")
+            .map(|element| element.and_then(|element| element.value()))
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Failed to read array");
+        assert_eq!(array, vec![TLVValueRef::Bool(true), TLVValueRef::Int8(-3)]);
+        assert_eq!(
+            members[0].enter().err().expect("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+        assert!(TLVReader::new(&[]).elements().next().is_none());
     }
 
     #[test]
-    fn test_read_i16() {
-        let test_bytes = &[0x01, 0x0F, 0xFF]; // Signed Integer, 2-octet, value -241
-        let tlv_reader = TLVReader::new(test_bytes);
-        assert_eq!(tlv_reader.read_i16().expect("Failed to read i16"), -241);
-    }
+    fn test_elements_malformed() {
+        // Structure {1 = {2 = true}} without both End of Container
+        let tlv_reader = TLVReader::new(&[0x15, 0x35, 0x01, 0x29, 0x02]);
+        let unterminated = TLVError::UnterminatedContainer { offset: 0 };
+        let mut elements = tlv_reader.elements();
+        assert_eq!(
+            elements
+                .next()
+                .expect("Element is expected")
+                .err()
+                .expect("Unterminated container is expected"),
+            unterminated
+        );
+        assert!(elements.next().is_none());
 
-    #[test]
-    fn test_read_i32() {
-        // Signed Integer, 4-octet, value 237998115
-        let test_bytes = &[0x02, 0x23, 0x90, 0x2f, 0x0E];
-        let tlv_reader = TLVReader::new(test_bytes);
+        // Stray End of Container after 1U
+        let tlv_reader = TLVReader::new(&[0x04, 0x01, 0x18]);
+        let results = tlv_reader
+            .elements()
+            .map(|element| element.map(|element| element.offset()))
+            .collect::<Vec<_>>();
+        assert_eq!(results, vec![Ok(0), Err(TLVError::EndOfContainer)]);
+
+        // Containers deeper than the limit are not entered
+        let tlv_reader = TLVReader::with_config(
+            &[0x16, 0x16, 0x18, 0x18],
+            TLVReaderConfig::default().max_depth(1),
+        );
+        let array = tlv_reader
+            .elements()
+            .next()
+            .expect("Element is expected")
+            .expect("Failed to read element");
+        let inner_array = array
+            .enter()
+            .expect("Failed to enter array")
+            .next()
+            .expect("Element is expected")
+            .expect("Failed to read element");
         assert_eq!(
-            tlv_reader.read_i32().expect("Failed to read i32"),
-            237998115
+            inner_array
+                .enter()
+                .err()
+                .expect("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(1)
+        );
+        assert_eq!(
+            array.value().expect_err("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(1)
+        );
+        assert_eq!(
+            inner_array
+                .value()
+                .expect_err("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(1)
+        );
+        let tlv_reader = TLVReader::with_config(
+            &[0x16, 0x16, 0x18, 0x18],
+            TLVReaderConfig::default().max_depth(2),
+        );
+        let array = tlv_reader
+            .elements()
+            .next()
+            .expect("Element is expected")
+            .expect("Failed to read element");
+        assert_eq!(
+            array.value(),
+            Ok(TLVValueRef::Array(vec![TLVValueRef::Array(vec![])]))
         );
-    }
 
-    #[test]
-    fn test_read_i64() {
-        // Signed Integer, 8-octet, value 40000000000
-        let test_bytes = &[0x03, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00];
-        let tlv_reader = TLVReader::new(test_bytes);
+        // Far deeper than the default limit of 64, which must not overflow the stack
+        let test_bytes = nested_arrays(30000);
+        let tlv_reader = TLVReader::new(&test_bytes);
+        let array = tlv_reader
+            .elements()
+            .next()
+            .expect("Element is expected")
+            .expect("Failed to read element");
         assert_eq!(
-            tlv_reader.read_i64().expect("Failed to read i64"),
-            40000000000
+            array.value().expect_err("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(64)
         );
     }
 
-    #[test]
-    fn test_read_f32() {
-        // Single precision floating point 17.9
-        let test_bytes = &[0x0a, 0x33, 0x33, 0x8f, 0x41];
-        let tlv_reader = TLVReader::new(test_bytes);
-        let expected: f32 = 17.9;
-        let actual = tlv_reader.read_f32().expect("Failed to read f32");
-        assert!((expected - actual).abs() < f32::EPSILON);
-
-        // Single precision floating point infinity (∞)
-        let test_bytes = &[0x0a, 0x00, 0x00, 0x80, 0x7f];
-        let tlv_reader = TLVReader::new(test_bytes);
-        let infinity = tlv_reader.read_f32().expect("Failed to read f32");
-        assert!(infinity.is_sign_positive());
-        assert!(infinity.is_infinite());
-
-        // Single precision floating point negative infinity (-∞)
-        let test_bytes = &[0x0a, 0x00, 0x00, 0x80, 0xff];
-        let tlv_reader = TLVReader::new(test_bytes);
-        let infinity = tlv_reader.read_f32().expect("Failed to read f32");
-        assert!(infinity.is_sign_negative());
-        assert!(infinity.is_infinite());
+    // Array of Boolean true, with as many control bytes as the count plus two
+    fn booleans_array(count: usize) -> Vec<u8> {
+        let mut bytes = vec![0x16];
+        bytes.resize(count + 1, 0x09);
+        bytes.push(0x18);
+        bytes
     }
 
     #[test]
-    fn test_read_f64() {
-        // Double precision floating point 17.9
-        let test_bytes = &[0x0b, 0x66, 0x66, 0x66, 0x66, 0x66, 0xe6, 0x31, 0x40];
-        let tlv_reader = TLVReader::new(test_bytes);
-        let expected: f64 = 17.9;
-        let actual = tlv_reader.read_f64().expect("Failed to read f64");
-        assert!((expected - actual).abs() < f64::EPSILON);
-
-        // Double precision floating point infinity (∞)
-        let test_bytes = &[0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x7f];
-        let tlv_reader = TLVReader::new(test_bytes);
-        let infinity = tlv_reader.read_f64().expect("Failed to read f64");
-        assert!(infinity.is_sign_positive());
-        assert!(infinity.is_infinite());
+    fn test_element_size() {
+        use crate::tags::ImplicitProfileLength;
+        use crate::writer::TLVWriter;
 
-        // Double precision floating point negative infinity (-∞)
-        let test_bytes = &[0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0xff];
-        let tlv_reader = TLVReader::new(test_bytes);
-        let infinity = tlv_reader.read_f64().expect("Failed to read f64");
-        assert!(infinity.is_sign_negative());
-        assert!(infinity.is_infinite());
+        let tags = [
+            TLVTag::Anonymous,
+            TLVTag::ContextSpecific(1),
+            TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 1 }),
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 1 }),
+            TLVTag::ImplicitProfile(ImplicitProfileLength::TwoOctets { tag_number: 1 }),
+            TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets { tag_number: 1 }),
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                vendor_id: 65521,
+                profile_number: 57069,
+                tag_number: 1,
+            }),
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+                vendor_id: 65521,
+                profile_number: 57069,
+                tag_number: 1,
+            }),
+        ];
+        let values = [
+            TLVValue::Int8(-1),
+            TLVValue::Int16(-1),
+            TLVValue::Int32(-1),
+            TLVValue::Int64(-1),
+            TLVValue::UInt8(1),
+            TLVValue::UInt16(1),
+            TLVValue::UInt32(1),
+            TLVValue::UInt64(1),
+            TLVValue::Bool(false),
+            TLVValue::Bool(true),
+            TLVValue::Float32(1.5),
+            TLVValue::Float64(1.5),
+            TLVValue::Null,
+            TLVValue::Utf8String(String::new()),
+            TLVValue::ByteString(vec![0x01; 300]),
+            TLVValue::Structure(vec![]),
+            TLVValue::Array(vec![TLVValue::Array(vec![]), TLVValue::UInt8(1)]),
+            TLVValue::List(vec![
+                (TLVTag::ContextSpecific(1), TLVValue::Structure(vec![])),
+                (TLVTag::Anonymous, TLVValue::List(vec![])),
+            ]),
+        ];
+        for tag in &tags {
+            let mut elements = Vec::new();
+            for value in &values {
+                let mut tlv_writer = TLVWriter::new();
+                tlv_writer
+                    .put_value(tag.clone(), value)
+                    .expect("Failed to write element");
+                elements.push(tlv_writer.into_bytes());
+            }
+            for width in [
+                TLVFieldSize::OneOctet,
+                TLVFieldSize::TwoOctets,
+                TLVFieldSize::FourOctets,
+                TLVFieldSize::EightOctets,
+            ] {
+                let mut tlv_writer = TLVWriter::new();
+                tlv_writer
+                    .put_str_with_len_width(tag.clone(), "Hi", width)
+                    .expect("Failed to write element");
+                elements.push(tlv_writer.into_bytes());
+                let mut tlv_writer = TLVWriter::new();
+                tlv_writer
+                    .put_bytes_with_len_width(tag.clone(), &[0x01, 0x02, 0x03], width)
+                    .expect("Failed to write element");
+                elements.push(tlv_writer.into_bytes());
+            }
+            for element in &elements {
+                assert_eq!(element_size(element), Ok(element.len()), "{:02x?}", element);
+                // Whatever follows the element
+                let followed = [element.as_slice(), &[0x18, 0x04]].concat();
+                assert_eq!(element_size(&followed), Ok(element.len()));
+                for len in 0..element.len() {
+                    element_size(&element[..len]).expect_err("Truncated element is expected");
+                }
+            }
+        }
     }
 
     #[test]
-    fn test_read_bool() {
-        let test_bytes = &[0x08]; // Boolean false
-        let tlv_reader = TLVReader::new(test_bytes);
-        assert!(!tlv_reader.read_bool().expect("Failed to read bool"));
-
-        let test_bytes = &[0x09]; // Boolean true
-        let tlv_reader = TLVReader::new(test_bytes);
-        assert!(tlv_reader.read_bool().expect("Failed to read bool"));
+    fn test_read_truncated_values() {
+        // Element types with fixed width values, and UTF-8 Strings with each length field width
+        let test_vectors: &[(u8, usize)] = &[
+            (0x00, 1),
+            (0x01, 2),
+            (0x02, 4),
+            (0x03, 8),
+            (0x04, 1),
+            (0x05, 2),
+            (0x06, 4),
+            (0x07, 8),
+            (0x0a, 4),
+            (0x0b, 8),
+            (0x0c, 1),
+            (0x0d, 2),
+            (0x0e, 4),
+            (0x0f, 8),
+        ];
+        for &(element_type, width) in test_vectors {
+            for available in 0..width {
+                let mut test_bytes = vec![element_type];
+                test_bytes.resize(1 + available, 0x00);
+                let truncated = TLVError::TruncatedValue {
+                    expected: width,
+                    available,
+                };
+                let tlv_reader = TLVReader::new(&test_bytes);
+                let read = match element_type {
+                    0x00..=0x03 => tlv_reader.read_int().map(drop),
+                    0x04..=0x07 => tlv_reader.read_uint().map(drop),
+                    0x0a => tlv_reader.read_f32().map(drop),
+                    0x0b => tlv_reader.read_f64().map(drop),
+                    _ => tlv_reader.read_str().map(drop),
+                };
+                assert_eq!(read, Err(truncated.clone()), "{:02x?}", test_bytes);
+                assert_eq!(
+                    tlv_reader.read_value(),
+                    Err(truncated.clone()),
+                    "{:02x?}",
+                    test_bytes
+                );
+                assert_eq!(
+                    element_size(&test_bytes),
+                    Err(truncated),
+                    "{:02x?}",
+                    test_bytes
+                );
+            }
+        }
     }
 
     #[test]
-    fn test_read_null() {
-        let test_bytes = &[0x14]; // Null
-        let tlv_reader = TLVReader::new(test_bytes);
-        tlv_reader.read_null().expect("Failed to read null");
+    fn test_element_size_truncated() {
+        assert_eq!(element_size(&[]), Err(TLVError::EndOfTLV));
+        assert_eq!(element_size(&[0x18]), Err(TLVError::EndOfContainer));
+        // UInt32 with 3 of its 4 value bytes
+        assert_eq!(
+            element_size(&[0x06, 0x01, 0x02, 0x03]),
+            Err(TLVError::TruncatedValue {
+                expected: 4,
+                available: 3
+            })
+        );
+        // UTF-8 String, 2-octet length 256, then its length field cut short
+        assert_eq!(
+            element_size(&[0x0d, 0x00, 0x01, 0x61]),
+            Err(TLVError::UnderRun)
+        );
+        assert_eq!(
+            element_size(&[0x0d, 0x00]),
+            Err(TLVError::TruncatedValue {
+                expected: 2,
+                available: 1
+            })
+        );
+        // Context tag missing
+        assert_eq!(
+            element_size(&[0x24]),
+            Err(TLVError::TruncatedTag {
+                tag_control: TagControl::ContextSpecific,
+                missing: 1
+            })
+        );
+        // Structure {0 = [} and Structure {0 = 1U
+        assert_eq!(
+            element_size(&[0x15, 0x36, 0x00]),
+            Err(TLVError::UnterminatedContainer { offset: 0 })
+        );
+        assert_eq!(
+            element_size(&[0x15, 0x24, 0x00, 0x01]),
+            Err(TLVError::UnterminatedContainer { offset: 0 })
+        );
+        // Reserved element type inside an Array
+        assert_eq!(
+            element_size(&[0x16, 0x19, 0x18]),
+            Err(TLVError::UnknownElementType {
+                value: 0x19,
+                offset: 1
+            })
+        );
     }
 
     #[test]
-    fn test_read_byte_str() {
-        // Octet String, 1-octet length specifying 5 octets 00 01 02 03 04
-        let test_bytes = &[0x10, 0x05, 0x00, 0x01, 0x02, 0x03, 0x04];
-        let tlv_reader = TLVReader::new(test_bytes);
+    fn test_read_max_elements() {
+        let config = TLVReaderConfig::default().max_elements(1000);
+        let tlv_reader = TLVReader::with_config(&booleans_array(998), config);
+        tlv_reader.read_value().expect("Failed to read value");
+        tlv_reader.read_value_ref().expect("Failed to read value");
+        let mut tlv_reader = TLVReader::with_config(&booleans_array(998), config);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        while tlv_reader.next().is_ok() {}
+        tlv_reader
+            .exit_container()
+            .expect("Failed to exit container");
+
+        let too_many = TLVError::TooManyElements { limit: 1000 };
+        let tlv_reader = TLVReader::with_config(&booleans_array(999), config);
         assert_eq!(
             tlv_reader
-                .read_byte_str()
-                .expect("Failed to read byte string"),
-            [0x00, 0x01, 0x02, 0x03, 0x04]
+                .read_value()
+                .expect_err("Too many elements is expected"),
+            too_many
+        );
+        assert_eq!(
+            tlv_reader
+                .read_value_ref()
+                .expect_err("Too many elements is expected"),
+            too_many
+        );
+        // Counted while moving the cursor, the End of Container is the element past the limit
+        let mut tlv_reader = TLVReader::with_config(&booleans_array(999), config);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        while tlv_reader.next().is_ok() {}
+        assert_eq!(
+            tlv_reader
+                .exit_container()
+                .expect_err("Too many elements is expected"),
+            too_many
         );
     }
 
+    // Arrays nested count deep around 1U
+    fn nested_arrays(count: usize) -> Vec<u8> {
+        let mut bytes = vec![0x16; count];
+        bytes.extend_from_slice(&[0x04, 0x01]);
+        bytes.resize(count * 2 + 2, 0x18);
+        bytes
+    }
+
     #[test]
-    fn test_read_char_str() {
-        // UTF-8 String, 1-octet length, "Hello!"
-        let test_bytes = &[0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21];
-        let tlv_reader = TLVReader::new(test_bytes);
+    fn test_read_value_ref_max_depth() {
+        // Far deeper than the default limit of 64, which must not overflow the stack
+        let tlv_reader = TLVReader::new(&nested_arrays(30000));
         assert_eq!(
             tlv_reader
-                .read_char_str()
-                .expect("Failed to read character string"),
-            "Hello!"
+                .read_value_ref()
+                .expect_err("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(64)
         );
 
-        // UTF-8 String, 1-octet length, "Tschüs"
-        let test_bytes = &[0x0c, 0x07, 0x54, 0x73, 0x63, 0x68, 0xc3, 0xbc, 0x73];
-        let mut tlv_reader = TLVReader::new(test_bytes);
+        let test_bytes = nested_arrays(4);
+        let tlv_reader =
+            TLVReader::with_config(&test_bytes, TLVReaderConfig::default().max_depth(4));
+        let mut value = tlv_reader.read_value_ref().expect("Failed to read value");
+        for _ in 0..4 {
+            value = match value {
+                TLVValueRef::Array(mut members) => members.remove(0),
+                _ => panic!("Array is expected"),
+            };
+        }
+        assert_eq!(value, TLVValueRef::UInt8(1));
+        let tlv_reader =
+            TLVReader::with_config(&test_bytes, TLVReaderConfig::default().max_depth(3));
         assert_eq!(
             tlv_reader
-                .read_char_str()
-                .expect("Failed to read character string"),
-            "Tschüs"
-        );
-        assert_eq!(
-            tlv_reader.next().expect_err("Sequence End is expected"),
-            TLVError::EndOfTLV
+                .read_value_ref()
+                .expect_err("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(3)
         );
     }
 
     #[test]
-    fn test_read_sequence() {
-        // Unsigned Integer, 8-octet, value 40000000000
-        // + Unsigned Integer, 1-octet, value 255
-        // + Signed Integer, 4-octet, value -904534
-        // + Boolean true
-        // + Null
-        // + Double precision floating point negative infinity (-∞)
-        // + UTF-8 String, 1-octet length, "The End."
+    fn test_decode() {
+        // Array [1U, 300U, 70000U], mixed widths
         let test_bytes = &[
-            0x07, 0x00, 0x90, 0x2f, 0x50, 0x09, 0x00, 0x00, 0x00, 0x04, 0xFF, 0x02, 0xAA, 0x32,
-            0xF2, 0xFF, 0x09, 0x14, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0xff, 0x0c,
-            0x08, 0x54, 0x68, 0x65, 0x20, 0x45, 0x6e, 0x64, 0x2e,
+            0x16, 0x04, 0x01, 0x05, 0x2c, 0x01, 0x06, 0x70, 0x11, 0x01, 0x00, 0x18,
         ];
         let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            Vec::<u32>::decode_tlv(&mut tlv_reader).expect("Failed to decode array"),
+            vec![1, 300, 70000]
+        );
+        assert_eq!(
+            Vec::<u16>::decode_tlv(&mut tlv_reader).expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
 
+        // Array [Null, -5]
+        let test_bytes = &[0x16, 0x14, 0x00, 0xfb, 0x18];
+        let mut tlv_reader = TLVReader::new(test_bytes);
         assert_eq!(
-            tlv_reader.read_u64().expect("Failed to read u64"),
-            40000000000
+            Vec::<Option<i8>>::decode_tlv(&mut tlv_reader).expect("Failed to decode array"),
+            vec![None, Some(-5)]
+        );
+        assert_eq!(
+            String::decode_tlv(&mut tlv_reader).expect_err("Invalid type is expected"),
+            TLVError::InvalidType
         );
+    }
 
-        tlv_reader
-            .next()
-            .expect("Failed to move pointer to next element");
-        assert_eq!(tlv_reader.read_u8().expect("Failed to read u8"), 255);
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_decode() {
+        #[derive(TLVDecode, Debug, PartialEq)]
+        struct DeviceType {
+            #[tlv(tag = 0)]
+            device_type: u32,
+            #[tlv(tag = 1)]
+            revision: u16,
+        }
 
-        tlv_reader
-            .next()
-            .expect("Failed to move pointer to next element");
-        assert_eq!(tlv_reader.read_i32().expect("Failed to read i32"), -904534);
+        #[derive(TLVDecode, Debug, PartialEq)]
+        #[tlv(deny_unknown)]
+        struct Descriptor {
+            #[tlv(tag = 0)]
+            device_types: Vec<DeviceType>,
+            #[tlv(tag = 1)]
+            label: Option<String>,
+            #[tlv(tag = 2)]
+            parts: Vec<u16>,
+        }
 
-        tlv_reader
-            .next()
-            .expect("Failed to move pointer to next element");
-        assert!(tlv_reader.read_bool().expect("Failed to read bool"));
+        // Structure {0 = [{0 = 256U, 1 = 1U, 2 = true}], 2 = [1U, 2U]}
+        let test_bytes = &[
+            0x15, 0x36, 0x00, 0x15, 0x25, 0x00, 0x00, 0x01, 0x24, 0x01, 0x01, 0x29, 0x02, 0x18,
+            0x18, 0x36, 0x02, 0x04, 0x01, 0x04, 0x02, 0x18, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            Descriptor::decode_tlv(&mut tlv_reader).expect("Failed to decode structure"),
+            Descriptor {
+                device_types: vec![DeviceType {
+                    device_type: 256,
+                    revision: 1
+                }],
+                label: None,
+                parts: vec![1, 2]
+            }
+        );
 
-        tlv_reader
-            .next()
-            .expect("Failed to move pointer to next element");
-        tlv_reader.read_null().expect("Failed to read null byte");
+        // Structure {0 = [], 1 = Null, 2 = []}
+        let test_bytes = &[0x15, 0x36, 0x00, 0x18, 0x34, 0x01, 0x36, 0x02, 0x18, 0x18];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            Descriptor::decode_tlv(&mut tlv_reader)
+                .expect("Failed to decode structure")
+                .label,
+            None
+        );
 
-        tlv_reader
-            .next()
-            .expect("Failed to move pointer to next element");
-        let infinity = tlv_reader.read_f64().expect("Failed to read f64");
-        assert!(infinity.is_sign_negative());
-        assert!(infinity.is_infinite());
+        // Structure {0 = [], 2 = [], 3 = 1U}
+        let test_bytes = &[
+            0x15, 0x36, 0x00, 0x18, 0x36, 0x02, 0x18, 0x24, 0x03, 0x01, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        assert_eq!(
+            Descriptor::decode_tlv(&mut tlv_reader).expect_err("Unknown member is expected"),
+            TLVError::UnknownMember(TLVTag::ContextSpecific(3))
+        );
 
-        tlv_reader
-            .next()
-            .expect("Failed to move pointer to next element");
+        // Structure {0 = [], 0 = [], 2 = []}
+        let test_bytes = &[
+            0x15, 0x36, 0x00, 0x18, 0x36, 0x00, 0x18, 0x36, 0x02, 0x18, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
         assert_eq!(
-            tlv_reader
-                .read_char_str()
-                .expect("Failed to read character string"),
-            "The End."
+            Descriptor::decode_tlv(&mut tlv_reader).expect_err("Duplicate member is expected"),
+            TLVError::DuplicateMember(TLVTag::ContextSpecific(0))
         );
 
+        // Structure {0 = 256U}
+        let test_bytes = &[0x15, 0x25, 0x00, 0x00, 0x01, 0x18];
+        let mut tlv_reader = TLVReader::new(test_bytes);
         assert_eq!(
-            tlv_reader.next().expect_err("Sequence End is expected"),
-            TLVError::EndOfTLV
+            DeviceType::decode_tlv(&mut tlv_reader).expect_err("Missing member is expected"),
+            TLVError::MissingMember(TLVTag::ContextSpecific(1))
         );
     }
 }
@@ -0,0 +1,327 @@
+// Best effort decoding of possibly corrupted captures, for diagnostic tools. An element failing to
+// decode is recorded with its offset, then decoding resumes at the next byte where an element does
+// decode or, inside a container, an End of Container is found. Containers are kept with the
+// members decoded, those left open at the end of the payload are closed there. Corrupted bytes may
+// well decode as elements, so the result is a guess: nothing else in the crate parses this way.
+
+use crate::errors::TLVError;
+use crate::reader::{TLVReader, TLVReaderConfig};
+use crate::tags::{TLVTag, TagControl};
+use crate::types::{ContainerType, ElementType, TLVType};
+use crate::value::{TLVDocument, TLVElement, TLVValue};
+use alloc::vec::Vec;
+use bytes::Bytes;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RecoveryError {
+    pub offset: usize,  // Of the element which failed to decode
+    pub skipped: usize, // Bytes passed over from the offset before decoding resumed
+    pub error: TLVError,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RecoveredDocument {
+    pub document: TLVDocument,
+    pub errors: Vec<RecoveryError>,
+}
+
+impl RecoveredDocument {
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+// Container being decoded, with the offset of its control byte
+struct OpenContainer {
+    container_type: ContainerType,
+    offset: usize,
+    tag: TLVTag,
+    members: Vec<(TLVTag, TLVValue)>,
+}
+
+enum Decoded {
+    Primitive(TLVTag, TLVValue, usize), // Encoded size of the element
+    Container(TLVTag, ContainerType, usize), // Size of its header
+    EndOfContainer,
+}
+
+impl TLVDocument {
+    // Never fails, see RecoveredDocument::is_complete
+    pub fn parse_recovering(bytes: &[u8]) -> RecoveredDocument {
+        Self::parse_recovering_with_config(bytes, &TLVReaderConfig::default())
+    }
+
+    pub fn parse_recovering_with_config(
+        bytes: &[u8],
+        config: &TLVReaderConfig,
+    ) -> RecoveredDocument {
+        let bytes = Bytes::copy_from_slice(bytes);
+        let mut recovered = RecoveredDocument::default();
+        let mut containers: Vec<OpenContainer> = Vec::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let decoded = match decode_at(&bytes, offset, config, &containers) {
+                Ok(decoded) => decoded,
+                Err(error) => {
+                    let resumed = (offset + 1..bytes.len())
+                        .find(|offset| decode_at(&bytes, *offset, config, &containers).is_ok())
+                        .unwrap_or(bytes.len());
+                    recovered.errors.push(RecoveryError {
+                        offset,
+                        skipped: resumed - offset,
+                        error,
+                    });
+                    offset = resumed;
+                    continue;
+                }
+            };
+            let (element_offset, tag, value) = match decoded {
+                Decoded::Primitive(tag, value, len) => {
+                    offset += len;
+                    (offset - len, tag, value)
+                }
+                Decoded::Container(tag, container_type, len) => {
+                    containers.push(OpenContainer {
+                        container_type,
+                        offset,
+                        tag,
+                        members: Vec::new(),
+                    });
+                    offset += len;
+                    continue;
+                }
+                Decoded::EndOfContainer => {
+                    offset += 1;
+                    close(&mut containers)
+                }
+            };
+            add(&mut recovered, &mut containers, element_offset, tag, value);
+        }
+
+        // Containers left open are closed with their members, the outermost is reported
+        if let Some(container) = containers.first() {
+            recovered.errors.push(RecoveryError {
+                offset: container.offset,
+                skipped: 0,
+                error: TLVError::UnterminatedContainer {
+                    offset: container.offset,
+                },
+            });
+        }
+        while !containers.is_empty() {
+            let (offset, tag, value) = close(&mut containers);
+            add(&mut recovered, &mut containers, offset, tag, value);
+        }
+        recovered.errors.sort_by_key(|error| error.offset);
+        recovered
+    }
+}
+
+// Element at the offset, with the subtree of primitives only
+fn decode_at(
+    bytes: &Bytes,
+    offset: usize,
+    config: &TLVReaderConfig,
+    containers: &[OpenContainer],
+) -> Result<Decoded, TLVError> {
+    if bytes[offset] == ElementType::EndOfContainer as u8 && !containers.is_empty() {
+        return Ok(Decoded::EndOfContainer);
+    }
+    let tlv_reader = TLVReader::from_bytes_at(bytes.clone(), offset, *config);
+    let tag = tlv_reader.read_tag()?;
+    match tlv_reader.read_type()? {
+        TLVType::Container(_) if containers.len() == config.max_depth => {
+            Err(TLVError::DepthLimitExceeded(config.max_depth))
+        }
+        TLVType::Container(container_type) => {
+            let header_len = 1 + TagControl::from(&tag).octets_count() as usize;
+            Ok(Decoded::Container(tag, container_type, header_len))
+        }
+        TLVType::Primitive(_) => {
+            let value = tlv_reader.read_value()?;
+            Ok(Decoded::Primitive(
+                tag,
+                value,
+                tlv_reader.current_element_len()?,
+            ))
+        }
+    }
+}
+
+// Offset, tag and value of the innermost container
+fn close(containers: &mut Vec<OpenContainer>) -> (usize, TLVTag, TLVValue) {
+    let Some(container) = containers.pop() else {
+        unreachable!("End of Container only decodes in a container")
+    };
+    let value = match container.container_type {
+        ContainerType::Structure => TLVValue::Structure(container.members),
+        ContainerType::List => TLVValue::List(container.members),
+        ContainerType::Array => TLVValue::Array(
+            container
+                .members
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect(),
+        ),
+    };
+    (container.offset, container.tag, value)
+}
+
+// Tagged Array members are kept without their tag, which is recorded as invalid
+fn add(
+    recovered: &mut RecoveredDocument,
+    containers: &mut [OpenContainer],
+    offset: usize,
+    tag: TLVTag,
+    value: TLVValue,
+) {
+    match containers.last_mut() {
+        Some(container) => {
+            if container.container_type == ContainerType::Array && tag != TLVTag::Anonymous {
+                recovered.errors.push(RecoveryError {
+                    offset,
+                    skipped: 0,
+                    error: TLVError::InvalidTag,
+                });
+            }
+            container.members.push((tag, value))
+        }
+        None => recovered
+            .document
+            .elements
+            .push(TLVElement::new(tag, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Primitives in the tree, the elements a corrupted capture is read for
+    fn count_primitives(document: &TLVDocument) -> usize {
+        fn count(value: &TLVValue) -> usize {
+            match value {
+                TLVValue::Structure(_) | TLVValue::Array(_) | TLVValue::List(_) => {
+                    value.into_iter().map(|(_, member)| count(member)).sum()
+                }
+                _ => 1,
+            }
+        }
+        document
+            .elements
+            .iter()
+            .map(|element| count(&element.value))
+            .sum()
+    }
+
+    #[test]
+    fn test_parse_recovering() {
+        // Structure {0 = 42U, 1 = reserved element type 1f, 1f, 1f, 2 = "a"}
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x3f, 0x1f, 0x1f, 0x2c, 0x02, 0x01, 0x61, 0x18,
+        ];
+        let recovered = TLVDocument::parse_recovering(test_bytes);
+        assert_eq!(
+            recovered.document.elements,
+            vec![TLVElement::new(
+                TLVTag::Anonymous,
+                TLVValue::Structure(vec![
+                    (TLVTag::ContextSpecific(0), TLVValue::UInt8(42)),
+                    (
+                        TLVTag::ContextSpecific(2),
+                        TLVValue::Utf8String(String::from("a"))
+                    ),
+                ])
+            )]
+        );
+        assert_eq!(
+            recovered.errors,
+            vec![RecoveryError {
+                offset: 4,
+                skipped: 3,
+                error: TLVError::UnknownElementType {
+                    value: 0x1f,
+                    offset: 4
+                },
+            }]
+        );
+        assert!(!recovered.is_complete());
+        assert_eq!(
+            TLVDocument::parse_recovering(&test_bytes[..4]),
+            RecoveredDocument {
+                document: TLVDocument::parse(&[0x15, 0x24, 0x00, 0x2a, 0x18])
+                    .expect("Failed to parse"),
+                errors: vec![RecoveryError {
+                    offset: 0,
+                    skipped: 0,
+                    error: TLVError::UnterminatedContainer { offset: 0 },
+                }],
+            }
+        );
+
+        // [1U, 1 = 2U, 3U cut short], the tagged member kept without its tag
+        let test_bytes = &[0x16, 0x04, 0x01, 0x24, 0x01, 0x02, 0x04];
+        let recovered = TLVDocument::parse_recovering(test_bytes);
+        assert_eq!(
+            recovered.document.elements,
+            vec![TLVElement::new(
+                TLVTag::Anonymous,
+                TLVValue::Array(vec![TLVValue::UInt8(1), TLVValue::UInt8(2)])
+            )]
+        );
+        let errors: Vec<_> = recovered
+            .errors
+            .iter()
+            .map(|error| (error.offset, error.skipped))
+            .collect();
+        assert_eq!(errors, vec![(0, 0), (3, 0), (6, 1)]);
+
+        // Well formed payloads decode as parse does them, stray End of Container markers skipped
+        let test_bytes = &[0x18, 0x04, 0x2a, 0x18, 0x09];
+        let recovered = TLVDocument::parse_recovering(test_bytes);
+        assert_eq!(
+            recovered.document,
+            TLVDocument::parse(&[0x04, 0x2a, 0x09]).expect("Failed to parse")
+        );
+        assert_eq!(recovered.errors.len(), 2);
+        assert!(TLVDocument::parse_recovering(&[]).is_complete());
+    }
+
+    #[test]
+    fn test_recover_corrupted_bytes() {
+        // [{0 = i, 1 = "sensor", 2 = [i as Int16, -1], 3 = true} for i in 0..50]
+        let test_bytes = TLVDocument::builder()
+            .array(TLVTag::Anonymous, |a| {
+                (0..50).fold(a, |a, i| {
+                    a.structure(|s| {
+                        s.uint(0, i)
+                            .string(1, "sensor")
+                            .array(2, |a| a.value(&(i as i16)).int(-1))
+                            .bool(3, true)
+                    })
+                })
+            })
+            .into_bytes()
+            .expect("Failed to build payload");
+        let document = TLVDocument::parse(&test_bytes).expect("Failed to parse");
+        assert_eq!(
+            TLVDocument::parse_recovering(&test_bytes).document,
+            document
+        );
+        let primitives = count_primitives(&document);
+
+        // Every byte corrupted in turn, most primitives are still found each time
+        for flipped_bits in [0xff, 0x80] {
+            let mut recovered_primitives = 0;
+            for offset in 0..test_bytes.len() {
+                let mut corrupted = test_bytes.clone();
+                corrupted[offset] ^= flipped_bits;
+                let recovered = TLVDocument::parse_recovering(&corrupted);
+                let recovered = count_primitives(&recovered.document);
+                assert!(recovered * 4 >= primitives * 3, "{}", offset);
+                recovered_primitives += recovered;
+            }
+            assert!(recovered_primitives * 100 >= primitives * test_bytes.len() * 99);
+        }
+    }
+}
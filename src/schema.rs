@@ -0,0 +1,479 @@
+use crate::errors::TLVError;
+use crate::reader::TLVReader;
+use crate::tags::TLVTag;
+use crate::types::{ContainerType, TLVType};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::{Bound, RangeBounds};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TLVSchema {
+    Any,
+    SignedInteger((Bound<i64>, Bound<i64>)),
+    UnsignedInteger((Bound<u64>, Bound<u64>)),
+    Boolean,
+    FloatingPointNumber,
+    UTF8String((Bound<usize>, Bound<usize>)), // Length in octets
+    ByteString((Bound<usize>, Bound<usize>)),
+    Null,
+    Nullable(Box<TLVSchema>),
+    Optional(Box<TLVSchema>), // Only meaningful for Structure members
+    Structure(Vec<(TLVTag, TLVSchema)>),
+    Array(Box<TLVSchema>, (Bound<usize>, Bound<usize>)), // Element schema, element count
+    List(Box<TLVSchema>, (Bound<usize>, Bound<usize>)),
+}
+
+fn bounds<T: Copy>(range: impl RangeBounds<T>) -> (Bound<T>, Bound<T>) {
+    (range.start_bound().cloned(), range.end_bound().cloned())
+}
+
+impl TLVSchema {
+    pub fn any() -> Self {
+        TLVSchema::Any
+    }
+
+    pub fn int(range: impl RangeBounds<i64>) -> Self {
+        TLVSchema::SignedInteger(bounds(range))
+    }
+
+    pub fn uint(range: impl RangeBounds<u64>) -> Self {
+        TLVSchema::UnsignedInteger(bounds(range))
+    }
+
+    pub fn boolean() -> Self {
+        TLVSchema::Boolean
+    }
+
+    pub fn float() -> Self {
+        TLVSchema::FloatingPointNumber
+    }
+
+    pub fn utf8(len: impl RangeBounds<usize>) -> Self {
+        TLVSchema::UTF8String(bounds(len))
+    }
+
+    pub fn bytes(len: impl RangeBounds<usize>) -> Self {
+        TLVSchema::ByteString(bounds(len))
+    }
+
+    pub fn null() -> Self {
+        TLVSchema::Null
+    }
+
+    pub fn nullable(schema: TLVSchema) -> Self {
+        TLVSchema::Nullable(Box::new(schema))
+    }
+
+    pub fn optional(schema: TLVSchema) -> Self {
+        TLVSchema::Optional(Box::new(schema))
+    }
+
+    pub fn structure(members: impl IntoIterator<Item = (TLVTag, TLVSchema)>) -> Self {
+        TLVSchema::Structure(members.into_iter().collect())
+    }
+
+    pub fn array(element: TLVSchema, len: impl RangeBounds<usize>) -> Self {
+        TLVSchema::Array(Box::new(element), bounds(len))
+    }
+
+    pub fn list(element: TLVSchema, len: impl RangeBounds<usize>) -> Self {
+        TLVSchema::List(Box::new(element), bounds(len))
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            TLVSchema::Any => "any element",
+            TLVSchema::SignedInteger(_) => "signed integer",
+            TLVSchema::UnsignedInteger(_) => "unsigned integer",
+            TLVSchema::Boolean => "boolean",
+            TLVSchema::FloatingPointNumber => "floating point number",
+            TLVSchema::UTF8String(_) => "UTF-8 string",
+            TLVSchema::ByteString(_) => "byte string",
+            TLVSchema::Null => "null",
+            TLVSchema::Nullable(schema) | TLVSchema::Optional(schema) => schema.description(),
+            TLVSchema::Structure(_) => "structure",
+            TLVSchema::Array(..) => "array",
+            TLVSchema::List(..) => "list",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ViolationKind {
+    UnexpectedType { expected: &'static str },
+    ValueOutOfRange,
+    LengthOutOfRange { len: usize },
+    MissingMember(TLVTag),
+    UnknownMember(TLVTag),
+    DuplicateMember(TLVTag),
+    UnexpectedTag(TLVTag), // Tagged Array member
+    TrailingElement,
+    Malformed(TLVError), // Validation cannot continue past malformed data
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SchemaViolation {
+    pub path: String, // Tag notation, e.g. 0/2[3]/1, empty for the top level element
+    pub offset: usize,
+    pub kind: ViolationKind,
+}
+
+// Validates the single top level element of the payload, collecting every violation found
+pub fn validate(bytes: &[u8], schema: &TLVSchema) -> Vec<SchemaViolation> {
+    let mut tlv_reader = TLVReader::new(bytes);
+    let mut violations = Vec::new();
+    let result = validate_element(&mut tlv_reader, schema, "", &mut violations).and_then(|_| {
+        match tlv_reader.next() {
+            Err(TLVError::EndOfTLV) => {}
+            Err(e) => return Err(e),
+            Ok(()) => violations.push(SchemaViolation {
+                path: String::new(),
+                offset: tlv_reader.offset(),
+                kind: ViolationKind::TrailingElement,
+            }),
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        violations.push(SchemaViolation {
+            path: String::new(),
+            offset: tlv_reader.offset(),
+            kind: ViolationKind::Malformed(e),
+        });
+    }
+    violations
+}
+
+pub(crate) fn member_path(path: &str, tag: &TLVTag) -> String {
+    if path.is_empty() {
+        tag.to_string()
+    } else {
+        format!("{}/{}", path, tag)
+    }
+}
+
+fn validate_element(
+    tlv_reader: &mut TLVReader,
+    schema: &TLVSchema,
+    path: &str,
+    violations: &mut Vec<SchemaViolation>,
+) -> Result<(), TLVError> {
+    let offset = tlv_reader.offset();
+    let violation = |kind| SchemaViolation {
+        path: path.to_string(),
+        offset,
+        kind,
+    };
+    let unexpected_type = ViolationKind::UnexpectedType {
+        expected: schema.description(),
+    };
+
+    // Type mismatches are violations, anything else is malformed data
+    macro_rules! read_or_report {
+        ($read:expr) => {
+            match $read {
+                Ok(value) => value,
+                Err(TLVError::InvalidType) => {
+                    violations.push(violation(unexpected_type));
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        };
+    }
+
+    match schema {
+        TLVSchema::Any => tlv_reader.read_type().map(|_| ())?,
+        TLVSchema::SignedInteger(range) => {
+            if !range.contains(&read_or_report!(tlv_reader.read_int())) {
+                violations.push(violation(ViolationKind::ValueOutOfRange));
+            }
+        }
+        TLVSchema::UnsignedInteger(range) => {
+            if !range.contains(&read_or_report!(tlv_reader.read_uint())) {
+                violations.push(violation(ViolationKind::ValueOutOfRange));
+            }
+        }
+        TLVSchema::Boolean => {
+            read_or_report!(tlv_reader.read_bool());
+        }
+        TLVSchema::FloatingPointNumber => {
+            if tlv_reader.read_f64().is_err() {
+                read_or_report!(tlv_reader.read_f32());
+            }
+        }
+        TLVSchema::UTF8String(len) => {
+            let value = read_or_report!(tlv_reader.read_char_str());
+            if !len.contains(&value.len()) {
+                violations.push(violation(ViolationKind::LengthOutOfRange {
+                    len: value.len(),
+                }));
+            }
+        }
+        TLVSchema::ByteString(len) => {
+            let value = read_or_report!(tlv_reader.read_byte_str());
+            if !len.contains(&value.len()) {
+                violations.push(violation(ViolationKind::LengthOutOfRange {
+                    len: value.len(),
+                }));
+            }
+        }
+        TLVSchema::Null => read_or_report!(tlv_reader.read_null()),
+        TLVSchema::Nullable(schema) => {
+            if tlv_reader.read_null().is_err() {
+                validate_element(tlv_reader, schema, path, violations)?;
+            }
+        }
+        TLVSchema::Optional(schema) => validate_element(tlv_reader, schema, path, violations)?,
+        TLVSchema::Structure(members) => {
+            if tlv_reader.read_type()? != TLVType::Container(ContainerType::Structure) {
+                violations.push(violation(unexpected_type));
+                return Ok(());
+            }
+            let mut seen_tags: Vec<TLVTag> = Vec::new();
+            tlv_reader.enter_container()?;
+            loop {
+                let tag = match tlv_reader.read_tag() {
+                    Err(TLVError::EndOfContainer) => break,
+                    result => result?,
+                };
+                let member_path = member_path(path, &tag);
+                if seen_tags.contains(&tag) {
+                    violations.push(SchemaViolation {
+                        path: member_path,
+                        offset: tlv_reader.offset(),
+                        kind: ViolationKind::DuplicateMember(tag.clone()),
+                    });
+                } else if let Some((_, member)) = members.iter().find(|(t, _)| *t == tag) {
+                    validate_element(tlv_reader, member, &member_path, violations)?;
+                } else {
+                    violations.push(SchemaViolation {
+                        path: member_path,
+                        offset: tlv_reader.offset(),
+                        kind: ViolationKind::UnknownMember(tag.clone()),
+                    });
+                }
+                seen_tags.push(tag);
+                match tlv_reader.next() {
+                    Err(TLVError::EndOfContainer) => break,
+                    result => result?,
+                }
+            }
+            tlv_reader.exit_container()?;
+            for (tag, member) in members {
+                if !matches!(member, TLVSchema::Optional(_)) && !seen_tags.contains(tag) {
+                    violations.push(violation(ViolationKind::MissingMember(tag.clone())));
+                }
+            }
+        }
+        TLVSchema::Array(element, len) | TLVSchema::List(element, len) => {
+            let expected_type = match schema {
+                TLVSchema::Array(..) => ContainerType::Array,
+                _ => ContainerType::List,
+            };
+            if tlv_reader.read_type()? != TLVType::Container(expected_type) {
+                violations.push(violation(unexpected_type));
+                return Ok(());
+            }
+            let mut count = 0;
+            tlv_reader.enter_container()?;
+            loop {
+                let tag = match tlv_reader.read_tag() {
+                    Err(TLVError::EndOfContainer) => break,
+                    result => result?,
+                };
+                let element_path = format!("{}[{}]", path, count);
+                if expected_type == ContainerType::Array && tag != TLVTag::Anonymous {
+                    violations.push(SchemaViolation {
+                        path: element_path.clone(),
+                        offset: tlv_reader.offset(),
+                        kind: ViolationKind::UnexpectedTag(tag),
+                    });
+                }
+                validate_element(tlv_reader, element, &element_path, violations)?;
+                count += 1;
+                match tlv_reader.next() {
+                    Err(TLVError::EndOfContainer) => break,
+                    result => result?,
+                }
+            }
+            tlv_reader.exit_container()?;
+            if !len.contains(&count) {
+                violations.push(violation(ViolationKind::LengthOutOfRange { len: count }));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_schema() -> TLVSchema {
+        TLVSchema::structure([
+            (TLVTag::ContextSpecific(0), TLVSchema::uint(..=255)),
+            (
+                TLVTag::ContextSpecific(1),
+                TLVSchema::optional(TLVSchema::utf8(..=32)),
+            ),
+            (
+                TLVTag::ContextSpecific(2),
+                TLVSchema::array(TLVSchema::int(0..=100), ..=4),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_validate_passing() {
+        // Structure {0 = 42U, 1 = "hi", 2 = [5, 10]}
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x2c, 0x01, 0x02, 0x68, 0x69, 0x36, 0x02, 0x00, 0x05, 0x00,
+            0x0a, 0x18, 0x18,
+        ];
+        assert_eq!(validate(test_bytes, &command_schema()), vec![]);
+
+        // Structure {0 = 42U, 2 = []}, optional member omitted
+        let test_bytes = &[0x15, 0x24, 0x00, 0x2a, 0x36, 0x02, 0x18, 0x18];
+        assert_eq!(validate(test_bytes, &command_schema()), vec![]);
+    }
+
+    #[test]
+    fn test_validate_multiple_violations() {
+        // Structure {0 = 300U, 2 = [5, -1, "x"], 3 = true}
+        let test_bytes = &[
+            0x15, 0x25, 0x00, 0x2c, 0x01, 0x36, 0x02, 0x00, 0x05, 0x00, 0xff, 0x0c, 0x01, 0x78,
+            0x18, 0x29, 0x03, 0x18,
+        ];
+        assert_eq!(
+            validate(test_bytes, &command_schema()),
+            vec![
+                SchemaViolation {
+                    path: String::from("0"),
+                    offset: 1,
+                    kind: ViolationKind::ValueOutOfRange
+                },
+                SchemaViolation {
+                    path: String::from("2[1]"),
+                    offset: 9,
+                    kind: ViolationKind::ValueOutOfRange
+                },
+                SchemaViolation {
+                    path: String::from("2[2]"),
+                    offset: 11,
+                    kind: ViolationKind::UnexpectedType {
+                        expected: "signed integer"
+                    }
+                },
+                SchemaViolation {
+                    path: String::from("3"),
+                    offset: 15,
+                    kind: ViolationKind::UnknownMember(TLVTag::ContextSpecific(3))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_members_and_lengths() {
+        // Empty Structure
+        let test_bytes = &[0x15, 0x18];
+        assert_eq!(
+            validate(test_bytes, &command_schema()),
+            vec![
+                SchemaViolation {
+                    path: String::new(),
+                    offset: 0,
+                    kind: ViolationKind::MissingMember(TLVTag::ContextSpecific(0))
+                },
+                SchemaViolation {
+                    path: String::new(),
+                    offset: 0,
+                    kind: ViolationKind::MissingMember(TLVTag::ContextSpecific(2))
+                },
+            ]
+        );
+
+        // Structure {0 = 1U, 0 = 2U, 1 = "", 2 = [1, 2, 3, 4, 5]}
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x01, 0x24, 0x00, 0x02, 0x2c, 0x01, 0x00, 0x36, 0x02, 0x00, 0x01,
+            0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00, 0x05, 0x18, 0x18,
+        ];
+        assert_eq!(
+            validate(test_bytes, &command_schema()),
+            vec![
+                SchemaViolation {
+                    path: String::from("0"),
+                    offset: 4,
+                    kind: ViolationKind::DuplicateMember(TLVTag::ContextSpecific(0))
+                },
+                SchemaViolation {
+                    path: String::from("2"),
+                    offset: 10,
+                    kind: ViolationKind::LengthOutOfRange { len: 5 }
+                },
+            ]
+        );
+
+        // Tagged Array member and a string longer than allowed
+        let schema = TLVSchema::array(TLVSchema::nullable(TLVSchema::utf8(1..=2)), ..);
+        let test_bytes = &[0x16, 0x14, 0x2c, 0x07, 0x03, 0x61, 0x62, 0x63, 0x18];
+        assert_eq!(
+            validate(test_bytes, &schema),
+            vec![
+                SchemaViolation {
+                    path: String::from("[1]"),
+                    offset: 2,
+                    kind: ViolationKind::UnexpectedTag(TLVTag::ContextSpecific(7))
+                },
+                SchemaViolation {
+                    path: String::from("[1]"),
+                    offset: 2,
+                    kind: ViolationKind::LengthOutOfRange { len: 3 }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_malformed() {
+        // Structure {0 = 42U, 1 = "hi" with a declared length of 3, swallowing the End of Container}
+        let test_bytes = &[0x15, 0x24, 0x00, 0x2a, 0x2c, 0x01, 0x03, 0x68, 0x69, 0x18];
+        assert_eq!(
+            validate(test_bytes, &command_schema()),
+            vec![SchemaViolation {
+                path: String::new(),
+                offset: 4,
+                kind: ViolationKind::Malformed(TLVError::UnterminatedContainer { offset: 0 })
+            }]
+        );
+
+        // Two top level elements
+        let test_bytes = &[0x09, 0x08];
+        assert_eq!(
+            validate(test_bytes, &TLVSchema::boolean()),
+            vec![SchemaViolation {
+                path: String::new(),
+                offset: 1,
+                kind: ViolationKind::TrailingElement
+            }]
+        );
+        assert_eq!(
+            validate(test_bytes, &TLVSchema::list(TLVSchema::any(), ..)),
+            vec![
+                SchemaViolation {
+                    path: String::new(),
+                    offset: 0,
+                    kind: ViolationKind::UnexpectedType { expected: "list" }
+                },
+                SchemaViolation {
+                    path: String::new(),
+                    offset: 1,
+                    kind: ViolationKind::TrailingElement
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+// Serde support for decoded trees, e.g. to keep them as JSON test fixtures and encode them later.
+// This is not a TLV serializer: TLVDocument, TLVElement, TLVValue and TLVTag map to serde's data
+// model, enum variants externally tagged by their name so the output describes itself:
+// {"elements": [{"tag": {"ContextSpecific": 1}, "value": {"UInt8": 42}}]}
+//
+// The representation is kept across versions, it only grows with new variants. Values keep their
+// width, Octet Strings are written as plain hex and floats as numbers, except non finite floats
+// which JSON has no numbers for: "inf", "-inf" and "nan:0x" followed by the bits of the NaN.
+
+use crate::hex;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::de::{Deserialize, Deserializer, Error, Unexpected};
+use serde::Serializer;
+
+pub(crate) mod byte_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::to_hex(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        hex::from_hex(&text).map_err(D::Error::custom)
+    }
+}
+
+const FLOAT_EXPECTED: &str = "a number, inf, -inf or nan:0x followed by the bits of the NaN";
+const NAN_PREFIX: &str = "nan:0x";
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Float<T> {
+    Number(T),
+    Text(String),
+}
+
+macro_rules! float_module {
+    ($name:ident, $type:ident, $bits:ident, $serialize:ident) => {
+        pub(crate) mod $name {
+            use super::*;
+            use alloc::format;
+
+            pub fn serialize<S: Serializer>(
+                value: &$type,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                let value = *value;
+                if value.is_finite() {
+                    serializer.$serialize(value)
+                } else if value.is_nan() {
+                    serializer.serialize_str(&format!("{}{:x}", NAN_PREFIX, value.to_bits()))
+                } else if value > 0.0 {
+                    serializer.serialize_str("inf")
+                } else {
+                    serializer.serialize_str("-inf")
+                }
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<$type, D::Error> {
+                let text = match Float::<$type>::deserialize(deserializer)? {
+                    Float::Number(value) => return Ok(value),
+                    Float::Text(text) => text,
+                };
+                let nan_bits = text
+                    .strip_prefix(NAN_PREFIX)
+                    .and_then(|bits| $bits::from_str_radix(bits, 16).ok());
+                match (text.as_str(), nan_bits.map($type::from_bits)) {
+                    ("inf", _) => Ok($type::INFINITY),
+                    ("-inf", _) => Ok($type::NEG_INFINITY),
+                    (_, Some(value)) if value.is_nan() => Ok(value),
+                    _ => Err(D::Error::invalid_value(
+                        Unexpected::Str(&text),
+                        &FLOAT_EXPECTED,
+                    )),
+                }
+            }
+        }
+    };
+}
+
+float_module!(float32, f32, u32, serialize_f32);
+float_module!(float64, f64, u64, serialize_f64);
+
+#[cfg(test)]
+mod tests {
+    use crate::tags::{
+        CommonProfileLength, FullyQualifiedProfileLength, ImplicitProfileLength, TLVTag,
+    };
+    use crate::value::{TLVDocument, TLVElement, TLVValue};
+    use alloc::string::String;
+    use alloc::vec;
+
+    // Tree through JSON and back, compared by encoding as NaN is not equal to itself
+    fn json_round_trip(document: &TLVDocument) -> TLVDocument {
+        let json = serde_json::to_string(document).expect("Failed to serialize");
+        serde_json::from_str(&json).expect("Failed to deserialize")
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let document = TLVDocument {
+            elements: vec![
+                TLVElement::new(
+                    TLVTag::Anonymous,
+                    TLVValue::Structure(vec![
+                        (TLVTag::ContextSpecific(0), TLVValue::UInt64(u64::MAX)),
+                        (TLVTag::ContextSpecific(1), TLVValue::Int64(i64::MIN)),
+                        (TLVTag::ContextSpecific(2), TLVValue::UInt16(1)),
+                        (
+                            TLVTag::ContextSpecific(3),
+                            TLVValue::ByteString(vec![0x00, 0xff, 0x10]),
+                        ),
+                        (TLVTag::ContextSpecific(4), TLVValue::ByteString(vec![])),
+                        (
+                            TLVTag::ContextSpecific(5),
+                            TLVValue::Utf8String(String::from("Tschüs \"\n")),
+                        ),
+                        (TLVTag::ContextSpecific(6), TLVValue::Float32(17.9)),
+                        (TLVTag::ContextSpecific(7), TLVValue::Float32(-0.0)),
+                        (
+                            TLVTag::ContextSpecific(8),
+                            TLVValue::Float64(f64::MIN_POSITIVE),
+                        ),
+                        (
+                            TLVTag::ContextSpecific(9),
+                            TLVValue::Float32(f32::NEG_INFINITY),
+                        ),
+                        (
+                            TLVTag::ContextSpecific(10),
+                            TLVValue::Float64(f64::from_bits(0x7ff8_0000_0000_0001)),
+                        ),
+                        (
+                            TLVTag::CommonProfile(CommonProfileLength::FourOctets {
+                                tag_number: 1,
+                            }),
+                            TLVValue::Array(vec![TLVValue::Null, TLVValue::Array(vec![])]),
+                        ),
+                        (
+                            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                                vendor_id: 0xfff1,
+                                profile_number: 0xdeed,
+                                tag_number: 1,
+                            }),
+                            TLVValue::List(vec![(TLVTag::Anonymous, TLVValue::Bool(true))]),
+                        ),
+                    ]),
+                ),
+                TLVElement::new(
+                    TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets {
+                        tag_number: 100000,
+                    }),
+                    TLVValue::Int8(-1),
+                ),
+            ],
+        };
+        let round_trip = json_round_trip(&document);
+        assert_eq!(round_trip.encode(), document.encode());
+    }
+
+    #[test]
+    fn test_serde_representation() {
+        let document = TLVDocument {
+            elements: vec![TLVElement::new(
+                TLVTag::ContextSpecific(1),
+                TLVValue::Structure(vec![
+                    (
+                        TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 2 }),
+                        TLVValue::ByteString(vec![0x01, 0xab]),
+                    ),
+                    (TLVTag::Anonymous, TLVValue::Float32(f32::INFINITY)),
+                    (TLVTag::Anonymous, TLVValue::Float64(f64::NAN)),
+                    (TLVTag::Anonymous, TLVValue::Float64(1.5)),
+                    (TLVTag::Anonymous, TLVValue::Null),
+                ]),
+            )],
+        };
+        let json = r#"{"elements":[{"tag":{"ContextSpecific":1},"value":{"Structure":[[{"CommonProfile":{"TwoOctets":{"tag_number":2}}},{"ByteString":"01ab"}],["Anonymous",{"Float32":"inf"}],["Anonymous",{"Float64":"nan:0x7ff8000000000000"}],["Anonymous",{"Float64":1.5}],["Anonymous","Null"]]}}]}"#;
+        assert_eq!(
+            serde_json::to_string(&document).expect("Failed to serialize"),
+            json
+        );
+
+        // Floats written as integers, any hex format for Octet Strings
+        assert_eq!(
+            serde_json::from_str::<TLVValue>(r#"{"Float32":2}"#).expect("Failed to deserialize"),
+            TLVValue::Float32(2.0)
+        );
+        assert_eq!(
+            serde_json::from_str::<TLVValue>(r#"{"ByteString":"hex:01:AB"}"#)
+                .expect("Failed to deserialize"),
+            TLVValue::ByteString(vec![0x01, 0xab])
+        );
+
+        for json in [
+            r#"{"Float32":"nan:0x3f800000"}"#,
+            r#"{"Float64":"Infinity"}"#,
+            r#"{"ByteString":"0"}"#,
+            r#"{"UInt8":256}"#,
+            r#"{"Int8":1.5}"#,
+            r#"{"Decimal":1}"#,
+        ] {
+            serde_json::from_str::<TLVValue>(json).expect_err(json);
+        }
+    }
+}
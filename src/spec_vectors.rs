@@ -0,0 +1,211 @@
+// Runs the Matter specification examples of testdata/spec_vectors.txt through the reader, the
+// document parser, the writer, value encoding, Display and analyze. Vectors are added to the file,
+// not here.
+
+use crate::errors::TLVError;
+use crate::hex;
+use crate::path::{extract, TLVPath};
+use crate::reader::{TLVReader, TLVReaderConfig};
+use crate::stats::analyze;
+use crate::types::TLVType;
+use crate::validation::validate;
+use crate::value::TLVDocument;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+const SPEC_VECTORS: &str = include_str!("testdata/spec_vectors.txt");
+
+#[derive(Debug, Default)]
+struct SpecVector {
+    description: String,
+    bytes: Vec<u8>,
+    tree: String,
+    pretty: String,
+    stats: String,
+    canonical: bool,
+}
+
+fn spec_vectors() -> Vec<SpecVector> {
+    let mut vectors = Vec::new();
+    let lines = SPEC_VECTORS
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .chain(["" /* Ends the last block */]);
+    let mut vector: Option<SpecVector> = None;
+    for line in lines {
+        if line.trim().is_empty() {
+            vectors.extend(vector.take());
+            continue;
+        }
+        let (key, value) = line
+            .split_once(": ")
+            .unwrap_or_else(|| panic!("Malformed line {:?}", line));
+        let vector = vector.get_or_insert_with(|| SpecVector {
+            canonical: true,
+            ..Default::default()
+        });
+        match key {
+            "description" => vector.description = value.to_string(),
+            "hex" => vector.bytes = hex::from_hex(value).expect("Failed to decode hex"),
+            "tree" => vector.tree = value.to_string(),
+            "pretty" => vector.pretty = value.to_string(),
+            "stats" => vector.stats = value.to_string(),
+            "canonical" => vector.canonical = value == "yes",
+            _ => panic!("Unknown key {:?}", key),
+        }
+    }
+    vectors
+}
+
+fn pretty(document: &TLVDocument) -> String {
+    let elements: Vec<String> = document
+        .elements
+        .iter()
+        .map(|element| element.to_string())
+        .collect();
+    elements.join(", ")
+}
+
+// Read again once the rest of the payload is appended when it fails for lack of bytes
+fn retrying<T>(
+    tlv_reader: &mut TLVReader,
+    rest: &mut Option<&[u8]>,
+    read: impl Fn(&mut TLVReader) -> Result<T, TLVError>,
+) -> Result<T, TLVError> {
+    match read(tlv_reader) {
+        Err(e) if e.is_cut_short() || e == TLVError::EndOfTLV => match rest.take() {
+            Some(rest) => {
+                tlv_reader.extend_payload(rest);
+                read(tlv_reader)
+            }
+            None => Err(e),
+        },
+        result => result,
+    }
+}
+
+// Elements met walking through the payload with the reader, given the bytes before the split first
+fn read_in_two_pieces(bytes: &[u8], split: usize) -> Result<Vec<String>, TLVError> {
+    let mut tlv_reader = TLVReader::new(&bytes[..split]);
+    let mut rest = Some(&bytes[split..]).filter(|rest| !rest.is_empty());
+    let mut elements = Vec::new();
+    loop {
+        let offset = tlv_reader.offset();
+        let header = retrying(&mut tlv_reader, &mut rest, |tlv_reader| {
+            Ok((tlv_reader.read_tag()?, tlv_reader.read_type()?))
+        });
+        let mut at_end = match header {
+            Ok((tag, TLVType::Container(container_type))) => {
+                elements.push(format!("{} {:?} {:?}", offset, tag, container_type));
+                retrying(&mut tlv_reader, &mut rest, TLVReader::enter_container)?;
+                continue;
+            }
+            Ok((tag, TLVType::Primitive(_))) => {
+                let value = retrying(&mut tlv_reader, &mut rest, |tlv_reader| {
+                    tlv_reader.read_value()
+                })?;
+                elements.push(format!("{} {:?} {:?}", offset, tag, value));
+                false
+            }
+            Err(TLVError::EndOfContainer) => true,
+            Err(e) => return Err(e),
+        };
+        loop {
+            if at_end {
+                retrying(&mut tlv_reader, &mut rest, TLVReader::exit_container)?;
+                elements.push(String::from("End of Container"));
+            }
+            match retrying(&mut tlv_reader, &mut rest, TLVReader::next) {
+                Ok(()) => break,
+                Err(TLVError::EndOfContainer) => at_end = true,
+                Err(TLVError::EndOfTLV) => return Ok(elements),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_spec_vectors() {
+    let vectors = spec_vectors();
+    assert!(vectors.len() > 30);
+    for vector in &vectors {
+        let description = vector.description.as_str();
+        let document = TLVDocument::parse(&vector.bytes)
+            .unwrap_or_else(|e| panic!("Failed to parse {}: {}", description, e));
+        // The vectors keep the (tag, value) form of the tree
+        let tree: Vec<_> = document
+            .elements
+            .iter()
+            .map(|element| (&element.tag, &element.value))
+            .collect();
+        assert_eq!(format!("{:?}", tree), vector.tree, "{}", description);
+        assert_eq!(pretty(&document), vector.pretty, "{}", description);
+        assert_eq!(
+            analyze(&vector.bytes).map(|stats| stats.to_string()),
+            Ok(vector.stats.clone()),
+            "{}",
+            description
+        );
+
+        let element = &document.elements[0];
+        let tlv_reader = TLVReader::new(&vector.bytes);
+        assert_eq!(
+            tlv_reader.read_tag().as_ref(),
+            Ok(&element.tag),
+            "{}",
+            description
+        );
+        assert_eq!(
+            tlv_reader.read_value().as_ref(),
+            Ok(&element.value),
+            "{}",
+            description
+        );
+        assert!(
+            validate(&vector.bytes, &TLVReaderConfig::default())
+                .expect("Failed to validate")
+                .is_valid(),
+            "{}",
+            description
+        );
+
+        let encoded = document.encode();
+        if vector.canonical {
+            assert_eq!(encoded, vector.bytes, "{}", description);
+            assert_eq!(
+                Ok(element.encode().as_slice()),
+                extract(&vector.bytes, &TLVPath::root()),
+                "{}",
+                description
+            );
+        } else {
+            assert_ne!(encoded, vector.bytes, "{}", description);
+            assert_eq!(
+                TLVDocument::parse(&encoded).expect("Failed to parse re-encoded document"),
+                document,
+                "{}",
+                description
+            );
+        }
+    }
+}
+
+#[test]
+fn test_spec_vectors_extend_payload() {
+    for vector in &spec_vectors() {
+        let description = vector.description.as_str();
+        let whole = read_in_two_pieces(&vector.bytes, vector.bytes.len());
+        assert!(whole.is_ok(), "{}", description);
+        for split in 0..vector.bytes.len() {
+            assert_eq!(
+                read_in_two_pieces(&vector.bytes, split),
+                whole,
+                "{} split at {}",
+                description,
+                split
+            );
+        }
+    }
+}
@@ -0,0 +1,180 @@
+// Statistics of a payload gathered in one walk without building a tree, e.g. to pick reader limits
+// or spot wide encodings in captured traffic. Memory use does not grow with the payload.
+
+use crate::errors::TLVError;
+use crate::tags::{ControlByte, TLVTag, TagControl, CONTROL_BYTE_SHIFT};
+use crate::types::{ContainerType, ElementType};
+use crate::value::TLVValueRef;
+use crate::visitor::{self, TLVVisitor};
+use core::fmt;
+use core::ops::ControlFlow;
+
+// Element types below End of Container
+const ELEMENT_TYPES: usize = ElementType::EndOfContainer as usize;
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TLVStats {
+    pub elements: usize, // Containers count as one element each, End of Container markers not
+    pub max_depth: usize,
+    pub string_bytes: usize, // Values of UTF-8 Strings, without their length fields
+    pub octet_bytes: usize,
+    pub largest_element: usize, // Encoded size, containers with their members
+    element_types: [usize; ELEMENT_TYPES],
+    tag_controls: u8, // Bit per tag control
+}
+
+impl TLVStats {
+    pub fn element_type_count(&self, element_type: ElementType) -> usize {
+        self.element_types
+            .get(element_type as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // Element types found with their counts, in element type order
+    pub fn element_types(&self) -> impl Iterator<Item = (ElementType, usize)> + '_ {
+        self.element_types
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .filter_map(|(element_type, count)| {
+                Some((ElementType::try_from(element_type as u8).ok()?, *count))
+            })
+    }
+
+    pub fn uses_tag_control(&self, tag_control: TagControl) -> bool {
+        self.tag_controls & (1 << (tag_control as u8 >> CONTROL_BYTE_SHIFT)) != 0
+    }
+
+    // Tag kinds found, in tag control order
+    pub fn tag_controls(&self) -> impl Iterator<Item = TagControl> + '_ {
+        (0..8)
+            .filter_map(|tag_control| TagControl::try_from(tag_control << CONTROL_BYTE_SHIFT).ok())
+            .filter(|tag_control| self.uses_tag_control(*tag_control))
+    }
+}
+
+// One line, e.g. "3 elements, depth 1, largest 8 bytes, string bytes 0, octet bytes 0,
+// tags [Anonymous, ContextSpecific], types [UInt8 x2, Structure x1]"
+impl fmt::Display for TLVStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} elements, depth {}, largest {} bytes, string bytes {}, octet bytes {}, tags [",
+            self.elements,
+            self.max_depth,
+            self.largest_element,
+            self.string_bytes,
+            self.octet_bytes
+        )?;
+        for (index, tag_control) in self.tag_controls().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:?}", tag_control)?;
+        }
+        write!(f, "], types [")?;
+        for (index, (element_type, count)) in self.element_types().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:?} x{}", element_type, count)?;
+        }
+        write!(f, "]")
+    }
+}
+
+struct StatsVisitor {
+    stats: TLVStats,
+    depth: usize,
+}
+
+impl TLVVisitor for StatsVisitor {
+    fn primitive(&mut self, _tag: &TLVTag, value: TLVValueRef<'_>) -> ControlFlow<()> {
+        match value {
+            TLVValueRef::Utf8String(string) => self.stats.string_bytes += string.len(),
+            TLVValueRef::ByteString(octets) => self.stats.octet_bytes += octets.len(),
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn enter_container(
+        &mut self,
+        _tag: &TLVTag,
+        _container_type: ContainerType,
+    ) -> ControlFlow<()> {
+        self.depth += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.depth);
+        ControlFlow::Continue(())
+    }
+
+    fn exit_container(&mut self, _container_type: ContainerType) -> ControlFlow<()> {
+        self.depth -= 1;
+        ControlFlow::Continue(())
+    }
+
+    fn element_bytes(&mut self, element: &[u8]) -> ControlFlow<()> {
+        let control_byte = ControlByte::from(element[0]);
+        let stats = &mut self.stats;
+        stats.elements += 1;
+        stats.largest_element = stats.largest_element.max(element.len());
+        if let Some(count) = stats
+            .element_types
+            .get_mut(control_byte.element_type as usize)
+        {
+            *count += 1;
+        }
+        stats.tag_controls |= 1 << control_byte.tag_control;
+        ControlFlow::Continue(())
+    }
+}
+
+pub fn analyze(bytes: &[u8]) -> Result<TLVStats, TLVError> {
+    let mut visitor = StatsVisitor {
+        stats: TLVStats::default(),
+        depth: 0,
+    };
+    // The visitor never breaks
+    let _ = visitor::walk(bytes, &mut visitor)?;
+    Ok(visitor.stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze() {
+        // Structure {1 = [1U, 300U], 2 = "abc", 0xfff1:1:3 = hex:0102}, Null
+        let test_bytes = &[
+            0x15, 0x36, 0x01, 0x04, 0x01, 0x05, 0x2c, 0x01, 0x18, 0x2c, 0x02, 0x03, 0x61, 0x62,
+            0x63, 0xd0, 0xf1, 0xff, 0x01, 0x00, 0x03, 0x00, 0x02, 0x01, 0x02, 0x18, 0x14,
+        ];
+        let stats = analyze(test_bytes).expect("Failed to analyze");
+        assert_eq!(stats.elements, 7);
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.string_bytes, 3);
+        assert_eq!(stats.octet_bytes, 2);
+        assert_eq!(stats.largest_element, 26);
+        assert_eq!(stats.element_type_count(ElementType::UInt8), 1);
+        assert_eq!(stats.element_type_count(ElementType::UInt16), 1);
+        assert_eq!(stats.element_type_count(ElementType::Int8), 0);
+        assert!(stats.uses_tag_control(TagControl::FullyQualified6Bytes));
+        assert!(!stats.uses_tag_control(TagControl::CommonProfile2Bytes));
+        assert_eq!(
+            stats.to_string(),
+            "7 elements, depth 2, largest 26 bytes, string bytes 3, octet bytes 2, \
+             tags [Anonymous, ContextSpecific, FullyQualified6Bytes], \
+             types [UInt8 x1, UInt16 x1, UTF8String1ByteLength x1, ByteString1ByteLength x1, \
+             Null x1, Structure x1, Array x1]"
+        );
+
+        assert_eq!(analyze(&[]), Ok(TLVStats::default()));
+        // Array [1U cut short
+        assert_eq!(
+            analyze(&[0x16, 0x04, 0x01]),
+            Err(TLVError::UnterminatedContainer { offset: 0 })
+        );
+    }
+}
@@ -0,0 +1,352 @@
+// Proptest strategies generating tags, values and documents, for property tests here and downstream.
+// Floats are never NaN so generated values compare equal to themselves.
+
+use crate::tags::{
+    CommonProfileLength, FullyQualifiedProfileLength, ImplicitProfileLength, TLVTag,
+};
+use crate::value::{TLVDocument, TLVElement, TLVValue};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ValueConfig {
+    pub max_depth: u32,
+    pub max_members: usize,
+    pub max_string_len: usize, // Bytes, length field boundaries up to this length are favored
+}
+
+impl Default for ValueConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            max_members: 6,
+            max_string_len: 300,
+        }
+    }
+}
+
+// Tags of any form, including non minimal widths
+pub fn tag() -> impl Strategy<Value = TLVTag> {
+    prop_oneof![1 => Just(TLVTag::Anonymous), 6 => member_tag()]
+}
+
+// Tags of any form but Anonymous
+pub fn member_tag() -> impl Strategy<Value = TLVTag> {
+    prop_oneof![
+        3 => any::<u8>().prop_map(TLVTag::ContextSpecific),
+        1 => any::<u16>().prop_map(|tag_number| {
+            TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number })
+        }),
+        1 => any::<u32>().prop_map(|tag_number| {
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number })
+        }),
+        1 => any::<u16>().prop_map(|tag_number| {
+            TLVTag::ImplicitProfile(ImplicitProfileLength::TwoOctets { tag_number })
+        }),
+        1 => any::<u32>().prop_map(|tag_number| {
+            TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets { tag_number })
+        }),
+        1 => (any::<u16>(), any::<u16>(), any::<u16>()).prop_map(
+            |(vendor_id, profile_number, tag_number)| {
+                TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                    vendor_id,
+                    profile_number,
+                    tag_number,
+                })
+            }
+        ),
+        1 => (any::<u16>(), any::<u16>(), any::<u32>()).prop_map(
+            |(vendor_id, profile_number, tag_number)| {
+                TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+                    vendor_id,
+                    profile_number,
+                    tag_number,
+                })
+            }
+        ),
+    ]
+}
+
+// Mostly short lengths, sometimes exactly at the 1 and 2 octet length field boundaries
+fn string_len(max_len: usize) -> impl Strategy<Value = usize> {
+    let mut boundaries = vec![0, max_len];
+    boundaries.extend(
+        [254, 255, 256, 65534, 65535, 65536]
+            .into_iter()
+            .filter(|len| *len <= max_len),
+    );
+    prop_oneof![
+        8 => 0..=max_len.min(32),
+        2 => select(boundaries),
+    ]
+}
+
+fn utf8_string(max_len: usize) -> impl Strategy<Value = String> {
+    prop_oneof![
+        string_len(max_len).prop_flat_map(|len| {
+            vec(0x20u8..0x7f, len).prop_map(|bytes| String::from_utf8(bytes).unwrap())
+        }),
+        vec(any::<char>(), 0..=max_len.min(16) / 4).prop_map(String::from_iter),
+    ]
+}
+
+fn byte_string(max_len: usize) -> impl Strategy<Value = Vec<u8>> {
+    string_len(max_len).prop_flat_map(|len| vec(any::<u8>(), len))
+}
+
+pub fn primitive(max_string_len: usize) -> impl Strategy<Value = TLVValue> {
+    prop_oneof![
+        any::<i8>().prop_map(TLVValue::Int8),
+        any::<i16>().prop_map(TLVValue::Int16),
+        any::<i32>().prop_map(TLVValue::Int32),
+        any::<i64>().prop_map(TLVValue::Int64),
+        any::<u8>().prop_map(TLVValue::UInt8),
+        any::<u16>().prop_map(TLVValue::UInt16),
+        any::<u32>().prop_map(TLVValue::UInt32),
+        any::<u64>().prop_map(TLVValue::UInt64),
+        any::<bool>().prop_map(TLVValue::Bool),
+        any::<f32>()
+            .prop_filter("NaN", |value| !value.is_nan())
+            .prop_map(TLVValue::Float32),
+        any::<f64>()
+            .prop_filter("NaN", |value| !value.is_nan())
+            .prop_map(TLVValue::Float64),
+        utf8_string(max_string_len).prop_map(TLVValue::Utf8String),
+        byte_string(max_string_len).prop_map(TLVValue::ByteString),
+        Just(TLVValue::Null),
+    ]
+}
+
+pub fn value() -> impl Strategy<Value = TLVValue> {
+    value_with(ValueConfig::default())
+}
+
+pub fn value_with(config: ValueConfig) -> impl Strategy<Value = TLVValue> {
+    let max_members = config.max_members;
+    primitive(config.max_string_len).prop_recursive(
+        config.max_depth,
+        (config.max_depth as usize * max_members) as u32,
+        max_members as u32,
+        move |element| {
+            prop_oneof![
+                vec((member_tag(), element.clone()), 0..=max_members).prop_map(TLVValue::Structure),
+                vec(element.clone(), 0..=max_members).prop_map(TLVValue::Array),
+                vec((tag(), element), 0..=max_members).prop_map(TLVValue::List),
+            ]
+        },
+    )
+}
+
+pub fn document() -> impl Strategy<Value = TLVDocument> {
+    document_with(ValueConfig::default())
+}
+
+pub fn document_with(config: ValueConfig) -> impl Strategy<Value = TLVDocument> {
+    vec((tag(), value_with(config)), 0..=config.max_members)
+        .prop_map(|elements| elements.into_iter().map(TLVElement::from).collect())
+}
+
+// The same value with integers, single precision floats and tags re-encoded at random widths able
+// to hold them, semantically equal to the original
+pub fn with_random_widths(value: TLVValue) -> impl Strategy<Value = TLVValue> {
+    any::<u64>().prop_map(move |seed| {
+        let mut state = seed | 1;
+        rewiden(&value, &mut state)
+    })
+}
+
+// Xorshift, enough to pick widths
+fn next_choice(state: &mut u64, choices: usize) -> usize {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state % choices as u64) as usize
+}
+
+fn pick(mut widths: Vec<TLVValue>, state: &mut u64) -> TLVValue {
+    let choice = next_choice(state, widths.len());
+    widths.swap_remove(choice)
+}
+
+fn random_int(value: i64, state: &mut u64) -> TLVValue {
+    let widths = [
+        i8::try_from(value).ok().map(TLVValue::Int8),
+        i16::try_from(value).ok().map(TLVValue::Int16),
+        i32::try_from(value).ok().map(TLVValue::Int32),
+        Some(TLVValue::Int64(value)),
+    ];
+    pick(widths.into_iter().flatten().collect(), state)
+}
+
+fn random_uint(value: u64, state: &mut u64) -> TLVValue {
+    let widths = [
+        u8::try_from(value).ok().map(TLVValue::UInt8),
+        u16::try_from(value).ok().map(TLVValue::UInt16),
+        u32::try_from(value).ok().map(TLVValue::UInt32),
+        Some(TLVValue::UInt64(value)),
+    ];
+    pick(widths.into_iter().flatten().collect(), state)
+}
+
+fn random_tag(tag: &TLVTag, state: &mut u64) -> TLVTag {
+    if next_choice(state, 2) == 0 {
+        return tag.minimal();
+    }
+    match tag.minimal() {
+        TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number }) => {
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets {
+                tag_number: tag_number as u32,
+            })
+        }
+        TLVTag::ImplicitProfile(ImplicitProfileLength::TwoOctets { tag_number }) => {
+            TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets {
+                tag_number: tag_number as u32,
+            })
+        }
+        TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+            vendor_id,
+            profile_number,
+            tag_number,
+        }) => TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+            vendor_id,
+            profile_number,
+            tag_number: tag_number as u32,
+        }),
+        tag => tag,
+    }
+}
+
+fn rewiden_members(members: &[(TLVTag, TLVValue)], state: &mut u64) -> Vec<(TLVTag, TLVValue)> {
+    members
+        .iter()
+        .map(|(tag, value)| (random_tag(tag, state), rewiden(value, state)))
+        .collect()
+}
+
+fn rewiden(value: &TLVValue, state: &mut u64) -> TLVValue {
+    match value {
+        TLVValue::Int8(value) => random_int(*value as i64, state),
+        TLVValue::Int16(value) => random_int(*value as i64, state),
+        TLVValue::Int32(value) => random_int(*value as i64, state),
+        TLVValue::Int64(value) => random_int(*value, state),
+        TLVValue::UInt8(value) => random_uint(*value as u64, state),
+        TLVValue::UInt16(value) => random_uint(*value as u64, state),
+        TLVValue::UInt32(value) => random_uint(*value as u64, state),
+        TLVValue::UInt64(value) => random_uint(*value, state),
+        TLVValue::Float32(value) => pick(
+            vec![TLVValue::Float32(*value), TLVValue::Float64(*value as f64)],
+            state,
+        ),
+        TLVValue::Structure(members) => TLVValue::Structure(rewiden_members(members, state)),
+        TLVValue::List(members) => TLVValue::List(rewiden_members(members, state)),
+        TLVValue::Array(members) => {
+            TLVValue::Array(members.iter().map(|value| rewiden(value, state)).collect())
+        }
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical::canonicalize;
+    use crate::reader::{TLVReader, TLVReaderConfig};
+    use crate::validation::validate;
+    use crate::writer::TLVWriter;
+
+    proptest! {
+        #[test]
+        fn test_encode_decode_identity(document in document()) {
+            let decoded = TLVDocument::parse(&document.encode()).expect("Failed to parse document");
+            prop_assert_eq!(decoded, document);
+        }
+
+        #[test]
+        fn test_borrowed_value_identity(value in value()) {
+            let mut tlv_writer = TLVWriter::new();
+            tlv_writer.put_value(TLVTag::Anonymous, &value).expect("Failed to write element");
+            let tlv_reader = TLVReader::new(tlv_writer.bytes());
+            prop_assert_eq!(
+                tlv_reader.read_value_ref().expect("Failed to read value").to_owned(),
+                value
+            );
+        }
+
+        #[test]
+        fn test_canonical_idempotence(document in document()) {
+            let canonical = canonicalize(&document.encode()).expect("Failed to canonicalize");
+            prop_assert_eq!(
+                canonicalize(&canonical).expect("Failed to canonicalize"),
+                canonical
+            );
+        }
+
+        #[test]
+        fn test_canonical_is_strictly_readable(document in document()) {
+            let canonical = canonicalize(&document.encode()).expect("Failed to canonicalize");
+            let config = TLVReaderConfig::default()
+                .require_minimal_encoding(true)
+                .require_canonical_order(true);
+            prop_assert!(TLVDocument::parse_with_config(&canonical, &config).is_ok());
+            prop_assert!(validate(&canonical, &config).expect("Failed to validate").is_valid());
+        }
+
+        #[test]
+        fn test_eq_by_number(value in primitive(8), number in any::<i64>()) {
+            // Numbers of the value itself, or another when it has none
+            let integer = match &value {
+                TLVValue::UInt64(value) => *value as i64,
+                value => i64::try_from(value.as_i128().unwrap_or(number as i128)).unwrap_or(number),
+            };
+            let held = value.as_i128();
+            prop_assert_eq!(value == integer as u8, held == Some((integer as u8).into()));
+            prop_assert_eq!(value == integer as u16, held == Some((integer as u16).into()));
+            prop_assert_eq!(value == integer as u32, held == Some((integer as u32).into()));
+            prop_assert_eq!(value == integer as u64, held == Some((integer as u64).into()));
+            prop_assert_eq!(value == integer as i8, held == Some((integer as i8).into()));
+            prop_assert_eq!(value == integer as i16, held == Some((integer as i16).into()));
+            prop_assert_eq!(value == integer as i32, held == Some((integer as i32).into()));
+            prop_assert_eq!(value == integer, held == Some(integer.into()));
+            prop_assert_eq!(value == integer, integer == value);
+            for probe in [true, false] {
+                prop_assert_eq!(value == probe, value == TLVValue::Bool(probe));
+            }
+            let string = <&str>::try_from(&value).unwrap_or("");
+            prop_assert_eq!(
+                value == string,
+                value == TLVValue::Utf8String(String::from(string))
+            );
+            prop_assert_eq!(string == value, value == string);
+        }
+
+        #[test]
+        fn test_random_widths_semantic_eq(
+            (value, rewidened) in value()
+                .prop_flat_map(|value| (Just(value.clone()), with_random_widths(value)))
+        ) {
+            let mut tlv_writer = TLVWriter::new();
+            tlv_writer.put_value(TLVTag::Anonymous, &rewidened).expect("Failed to write element");
+            let decoded = TLVReader::new(tlv_writer.bytes())
+                .read_value()
+                .expect("Failed to read value");
+            prop_assert!(decoded.semantic_eq(&value));
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(24))]
+
+        #[test]
+        fn test_long_strings_identity(
+            document in document_with(ValueConfig {
+                max_depth: 1,
+                max_members: 2,
+                max_string_len: 65540,
+            })
+        ) {
+            let decoded = TLVDocument::parse(&document.encode()).expect("Failed to parse document");
+            prop_assert_eq!(decoded, document);
+        }
+    }
+}
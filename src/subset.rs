@@ -0,0 +1,222 @@
+// Structural matching of a payload against an expected value, for assertions on responses that
+// only care about some of their fields. Values compare as TLVValue::semantic_eq does, Structure
+// members match by tag whatever their order and members not expected are ignored. Expected Array
+// and List members must appear in that order, with others between them, unless the configuration
+// lets them appear in any order.
+
+use crate::errors::TLVError;
+use crate::tags::TLVTag;
+use crate::value::{TLVDocument, TLVValue};
+use alloc::vec;
+
+#[derive(Debug, Copy, Clone)]
+pub struct SubsetConfig {
+    pub ordered: bool,
+}
+
+impl Default for SubsetConfig {
+    fn default() -> Self {
+        Self { ordered: true }
+    }
+}
+
+impl SubsetConfig {
+    // Unordered Array and List members match in any order, each actual member at most once
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+}
+
+// Whether a top level element of the payload holds the expected value, fails when it is malformed
+pub fn tlv_contains(haystack: &[u8], expected: &TLVValue) -> Result<bool, TLVError> {
+    tlv_contains_with_config(haystack, expected, &SubsetConfig::default())
+}
+
+pub fn tlv_contains_with_config(
+    haystack: &[u8],
+    expected: &TLVValue,
+    config: &SubsetConfig,
+) -> Result<bool, TLVError> {
+    Ok(TLVDocument::parse(haystack)?.matches_subset_with_config(expected, config))
+}
+
+impl TLVDocument {
+    pub fn matches_subset(&self, expected: &TLVValue) -> bool {
+        self.matches_subset_with_config(expected, &SubsetConfig::default())
+    }
+
+    pub fn matches_subset_with_config(&self, expected: &TLVValue, config: &SubsetConfig) -> bool {
+        self.elements
+            .iter()
+            .any(|element| is_subset(&element.value, expected, config))
+    }
+}
+
+// Whether actual holds everything expected does, values other than containers being equal
+pub fn is_subset(actual: &TLVValue, expected: &TLVValue, config: &SubsetConfig) -> bool {
+    match (actual, expected) {
+        (TLVValue::Structure(actual), TLVValue::Structure(expected)) => {
+            expected.iter().all(|(expected_tag, expected)| {
+                actual.iter().any(|(tag, actual)| {
+                    same_tag(tag, expected_tag) && is_subset(actual, expected, config)
+                })
+            })
+        }
+        (TLVValue::List(actual), TLVValue::List(expected)) => members_match(
+            actual,
+            expected,
+            config,
+            |(tag, actual), (expected_tag, expected)| {
+                same_tag(tag, expected_tag) && is_subset(actual, expected, config)
+            },
+        ),
+        (TLVValue::Array(actual), TLVValue::Array(expected)) => {
+            members_match(actual, expected, config, |actual, expected| {
+                is_subset(actual, expected, config)
+            })
+        }
+        _ => actual.semantic_eq(expected),
+    }
+}
+
+fn same_tag(a: &TLVTag, b: &TLVTag) -> bool {
+    a.canonical_cmp(b).is_eq()
+}
+
+// Ordered members match the earliest actual member after the previous match, which finds a match
+// whenever there is one. Unordered members take the first actual member not taken yet.
+fn members_match<T>(
+    actual: &[T],
+    expected: &[T],
+    config: &SubsetConfig,
+    matches: impl Fn(&T, &T) -> bool,
+) -> bool {
+    if config.ordered {
+        let mut actual = actual.iter();
+        expected
+            .iter()
+            .all(|expected| actual.any(|actual| matches(actual, expected)))
+    } else {
+        let mut taken = vec![false; actual.len()];
+        expected.iter().all(|expected| {
+            let found = actual
+                .iter()
+                .enumerate()
+                .find(|(index, actual)| !taken[*index] && matches(actual, expected));
+            match found {
+                Some((index, _)) => {
+                    taken[index] = true;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::TLVElement;
+    use alloc::string::String;
+
+    // Structure {0 = 0U, 1 = {2 = "on", 3 = 21.5}, 4 = [1U, 2U, 3U], 5 = List [6 = true, 7 = Null]}
+    const RESPONSE: &[u8] = &[
+        0x15, 0x24, 0x00, 0x00, 0x35, 0x01, 0x2c, 0x02, 0x02, 0x6f, 0x6e, 0x2b, 0x03, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x80, 0x35, 0x40, 0x18, 0x36, 0x04, 0x04, 0x01, 0x04, 0x02, 0x04, 0x03,
+        0x18, 0x37, 0x05, 0x29, 0x06, 0x34, 0x07, 0x18, 0x18,
+    ];
+
+    fn structure(members: &[(u8, TLVValue)]) -> TLVValue {
+        TLVValue::Structure(
+            members
+                .iter()
+                .map(|(tag, value)| (TLVTag::ContextSpecific(*tag), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_tlv_contains() {
+        // Present, integers of another width and members out of order
+        let status = structure(&[(0, TLVValue::UInt32(0))]);
+        assert_eq!(tlv_contains(RESPONSE, &status), Ok(true));
+        let nested = structure(&[
+            (
+                4,
+                TLVValue::Array(vec![TLVValue::UInt8(1), TLVValue::UInt8(3)]),
+            ),
+            (
+                1,
+                structure(&[
+                    (3, TLVValue::Float64(21.5)),
+                    (2, TLVValue::Utf8String(String::from("on"))),
+                ]),
+            ),
+        ]);
+        assert_eq!(tlv_contains(RESPONSE, &nested), Ok(true));
+        assert_eq!(tlv_contains(RESPONSE, &structure(&[])), Ok(true));
+
+        // Missing member, wrong value, wrong type
+        let missing = structure(&[(1, structure(&[(9, TLVValue::Null)]))]);
+        assert_eq!(tlv_contains(RESPONSE, &missing), Ok(false));
+        let wrong_value = structure(&[(0, TLVValue::UInt8(1))]);
+        assert_eq!(tlv_contains(RESPONSE, &wrong_value), Ok(false));
+        let wrong_sign = structure(&[(0, TLVValue::Int8(0))]);
+        assert_eq!(tlv_contains(RESPONSE, &wrong_sign), Ok(false));
+        let wrong_type = structure(&[(4, structure(&[]))]);
+        assert_eq!(tlv_contains(RESPONSE, &wrong_type), Ok(false));
+
+        assert_eq!(
+            tlv_contains(&RESPONSE[..4], &status),
+            Err(TLVError::UnterminatedContainer { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn test_matches_subset_order() {
+        let document = TLVDocument::parse(RESPONSE).expect("Failed to parse document");
+
+        let reversed = structure(&[(
+            4,
+            TLVValue::Array(vec![TLVValue::UInt8(3), TLVValue::UInt8(1)]),
+        )]);
+        assert!(!document.matches_subset(&reversed));
+        let unordered = SubsetConfig::default().ordered(false);
+        assert!(document.matches_subset_with_config(&reversed, &unordered));
+
+        // Each member matches once
+        let repeated = structure(&[(
+            4,
+            TLVValue::Array(vec![TLVValue::UInt8(2), TLVValue::UInt8(2)]),
+        )]);
+        assert!(!document.matches_subset_with_config(&repeated, &unordered));
+
+        let list = |members: &[(u8, TLVValue)]| {
+            TLVValue::List(
+                members
+                    .iter()
+                    .map(|(tag, value)| (TLVTag::ContextSpecific(*tag), value.clone()))
+                    .collect(),
+            )
+        };
+        let in_order = structure(&[(5, list(&[(6, TLVValue::Bool(true)), (7, TLVValue::Null)]))]);
+        assert!(document.matches_subset(&in_order));
+        let out_of_order =
+            structure(&[(5, list(&[(7, TLVValue::Null), (6, TLVValue::Bool(true))]))]);
+        assert!(!document.matches_subset(&out_of_order));
+        assert!(document.matches_subset_with_config(&out_of_order, &unordered));
+        let wrong_tag = structure(&[(5, list(&[(8, TLVValue::Null)]))]);
+        assert!(!document.matches_subset_with_config(&wrong_tag, &unordered));
+
+        // Any top level element may hold the expected value
+        let mut document = document;
+        document
+            .elements
+            .insert(0, TLVElement::new(TLVTag::Anonymous, TLVValue::Bool(false)));
+        assert!(document.matches_subset(&in_order));
+        assert!(document.matches_subset(&TLVValue::Bool(false)));
+        assert!(!document.matches_subset(&TLVValue::Utf8String(String::new())));
+    }
+}
@@ -1,10 +1,80 @@
 use crate::errors::TLVError;
-use crate::util;
+use crate::types::{ElementType, PrimitiveLengthType, TLVFieldSize, TLVType};
+use crate::wire;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
 use num::FromPrimitive;
 
 pub const CONTROL_BYTE_SHIFT: u8 = 5;
+pub const ELEMENT_TYPE_MASK: u8 = 0x1F;
 
-#[derive(Debug, num_derive::ToPrimitive, num_derive::FromPrimitive)]
+// Control byte split into its 3 bit tag control and 5 bit element type
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ControlByte {
+    pub tag_control: u8,
+    pub element_type: u8,
+}
+
+impl From<u8> for ControlByte {
+    fn from(byte: u8) -> Self {
+        Self {
+            tag_control: byte >> CONTROL_BYTE_SHIFT,
+            element_type: byte & ELEMENT_TYPE_MASK,
+        }
+    }
+}
+
+// Control byte decoded on its own, for logs and test assertions. Displays as "ctx-tag | UInt16".
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ControlByteInfo {
+    pub tag_control: TagControl,
+    pub element_type: ElementType,
+    // Size of the length field of strings
+    pub length_field_size: Option<TLVFieldSize>,
+}
+
+impl ControlByteInfo {
+    // Reserved element types fail with InvalidType, a tagged End of Container with InvalidTag
+    pub fn parse(byte: u8) -> Result<Self, TLVError> {
+        let control_byte = ControlByte::from(byte);
+        let tag_control = TagControl::try_from(control_byte.tag_control << CONTROL_BYTE_SHIFT)?;
+        let element_type = ElementType::try_from(control_byte.element_type)?;
+        if element_type == ElementType::EndOfContainer && tag_control != TagControl::Anonymous {
+            return Err(TLVError::InvalidTag);
+        }
+        let length_field_size = match TLVType::try_from(element_type) {
+            Ok(TLVType::Primitive(PrimitiveLengthType::Specified(specified_len_type))) => {
+                Some(specified_len_type.length_field_size())
+            }
+            _ => None,
+        };
+        Ok(Self {
+            tag_control,
+            element_type,
+            length_field_size,
+        })
+    }
+}
+
+impl fmt::Display for ControlByteInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tag_control = match self.tag_control {
+            TagControl::Anonymous => "anonymous",
+            TagControl::ContextSpecific => "ctx-tag",
+            TagControl::CommonProfile2Bytes => "common-profile-2",
+            TagControl::CommonProfile4Bytes => "common-profile-4",
+            TagControl::ImplicitProfile2Bytes => "implicit-profile-2",
+            TagControl::ImplicitProfile4Bytes => "implicit-profile-4",
+            TagControl::FullyQualified6Bytes => "fully-qualified-6",
+            TagControl::FullyQualified8Bytes => "fully-qualified-8",
+        };
+        write!(f, "{} | {:?}", tag_control, self.element_type)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, num_derive::ToPrimitive, num_derive::FromPrimitive)]
 #[repr(u8)]
 pub enum TagControl {
     Anonymous = 0x00,
@@ -26,19 +96,36 @@ impl TryFrom<u8> for TagControl {
     }
 }
 
+impl TagControl {
+    // Octets of the tag following the control byte
+    pub fn octets_count(&self) -> u8 {
+        match self {
+            TagControl::Anonymous => 0,
+            TagControl::ContextSpecific => 1,
+            TagControl::CommonProfile2Bytes | TagControl::ImplicitProfile2Bytes => 2,
+            TagControl::CommonProfile4Bytes | TagControl::ImplicitProfile4Bytes => 4,
+            TagControl::FullyQualified6Bytes => 6,
+            TagControl::FullyQualified8Bytes => 8,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CommonProfileLength {
     TwoOctets { tag_number: u16 },
     FourOctets { tag_number: u32 },
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ImplicitProfileLength {
     TwoOctets { tag_number: u16 },
     FourOctets { tag_number: u32 },
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FullyQualifiedProfileLength {
     SixOctets {
         vendor_id: u16,
@@ -53,6 +140,7 @@ pub enum FullyQualifiedProfileLength {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TLVTag {
     Anonymous,
     ContextSpecific(u8),
@@ -62,6 +150,13 @@ pub enum TLVTag {
 }
 
 impl TLVTag {
+    // Context tag numbers fit in a single octet, wider numbers are rejected instead of truncated
+    pub fn context(tag_number: u32) -> Result<TLVTag, TLVError> {
+        u8::try_from(tag_number)
+            .map(TLVTag::ContextSpecific)
+            .map_err(|_| TLVError::InvalidTag)
+    }
+
     pub fn octets_count(&self) -> u8 {
         match self {
             TLVTag::Anonymous => 0,
@@ -88,6 +183,214 @@ impl TLVTag {
             },
         }
     }
+
+    // Fully qualified tag of the common profile, vendor 0 and profile 0, which has the shorter
+    // common profile form. Other profiles of vendor 0 are Matter standard profiles, only written
+    // fully qualified.
+    pub fn is_qualified_common_profile(&self) -> bool {
+        matches!(
+            self,
+            TLVTag::FullyQualifiedProfile(
+                FullyQualifiedProfileLength::SixOctets {
+                    vendor_id: 0,
+                    profile_number: 0,
+                    tag_number: _,
+                } | FullyQualifiedProfileLength::EightOctets {
+                    vendor_id: 0,
+                    profile_number: 0,
+                    tag_number: _,
+                }
+            )
+        )
+    }
+
+    // Common profile form of a fully qualified common profile tag, with a tag field of the same
+    // width. Other tags are returned as they are.
+    pub fn unqualified(&self) -> TLVTag {
+        match self {
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                vendor_id: 0,
+                profile_number: 0,
+                tag_number,
+            }) => TLVTag::CommonProfile(CommonProfileLength::TwoOctets {
+                tag_number: *tag_number,
+            }),
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+                vendor_id: 0,
+                profile_number: 0,
+                tag_number,
+            }) => TLVTag::CommonProfile(CommonProfileLength::FourOctets {
+                tag_number: *tag_number,
+            }),
+            _ => self.clone(),
+        }
+    }
+
+    // Same tag using the narrowest tag field, fully qualified common profile tags in their common
+    // profile form
+    pub fn minimal(&self) -> TLVTag {
+        if self.is_qualified_common_profile() {
+            return self.unqualified().minimal();
+        }
+        match self {
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number }) => {
+                match u16::try_from(*tag_number) {
+                    Ok(tag_number) => {
+                        TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number })
+                    }
+                    Err(_) => self.clone(),
+                }
+            }
+            TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets { tag_number }) => {
+                match u16::try_from(*tag_number) {
+                    Ok(tag_number) => {
+                        TLVTag::ImplicitProfile(ImplicitProfileLength::TwoOctets { tag_number })
+                    }
+                    Err(_) => self.clone(),
+                }
+            }
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+                vendor_id,
+                profile_number,
+                tag_number,
+            }) => match u16::try_from(*tag_number) {
+                Ok(tag_number) => {
+                    TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                        vendor_id: *vendor_id,
+                        profile_number: *profile_number,
+                        tag_number,
+                    })
+                }
+                Err(_) => self.clone(),
+            },
+            _ => self.clone(),
+        }
+    }
+
+    // Kind rank, vendor, profile and tag number, ignoring the width of the tag field. Fully
+    // qualified common profile tags rank as their common profile form.
+    pub(crate) fn canonical_key(&self) -> (u8, u16, u16, u32) {
+        if self.is_qualified_common_profile() {
+            return self.unqualified().canonical_key();
+        }
+        match self {
+            TLVTag::Anonymous => (0, 0, 0, 0),
+            TLVTag::ContextSpecific(tag_number) => (1, 0, 0, *tag_number as u32),
+            TLVTag::CommonProfile(profile) => match profile {
+                CommonProfileLength::TwoOctets { tag_number } => (2, 0, 0, *tag_number as u32),
+                CommonProfileLength::FourOctets { tag_number } => (2, 0, 0, *tag_number),
+            },
+            TLVTag::ImplicitProfile(profile) => match profile {
+                ImplicitProfileLength::TwoOctets { tag_number } => (3, 0, 0, *tag_number as u32),
+                ImplicitProfileLength::FourOctets { tag_number } => (3, 0, 0, *tag_number),
+            },
+            TLVTag::FullyQualifiedProfile(profile) => match profile {
+                FullyQualifiedProfileLength::SixOctets {
+                    vendor_id,
+                    profile_number,
+                    tag_number,
+                } => (4, *vendor_id, *profile_number, *tag_number as u32),
+                FullyQualifiedProfileLength::EightOctets {
+                    vendor_id,
+                    profile_number,
+                    tag_number,
+                } => (4, *vendor_id, *profile_number, *tag_number),
+            },
+        }
+    }
+
+    // Canonical ordering of structure members (Matter specification A.2.4): context tags by
+    // number, then common profile tags, implicit profile tags and fully qualified tags by vendor,
+    // profile and number. The same tag encoded with different widths compares equal, as do fully
+    // qualified common profile tags and their common profile form.
+    pub fn canonical_cmp(&self, other: &TLVTag) -> Ordering {
+        self.canonical_key().cmp(&other.canonical_key())
+    }
+}
+
+// Notation used by the Matter tooling: 1, CHIP::1, Implicit::1 and 65521::57069:1
+impl fmt::Display for TLVTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TLVTag::Anonymous => write!(f, "Anonymous"),
+            TLVTag::ContextSpecific(tag_number) => write!(f, "{}", tag_number),
+            TLVTag::CommonProfile(profile) => match profile {
+                CommonProfileLength::TwoOctets { tag_number } => write!(f, "CHIP::{}", tag_number),
+                CommonProfileLength::FourOctets { tag_number } => {
+                    write!(f, "CHIP::{}", tag_number)
+                }
+            },
+            TLVTag::ImplicitProfile(profile) => match profile {
+                ImplicitProfileLength::TwoOctets { tag_number } => {
+                    write!(f, "Implicit::{}", tag_number)
+                }
+                ImplicitProfileLength::FourOctets { tag_number } => {
+                    write!(f, "Implicit::{}", tag_number)
+                }
+            },
+            TLVTag::FullyQualifiedProfile(profile) => match profile {
+                FullyQualifiedProfileLength::SixOctets {
+                    vendor_id,
+                    profile_number,
+                    tag_number,
+                } => write!(f, "{}::{}:{}", vendor_id, profile_number, tag_number),
+                FullyQualifiedProfileLength::EightOctets {
+                    vendor_id,
+                    profile_number,
+                    tag_number,
+                } => write!(f, "{}::{}:{}", vendor_id, profile_number, tag_number),
+            },
+        }
+    }
+}
+
+// Parses the Display notation, profile tags take their narrowest form
+impl FromStr for TLVTag {
+    type Err = TLVError;
+
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        let number = |digits: &str| digits.parse::<u32>().map_err(|_| TLVError::InvalidTag);
+        let short_number = |digits: &str| digits.parse::<u16>().map_err(|_| TLVError::InvalidTag);
+        if notation == "Anonymous" {
+            return Ok(TLVTag::Anonymous);
+        }
+        if let Some(tag_number) = notation.strip_prefix("CHIP::") {
+            let tag_number = number(tag_number)?;
+            return Ok(TLVTag::CommonProfile(match u16::try_from(tag_number) {
+                Ok(tag_number) => CommonProfileLength::TwoOctets { tag_number },
+                Err(_) => CommonProfileLength::FourOctets { tag_number },
+            }));
+        }
+        if let Some(tag_number) = notation.strip_prefix("Implicit::") {
+            let tag_number = number(tag_number)?;
+            return Ok(TLVTag::ImplicitProfile(match u16::try_from(tag_number) {
+                Ok(tag_number) => ImplicitProfileLength::TwoOctets { tag_number },
+                Err(_) => ImplicitProfileLength::FourOctets { tag_number },
+            }));
+        }
+        if let Some((vendor_id, profile_tag)) = notation.split_once("::") {
+            let (profile_number, tag_number) =
+                profile_tag.split_once(':').ok_or(TLVError::InvalidTag)?;
+            let vendor_id = short_number(vendor_id)?;
+            let profile_number = short_number(profile_number)?;
+            let tag_number = number(tag_number)?;
+            return Ok(TLVTag::FullyQualifiedProfile(
+                match u16::try_from(tag_number) {
+                    Ok(tag_number) => FullyQualifiedProfileLength::SixOctets {
+                        vendor_id,
+                        profile_number,
+                        tag_number,
+                    },
+                    Err(_) => FullyQualifiedProfileLength::EightOctets {
+                        vendor_id,
+                        profile_number,
+                        tag_number,
+                    },
+                },
+            ));
+        }
+        TLVTag::context(number(notation)?)
+    }
 }
 
 pub fn parse_tag(
@@ -95,44 +398,52 @@ pub fn parse_tag(
     remaining_bytes: &[u8],
 ) -> Result<(&[u8], TLVTag), TLVError> {
     let tag_control = TagControl::try_from(tag_control_byte)?;
+    // Checked up front so a short buffer reports the missing octets rather than a parse failure
+    let tag_len = tag_control.octets_count() as usize;
+    if remaining_bytes.len() < tag_len {
+        return Err(TLVError::TruncatedTag {
+            tag_control,
+            missing: tag_len - remaining_bytes.len(),
+        });
+    }
     let (remaining_bytes, tlv_tag) = match tag_control {
         TagControl::Anonymous => (remaining_bytes, TLVTag::Anonymous),
         TagControl::ContextSpecific => {
-            let (remaining_bytes, tag_number) = util::parse_u8(remaining_bytes)?;
+            let (remaining_bytes, tag_number) = wire::parse_u8(remaining_bytes)?;
             (remaining_bytes, TLVTag::ContextSpecific(tag_number))
         }
         TagControl::CommonProfile2Bytes => {
-            let (remaining_bytes, tag_number) = util::parse_u16(remaining_bytes)?;
+            let (remaining_bytes, tag_number) = wire::parse_u16(remaining_bytes)?;
             (
                 remaining_bytes,
                 TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number }),
             )
         }
         TagControl::CommonProfile4Bytes => {
-            let (remaining_bytes, tag_number) = util::parse_u32(remaining_bytes)?;
+            let (remaining_bytes, tag_number) = wire::parse_u32(remaining_bytes)?;
             (
                 remaining_bytes,
                 TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number }),
             )
         }
         TagControl::ImplicitProfile2Bytes => {
-            let (remaining_bytes, tag_number) = util::parse_u16(remaining_bytes)?;
+            let (remaining_bytes, tag_number) = wire::parse_u16(remaining_bytes)?;
             (
                 remaining_bytes,
                 TLVTag::ImplicitProfile(ImplicitProfileLength::TwoOctets { tag_number }),
             )
         }
         TagControl::ImplicitProfile4Bytes => {
-            let (remaining_bytes, tag_number) = util::parse_u32(remaining_bytes)?;
+            let (remaining_bytes, tag_number) = wire::parse_u32(remaining_bytes)?;
             (
                 remaining_bytes,
                 TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets { tag_number }),
             )
         }
         TagControl::FullyQualified6Bytes => {
-            let (remaining_bytes, vendor_id) = util::parse_u16(remaining_bytes)?;
-            let (remaining_bytes, profile_number) = util::parse_u16(remaining_bytes)?;
-            let (remaining_bytes, tag_number) = util::parse_u16(remaining_bytes)?;
+            let (remaining_bytes, vendor_id) = wire::parse_u16(remaining_bytes)?;
+            let (remaining_bytes, profile_number) = wire::parse_u16(remaining_bytes)?;
+            let (remaining_bytes, tag_number) = wire::parse_u16(remaining_bytes)?;
             (
                 remaining_bytes,
                 TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
@@ -143,9 +454,9 @@ pub fn parse_tag(
             )
         }
         TagControl::FullyQualified8Bytes => {
-            let (remaining_bytes, vendor_id) = util::parse_u16(remaining_bytes)?;
-            let (remaining_bytes, profile_number) = util::parse_u16(remaining_bytes)?;
-            let (remaining_bytes, tag_number) = util::parse_u32(remaining_bytes)?;
+            let (remaining_bytes, vendor_id) = wire::parse_u16(remaining_bytes)?;
+            let (remaining_bytes, profile_number) = wire::parse_u16(remaining_bytes)?;
+            let (remaining_bytes, tag_number) = wire::parse_u32(remaining_bytes)?;
             (
                 remaining_bytes,
                 TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
@@ -159,8 +470,8 @@ pub fn parse_tag(
     Ok((remaining_bytes, tlv_tag))
 }
 
-impl From<TLVTag> for TagControl {
-    fn from(tag: TLVTag) -> Self {
+impl From<&TLVTag> for TagControl {
+    fn from(tag: &TLVTag) -> Self {
         match tag {
             TLVTag::Anonymous => TagControl::Anonymous,
             TLVTag::ContextSpecific(_) => TagControl::ContextSpecific,
@@ -180,17 +491,38 @@ impl From<TLVTag> for TagControl {
     }
 }
 
-pub fn tag_bytes(tag: TLVTag) -> Vec<u8> {
+impl From<TLVTag> for TagControl {
+    fn from(tag: TLVTag) -> Self {
+        Self::from(&tag)
+    }
+}
+
+pub fn tag_bytes(tag: &TLVTag) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    put_tag_bytes(tag, &mut bytes);
+    bytes
+}
+
+// Appends the tag octets following the control byte, without allocating a Vec for them
+pub(crate) fn put_tag_bytes(tag: &TLVTag, output: &mut Vec<u8>) {
     match tag {
-        TLVTag::Anonymous => vec![],
-        TLVTag::ContextSpecific(tag_number) => tag_number.to_le_bytes().to_vec(),
+        TLVTag::Anonymous => {}
+        TLVTag::ContextSpecific(tag_number) => output.push(*tag_number),
         TLVTag::CommonProfile(profile_len) => match profile_len {
-            CommonProfileLength::TwoOctets { tag_number } => tag_number.to_le_bytes().to_vec(),
-            CommonProfileLength::FourOctets { tag_number } => tag_number.to_le_bytes().to_vec(),
+            CommonProfileLength::TwoOctets { tag_number } => {
+                output.extend_from_slice(&tag_number.to_le_bytes())
+            }
+            CommonProfileLength::FourOctets { tag_number } => {
+                output.extend_from_slice(&tag_number.to_le_bytes())
+            }
         },
         TLVTag::ImplicitProfile(profile_len) => match profile_len {
-            ImplicitProfileLength::TwoOctets { tag_number } => tag_number.to_le_bytes().to_vec(),
-            ImplicitProfileLength::FourOctets { tag_number } => tag_number.to_le_bytes().to_vec(),
+            ImplicitProfileLength::TwoOctets { tag_number } => {
+                output.extend_from_slice(&tag_number.to_le_bytes())
+            }
+            ImplicitProfileLength::FourOctets { tag_number } => {
+                output.extend_from_slice(&tag_number.to_le_bytes())
+            }
         },
         TLVTag::FullyQualifiedProfile(profile_len) => match profile_len {
             FullyQualifiedProfileLength::SixOctets {
@@ -198,21 +530,186 @@ pub fn tag_bytes(tag: TLVTag) -> Vec<u8> {
                 profile_number,
                 tag_number,
             } => {
-                let mut bytes = vendor_id.to_le_bytes().to_vec();
-                bytes.extend_from_slice(&profile_number.to_le_bytes());
-                bytes.extend_from_slice(&tag_number.to_le_bytes());
-                bytes
+                output.extend_from_slice(&vendor_id.to_le_bytes());
+                output.extend_from_slice(&profile_number.to_le_bytes());
+                output.extend_from_slice(&tag_number.to_le_bytes());
             }
             FullyQualifiedProfileLength::EightOctets {
                 vendor_id,
                 profile_number,
                 tag_number,
             } => {
-                let mut bytes = vendor_id.to_le_bytes().to_vec();
-                bytes.extend_from_slice(&profile_number.to_le_bytes());
-                bytes.extend_from_slice(&tag_number.to_le_bytes());
-                bytes
+                output.extend_from_slice(&vendor_id.to_le_bytes());
+                output.extend_from_slice(&profile_number.to_le_bytes());
+                output.extend_from_slice(&tag_number.to_le_bytes());
             }
         },
     }
 }
+
+// Tags of the Matter specification, kept here so the numbers are not repeated by callers
+pub mod well_known {
+    use super::{CommonProfileLength, FullyQualifiedProfileLength, TLVTag};
+
+    // The Matter common profile is vendor 0, profile 0
+    pub const MATTER_VENDOR_ID: u16 = 0x0000;
+    pub const MATTER_COMMON_PROFILE: u16 = 0x0000;
+
+    // Context tag number of the revision member closing every Interaction Model message
+    pub const INTERACTION_MODEL_REVISION: u8 = 0xff;
+
+    // Tag in the Matter common profile using the narrowest tag field
+    pub const fn matter_core_tag(tag_number: u32) -> TLVTag {
+        if tag_number <= u16::MAX as u32 {
+            TLVTag::CommonProfile(CommonProfileLength::TwoOctets {
+                tag_number: tag_number as u16,
+            })
+        } else {
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number })
+        }
+    }
+
+    // Same tag in its fully qualified form
+    pub const fn matter_core_tag_fully_qualified(tag_number: u16) -> TLVTag {
+        TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+            vendor_id: MATTER_VENDOR_ID,
+            profile_number: MATTER_COMMON_PROFILE,
+            tag_number,
+        })
+    }
+
+    // Context tag numbers of the Matter certificate structure (Matter specification 6.5.2)
+    pub mod certificate {
+        pub const SERIAL_NUMBER: u8 = 1;
+        pub const SIGNATURE_ALGORITHM: u8 = 2;
+        pub const ISSUER: u8 = 3;
+        pub const NOT_BEFORE: u8 = 4;
+        pub const NOT_AFTER: u8 = 5;
+        pub const SUBJECT: u8 = 6;
+        pub const PUBLIC_KEY_ALGORITHM: u8 = 7;
+        pub const ELLIPTIC_CURVE_ID: u8 = 8;
+        pub const EC_PUBLIC_KEY: u8 = 9;
+        pub const EXTENSIONS: u8 = 10;
+        pub const SIGNATURE: u8 = 11;
+    }
+
+    // Context tag numbers of the Matter specific distinguished name attributes
+    pub mod distinguished_name {
+        pub const COMMON_NAME: u8 = 1;
+        pub const MATTER_NODE_ID: u8 = 17;
+        pub const MATTER_FIRMWARE_SIGNING_ID: u8 = 18;
+        pub const MATTER_ICAC_ID: u8 = 19;
+        pub const MATTER_RCAC_ID: u8 = 20;
+        pub const MATTER_FABRIC_ID: u8 = 21;
+        pub const MATTER_NOC_CAT: u8 = 22;
+    }
+
+    // Context tag numbers of the certificate extensions list
+    pub mod certificate_extension {
+        pub const BASIC_CONSTRAINTS: u8 = 1;
+        pub const KEY_USAGE: u8 = 2;
+        pub const EXTENDED_KEY_USAGE: u8 = 3;
+        pub const SUBJECT_KEY_ID: u8 = 4;
+        pub const AUTHORITY_KEY_ID: u8 = 5;
+        pub const FUTURE_EXTENSION: u8 = 6;
+    }
+
+    // Context tag numbers of the Interaction Model status structures (Matter specification 10.6)
+    pub mod status_ib {
+        pub const STATUS: u8 = 0;
+        pub const CLUSTER_STATUS: u8 = 1;
+    }
+
+    pub mod status_response_message {
+        pub const STATUS: u8 = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_byte_info() {
+        let control_byte = ControlByteInfo::parse(0x25).expect("Failed to parse control byte");
+        assert_eq!(
+            control_byte,
+            ControlByteInfo {
+                tag_control: TagControl::ContextSpecific,
+                element_type: ElementType::UInt16,
+                length_field_size: None,
+            }
+        );
+        assert_eq!(control_byte.to_string(), "ctx-tag | UInt16");
+        let control_byte = ControlByteInfo::parse(0xd1).expect("Failed to parse control byte");
+        assert_eq!(
+            control_byte.length_field_size,
+            Some(TLVFieldSize::TwoOctets)
+        );
+        assert_eq!(
+            control_byte.to_string(),
+            "fully-qualified-6 | ByteString2ByteLength"
+        );
+
+        for byte in 0..=u8::MAX {
+            let element_type = byte & ELEMENT_TYPE_MASK;
+            let expected = if element_type > 0x18 {
+                Err(TLVError::InvalidType)
+            } else if element_type == 0x18 && byte != 0x18 {
+                Err(TLVError::InvalidTag)
+            } else {
+                Ok(byte)
+            };
+            let control_byte = ControlByteInfo::parse(byte);
+            assert_eq!(
+                control_byte
+                    .as_ref()
+                    .map(|c| c.tag_control as u8 | c.element_type as u8)
+                    .map_err(Clone::clone),
+                expected,
+                "control byte {:#04x}",
+                byte
+            );
+            let is_string = (0x0c..=0x13).contains(&element_type);
+            assert_eq!(
+                control_byte.is_ok_and(|c| c.length_field_size.is_some()),
+                is_string && expected.is_ok()
+            );
+        }
+        let valid = (0..=u8::MAX)
+            .filter(|byte| ControlByteInfo::parse(*byte).is_ok())
+            .count();
+        // 24 element types under each of the 8 tag controls, plus End of Container
+        assert_eq!(valid, 24 * 8 + 1);
+    }
+
+    #[test]
+    fn test_context_tag() {
+        assert_eq!(
+            TLVTag::context(0).expect("Failed to build context tag"),
+            TLVTag::ContextSpecific(0)
+        );
+        assert_eq!(
+            TLVTag::context(255).expect("Failed to build context tag"),
+            TLVTag::ContextSpecific(255)
+        );
+        assert_eq!(
+            TLVTag::context(256).expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        assert_eq!(
+            TLVTag::context(u32::MAX).expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        assert_eq!(
+            "255".parse::<TLVTag>().expect("Failed to parse tag"),
+            TLVTag::ContextSpecific(255)
+        );
+        assert_eq!(
+            "256"
+                .parse::<TLVTag>()
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+    }
+}
@@ -0,0 +1,33 @@
+// generate_rust_code output for TEST_BYTES of codegen.rs, in a block for include!
+{
+    w.start_container(TLVTag::Anonymous, ContainerType::Structure)?;
+        w.put(TLVTag::ContextSpecific(0), &-17i8)?;
+        w.put(TLVTag::ContextSpecific(1), &42i16)?;
+        w.put(TLVTag::ContextSpecific(2), &-170000i32)?;
+        w.put(TLVTag::ContextSpecific(3), &-9223372036854775808i64)?;
+        w.put(TLVTag::ContextSpecific(4), &255u8)?;
+        w.put(TLVTag::ContextSpecific(5), &1u16)?;
+        w.put(TLVTag::ContextSpecific(6), &0u32)?;
+        w.put(TLVTag::ContextSpecific(7), &18446744073709551615u64)?;
+        w.put(TLVTag::ContextSpecific(8), &true)?;
+        w.put(TLVTag::ContextSpecific(9), &false)?;
+        w.put(TLVTag::ContextSpecific(10), &17.9f32)?;
+        w.put(TLVTag::ContextSpecific(11), &f64::from_bits(0x7ff8000000000001))?;
+        w.put(TLVTag::ContextSpecific(12), &f32::NEG_INFINITY)?;
+        w.put(TLVTag::ContextSpecific(13), &-0.0f64)?;
+        w.put_null(TLVTag::ContextSpecific(14))?;
+        w.put(TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 1 }), "Tschüs")?;
+        w.put_str_with_len_width(TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets { tag_number: 100000 }), "a\"\n", TLVFieldSize::TwoOctets)?;
+        w.put(TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets { vendor_id: 65521, profile_number: 57069, tag_number: 1 }), &[0x00, 0x01, 0x02][..])?;
+        w.put_bytes_with_len_width(TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets { vendor_id: 65521, profile_number: 57069, tag_number: 2857762541 }), &[], TLVFieldSize::FourOctets)?;
+        w.start_container(TLVTag::ContextSpecific(15), ContainerType::Array)?;
+            w.put(TLVTag::Anonymous, &1u8)?;
+            w.start_container(TLVTag::Anonymous, ContainerType::Structure)?;
+            w.end_container()?;
+            w.start_container(TLVTag::Anonymous, ContainerType::List)?;
+                w.put(TLVTag::ContextSpecific(0), &1u8)?;
+            w.end_container()?;
+        w.end_container()?;
+    w.end_container()?;
+    w.put_null(TLVTag::Anonymous)?;
+}
@@ -0,0 +1,99 @@
+// Assertions for tests of types encoded to TLV, here and downstream, e.g. in crates implementing
+// Matter clusters. Failures show the annotated hexdump of both sides rather than two byte arrays.
+
+use crate::canonical;
+use crate::hexdump::annotated_hexdump;
+use crate::reader::{TLVDecode, TLVReader};
+use crate::writer::TLVEncode;
+use core::fmt::Debug;
+
+#[track_caller]
+fn assert_bytes_eq(message: &str, left: &[u8], right: &[u8]) {
+    if left != right {
+        panic!(
+            "{}\n  left:\n{}\n right:\n{}",
+            message,
+            annotated_hexdump(left),
+            annotated_hexdump(right)
+        );
+    }
+}
+
+// Encodes the value as an anonymous element, compares the bytes to the expected ones if given, then
+// decodes them back and compares the result to the value
+#[track_caller]
+pub fn assert_roundtrip<T>(value: &T, expected_bytes: Option<&[u8]>)
+where
+    T: TLVEncode + TLVDecode + PartialEq + Debug,
+{
+    let bytes = value.encode_tlv();
+    if let Some(expected_bytes) = expected_bytes {
+        assert_bytes_eq(
+            "encoding differs from the expected bytes",
+            &bytes,
+            expected_bytes,
+        );
+    }
+    let mut tlv_reader = TLVReader::new(&bytes);
+    let decoded = match T::decode_tlv(&mut tlv_reader) {
+        Ok(decoded) => decoded,
+        Err(e) => panic!(
+            "failed to decode {:?}: {}\n{}",
+            value,
+            e,
+            annotated_hexdump(&bytes)
+        ),
+    };
+    if decoded != *value {
+        panic!(
+            "decoded value differs\n  left: {:?}\n right: {:?}\n{}",
+            decoded,
+            value,
+            annotated_hexdump(&bytes)
+        );
+    }
+}
+
+// The payload is in canonical form, see canonical::canonicalize
+#[track_caller]
+pub fn assert_canonical(bytes: &[u8]) {
+    match canonical::canonicalize(bytes) {
+        Ok(canonical) => assert_bytes_eq("payload is not canonical", bytes, &canonical),
+        Err(e) => panic!(
+            "failed to parse payload: {}\n{}",
+            e,
+            annotated_hexdump(bytes)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use bytes::Bytes;
+
+    #[test]
+    fn test_assert_roundtrip() {
+        // Unsigned Integer, 1-octet, value 7
+        assert_roundtrip(&7u8, Some(&[0x04, 0x07]));
+        assert_roundtrip(&String::from("Hi"), None);
+        assert_roundtrip(&-1.5f64, None);
+        assert_roundtrip(&Bytes::from_static(&[0x01, 0x02]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "encoding differs from the expected bytes")]
+    fn test_assert_roundtrip_bytes_differ() {
+        // Unsigned Integer, 2-octet, value 7
+        assert_roundtrip(&7u8, Some(&[0x05, 0x07, 0x00]));
+    }
+
+    #[test]
+    #[should_panic(expected = "payload is not canonical")]
+    fn test_assert_canonical() {
+        // Structure {1 = 1U, 0 = 1U}
+        assert_canonical(&[0x15, 0x24, 0x00, 0x01, 0x24, 0x01, 0x01, 0x18]);
+        assert_canonical(&[0x15, 0x24, 0x01, 0x01, 0x24, 0x00, 0x01, 0x18]);
+    }
+}
@@ -1,8 +1,8 @@
 use crate::errors::TLVError;
-use crate::util;
+use crate::wire;
 use num::FromPrimitive;
 
-#[derive(Debug, num_derive::ToPrimitive, num_derive::FromPrimitive)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, num_derive::ToPrimitive, num_derive::FromPrimitive)]
 #[repr(u8)]
 pub enum ElementType {
     Int8 = 0x00,
@@ -32,6 +32,13 @@ pub enum ElementType {
     EndOfContainer = 0x18,
 }
 
+// Element types 0x19 to 0x1F are reserved. Their value length is unknown, so elements of these
+// types cannot be skipped and readers always fail on them. A reserved type the specification later
+// gives a fixed length becomes skippable by adding its variant above.
+pub fn is_reserved_element_type(element_type: u8) -> bool {
+    element_type > ElementType::EndOfContainer as u8
+}
+
 impl TryFrom<u8> for ElementType {
     type Error = TLVError;
 
@@ -57,13 +64,43 @@ pub enum UnsignedInteger {
     UInt64 = 8,
 }
 
+impl SignedInteger {
+    // Narrowest width able to hold the value
+    pub fn minimal_for(value: i64) -> Self {
+        if i8::try_from(value).is_ok() {
+            SignedInteger::Int8
+        } else if i16::try_from(value).is_ok() {
+            SignedInteger::Int16
+        } else if i32::try_from(value).is_ok() {
+            SignedInteger::Int32
+        } else {
+            SignedInteger::Int64
+        }
+    }
+}
+
+impl UnsignedInteger {
+    // Narrowest width able to hold the value
+    pub fn minimal_for(value: u64) -> Self {
+        if u8::try_from(value).is_ok() {
+            UnsignedInteger::UInt8
+        } else if u16::try_from(value).is_ok() {
+            UnsignedInteger::UInt16
+        } else if u32::try_from(value).is_ok() {
+            UnsignedInteger::UInt32
+        } else {
+            UnsignedInteger::UInt64
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum FloatingPoint {
     FloatingPointNumber32 = 4,
     FloatingPointNumber64 = 8,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum PredeterminedLenPrimitive {
     SignedInteger(SignedInteger),
     UnsignedInteger(UnsignedInteger),
@@ -72,7 +109,7 @@ pub enum PredeterminedLenPrimitive {
     Null,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum UTF8StrLen {
     OneOctet,
     TwoOctets,
@@ -80,7 +117,7 @@ pub enum UTF8StrLen {
     EightOctets,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum ByteStrLen {
     OneOctet,
     TwoOctets,
@@ -88,19 +125,19 @@ pub enum ByteStrLen {
     EightOctets,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum SpecifiedLenPrimitive {
     UTF8String(UTF8StrLen),
     ByteString(ByteStrLen),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum PrimitiveLengthType {
     Predetermined(PredeterminedLenPrimitive),
     Specified(SpecifiedLenPrimitive),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 #[repr(u8)]
 pub enum ContainerType {
     Structure = 0x15,
@@ -108,7 +145,17 @@ pub enum ContainerType {
     List = 0x17,
 }
 
-#[derive(Debug, PartialEq)]
+impl From<ContainerType> for ElementType {
+    fn from(container_type: ContainerType) -> Self {
+        match container_type {
+            ContainerType::Structure => ElementType::Structure,
+            ContainerType::Array => ElementType::Array,
+            ContainerType::List => ElementType::List,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum TLVType {
     Primitive(PrimitiveLengthType),
     Container(ContainerType),
@@ -270,29 +317,21 @@ pub enum TLVFieldSize {
 }
 
 impl TLVFieldSize {
-    pub fn parse_field_size<'a>(&self, bytes: &'a [u8]) -> Result<(&'a [u8], usize), TLVError> {
-        let len_octets_count = *self as usize;
-        if len_octets_count > bytes.len() {
-            return Err(TLVError::UnderRun);
+    // Narrowest length field able to hold the length
+    pub fn minimal_for(len: usize) -> Self {
+        if u8::try_from(len).is_ok() {
+            TLVFieldSize::OneOctet
+        } else if u16::try_from(len).is_ok() {
+            TLVFieldSize::TwoOctets
+        } else if u32::try_from(len).is_ok() {
+            TLVFieldSize::FourOctets
+        } else {
+            TLVFieldSize::EightOctets
         }
-        Ok(match self {
-            TLVFieldSize::OneOctet => {
-                let (remaining_bytes, u8_value) = util::parse_u8(bytes)?;
-                (remaining_bytes, u8_value as usize)
-            }
-            TLVFieldSize::TwoOctets => {
-                let (remaining_bytes, u16_value) = util::parse_u16(bytes)?;
-                (remaining_bytes, u16_value as usize)
-            }
-            TLVFieldSize::FourOctets => {
-                let (remaining_bytes, u32_value) = util::parse_u32(bytes)?;
-                (remaining_bytes, u32_value as usize)
-            }
-            TLVFieldSize::EightOctets => {
-                let (remaining_bytes, u64_value) = util::parse_u64(bytes)?;
-                (remaining_bytes, u64_value as usize)
-            }
-        })
+    }
+
+    pub fn parse_field_size<'a>(&self, bytes: &'a [u8]) -> Result<(&'a [u8], usize), TLVError> {
+        wire::parse_length_field(bytes, *self)
     }
 
     pub fn extract_field_sized_bytes<'a>(&self, bytes: &'a [u8]) -> Result<&'a [u8], TLVError> {
@@ -0,0 +1,73 @@
+// Canonical TLV expects UTF-8 strings in Normalization Form C, composed characters are preferred
+// to a base character followed by combining marks.
+
+use alloc::string::String;
+use unicode_normalization::UnicodeNormalization;
+
+pub fn is_nfc(value: &str) -> bool {
+    unicode_normalization::is_nfc(value)
+}
+
+pub fn to_nfc(value: &str) -> String {
+    value.nfc().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::TLVError;
+    use crate::reader::{TLVReader, TLVReaderConfig};
+    use crate::tags::TLVTag;
+    use crate::validation::{validate, Finding, FindingKind};
+    use crate::writer::TLVWriter;
+
+    #[test]
+    fn test_nfc_round_trip() {
+        // UTF-8 String, 1-octet length, "Tschu" followed by a combining diaeresis then "s"
+        let test_bytes = &[0x0c, 0x08, 0x54, 0x73, 0x63, 0x68, 0x75, 0xcc, 0x88, 0x73];
+        let config = TLVReaderConfig::default().require_nfc(true);
+        let tlv_reader = TLVReader::with_config(test_bytes, config);
+        assert_eq!(
+            tlv_reader
+                .read_char_str()
+                .expect_err("Not NFC normalized is expected"),
+            TLVError::NotNfcNormalized { offset: 2 }
+        );
+        let report = validate(test_bytes, &config).expect("Failed to validate");
+        assert_eq!(
+            report.findings,
+            vec![Finding {
+                offset: 2,
+                kind: FindingKind::NotNfcNormalized
+            }]
+        );
+
+        // Permissive by default
+        let decomposed = TLVReader::new(test_bytes)
+            .read_char_str()
+            .expect("Failed to read character string");
+        assert!(!is_nfc(&decomposed));
+        let composed = to_nfc(&decomposed);
+        assert_eq!(composed, "Tschüs");
+        assert!(is_nfc(&composed));
+
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put(TLVTag::Anonymous, composed.as_str())
+            .expect("Failed to write element");
+        let bytes = tlv_writer.into_bytes();
+        assert_eq!(
+            bytes,
+            [0x0c, 0x07, 0x54, 0x73, 0x63, 0x68, 0xc3, 0xbc, 0x73]
+        );
+        assert_eq!(
+            TLVReader::with_config(&bytes, config)
+                .read_char_str()
+                .expect("Failed to read character string"),
+            "Tschüs"
+        );
+        assert!(validate(&bytes, &config)
+            .expect("Failed to validate")
+            .is_valid());
+    }
+}
@@ -0,0 +1,662 @@
+use crate::canonical;
+use crate::errors::TLVError;
+use crate::reader::TLVReaderConfig;
+use crate::schema;
+use crate::tags::{self, ControlByte, TLVTag, TagControl};
+use crate::types::{
+    ContainerType, ElementType, PrimitiveLengthType, SpecifiedLenPrimitive, TLVType,
+};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::iter::once;
+use core::str::from_utf8;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FindingKind {
+    Truncated, // Header or declared length past the end of the payload
+    InvalidElementType(u8),
+    TaggedEndOfContainer,
+    StrayEndOfContainer,
+    UnterminatedContainer, // Offset of the outermost open container's control byte
+    InvalidUtf8,
+    AnonymousMember,         // Structure member without a tag
+    UnexpectedTag(TLVTag),   // Tagged Array member
+    DuplicateMember(TLVTag), // Structure member sharing its tag with an earlier one
+    ElementTooLarge { declared: usize, limit: usize },
+    NonMinimalEncoding,
+    NonCanonicalNaN,
+    MemberOutOfOrder { path: String }, // First structure member out of canonical tag order
+    TrailingBytes,
+    NulCharacter,     // Offset of the NUL character
+    NotNfcNormalized, // Offset of the string's value
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Finding {
+    pub offset: usize,
+    pub kind: FindingKind,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ValidationReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+// Container entered by the walk
+struct OpenContainer {
+    container_type: ContainerType,
+    offset: usize, // Of its control byte
+    tag: TLVTag,
+    index: usize, // Among the members of the enclosing container
+    members: usize,
+    member_tags: Vec<TLVTag>,     // Only kept when rejecting duplicate tags
+    previous_tag: Option<TLVTag>, // Only kept when checking the canonical order
+    ordered: bool,                // No member out of canonical order found yet
+}
+
+// Notation of schema violations, e.g. 0/2[3]/1, empty for a top level element
+fn member_path(containers: &[OpenContainer], tag: &TLVTag, index: usize) -> String {
+    let members = containers
+        .iter()
+        .skip(1)
+        .map(|container| (&container.tag, container.index))
+        .chain(once((tag, index)));
+    containers
+        .iter()
+        .zip(members)
+        .fold(
+            String::new(),
+            |path, (container, (tag, index))| match container.container_type {
+                ContainerType::Structure => schema::member_path(&path, tag),
+                _ => format!("{}[{}]", path, index),
+            },
+        )
+}
+
+// Checks the payload is well formed without decoding values, collecting every finding. The walk
+// stops at the first element whose size cannot be known, only nesting deeper than max_depth or more
+// than max_elements control bytes fail the whole check.
+pub fn validate(bytes: &[u8], config: &TLVReaderConfig) -> Result<ValidationReport, TLVError> {
+    let mut findings = Vec::new();
+    let mut containers: Vec<OpenContainer> = Vec::new();
+    let mut offset = 0;
+    let mut elements = 0;
+    let mut finding = |offset, kind| findings.push(Finding { offset, kind });
+
+    while offset < bytes.len() {
+        if config.single_element && containers.is_empty() && offset > 0 {
+            finding(offset, FindingKind::TrailingBytes);
+            break;
+        }
+        elements += 1;
+        if elements > config.max_elements {
+            return Err(TLVError::TooManyElements {
+                limit: config.max_elements,
+            });
+        }
+        let element_offset = offset;
+        let control_byte = ControlByte::from(bytes[offset]);
+        if control_byte.element_type == ElementType::EndOfContainer as u8 {
+            if control_byte.tag_control != 0 {
+                finding(offset, FindingKind::TaggedEndOfContainer);
+            }
+            if containers.pop().is_none() {
+                finding(offset, FindingKind::StrayEndOfContainer);
+            }
+            // Past the tag of a tagged End of Container as well
+            let tag_control_byte = control_byte.tag_control << tags::CONTROL_BYTE_SHIFT;
+            offset += 1 + TagControl::try_from(tag_control_byte)?.octets_count() as usize;
+            continue;
+        }
+        let tlv_type =
+            match ElementType::try_from(control_byte.element_type).and_then(TLVType::try_from) {
+                Ok(tlv_type) => tlv_type,
+                Err(_) => {
+                    finding(
+                        offset,
+                        FindingKind::InvalidElementType(control_byte.element_type),
+                    );
+                    break;
+                }
+            };
+        let tag_control_byte = control_byte.tag_control << tags::CONTROL_BYTE_SHIFT;
+        let (remaining_bytes, tag) = match tags::parse_tag(tag_control_byte, &bytes[offset + 1..]) {
+            Ok(parsed) => parsed,
+            Err(_) => {
+                finding(offset, FindingKind::Truncated);
+                break;
+            }
+        };
+        if config.require_minimal_encoding {
+            if canonical::is_non_canonical_nan(tlv_type, remaining_bytes) {
+                finding(element_offset, FindingKind::NonCanonicalNaN);
+            } else if !canonical::is_minimal_encoding(&tag, tlv_type, remaining_bytes) {
+                finding(element_offset, FindingKind::NonMinimalEncoding);
+            }
+        }
+        let index = containers.last().map_or(0, |container| container.members);
+        let out_of_order = config.require_canonical_order
+            && containers.last().is_some_and(|container| {
+                container.container_type == ContainerType::Structure
+                    && container.ordered
+                    && container.previous_tag.as_ref().is_some_and(|previous_tag| {
+                        tag.canonical_cmp(previous_tag) == Ordering::Less
+                    })
+            });
+        if out_of_order {
+            let path = member_path(&containers, &tag, index);
+            finding(offset, FindingKind::MemberOutOfOrder { path });
+        }
+        if let Some(container) = containers.last_mut() {
+            container.members += 1;
+            match container.container_type {
+                ContainerType::Structure
+                    if config.enforce_tag_rules && tag == TLVTag::Anonymous =>
+                {
+                    finding(offset, FindingKind::AnonymousMember)
+                }
+                ContainerType::Structure => {
+                    if config.reject_duplicate_tags {
+                        if container.member_tags.contains(&tag) {
+                            finding(offset, FindingKind::DuplicateMember(tag.clone()));
+                        } else {
+                            container.member_tags.push(tag.clone());
+                        }
+                    }
+                    // Only the first out of order member of a structure is reported
+                    if config.require_canonical_order && container.ordered {
+                        container.ordered = !out_of_order;
+                        container.previous_tag = Some(tag.clone());
+                    }
+                }
+                ContainerType::Array if config.enforce_tag_rules && tag != TLVTag::Anonymous => {
+                    finding(offset, FindingKind::UnexpectedTag(tag.clone()))
+                }
+                ContainerType::Array => {}
+                // Anonymous and tagged members, repeated tags and any order are all valid in a List
+                ContainerType::List => {}
+            }
+        }
+        offset = bytes.len() - remaining_bytes.len();
+
+        let value_len = match tlv_type {
+            TLVType::Container(container_type) => {
+                if containers.len() == config.max_depth {
+                    return Err(TLVError::DepthLimitExceeded(config.max_depth));
+                }
+                containers.push(OpenContainer {
+                    container_type,
+                    offset: element_offset,
+                    tag,
+                    index,
+                    members: 0,
+                    member_tags: Vec::new(),
+                    previous_tag: None,
+                    ordered: true,
+                });
+                continue;
+            }
+            TLVType::Primitive(PrimitiveLengthType::Predetermined(predetermined)) => {
+                predetermined.value_octets_count()
+            }
+            TLVType::Primitive(PrimitiveLengthType::Specified(specified)) => {
+                match specified
+                    .length_field_size()
+                    .parse_field_size(remaining_bytes)
+                {
+                    Ok((remaining_bytes, len)) => {
+                        offset = bytes.len() - remaining_bytes.len();
+                        if len > config.max_element_len && len <= remaining_bytes.len() {
+                            finding(
+                                element_offset,
+                                FindingKind::ElementTooLarge {
+                                    declared: len,
+                                    limit: config.max_element_len,
+                                },
+                            );
+                        }
+                        let is_utf8 = matches!(specified, SpecifiedLenPrimitive::UTF8String(_));
+                        if is_utf8 && len <= remaining_bytes.len() {
+                            let value = &remaining_bytes[..len];
+                            if config.check_utf8 && from_utf8(value).is_err() {
+                                finding(element_offset, FindingKind::InvalidUtf8);
+                            }
+                            match config.check_string(value, offset) {
+                                Err(TLVError::NulCharacter { offset }) => {
+                                    finding(offset, FindingKind::NulCharacter)
+                                }
+                                Err(TLVError::NotNfcNormalized { offset }) => {
+                                    finding(offset, FindingKind::NotNfcNormalized)
+                                }
+                                _ => {}
+                            }
+                        }
+                        len
+                    }
+                    Err(_) => {
+                        finding(element_offset, FindingKind::Truncated);
+                        break;
+                    }
+                }
+            }
+        };
+        if value_len > bytes.len() - offset {
+            finding(element_offset, FindingKind::Truncated);
+            break;
+        }
+        offset += value_len;
+    }
+
+    // Every open container is unterminated, the outermost one is reported
+    if let Some(container) = containers.first() {
+        finding(container.offset, FindingKind::UnterminatedContainer);
+    }
+    Ok(ValidationReport { findings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_element() -> TLVReaderConfig {
+        TLVReaderConfig::default().single_element(true)
+    }
+
+    fn findings(bytes: &[u8], config: &TLVReaderConfig) -> Vec<Finding> {
+        validate(bytes, config)
+            .expect("Failed to validate")
+            .findings
+    }
+
+    #[test]
+    fn test_validate_well_formed() {
+        // Structure {0 = 42U, 1 = [true, -3], 2 = List [[Anonymous = "a", 3 = Null]]}
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x00, 0xfd, 0x18, 0x37, 0x02, 0x0c, 0x01,
+            0x61, 0x34, 0x03, 0x18, 0x18,
+        ];
+        assert!(validate(test_bytes, &single_element())
+            .expect("Failed to validate")
+            .is_valid());
+        assert!(validate(&[], &single_element())
+            .expect("Failed to validate")
+            .is_valid());
+
+        // 42U + true, a sequence of top level elements
+        let test_bytes = &[0x04, 0x2a, 0x09];
+        let config = TLVReaderConfig::default();
+        assert!(findings(test_bytes, &config).is_empty());
+        assert_eq!(
+            findings(test_bytes, &single_element()),
+            vec![Finding {
+                offset: 2,
+                kind: FindingKind::TrailingBytes
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_broken_vectors() {
+        let test_vectors: &[(&[u8], Vec<Finding>)] = &[
+            // Structure {0 = 42} without its End of Container
+            (
+                &[0x15, 0x20, 0x00, 0x2a],
+                vec![Finding {
+                    offset: 0,
+                    kind: FindingKind::UnterminatedContainer,
+                }],
+            ),
+            // Stray End of Container after 1U
+            (
+                &[0x16, 0x04, 0x01, 0x18, 0x18],
+                vec![Finding {
+                    offset: 4,
+                    kind: FindingKind::TrailingBytes,
+                }],
+            ),
+            // Element type of End of Container with a non anonymous tag
+            (
+                &[0x15, 0x38, 0x01],
+                vec![Finding {
+                    offset: 1,
+                    kind: FindingKind::TaggedEndOfContainer,
+                }],
+            ),
+            // Reserved element type 0x19
+            (
+                &[0x19, 0x00],
+                vec![Finding {
+                    offset: 0,
+                    kind: FindingKind::InvalidElementType(0x19),
+                }],
+            ),
+            // Octet String, 8-octet length of u64::MAX
+            (
+                &[0x13, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00],
+                vec![Finding {
+                    offset: 0,
+                    kind: FindingKind::Truncated,
+                }],
+            ),
+            // UTF-8 String, 2-octet length field cut short
+            (
+                &[0x0d, 0x01],
+                vec![Finding {
+                    offset: 0,
+                    kind: FindingKind::Truncated,
+                }],
+            ),
+            // Fully qualified tag cut short
+            (
+                &[0xc4, 0xf1, 0xff],
+                vec![Finding {
+                    offset: 0,
+                    kind: FindingKind::Truncated,
+                }],
+            ),
+            // Array [Unsigned Integer, 4-octet, cut short], both the element and the Array are reported
+            (
+                &[0x16, 0x06, 0x01, 0x02],
+                vec![
+                    Finding {
+                        offset: 1,
+                        kind: FindingKind::Truncated,
+                    },
+                    Finding {
+                        offset: 0,
+                        kind: FindingKind::UnterminatedContainer,
+                    },
+                ],
+            ),
+            // Structure {0 = invalid UTF-8 byte ff, Anonymous = 1U, 1 = [2 = 2U]}
+            (
+                &[
+                    0x15, 0x2c, 0x00, 0x01, 0xff, 0x04, 0x01, 0x36, 0x01, 0x24, 0x02, 0x02, 0x18,
+                    0x18,
+                ],
+                vec![
+                    Finding {
+                        offset: 1,
+                        kind: FindingKind::InvalidUtf8,
+                    },
+                    Finding {
+                        offset: 5,
+                        kind: FindingKind::AnonymousMember,
+                    },
+                    Finding {
+                        offset: 9,
+                        kind: FindingKind::UnexpectedTag(TLVTag::ContextSpecific(2)),
+                    },
+                ],
+            ),
+        ];
+        for (test_bytes, expected) in test_vectors {
+            assert_eq!(&findings(test_bytes, &single_element()), expected);
+        }
+
+        // Stray End of Container in a sequence of top level elements
+        let config = TLVReaderConfig::default();
+        assert_eq!(
+            findings(&[0x04, 0x01, 0x18, 0x09], &config),
+            vec![Finding {
+                offset: 2,
+                kind: FindingKind::StrayEndOfContainer
+            }]
+        );
+
+        // UTF-8 check disabled
+        let config = single_element().check_utf8(false);
+        assert!(findings(&[0x0c, 0x01, 0xff], &config).is_empty());
+    }
+
+    #[test]
+    fn test_validate_unterminated_containers() {
+        // Boolean true + Array, mix of element types, [42, -170000, {}, 17.9, "Hello!"]
+        let test_bytes = &[
+            0x09, 0x16, 0x00, 0x2a, 0x02, 0xf0, 0x67, 0xfd, 0xff, 0x15, 0x18, 0x0a, 0x33, 0x33,
+            0x8f, 0x41, 0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21, 0x18,
+        ];
+        assert!(findings(test_bytes, &TLVReaderConfig::default()).is_empty());
+        // Truncated right before each End of Container, only the outermost open Array is reported
+        for end in [10, 24] {
+            assert_eq!(
+                findings(&test_bytes[..end], &TLVReaderConfig::default()),
+                vec![Finding {
+                    offset: 1,
+                    kind: FindingKind::UnterminatedContainer,
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_max_elements() {
+        // Array of 998 then 999 Boolean true, 1000 then 1001 control bytes
+        let mut test_bytes = vec![0x16];
+        test_bytes.extend([0x09; 998]);
+        test_bytes.push(0x18);
+        let config = TLVReaderConfig::default().max_elements(1000);
+        assert!(findings(&test_bytes, &config).is_empty());
+        test_bytes.insert(1, 0x09);
+        assert_eq!(
+            validate(&test_bytes, &config).expect_err("Too many elements is expected"),
+            TLVError::TooManyElements { limit: 1000 }
+        );
+    }
+
+    #[test]
+    fn test_validate_config() {
+        // Structure {0 = "Hello!", 0 = 1U, Anonymous = [1 = 2U]}
+        let test_bytes = &[
+            0x15, 0x2c, 0x00, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21, 0x24, 0x00, 0x01, 0x16,
+            0x24, 0x01, 0x02, 0x18, 0x18,
+        ];
+        assert_eq!(
+            findings(test_bytes, &TLVReaderConfig::default()),
+            vec![
+                Finding {
+                    offset: 13,
+                    kind: FindingKind::AnonymousMember,
+                },
+                Finding {
+                    offset: 14,
+                    kind: FindingKind::UnexpectedTag(TLVTag::ContextSpecific(1)),
+                },
+            ]
+        );
+        let config = TLVReaderConfig::default()
+            .enforce_tag_rules(false)
+            .reject_duplicate_tags(true)
+            .max_element_len(4);
+        assert_eq!(
+            findings(test_bytes, &config),
+            vec![
+                Finding {
+                    offset: 1,
+                    kind: FindingKind::ElementTooLarge {
+                        declared: 6,
+                        limit: 4
+                    },
+                },
+                Finding {
+                    offset: 10,
+                    kind: FindingKind::DuplicateMember(TLVTag::ContextSpecific(0)),
+                },
+            ]
+        );
+
+        // Structure {1 = "a" followed by a NUL character, 2 = NUL character}
+        let test_bytes = &[
+            0x15, 0x2c, 0x01, 0x02, 0x61, 0x00, 0x2c, 0x02, 0x01, 0x00, 0x18,
+        ];
+        assert!(findings(test_bytes, &TLVReaderConfig::default()).is_empty());
+        assert_eq!(
+            findings(test_bytes, &TLVReaderConfig::default().strict_strings(true)),
+            vec![
+                Finding {
+                    offset: 5,
+                    kind: FindingKind::NulCharacter,
+                },
+                Finding {
+                    offset: 9,
+                    kind: FindingKind::NulCharacter,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_list_members() {
+        // List [[0 = 1U, 0 = 2U, Anonymous = 3U, 1 = List [[true, 2 = Null, 2 = Null]], 0 = 4U]]
+        let mut test_bytes = [
+            0x17, 0x24, 0x00, 0x01, 0x24, 0x00, 0x02, 0x04, 0x03, 0x37, 0x01, 0x09, 0x34, 0x02,
+            0x34, 0x02, 0x18, 0x24, 0x00, 0x04, 0x18,
+        ];
+        let config = TLVReaderConfig::default()
+            .reject_duplicate_tags(true)
+            .require_canonical_order(true);
+        assert!(findings(&test_bytes, &config).is_empty());
+
+        // Same members in a Structure, then in an Array, the nested List still accepted
+        test_bytes[0] = ContainerType::Structure as u8;
+        let config = TLVReaderConfig::default().reject_duplicate_tags(true);
+        assert_eq!(
+            findings(&test_bytes, &config),
+            vec![
+                Finding {
+                    offset: 4,
+                    kind: FindingKind::DuplicateMember(TLVTag::ContextSpecific(0)),
+                },
+                Finding {
+                    offset: 7,
+                    kind: FindingKind::AnonymousMember,
+                },
+                Finding {
+                    offset: 17,
+                    kind: FindingKind::DuplicateMember(TLVTag::ContextSpecific(0)),
+                },
+            ]
+        );
+        test_bytes[0] = ContainerType::Array as u8;
+        let unexpected_tags: Vec<usize> = findings(&test_bytes, &config)
+            .iter()
+            .map(|finding| finding.offset)
+            .collect();
+        assert_eq!(unexpected_tags, vec![1, 4, 9, 17]);
+    }
+
+    #[test]
+    fn test_validate_minimal_encoding() {
+        // Structure {0 = 5 as UInt32, CHIP::1 = "a" with a 2-octet length}
+        let test_bytes = &[
+            0x15, 0x26, 0x00, 0x05, 0x00, 0x00, 0x00, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00,
+            0x61, 0x18,
+        ];
+        assert!(findings(test_bytes, &TLVReaderConfig::default()).is_empty());
+        assert_eq!(
+            findings(
+                test_bytes,
+                &TLVReaderConfig::default().require_minimal_encoding(true)
+            ),
+            vec![
+                Finding {
+                    offset: 1,
+                    kind: FindingKind::NonMinimalEncoding,
+                },
+                Finding {
+                    offset: 7,
+                    kind: FindingKind::NonMinimalEncoding,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_canonical_nans() {
+        // [Single precision signaling NaN, NaN, double precision NaN, +inf]
+        let test_bytes = &[
+            0x16, 0x0a, 0x00, 0x00, 0xa0, 0x7f, 0x0a, 0x00, 0x00, 0xc0, 0x7f, 0x0b, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0xf8, 0xff, 0x0a, 0x00, 0x00, 0x80, 0x7f, 0x18,
+        ];
+        assert!(findings(test_bytes, &TLVReaderConfig::default()).is_empty());
+        assert_eq!(
+            findings(
+                test_bytes,
+                &TLVReaderConfig::default().require_minimal_encoding(true)
+            ),
+            vec![
+                Finding {
+                    offset: 1,
+                    kind: FindingKind::NonCanonicalNaN,
+                },
+                Finding {
+                    offset: 11,
+                    kind: FindingKind::NonCanonicalNaN,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_canonical_order() {
+        let config = TLVReaderConfig::default().require_canonical_order(true);
+        let out_of_order = |offset, path: &str| Finding {
+            offset,
+            kind: FindingKind::MemberOutOfOrder {
+                path: String::from(path),
+            },
+        };
+
+        // Structure {0 = 1U, 1 = 1U, CHIP::1 = 1U, 65521::57069:1 = 1U}
+        let ordered = &[
+            0x15, 0x24, 0x00, 0x01, 0x24, 0x01, 0x01, 0x44, 0x01, 0x00, 0x01, 0xc4, 0xf1, 0xff,
+            0xed, 0xde, 0x01, 0x00, 0x01, 0x18,
+        ];
+        assert!(findings(ordered, &config).is_empty());
+
+        // Structure {65521::57069:1 = 1U, CHIP::1 = 1U, 1 = 1U, 0 = 1U}, only the first out of
+        // order member is reported
+        let reversed = &[
+            0x15, 0xc4, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x01, 0x44, 0x01, 0x00, 0x01, 0x24,
+            0x01, 0x01, 0x24, 0x00, 0x01, 0x18,
+        ];
+        assert!(findings(reversed, &TLVReaderConfig::default()).is_empty());
+        assert_eq!(
+            findings(reversed, &config),
+            vec![out_of_order(9, "CHIP::1")]
+        );
+
+        // Structure {0 = {2 = 1U, CHIP::1 = 1U, 1 = 1U}, 1 = [{65521::57069:1 = 1U, 3 = 1U}]},
+        // context and fully qualified tags interleaved in nested structures
+        let interleaved = &[
+            0x15, 0x35, 0x00, 0x24, 0x02, 0x01, 0x44, 0x01, 0x00, 0x01, 0x24, 0x01, 0x01, 0x18,
+            0x36, 0x01, 0x15, 0xc4, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x01, 0x24, 0x03, 0x01,
+            0x18, 0x18, 0x18,
+        ];
+        assert_eq!(
+            findings(interleaved, &config),
+            vec![out_of_order(10, "0/1"), out_of_order(25, "1[0]/3")]
+        );
+    }
+
+    #[test]
+    fn test_validate_depth_limit() {
+        // [[[]]]
+        let test_bytes = &[0x16, 0x16, 0x16, 0x18, 0x18, 0x18];
+        let config = single_element().max_depth(2);
+        assert_eq!(
+            validate(test_bytes, &config).expect_err("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(2)
+        );
+        let config = single_element().max_depth(3);
+        assert!(findings(test_bytes, &config).is_empty());
+    }
+}
@@ -0,0 +1,1677 @@
+use crate::builder::DocumentBuilder;
+use crate::errors::TLVError;
+use crate::hex::{self, HexFormat};
+use crate::path::{self, TLVPath};
+use crate::reader::{self, TLVReader, TLVReaderConfig};
+use crate::tags::TLVTag;
+use crate::types::{
+    ContainerType, FloatingPoint, PredeterminedLenPrimitive, PrimitiveLengthType, SignedInteger,
+    SpecifiedLenPrimitive, TLVType, UnsignedInteger,
+};
+use crate::writer::TLVEncode;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use bytes::Bytes;
+use core::cmp::Ordering;
+use core::fmt;
+use core::slice;
+
+// Equality of octets in a time depending on their length only, the position of the first
+// difference does not show. Lengths differing return at once, they are not secret in TLV payloads
+// which carry them in the clear.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    // black_box keeps the compiler from turning the loop into one returning at the first difference
+    let difference = a.iter().zip(b).fold(0, |difference, (a, b)| {
+        core::hint::black_box(difference | (a ^ b))
+    });
+    difference == 0
+}
+
+// Resolution of a member present in both structures of TLVValue::merge_structure
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum MergeStrategy {
+    OtherWins,
+    SelfWins,
+    ErrorOnConflict,
+}
+
+// Owned element value, numbers keep the width they were encoded with
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TLVValue {
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    UInt8(u8),
+    UInt16(u16),
+    UInt32(u32),
+    UInt64(u64),
+    Bool(bool),
+    Float32(#[cfg_attr(feature = "serde", serde(with = "crate::serde::float32"))] f32),
+    Float64(#[cfg_attr(feature = "serde", serde(with = "crate::serde::float64"))] f64),
+    Utf8String(String),
+    ByteString(#[cfg_attr(feature = "serde", serde(with = "crate::serde::byte_string"))] Vec<u8>),
+    Null,
+    Structure(Vec<(TLVTag, TLVValue)>),
+    Array(Vec<TLVValue>), // Members are anonymous
+    List(Vec<(TLVTag, TLVValue)>),
+}
+
+impl TLVValue {
+    // Equality of the encoded meaning: integers of the same signedness and floats compare by value
+    // whatever their width, tags ignore the width of their field and NaN equals NaN
+    pub fn semantic_eq(&self, other: &TLVValue) -> bool {
+        if let (Some(a), Some(b)) = (self.as_i128(), other.as_i128()) {
+            return a == b && self.is_signed() == other.is_signed();
+        }
+        if let (Some(a), Some(b)) = (self.as_f64(), other.as_f64()) {
+            return a == b || (a.is_nan() && b.is_nan());
+        }
+        match (self, other) {
+            (TLVValue::Structure(a), TLVValue::Structure(b))
+            | (TLVValue::List(a), TLVValue::List(b)) => {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|((a_tag, a), (b_tag, b))| {
+                        a_tag.canonical_cmp(b_tag).is_eq() && a.semantic_eq(b)
+                    })
+            }
+            (TLVValue::Array(a), TLVValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.semantic_eq(b))
+            }
+            _ => self == other,
+        }
+    }
+
+    // Whether this is an Octet String holding the octets, compared in constant time for secrets
+    // such as verifiers, nonces or keys. See constant_time_eq for what timing still tells.
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        match self {
+            TLVValue::ByteString(octets) => constant_time_eq(octets, other),
+            _ => false,
+        }
+    }
+
+    // Members of a container, 0 for other values
+    pub fn len(&self) -> usize {
+        match self {
+            TLVValue::Structure(members) | TLVValue::List(members) => members.len(),
+            TLVValue::Array(members) => members.len(),
+            _ => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Members of a Structure, nothing for other values
+    pub fn iter_structure(&self) -> impl Iterator<Item = (&TLVTag, &TLVValue)> {
+        let members = match self {
+            TLVValue::Structure(members) => members.as_slice(),
+            _ => &[],
+        };
+        members.iter().map(|(tag, value)| (tag, value))
+    }
+
+    // Merges the members of another Structure into this one, e.g. a delta report onto a cached full
+    // state. Members on both sides are merged when both are Structures and resolved by the strategy
+    // otherwise, Arrays and Lists being replaced whole. Tags match whatever the width of their
+    // field, members only in other are appended. Fails with InvalidType unless both are Structures
+    // and with MergeConflict when members differ under ErrorOnConflict, leaving self unchanged.
+    pub fn merge_structure(
+        &mut self,
+        other: &TLVValue,
+        strategy: MergeStrategy,
+    ) -> Result<(), TLVError> {
+        match (&*self, other) {
+            (TLVValue::Structure(members), TLVValue::Structure(other)) => {
+                let mut merged = members.clone();
+                merge_members(&mut merged, other, strategy)?;
+                *self = TLVValue::Structure(merged);
+                Ok(())
+            }
+            _ => Err(TLVError::InvalidType),
+        }
+    }
+
+    pub(crate) fn as_i128(&self) -> Option<i128> {
+        Some(match self {
+            TLVValue::Int8(value) => *value as i128,
+            TLVValue::Int16(value) => *value as i128,
+            TLVValue::Int32(value) => *value as i128,
+            TLVValue::Int64(value) => *value as i128,
+            TLVValue::UInt8(value) => *value as i128,
+            TLVValue::UInt16(value) => *value as i128,
+            TLVValue::UInt32(value) => *value as i128,
+            TLVValue::UInt64(value) => *value as i128,
+            _ => return None,
+        })
+    }
+
+    fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            TLVValue::Int8(_) | TLVValue::Int16(_) | TLVValue::Int32(_) | TLVValue::Int64(_)
+        )
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            TLVValue::Float32(value) => Some(*value as f64),
+            TLVValue::Float64(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+fn merge_members(
+    members: &mut Vec<(TLVTag, TLVValue)>,
+    other: &[(TLVTag, TLVValue)],
+    strategy: MergeStrategy,
+) -> Result<(), TLVError> {
+    for (tag, value) in other {
+        let member = members
+            .iter_mut()
+            .find(|(member_tag, _)| member_tag.canonical_cmp(tag).is_eq());
+        let Some((_, member)) = member else {
+            members.push((tag.clone(), value.clone()));
+            continue;
+        };
+        if let (TLVValue::Structure(members), TLVValue::Structure(other)) = (&mut *member, value) {
+            merge_members(members, other, strategy)?;
+        } else if !member.semantic_eq(value) {
+            match strategy {
+                MergeStrategy::OtherWins => *member = value.clone(),
+                MergeStrategy::SelfWins => {}
+                MergeStrategy::ErrorOnConflict => return Err(TLVError::MergeConflict(tag.clone())),
+            }
+        }
+    }
+    Ok(())
+}
+
+// Integers of any width whose value fits, of the same signedness
+macro_rules! integer_from_value {
+    ($type:ty, $signed:expr) => {
+        impl TryFrom<&TLVValue> for $type {
+            type Error = TLVError;
+
+            fn try_from(value: &TLVValue) -> Result<Self, TLVError> {
+                match value.as_i128() {
+                    Some(integer) if value.is_signed() == $signed => {
+                        <$type>::try_from(integer).map_err(|_| TLVError::InvalidType)
+                    }
+                    _ => Err(TLVError::InvalidType),
+                }
+            }
+        }
+    };
+}
+
+integer_from_value!(u8, false);
+integer_from_value!(u16, false);
+integer_from_value!(u32, false);
+integer_from_value!(u64, false);
+integer_from_value!(i8, true);
+integer_from_value!(i16, true);
+integer_from_value!(i32, true);
+integer_from_value!(i64, true);
+
+impl TryFrom<&TLVValue> for f32 {
+    type Error = TLVError;
+
+    fn try_from(value: &TLVValue) -> Result<Self, TLVError> {
+        match value {
+            TLVValue::Float32(value) => Ok(*value),
+            _ => Err(TLVError::InvalidType),
+        }
+    }
+}
+
+// Single precision values are widened
+impl TryFrom<&TLVValue> for f64 {
+    type Error = TLVError;
+
+    fn try_from(value: &TLVValue) -> Result<Self, TLVError> {
+        value.as_f64().ok_or(TLVError::InvalidType)
+    }
+}
+
+impl TryFrom<&TLVValue> for bool {
+    type Error = TLVError;
+
+    fn try_from(value: &TLVValue) -> Result<Self, TLVError> {
+        match value {
+            TLVValue::Bool(value) => Ok(*value),
+            _ => Err(TLVError::InvalidType),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a TLVValue> for &'a str {
+    type Error = TLVError;
+
+    fn try_from(value: &'a TLVValue) -> Result<Self, TLVError> {
+        match value {
+            TLVValue::Utf8String(value) => Ok(value),
+            _ => Err(TLVError::InvalidType),
+        }
+    }
+}
+
+impl TryFrom<&TLVValue> for String {
+    type Error = TLVError;
+
+    fn try_from(value: &TLVValue) -> Result<Self, TLVError> {
+        <&str>::try_from(value).map(String::from)
+    }
+}
+
+impl<'a> TryFrom<&'a TLVValue> for &'a [u8] {
+    type Error = TLVError;
+
+    fn try_from(value: &'a TLVValue) -> Result<Self, TLVError> {
+        match value {
+            TLVValue::ByteString(value) => Ok(value),
+            _ => Err(TLVError::InvalidType),
+        }
+    }
+}
+
+impl TryFrom<&TLVValue> for Vec<u8> {
+    type Error = TLVError;
+
+    fn try_from(value: &TLVValue) -> Result<Self, TLVError> {
+        <&[u8]>::try_from(value).map(<[u8]>::to_vec)
+    }
+}
+
+// Equal when the integer of any width and signedness holds the same number, so UInt16(300) == 300i64
+// and UInt8(42) == 42 but UInt64(u64::MAX) != -1i64
+macro_rules! eq_by_number {
+    ($($type:ty),*) => {
+        $(
+            impl PartialEq<$type> for TLVValue {
+                fn eq(&self, other: &$type) -> bool {
+                    self.as_i128() == Some(*other as i128)
+                }
+            }
+
+            impl PartialEq<TLVValue> for $type {
+                fn eq(&self, other: &TLVValue) -> bool {
+                    other == self
+                }
+            }
+        )*
+    };
+}
+
+eq_by_number!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+// Equal when the value converts to the other operand as TryFrom does it
+macro_rules! eq_through_try_from {
+    ($($type:ty),*) => {
+        $(
+            impl PartialEq<$type> for TLVValue {
+                fn eq(&self, other: &$type) -> bool {
+                    <$type>::try_from(self).is_ok_and(|value| value == *other)
+                }
+            }
+
+            impl PartialEq<TLVValue> for $type {
+                fn eq(&self, other: &TLVValue) -> bool {
+                    other == self
+                }
+            }
+        )*
+    };
+}
+
+eq_through_try_from!(bool, &str);
+
+static ANONYMOUS: TLVTag = TLVTag::Anonymous;
+
+// Members of a Structure, List or Array with their tags, Array members being anonymous. Other
+// values have no members.
+pub struct Members<'a> {
+    inner: MembersInner<'a>,
+}
+
+enum MembersInner<'a> {
+    Tagged(slice::Iter<'a, (TLVTag, TLVValue)>),
+    Anonymous(slice::Iter<'a, TLVValue>),
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = (&'a TLVTag, &'a TLVValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            MembersInner::Tagged(members) => members.next().map(|(tag, value)| (tag, value)),
+            MembersInner::Anonymous(members) => members.next().map(|value| (&ANONYMOUS, value)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            MembersInner::Tagged(members) => members.size_hint(),
+            MembersInner::Anonymous(members) => members.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for Members<'_> {}
+
+impl<'a> IntoIterator for &'a TLVValue {
+    type Item = (&'a TLVTag, &'a TLVValue);
+    type IntoIter = Members<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let inner = match self {
+            TLVValue::Structure(members) | TLVValue::List(members) => {
+                MembersInner::Tagged(members.iter())
+            }
+            TLVValue::Array(members) => MembersInner::Anonymous(members.iter()),
+            _ => MembersInner::Tagged([].iter()),
+        };
+        Members { inner }
+    }
+}
+
+// Notation of the examples in the tests: 42U, -3, "a", hex:0102, {0 = 42U}, [true] and
+// [[Anonymous = Null]]
+impl fmt::Display for TLVValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let members = |f: &mut fmt::Formatter<'_>, members: &[(TLVTag, TLVValue)]| {
+            for (index, (tag, value)) in members.iter().enumerate() {
+                let separator = if index > 0 { ", " } else { "" };
+                write!(f, "{}{} = {}", separator, tag, value)?;
+            }
+            Ok(())
+        };
+        match self {
+            TLVValue::Int8(value) => write!(f, "{}", value),
+            TLVValue::Int16(value) => write!(f, "{}", value),
+            TLVValue::Int32(value) => write!(f, "{}", value),
+            TLVValue::Int64(value) => write!(f, "{}", value),
+            TLVValue::UInt8(value) => write!(f, "{}U", value),
+            TLVValue::UInt16(value) => write!(f, "{}U", value),
+            TLVValue::UInt32(value) => write!(f, "{}U", value),
+            TLVValue::UInt64(value) => write!(f, "{}U", value),
+            TLVValue::Bool(value) => write!(f, "{}", value),
+            TLVValue::Float32(value) => write!(f, "{}", value),
+            TLVValue::Float64(value) => write!(f, "{}", value),
+            TLVValue::Utf8String(value) => write!(f, "{:?}", value),
+            TLVValue::ByteString(value) => {
+                write!(f, "{}", hex::to_hex_with_format(value, HexFormat::Prefixed))
+            }
+            TLVValue::Null => write!(f, "Null"),
+            TLVValue::Structure(value) => {
+                write!(f, "{{")?;
+                members(f, value)?;
+                write!(f, "}}")
+            }
+            TLVValue::Array(value) => {
+                write!(f, "[")?;
+                for (index, value) in value.iter().enumerate() {
+                    let separator = if index > 0 { ", " } else { "" };
+                    write!(f, "{}{}", separator, value)?;
+                }
+                write!(f, "]")
+            }
+            TLVValue::List(value) => {
+                write!(f, "[[")?;
+                members(f, value)?;
+                write!(f, "]]")
+            }
+        }
+    }
+}
+
+// Borrowed counterpart of TLVValue, strings point into the reader's buffer
+#[derive(Debug, PartialEq, Clone)]
+pub enum TLVValueRef<'a> {
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    UInt8(u8),
+    UInt16(u16),
+    UInt32(u32),
+    UInt64(u64),
+    Bool(bool),
+    Float32(f32),
+    Float64(f64),
+    Utf8String(&'a str),
+    ByteString(&'a [u8]),
+    Null,
+    Structure(Vec<(TLVTag, TLVValueRef<'a>)>),
+    Array(Vec<TLVValueRef<'a>>),
+    List(Vec<(TLVTag, TLVValueRef<'a>)>),
+}
+
+impl TLVValueRef<'_> {
+    #[allow(clippy::wrong_self_convention)] // Converts to TLVValue, not to another TLVValueRef
+    pub fn to_owned(&self) -> TLVValue {
+        let owned_members = |members: &[(TLVTag, TLVValueRef)]| {
+            members
+                .iter()
+                .map(|(tag, value)| (tag.clone(), value.to_owned()))
+                .collect()
+        };
+        match self {
+            TLVValueRef::Int8(value) => TLVValue::Int8(*value),
+            TLVValueRef::Int16(value) => TLVValue::Int16(*value),
+            TLVValueRef::Int32(value) => TLVValue::Int32(*value),
+            TLVValueRef::Int64(value) => TLVValue::Int64(*value),
+            TLVValueRef::UInt8(value) => TLVValue::UInt8(*value),
+            TLVValueRef::UInt16(value) => TLVValue::UInt16(*value),
+            TLVValueRef::UInt32(value) => TLVValue::UInt32(*value),
+            TLVValueRef::UInt64(value) => TLVValue::UInt64(*value),
+            TLVValueRef::Bool(value) => TLVValue::Bool(*value),
+            TLVValueRef::Float32(value) => TLVValue::Float32(*value),
+            TLVValueRef::Float64(value) => TLVValue::Float64(*value),
+            TLVValueRef::Utf8String(value) => TLVValue::Utf8String(String::from(*value)),
+            TLVValueRef::ByteString(value) => TLVValue::ByteString(value.to_vec()),
+            TLVValueRef::Null => TLVValue::Null,
+            TLVValueRef::Structure(members) => TLVValue::Structure(owned_members(members)),
+            TLVValueRef::Array(members) => {
+                TLVValue::Array(members.iter().map(TLVValueRef::to_owned).collect())
+            }
+            TLVValueRef::List(members) => TLVValue::List(owned_members(members)),
+        }
+    }
+}
+
+// Value with its tag, e.g. a top level element of a document. Displays as "1 = 42U", anonymous
+// elements as their value alone.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TLVElement {
+    pub tag: TLVTag,
+    pub value: TLVValue,
+}
+
+impl TLVElement {
+    pub fn new(tag: TLVTag, value: TLVValue) -> Self {
+        Self { tag, value }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        self.value.encode_tlv_with_tag(&self.tag)
+    }
+
+    // First element of the bytes and the bytes following it, empty input fails with EndOfTLV
+    pub fn decode(bytes: &[u8]) -> Result<(Self, &[u8]), TLVError> {
+        let element_len = reader::element_size(bytes)?;
+        let (element, rest) = bytes.split_at(element_len);
+        let tlv_reader = TLVReader::new(element);
+        let tag = tlv_reader.read_tag()?;
+        let value = tlv_reader.read_value()?;
+        Ok((Self { tag, value }, rest))
+    }
+}
+
+impl From<(TLVTag, TLVValue)> for TLVElement {
+    fn from((tag, value): (TLVTag, TLVValue)) -> Self {
+        Self { tag, value }
+    }
+}
+
+impl From<TLVElement> for (TLVTag, TLVValue) {
+    fn from(element: TLVElement) -> Self {
+        (element.tag, element.value)
+    }
+}
+
+impl fmt::Display for TLVElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.tag {
+            TLVTag::Anonymous => write!(f, "{}", self.value),
+            ref tag => write!(f, "{} = {}", tag, self.value),
+        }
+    }
+}
+
+// Top level elements of a payload
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TLVDocument {
+    pub elements: Vec<TLVElement>,
+}
+
+impl IntoIterator for TLVDocument {
+    type Item = TLVElement;
+    type IntoIter = alloc::vec::IntoIter<TLVElement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a TLVDocument {
+    type Item = &'a TLVElement;
+    type IntoIter = slice::Iter<'a, TLVElement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+
+impl FromIterator<TLVElement> for TLVDocument {
+    fn from_iter<I: IntoIterator<Item = TLVElement>>(elements: I) -> Self {
+        Self {
+            elements: elements.into_iter().collect(),
+        }
+    }
+}
+
+impl TLVDocument {
+    pub fn builder() -> DocumentBuilder {
+        DocumentBuilder::new()
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, TLVError> {
+        Self::parse_with_config(bytes, &TLVReaderConfig::default())
+    }
+
+    pub fn parse_with_config(bytes: &[u8], config: &TLVReaderConfig) -> Result<Self, TLVError> {
+        Self::read(TLVReader::with_config(bytes, *config))
+    }
+
+    // Payloads stored back to back, each top level element is a document of its own
+    pub fn parse_all(bytes: &[u8]) -> Result<Vec<Self>, TLVError> {
+        Self::documents(bytes).collect()
+    }
+
+    pub fn documents(bytes: &[u8]) -> TLVDocuments {
+        Self::documents_with_config(bytes, &TLVReaderConfig::default())
+    }
+
+    pub fn documents_with_config(bytes: &[u8], config: &TLVReaderConfig) -> TLVDocuments {
+        TLVDocuments {
+            bytes: Bytes::copy_from_slice(bytes),
+            config: *config,
+            offset: 0,
+            index: 0,
+            done: false,
+        }
+    }
+
+    fn read(mut tlv_reader: TLVReader) -> Result<Self, TLVError> {
+        let config = *tlv_reader.config();
+        let mut elements = Vec::new();
+        loop {
+            let tag = match tlv_reader.read_tag() {
+                Err(TLVError::EndOfTLV) => break, // Empty payload
+                result => result?,
+            };
+            elements.push(TLVElement::new(tag, read_value(&mut tlv_reader)?));
+            match tlv_reader.next() {
+                Err(TLVError::EndOfTLV) => break,
+                Ok(()) if config.single_element => {
+                    return Err(TLVError::TrailingBytes {
+                        offset: tlv_reader.offset(),
+                    })
+                }
+                result => result?,
+            }
+        }
+        Ok(Self { elements })
+    }
+
+    // Value of the path converted to T, integers of any width fitting T are accepted. Errors are
+    // wrapped in AtPath: PathNotFound when no element matches, InvalidType when the value does
+    // not convert.
+    pub fn get<'a, T>(&'a self, path: &TLVPath) -> Result<T, TLVError>
+    where
+        T: TryFrom<&'a TLVValue, Error = TLVError>,
+    {
+        path::lookup(&self.elements, path)
+            .and_then(T::try_from)
+            .map_err(|e| TLVError::AtPath {
+                path: path.clone(),
+                error: Box::new(e),
+            })
+    }
+
+    // None when no element matches the path, other errors as get
+    pub fn get_opt<'a, T>(&'a self, path: &TLVPath) -> Result<Option<T>, TLVError>
+    where
+        T: TryFrom<&'a TLVValue, Error = TLVError>,
+    {
+        match self.get(path) {
+            Err(TLVError::AtPath { error, .. })
+                if matches!(*error, TLVError::PathNotFound { .. }) =>
+            {
+                Ok(None)
+            }
+            result => result.map(Some),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for element in &self.elements {
+            element.value.encode_tlv_into(&element.tag, &mut bytes);
+        }
+        bytes
+    }
+}
+
+// Documents of a stream in order, see TLVDocument::documents. A document cut short by the end of the
+// stream fails with NeedMoreBytes, other errors are wrapped in InDocument, offsets in errors are
+// those of the stream. Nothing follows an error.
+pub struct TLVDocuments {
+    bytes: Bytes,
+    config: TLVReaderConfig,
+    offset: usize,
+    index: usize,
+    done: bool,
+}
+
+impl Iterator for TLVDocuments {
+    type Item = Result<TLVDocument, TLVError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.offset == self.bytes.len() {
+            return None;
+        }
+        let (document, offset) = (self.index, self.offset);
+        let result = match reader::element_size(&self.bytes[offset..]) {
+            Ok(len) => {
+                self.offset += len;
+                TLVDocument::read(TLVReader::from_bytes_at(
+                    self.bytes.slice(..self.offset),
+                    offset,
+                    self.config,
+                ))
+            }
+            Err(e) if e.is_cut_short() => Err(TLVError::NeedMoreBytes { document, offset }),
+            Err(e) => Err(e),
+        };
+        self.index += 1;
+        self.done = result.is_err();
+        Some(result.map_err(|e| match e {
+            TLVError::NeedMoreBytes { .. } => e,
+            e => TLVError::InDocument {
+                document,
+                offset,
+                error: Box::new(e),
+            },
+        }))
+    }
+}
+
+// Whole payload with the default configuration, see TLVDocument::parse
+impl TryFrom<&[u8]> for TLVDocument {
+    type Error = TLVError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, TLVError> {
+        Self::parse(bytes)
+    }
+}
+
+// Value of the one element of the payload, its tag is dropped. Bytes after it fail with
+// TrailingBytes, an empty payload with EndOfTLV.
+impl TryFrom<&[u8]> for TLVValue {
+    type Error = TLVError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, TLVError> {
+        let config = TLVReaderConfig::default().single_element(true);
+        let mut document = TLVDocument::parse_with_config(bytes, &config)?;
+        match document.elements.pop() {
+            Some(element) => Ok(element.value),
+            None => Err(TLVError::EndOfTLV),
+        }
+    }
+}
+
+// Container entered by read_value, with its tag and the members read so far
+type OpenContainer = (ContainerType, TLVTag, Vec<(TLVTag, TLVValue)>);
+
+// Reads the element at the cursor with its whole subtree, the cursor is left on that element.
+// Containers are read with a stack on the heap rather than by recursion, so parsing at the maximum
+// depth needs no more thread stack than parsing a flat payload
+pub(crate) fn read_value(tlv_reader: &mut TLVReader) -> Result<TLVValue, TLVError> {
+    let mut containers: Vec<OpenContainer> = Vec::new();
+    let mut tag = TLVTag::Anonymous; // Of the element under the cursor, unused for the outermost
+    loop {
+        let (mut member_tag, mut value) = match tlv_reader.read_type()? {
+            TLVType::Container(container_type) => {
+                tlv_reader.enter_container()?;
+                containers.push((container_type, tag, Vec::new()));
+                match tlv_reader.read_tag() {
+                    Err(TLVError::EndOfContainer) => close_container(tlv_reader, &mut containers)?,
+                    result => {
+                        tag = result?;
+                        continue;
+                    }
+                }
+            }
+            TLVType::Primitive(primitive_length_type) => {
+                (tag, read_primitive(tlv_reader, primitive_length_type)?)
+            }
+        };
+        // Adds the value to its container, closing the containers it was the last member of
+        loop {
+            let Some((_, _, members)) = containers.last_mut() else {
+                return Ok(value);
+            };
+            members.push((member_tag, value));
+            match tlv_reader.next() {
+                Err(TLVError::EndOfContainer) => {
+                    (member_tag, value) = close_container(tlv_reader, &mut containers)?;
+                }
+                result => {
+                    result?;
+                    tag = tlv_reader.read_tag()?;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Exits the innermost container read, its tag and value are returned
+fn close_container(
+    tlv_reader: &mut TLVReader,
+    containers: &mut Vec<OpenContainer>,
+) -> Result<(TLVTag, TLVValue), TLVError> {
+    let (container_type, tag, members) = containers.pop().ok_or(TLVError::Internal {
+        context: "no container to close",
+    })?;
+    tlv_reader.exit_container()?;
+    let value = match container_type {
+        ContainerType::Structure => {
+            check_structure_members(&members, tlv_reader.config())?;
+            TLVValue::Structure(members)
+        }
+        // Members keep their order, tagged or not and tags repeated
+        ContainerType::List => TLVValue::List(members),
+        ContainerType::Array => TLVValue::Array(
+            members
+                .into_iter()
+                .map(|(tag, value)| match tag {
+                    TLVTag::Anonymous => Ok(value),
+                    _ => Err(TLVError::InvalidTag),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+    };
+    Ok((tag, value))
+}
+
+fn read_primitive(
+    tlv_reader: &TLVReader,
+    primitive_length_type: PrimitiveLengthType,
+) -> Result<TLVValue, TLVError> {
+    Ok(match primitive_length_type {
+        PrimitiveLengthType::Predetermined(predetermined) => match predetermined {
+            PredeterminedLenPrimitive::SignedInteger(width) => match width {
+                SignedInteger::Int8 => TLVValue::Int8(tlv_reader.read_i8()?),
+                SignedInteger::Int16 => TLVValue::Int16(tlv_reader.read_i16()?),
+                SignedInteger::Int32 => TLVValue::Int32(tlv_reader.read_i32()?),
+                SignedInteger::Int64 => TLVValue::Int64(tlv_reader.read_i64()?),
+            },
+            PredeterminedLenPrimitive::UnsignedInteger(width) => match width {
+                UnsignedInteger::UInt8 => TLVValue::UInt8(tlv_reader.read_u8()?),
+                UnsignedInteger::UInt16 => TLVValue::UInt16(tlv_reader.read_u16()?),
+                UnsignedInteger::UInt32 => TLVValue::UInt32(tlv_reader.read_u32()?),
+                UnsignedInteger::UInt64 => TLVValue::UInt64(tlv_reader.read_u64()?),
+            },
+            PredeterminedLenPrimitive::FloatingPointNumber(
+                FloatingPoint::FloatingPointNumber32,
+            ) => TLVValue::Float32(tlv_reader.read_f32()?),
+            PredeterminedLenPrimitive::FloatingPointNumber(
+                FloatingPoint::FloatingPointNumber64,
+            ) => TLVValue::Float64(tlv_reader.read_f64()?),
+            PredeterminedLenPrimitive::Boolean(value) => TLVValue::Bool(value),
+            PredeterminedLenPrimitive::Null => TLVValue::Null,
+        },
+        PrimitiveLengthType::Specified(SpecifiedLenPrimitive::UTF8String(_)) => {
+            TLVValue::Utf8String(tlv_reader.read_char_str()?)
+        }
+        PrimitiveLengthType::Specified(SpecifiedLenPrimitive::ByteString(_)) => {
+            TLVValue::ByteString(tlv_reader.read_byte_str()?)
+        }
+    })
+}
+
+// Array members are always anonymous, as the values hold no tags
+pub(crate) fn check_structure_members<T>(
+    members: &[(TLVTag, T)],
+    config: &TLVReaderConfig,
+) -> Result<(), TLVError> {
+    for (index, (tag, _)) in members.iter().enumerate() {
+        if config.enforce_tag_rules && *tag == TLVTag::Anonymous {
+            return Err(TLVError::InvalidTag);
+        }
+        if config.reject_duplicate_tags && members[..index].iter().any(|(other, _)| other == tag) {
+            return Err(TLVError::DuplicateMember(tag.clone()));
+        }
+        if config.require_canonical_order
+            && index > 0
+            && tag.canonical_cmp(&members[index - 1].0) == Ordering::Less
+        {
+            return Err(TLVError::MemberOutOfOrder(tag.clone()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::{well_known, CommonProfileLength};
+
+    #[test]
+    fn test_parse_document() {
+        // Structure {0 = 42U, 1 = [true, -3], 2 = List [[Anonymous = "a", 3 = Null]]}, 1.5
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x00, 0xfd, 0x18, 0x37, 0x02, 0x0c, 0x01,
+            0x61, 0x34, 0x03, 0x18, 0x18, 0x0a, 0x00, 0x00, 0xc0, 0x3f,
+        ];
+        let document = TLVDocument::parse(test_bytes).expect("Failed to parse document");
+        assert_eq!(
+            document.elements,
+            vec![
+                TLVElement::new(
+                    TLVTag::Anonymous,
+                    TLVValue::Structure(vec![
+                        (TLVTag::ContextSpecific(0), TLVValue::UInt8(42)),
+                        (
+                            TLVTag::ContextSpecific(1),
+                            TLVValue::Array(vec![TLVValue::Bool(true), TLVValue::Int8(-3)])
+                        ),
+                        (
+                            TLVTag::ContextSpecific(2),
+                            TLVValue::List(vec![
+                                (TLVTag::Anonymous, TLVValue::Utf8String(String::from("a"))),
+                                (TLVTag::ContextSpecific(3), TLVValue::Null)
+                            ])
+                        ),
+                    ])
+                ),
+                TLVElement::new(TLVTag::Anonymous, TLVValue::Float32(1.5)),
+            ]
+        );
+        assert_eq!(document.encode(), test_bytes);
+        assert_eq!(
+            document.elements[0].value.to_string(),
+            "{0 = 42U, 1 = [true, -3], 2 = [[Anonymous = \"a\", 3 = Null]]}"
+        );
+        assert_eq!(
+            TLVValue::ByteString(vec![0x01, 0x02]).to_string(),
+            "hex:0102"
+        );
+        assert_eq!(
+            TLVDocument::parse(&[]).expect("Failed to parse empty document"),
+            TLVDocument::default()
+        );
+    }
+
+    // Paths of every nested value, 0/2[1] is member 1 of the List at tag 2 of the Structure at tag 0
+    fn collect_paths(value: &TLVValue, path: &str, paths: &mut Vec<String>) {
+        for (index, (tag, member)) in value.into_iter().enumerate() {
+            let member_path = match tag {
+                TLVTag::Anonymous => alloc::format!("{}[{}]", path, index),
+                tag => alloc::format!("{}/{}", path, tag),
+            };
+            paths.push(member_path.clone());
+            collect_paths(member, &member_path, paths);
+        }
+    }
+
+    #[test]
+    fn test_iterate_members() {
+        // Structure {0 = 42U, 1 = [true, -3], 2 = List [[Anonymous = "a", 3 = Null]]}
+        let test_bytes = &[
+            0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x00, 0xfd, 0x18, 0x37, 0x02, 0x0c, 0x01,
+            0x61, 0x34, 0x03, 0x18, 0x18,
+        ];
+        let document = TLVDocument::parse(test_bytes).expect("Failed to parse document");
+        let root = &document.elements[0].value;
+        let mut paths = Vec::new();
+        collect_paths(root, "", &mut paths);
+        assert_eq!(
+            paths,
+            vec!["/0", "/1", "/1[0]", "/1[1]", "/2", "/2[0]", "/2/3"]
+        );
+
+        assert_eq!(root.len(), 3);
+        assert_eq!(root.into_iter().len(), 3);
+        let tags: Vec<&TLVTag> = root.iter_structure().map(|(tag, _)| tag).collect();
+        assert_eq!(
+            tags,
+            [
+                &TLVTag::ContextSpecific(0),
+                &TLVTag::ContextSpecific(1),
+                &TLVTag::ContextSpecific(2)
+            ]
+        );
+        let (_, array) = root.iter_structure().nth(1).expect("Failed to find member");
+        assert_eq!(array.iter_structure().count(), 0);
+        assert_eq!(array.into_iter().count(), 2);
+
+        // Primitives and empty containers have no members
+        for value in [
+            TLVValue::UInt8(42),
+            TLVValue::Null,
+            TLVValue::Array(Vec::new()),
+        ] {
+            assert!(value.is_empty());
+            assert_eq!(value.into_iter().next(), None);
+        }
+    }
+
+    #[test]
+    fn test_parse_document_malformed() {
+        // Array [0 = 1U]
+        assert_eq!(
+            TLVDocument::parse(&[0x16, 0x24, 0x00, 0x01, 0x18])
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        // Structure {0 = 1U} without its End of Container
+        assert_eq!(
+            TLVDocument::parse(&[0x15, 0x24, 0x00, 0x01])
+                .expect_err("Unterminated container is expected"),
+            TLVError::UnterminatedContainer { offset: 0 }
+        );
+        // Stray End of Container
+        assert_eq!(
+            TLVDocument::parse(&[0x04, 0x01, 0x18]).expect_err("End of container is expected"),
+            TLVError::EndOfContainer
+        );
+    }
+
+    #[test]
+    fn test_try_from_bytes() {
+        // Context tag 1, Structure {0 = 42U}
+        let test_bytes: &[u8] = &[0x35, 0x01, 0x24, 0x00, 0x2a, 0x18];
+        let value: TLVValue = test_bytes.try_into().expect("Failed to parse value");
+        assert_eq!(
+            value,
+            TLVValue::Structure(vec![(TLVTag::ContextSpecific(0), TLVValue::UInt8(42))])
+        );
+        let document = TLVDocument::try_from(test_bytes).expect("Failed to parse document");
+        assert_eq!(
+            document.elements,
+            vec![TLVElement::new(TLVTag::ContextSpecific(1), value)]
+        );
+
+        // Followed by Boolean true, a second document element
+        let test_bytes: &[u8] = &[0x35, 0x01, 0x24, 0x00, 0x2a, 0x18, 0x09];
+        assert_eq!(
+            TLVValue::try_from(test_bytes).expect_err("Trailing bytes is expected"),
+            TLVError::TrailingBytes { offset: 6 }
+        );
+        assert_eq!(
+            TLVDocument::try_from(test_bytes)
+                .expect("Failed to parse document")
+                .elements
+                .len(),
+            2
+        );
+
+        // Cut short in a value, then before the End of Container
+        let test_vectors: &[(&[u8], TLVError)] = &[
+            (
+                &[0x35, 0x01, 0x24, 0x00],
+                TLVError::TruncatedValue {
+                    expected: 1,
+                    available: 0,
+                },
+            ),
+            (
+                &[0x35, 0x01, 0x24, 0x00, 0x2a],
+                TLVError::UnterminatedContainer { offset: 0 },
+            ),
+        ];
+        for (test_bytes, error) in test_vectors {
+            assert_eq!(TLVValue::try_from(*test_bytes).as_ref(), Err(error));
+            assert_eq!(TLVDocument::try_from(*test_bytes).as_ref(), Err(error));
+        }
+        assert_eq!(TLVValue::try_from(&[][..]), Err(TLVError::EndOfTLV));
+        assert_eq!(TLVDocument::try_from(&[][..]), Ok(TLVDocument::default()));
+    }
+
+    #[test]
+    fn test_element_decode() {
+        // 1 = 42U, Boolean true
+        let test_bytes: &[u8] = &[0x24, 0x01, 0x2a, 0x09];
+        let (element, rest) = TLVElement::decode(test_bytes).expect("Failed to decode element");
+        assert_eq!(
+            element,
+            TLVElement::new(TLVTag::ContextSpecific(1), TLVValue::UInt8(42))
+        );
+        assert_eq!(element.to_string(), "1 = 42U");
+        assert_eq!(element.encode(), &test_bytes[..3]);
+        assert_eq!(rest, &[0x09]);
+
+        let (element, rest) = TLVElement::decode(rest).expect("Failed to decode element");
+        assert_eq!(element, (TLVTag::Anonymous, TLVValue::Bool(true)).into());
+        assert_eq!(element.to_string(), "true");
+        assert!(rest.is_empty());
+        assert_eq!(
+            TLVElement::decode(rest).expect_err("End of TLV is expected"),
+            TLVError::EndOfTLV
+        );
+
+        let document: TLVDocument = TLVDocument::parse(test_bytes)
+            .expect("Failed to parse document")
+            .into_iter()
+            .rev()
+            .collect();
+        let (tag, value) = document.elements[1].clone().into();
+        assert_eq!(tag, TLVTag::ContextSpecific(1));
+        assert_eq!(value, TLVValue::UInt8(42));
+        assert_eq!(document.encode(), &[0x09, 0x24, 0x01, 0x2a]);
+    }
+
+    #[test]
+    fn test_get() {
+        // Structure {0 = 300U as UInt32, 1 = -2, 2 = {0 = "Hi", 1 = hex:0102, 2 = [1.5, 2.5]},
+        // 3 = [[0 = true, CHIP::1 = Null]]}
+        let document = TLVDocument::builder()
+            .structure(TLVTag::Anonymous, |s| {
+                s.value(0, &300u32)
+                    .int(1, -2)
+                    .structure(2, |s| {
+                        s.string(0, "Hi")
+                            .bytes(1, &[0x01, 0x02])
+                            .array(2, |a| a.float(1.5).double(2.5))
+                    })
+                    .list(3, |l| l.bool(0, true).null(well_known::matter_core_tag(1)))
+            })
+            .build()
+            .expect("Failed to build document");
+        let root = TLVPath::root();
+        assert_eq!(document.get::<u16>(&root.clone().tag(0)), Ok(300));
+        assert_eq!(document.get::<u64>(&root.clone().tag(0)), Ok(300));
+        assert_eq!(document.get::<i8>(&root.clone().tag(1)), Ok(-2));
+        assert_eq!(document.get::<&str>(&root.clone().tag(2).tag(0)), Ok("Hi"));
+        assert_eq!(
+            document.get::<String>(&root.clone().tag(2).tag(0)),
+            Ok(String::from("Hi"))
+        );
+        assert_eq!(
+            document.get::<&[u8]>(&root.clone().tag(2).tag(1)),
+            Ok(&[0x01, 0x02][..])
+        );
+        assert_eq!(
+            document.get::<f64>(&root.clone().tag(2).tag(2).index(0)),
+            Ok(1.5)
+        );
+        assert_eq!(
+            document.get::<f64>(&root.clone().tag(2).tag(2).index(1)),
+            Ok(2.5)
+        );
+        assert_eq!(document.get::<bool>(&root.clone().tag(3).tag(0)), Ok(true));
+        assert_eq!(
+            document.get_opt::<bool>(&root.clone().tag(3).tag(0)),
+            Ok(Some(true))
+        );
+
+        // Wrong type, value out of the range of the type and missing element
+        let path = root.clone().tag(2).tag(0);
+        let error = document
+            .get::<u8>(&path)
+            .expect_err("Invalid type is expected");
+        assert_eq!(
+            error,
+            TLVError::AtPath {
+                path: path.clone(),
+                error: Box::new(TLVError::InvalidType)
+            }
+        );
+        assert_eq!(error.to_string(), "invalid element type at path 2/0");
+        assert_eq!(document.get_opt::<u8>(&path), Err(error));
+        for path in [root.clone().tag(0), root.clone().tag(1)] {
+            assert_eq!(
+                document.get::<u8>(&path),
+                Err(TLVError::AtPath {
+                    path: path.clone(),
+                    error: Box::new(TLVError::InvalidType)
+                })
+            );
+        }
+        assert_eq!(
+            document.get::<f32>(&root.clone().tag(2).tag(2).index(1)),
+            Err(TLVError::AtPath {
+                path: root.clone().tag(2).tag(2).index(1),
+                error: Box::new(TLVError::InvalidType)
+            })
+        );
+        let path = root.clone().tag(2).tag(7);
+        assert_eq!(
+            document.get::<u8>(&path),
+            Err(TLVError::AtPath {
+                path: path.clone(),
+                error: Box::new(TLVError::PathNotFound { step: 1 })
+            })
+        );
+        assert_eq!(document.get_opt::<u8>(&path), Ok(None));
+        assert_eq!(
+            TLVDocument::default().get_opt::<u8>(&root),
+            Err(TLVError::AtPath {
+                path: root,
+                error: Box::new(TLVError::EndOfTLV)
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_deep_nesting() {
+        // 128 nested arrays around 1U
+        let depth = 128;
+        let mut test_bytes = vec![0x16; depth];
+        test_bytes.extend([0x04, 0x01]);
+        test_bytes.extend(vec![0x18; depth]);
+
+        // Parsed on a thread with a small stack, the trees are compared once back on this thread
+        let config = TLVReaderConfig::default().max_depth(depth);
+        let tlv_reader = TLVReader::with_config(&test_bytes, config);
+        let parsed = std::thread::scope(|scope| {
+            std::thread::Builder::new()
+                .stack_size(32 * 1024)
+                .spawn_scoped(scope, || {
+                    let document = TLVDocument::parse_with_config(&test_bytes, &config)
+                        .expect("Failed to parse");
+                    let too_deep =
+                        TLVDocument::parse_with_config(&test_bytes, &config.max_depth(depth - 1))
+                            .expect_err("Depth limit exceeded is expected");
+                    let value_ref = tlv_reader.read_value_ref().expect("Failed to read value");
+                    let too_deep_ref =
+                        TLVReader::with_config(&test_bytes, config.max_depth(depth - 1))
+                            .read_value_ref()
+                            .expect_err("Depth limit exceeded is expected");
+                    (document, too_deep, value_ref, too_deep_ref)
+                })
+                .expect("Failed to spawn thread")
+                .join()
+                .expect("Failed to parse on a small stack")
+        });
+
+        let mut expected = TLVValue::UInt8(1);
+        for _ in 0..depth {
+            expected = TLVValue::Array(vec![expected]);
+        }
+        assert_eq!(parsed.2.to_owned(), expected);
+        assert_eq!(parsed.3, TLVError::DepthLimitExceeded(depth - 1));
+        assert_eq!(
+            parsed.0,
+            TLVDocument {
+                elements: vec![TLVElement::new(TLVTag::Anonymous, expected)]
+            }
+        );
+        assert_eq!(parsed.1, TLVError::DepthLimitExceeded(depth - 1));
+        assert_eq!(parsed.0.encode(), test_bytes);
+    }
+
+    #[test]
+    fn test_parse_with_config() {
+        let parse =
+            |bytes: &[u8], config: TLVReaderConfig| TLVDocument::parse_with_config(bytes, &config);
+
+        // [[[]]]
+        let test_bytes = &[0x16, 0x16, 0x16, 0x18, 0x18, 0x18];
+        parse(test_bytes, TLVReaderConfig::default().max_depth(3)).expect("Failed to parse");
+        assert_eq!(
+            parse(test_bytes, TLVReaderConfig::default().max_depth(2))
+                .expect_err("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(2)
+        );
+
+        // Ten Boolean true, one control byte each
+        let test_bytes = &[0x09; 10];
+        parse(test_bytes, TLVReaderConfig::default().max_elements(10)).expect("Failed to parse");
+        assert_eq!(
+            parse(test_bytes, TLVReaderConfig::default().max_elements(9))
+                .expect_err("Too many elements is expected"),
+            TLVError::TooManyElements { limit: 9 }
+        );
+
+        // UTF-8 String, 1-octet length, "Hello!"
+        let test_bytes = &[0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21];
+        assert_eq!(
+            parse(test_bytes, TLVReaderConfig::default().max_element_len(5))
+                .expect_err("Element too large is expected"),
+            TLVError::ElementTooLarge {
+                declared: 6,
+                limit: 5
+            }
+        );
+
+        // Structure {0 = 1U, 0 = 2U}
+        let test_bytes = &[0x15, 0x24, 0x00, 0x01, 0x24, 0x00, 0x02, 0x18];
+        parse(test_bytes, TLVReaderConfig::default()).expect("Failed to parse");
+        assert_eq!(
+            parse(
+                test_bytes,
+                TLVReaderConfig::default().reject_duplicate_tags(true)
+            )
+            .expect_err("Duplicate member is expected"),
+            TLVError::DuplicateMember(TLVTag::ContextSpecific(0))
+        );
+
+        // Structure {Anonymous = 1U}
+        let test_bytes = &[0x15, 0x04, 0x01, 0x18];
+        assert_eq!(
+            parse(test_bytes, TLVReaderConfig::default()).expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        assert_eq!(
+            parse(
+                test_bytes,
+                TLVReaderConfig::default().enforce_tag_rules(false)
+            )
+            .expect("Failed to parse")
+            .elements,
+            vec![TLVElement::new(
+                TLVTag::Anonymous,
+                TLVValue::Structure(vec![(TLVTag::Anonymous, TLVValue::UInt8(1))])
+            )]
+        );
+
+        // UTF-8 String, 1-octet length, "a" followed by the invalid UTF-8 byte ff
+        let test_bytes = &[0x0c, 0x02, 0x61, 0xff];
+        assert_eq!(
+            parse(test_bytes, TLVReaderConfig::default()).expect_err("Parse error is expected"),
+            TLVError::ParseError
+        );
+        assert_eq!(
+            parse(test_bytes, TLVReaderConfig::default().check_utf8(false))
+                .expect("Failed to parse")
+                .elements,
+            vec![TLVElement::new(
+                TLVTag::Anonymous,
+                TLVValue::Utf8String(String::from("a\u{fffd}"))
+            )]
+        );
+
+        // Structure {1 = 1U, 0 = 2U}
+        let test_bytes = &[0x15, 0x24, 0x01, 0x01, 0x24, 0x00, 0x02, 0x18];
+        parse(test_bytes, TLVReaderConfig::default()).expect("Failed to parse");
+        assert_eq!(
+            parse(
+                test_bytes,
+                TLVReaderConfig::default().require_canonical_order(true)
+            )
+            .expect_err("Member out of order is expected"),
+            TLVError::MemberOutOfOrder(TLVTag::ContextSpecific(0))
+        );
+
+        // 42U + true
+        let test_bytes = &[0x04, 0x2a, 0x09];
+        parse(test_bytes, TLVReaderConfig::default()).expect("Failed to parse");
+        assert_eq!(
+            parse(test_bytes, TLVReaderConfig::default().single_element(true))
+                .expect_err("Trailing bytes are expected"),
+            TLVError::TrailingBytes { offset: 2 }
+        );
+    }
+
+    #[test]
+    fn test_parse_list_members() {
+        // List [[0 = 1U, 0 = 2U, Anonymous = 3U, 1 = List [[true, 2 = Null, 2 = Null]], 0 = 4U]]
+        let test_bytes = &[
+            0x17, 0x24, 0x00, 0x01, 0x24, 0x00, 0x02, 0x04, 0x03, 0x37, 0x01, 0x09, 0x34, 0x02,
+            0x34, 0x02, 0x18, 0x24, 0x00, 0x04, 0x18,
+        ];
+        let config = TLVReaderConfig::default()
+            .reject_duplicate_tags(true)
+            .require_canonical_order(true);
+        let document =
+            TLVDocument::parse_with_config(test_bytes, &config).expect("Failed to parse");
+        let members = vec![
+            (TLVTag::ContextSpecific(0), TLVValue::UInt8(1)),
+            (TLVTag::ContextSpecific(0), TLVValue::UInt8(2)),
+            (TLVTag::Anonymous, TLVValue::UInt8(3)),
+            (
+                TLVTag::ContextSpecific(1),
+                TLVValue::List(vec![
+                    (TLVTag::Anonymous, TLVValue::Bool(true)),
+                    (TLVTag::ContextSpecific(2), TLVValue::Null),
+                    (TLVTag::ContextSpecific(2), TLVValue::Null),
+                ]),
+            ),
+            (TLVTag::ContextSpecific(0), TLVValue::UInt8(4)),
+        ];
+        assert_eq!(document.elements[0].value, TLVValue::List(members));
+        assert_eq!(document.encode(), test_bytes);
+
+        // Same members in a Structure
+        let mut test_bytes = test_bytes.to_vec();
+        test_bytes[0] = ContainerType::Structure as u8;
+        assert_eq!(
+            TLVDocument::parse_with_config(&test_bytes, &config)
+                .expect_err("Duplicate member is expected"),
+            TLVError::DuplicateMember(TLVTag::ContextSpecific(0))
+        );
+        assert_eq!(
+            TLVDocument::parse_with_config(&test_bytes, &config.reject_duplicate_tags(false))
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+    }
+
+    #[test]
+    fn test_parse_all() {
+        // Structure {0 = 42U}, Unsigned Integer 7, Array [true, "Hi"]
+        let documents: &[&[u8]] = &[
+            &[0x15, 0x24, 0x00, 0x2a, 0x18],
+            &[0x04, 0x07],
+            &[0x16, 0x09, 0x0c, 0x02, 0x48, 0x69, 0x18],
+        ];
+        let stream = documents.concat();
+        let expected: Vec<_> = documents
+            .iter()
+            .map(|bytes| TLVDocument::parse(bytes).expect("Failed to parse document"))
+            .collect();
+        assert_eq!(TLVDocument::parse_all(&stream), Ok(expected.clone()));
+        assert_eq!(TLVDocument::parse_all(&[]), Ok(vec![]));
+
+        // Every cut inside the last document is distinguishable from corruption
+        for len in 8..stream.len() {
+            assert_eq!(
+                TLVDocument::parse_all(&stream[..len]),
+                Err(TLVError::NeedMoreBytes {
+                    document: 2,
+                    offset: 7
+                })
+            );
+        }
+        let mut truncated = stream.clone();
+        truncated.extend([0x15, 0x24, 0x00]);
+        let mut documents = TLVDocument::documents(&truncated);
+        for document in &expected {
+            assert_eq!(documents.next(), Some(Ok(document.clone())));
+        }
+        assert_eq!(
+            documents.next(),
+            Some(Err(TLVError::NeedMoreBytes {
+                document: 3,
+                offset: 14
+            }))
+        );
+        assert_eq!(documents.next(), None);
+
+        // Errors in a complete document give offsets in the stream, Unsigned Integer 7 on 2 octets
+        let mut corrupted = stream.clone();
+        corrupted.splice(5..7, [0x05, 0x07, 0x00]);
+        let config = TLVReaderConfig::default().require_minimal_encoding(true);
+        let mut documents = TLVDocument::documents_with_config(&corrupted, &config);
+        assert_eq!(documents.next(), Some(Ok(expected[0].clone())));
+        assert_eq!(
+            documents.next(),
+            Some(Err(TLVError::InDocument {
+                document: 1,
+                offset: 5,
+                error: Box::new(TLVError::NonMinimalEncoding { offset: 5 })
+            }))
+        );
+        assert_eq!(documents.next(), None);
+        corrupted[5] = 0x18;
+        assert_eq!(
+            TLVDocument::parse_all(&corrupted),
+            Err(TLVError::InDocument {
+                document: 1,
+                offset: 5,
+                error: Box::new(TLVError::EndOfContainer)
+            })
+        );
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        let key: Vec<u8> = (0..32).collect();
+        assert!(TLVValue::ByteString(key.clone()).ct_eq(&key));
+        assert!(TLVValue::ByteString(vec![]).ct_eq(&[]));
+        let mut other = key.clone();
+        other[31] ^= 0x01;
+        assert!(!TLVValue::ByteString(key.clone()).ct_eq(&other));
+        other[31] ^= 0x01;
+        other[0] ^= 0x80;
+        assert!(!TLVValue::ByteString(key.clone()).ct_eq(&other));
+        assert!(!TLVValue::ByteString(key.clone()).ct_eq(&key[..31]));
+        assert!(!TLVValue::ByteString(vec![]).ct_eq(&[0x00]));
+        // Other types holding the same octets
+        assert!(!TLVValue::Utf8String(String::from("ab")).ct_eq(b"ab"));
+        assert!(!TLVValue::UInt8(1).ct_eq(&[0x01]));
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+    }
+
+    #[test]
+    fn test_semantic_eq() {
+        assert!(TLVValue::UInt8(1).semantic_eq(&TLVValue::UInt64(1)));
+        assert!(!TLVValue::UInt8(1).semantic_eq(&TLVValue::Int8(1)));
+        assert!(!TLVValue::Int16(-1).semantic_eq(&TLVValue::Int16(1)));
+        assert!(TLVValue::Float32(1.5).semantic_eq(&TLVValue::Float64(1.5)));
+        assert!(TLVValue::Float64(f64::NAN).semantic_eq(&TLVValue::Float32(f32::NAN)));
+        assert!(!TLVValue::Float64(0.1).semantic_eq(&TLVValue::Float32(0.1)));
+        assert!(TLVValue::Structure(vec![(
+            TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number: 1 }),
+            TLVValue::Array(vec![TLVValue::Int8(-1)])
+        )])
+        .semantic_eq(&TLVValue::Structure(vec![(
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 1 }),
+            TLVValue::Array(vec![TLVValue::Int64(-1)])
+        )])));
+        assert!(
+            !TLVValue::List(vec![(TLVTag::ContextSpecific(1), TLVValue::Null)]).semantic_eq(
+                &TLVValue::Structure(vec![(TLVTag::ContextSpecific(1), TLVValue::Null)])
+            )
+        );
+    }
+
+    #[test]
+    fn test_eq_numbers() {
+        assert_eq!(TLVValue::UInt8(42), 42u32);
+        assert_eq!(TLVValue::UInt64(42), 42u8);
+        assert_eq!(TLVValue::Int16(-3), -3i64);
+        assert_eq!(-3i8, TLVValue::Int64(-3));
+        assert_eq!(TLVValue::UInt16(300), 300i64);
+        assert_eq!(TLVValue::UInt8(42), 42);
+        assert_eq!(42, TLVValue::UInt8(42));
+        assert_eq!(TLVValue::UInt64(5), 5i64);
+        assert_eq!(TLVValue::Int8(5), 5u64);
+        assert_ne!(TLVValue::UInt64(u64::MAX), -1i64);
+        assert_ne!(TLVValue::Int8(-1), u64::MAX);
+        assert_ne!(TLVValue::UInt16(300), 44u8);
+        assert_ne!(TLVValue::Float32(1.0), 1u8);
+        assert_eq!(TLVValue::Bool(true), true);
+        assert_ne!(false, TLVValue::UInt8(0));
+        assert_eq!(TLVValue::Utf8String(String::from("on")), "on");
+        assert_eq!("on", TLVValue::Utf8String(String::from("on")));
+        assert_ne!(TLVValue::ByteString(b"on".to_vec()), "on");
+    }
+
+    #[test]
+    fn test_merge_structure() {
+        let structure = |members: Vec<(u8, TLVValue)>| {
+            TLVValue::Structure(
+                members
+                    .into_iter()
+                    .map(|(tag, value)| (TLVTag::ContextSpecific(tag), value))
+                    .collect(),
+            )
+        };
+        // {0 = 1U, 1 = {0 = true, 1 = "on"}, 2 = [1U, 2U]}
+        let state = structure(vec![
+            (0, TLVValue::UInt8(1)),
+            (
+                1,
+                structure(vec![
+                    (0, TLVValue::Bool(true)),
+                    (1, TLVValue::Utf8String(String::from("on"))),
+                ]),
+            ),
+            (
+                2,
+                TLVValue::Array(vec![TLVValue::UInt8(1), TLVValue::UInt8(2)]),
+            ),
+        ]);
+        // {1 = {1 = "off", 2 = 5U}, 2 = [3U], 3 = Null}
+        let delta = structure(vec![
+            (
+                1,
+                structure(vec![
+                    (1, TLVValue::Utf8String(String::from("off"))),
+                    (2, TLVValue::UInt8(5)),
+                ]),
+            ),
+            (2, TLVValue::Array(vec![TLVValue::UInt8(3)])),
+            (3, TLVValue::Null),
+        ]);
+
+        let mut merged = state.clone();
+        merged
+            .merge_structure(&delta, MergeStrategy::OtherWins)
+            .expect("Failed to merge structures");
+        assert_eq!(
+            merged,
+            structure(vec![
+                (0, TLVValue::UInt8(1)),
+                (
+                    1,
+                    structure(vec![
+                        (0, TLVValue::Bool(true)),
+                        (1, TLVValue::Utf8String(String::from("off"))),
+                        (2, TLVValue::UInt8(5)),
+                    ]),
+                ),
+                (2, TLVValue::Array(vec![TLVValue::UInt8(3)])),
+                (3, TLVValue::Null),
+            ])
+        );
+
+        let mut merged = state.clone();
+        merged
+            .merge_structure(&delta, MergeStrategy::SelfWins)
+            .expect("Failed to merge structures");
+        assert_eq!(
+            merged,
+            structure(vec![
+                (0, TLVValue::UInt8(1)),
+                (
+                    1,
+                    structure(vec![
+                        (0, TLVValue::Bool(true)),
+                        (1, TLVValue::Utf8String(String::from("on"))),
+                        (2, TLVValue::UInt8(5)),
+                    ]),
+                ),
+                (
+                    2,
+                    TLVValue::Array(vec![TLVValue::UInt8(1), TLVValue::UInt8(2)])
+                ),
+                (3, TLVValue::Null),
+            ])
+        );
+
+        // Disjoint members and members of equal value do not conflict
+        let mut merged = state.clone();
+        merged
+            .merge_structure(
+                &structure(vec![(0, TLVValue::UInt64(1)), (4, TLVValue::Bool(false))]),
+                MergeStrategy::ErrorOnConflict,
+            )
+            .expect("Failed to merge structures");
+        assert_eq!(merged.len(), 4);
+        let mut merged = state.clone();
+        assert_eq!(
+            merged
+                .merge_structure(&delta, MergeStrategy::ErrorOnConflict)
+                .expect_err("Merge conflict is expected"),
+            TLVError::MergeConflict(TLVTag::ContextSpecific(1))
+        );
+        assert_eq!(merged, state);
+        assert_eq!(
+            merged
+                .merge_structure(&TLVValue::Null, MergeStrategy::OtherWins)
+                .expect_err("Invalid type is expected"),
+            TLVError::InvalidType
+        );
+    }
+
+    #[test]
+    fn test_read_value_ref() {
+        // Structure {0 = "Hi", 1 = [h'0102', -1], 2 = List [[Anonymous = Null]]}
+        let test_bytes = &[
+            0x15, 0x2c, 0x00, 0x02, 0x48, 0x69, 0x36, 0x01, 0x10, 0x02, 0x01, 0x02, 0x00, 0xff,
+            0x18, 0x37, 0x02, 0x14, 0x18, 0x18,
+        ];
+        let tlv_reader = TLVReader::new(test_bytes);
+        let value = tlv_reader.read_value_ref().expect("Failed to read value");
+        assert_eq!(
+            value,
+            TLVValueRef::Structure(vec![
+                (TLVTag::ContextSpecific(0), TLVValueRef::Utf8String("Hi")),
+                (
+                    TLVTag::ContextSpecific(1),
+                    TLVValueRef::Array(vec![
+                        TLVValueRef::ByteString(&[0x01, 0x02]),
+                        TLVValueRef::Int8(-1)
+                    ])
+                ),
+                (
+                    TLVTag::ContextSpecific(2),
+                    TLVValueRef::List(vec![(TLVTag::Anonymous, TLVValueRef::Null)])
+                ),
+            ])
+        );
+        assert_eq!(
+            value.to_owned(),
+            tlv_reader.read_value().expect("Failed to read value")
+        );
+
+        // Array [0 = 1U]
+        assert_eq!(
+            TLVReader::new(&[0x16, 0x24, 0x00, 0x01, 0x18])
+                .read_value_ref()
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        // Structure {0 = 1U} without its End of Container
+        assert_eq!(
+            TLVReader::new(&[0x15, 0x24, 0x00, 0x01])
+                .read_value_ref()
+                .expect_err("Unterminated container is expected"),
+            TLVError::UnterminatedContainer { offset: 0 }
+        );
+    }
+
+    #[test]
+    fn test_read_value() {
+        // Structure {0 = [1U], 1 = "Hi"}
+        let test_bytes = &[
+            0x15, 0x36, 0x00, 0x04, 0x01, 0x18, 0x2c, 0x01, 0x02, 0x48, 0x69, 0x18,
+        ];
+        let mut tlv_reader = TLVReader::new(test_bytes);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(
+            tlv_reader.read_value().expect("Failed to read value"),
+            TLVValue::Array(vec![TLVValue::UInt8(1)])
+        );
+        assert_eq!(tlv_reader.offset(), 1);
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(
+            tlv_reader.read_value().expect("Failed to read value"),
+            TLVValue::Utf8String(String::from("Hi"))
+        );
+        assert_eq!(
+            tlv_reader.next().expect_err("Container End is expected"),
+            TLVError::EndOfContainer
+        );
+    }
+}
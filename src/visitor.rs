@@ -0,0 +1,305 @@
+// Walks a payload calling back a visitor for each element, without building a tree. Strings are
+// borrowed from the walked buffer, returning Break from a callback stops the walk.
+
+use crate::errors::TLVError;
+use crate::reader::{TLVReader, TLVReaderConfig};
+use crate::tags::TLVTag;
+use crate::types::{ContainerType, TLVType};
+use crate::value::TLVValueRef;
+use core::ops::ControlFlow;
+
+pub trait TLVVisitor {
+    fn primitive(&mut self, tag: &TLVTag, value: TLVValueRef<'_>) -> ControlFlow<()>;
+
+    fn enter_container(
+        &mut self,
+        _tag: &TLVTag,
+        _container_type: ContainerType,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn exit_container(&mut self, _container_type: ContainerType) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    // Encoded element visited last, called after primitive and after exit_container, where it
+    // spans the whole container
+    fn element_bytes(&mut self, _element: &[u8]) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+pub fn walk<V: TLVVisitor + ?Sized>(
+    bytes: &[u8],
+    visitor: &mut V,
+) -> Result<ControlFlow<()>, TLVError> {
+    walk_with_config(bytes, &TLVReaderConfig::default(), visitor)
+}
+
+pub fn walk_with_max_depth<V: TLVVisitor + ?Sized>(
+    bytes: &[u8],
+    max_depth: usize,
+    visitor: &mut V,
+) -> Result<ControlFlow<()>, TLVError> {
+    walk_with_config(
+        bytes,
+        &TLVReaderConfig::default().max_depth(max_depth),
+        visitor,
+    )
+}
+
+// Containers nested deeper than max_depth fail with DepthLimitExceeded before being entered, the
+// walk fails with TooManyElements once past max_elements
+pub fn walk_with_config<V: TLVVisitor + ?Sized>(
+    bytes: &[u8],
+    config: &TLVReaderConfig,
+    visitor: &mut V,
+) -> Result<ControlFlow<()>, TLVError> {
+    if bytes.is_empty() {
+        return Ok(ControlFlow::Continue(()));
+    }
+    let max_depth = config.max_depth;
+    let mut tlv_reader = TLVReader::with_config(bytes, *config);
+    loop {
+        let mut at_end = match tlv_reader.read_tag() {
+            Ok(tag) => match tlv_reader.read_type()? {
+                TLVType::Container(container_type) => {
+                    if tlv_reader.container_depth() == max_depth {
+                        return Err(TLVError::DepthLimitExceeded(max_depth));
+                    }
+                    if visitor.enter_container(&tag, container_type).is_break() {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                    tlv_reader.enter_container()?;
+                    continue; // Cursor on the first member or at the end of an empty container
+                }
+                TLVType::Primitive(_) => {
+                    if visitor
+                        .primitive(&tag, tlv_reader.read_value_ref()?)
+                        .is_break()
+                    {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                    if visitor
+                        .element_bytes(current_element(bytes, &tlv_reader)?)
+                        .is_break()
+                    {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                    false
+                }
+            },
+            Err(TLVError::EndOfContainer) => true,
+            Err(e) => return Err(e),
+        };
+        // Leaves every container ending here, then moves to the following sibling
+        loop {
+            if at_end {
+                let container_type = tlv_reader
+                    .container_type()
+                    .ok_or(TLVError::EndOfContainer)?;
+                tlv_reader.exit_container()?;
+                if visitor.exit_container(container_type).is_break() {
+                    return Ok(ControlFlow::Break(()));
+                }
+                if visitor
+                    .element_bytes(current_element(bytes, &tlv_reader)?)
+                    .is_break()
+                {
+                    return Ok(ControlFlow::Break(()));
+                }
+            }
+            match tlv_reader.next() {
+                Ok(()) => break,
+                Err(TLVError::EndOfContainer) => at_end = true,
+                Err(TLVError::EndOfTLV) => return Ok(ControlFlow::Continue(())),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn current_element<'a>(bytes: &'a [u8], tlv_reader: &TLVReader) -> Result<&'a [u8], TLVError> {
+    let start = tlv_reader.offset();
+    Ok(&bytes[start..start + tlv_reader.current_element_len()?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    #[derive(Default)]
+    struct CountingVisitor {
+        primitives: usize,
+        containers: usize,
+        depth: usize,
+        max_depth: usize,
+        stop_after: Option<usize>, // Primitives
+    }
+
+    impl TLVVisitor for CountingVisitor {
+        fn primitive(&mut self, _tag: &TLVTag, _value: TLVValueRef<'_>) -> ControlFlow<()> {
+            self.primitives += 1;
+            match self.stop_after {
+                Some(count) if self.primitives == count => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+
+        fn enter_container(
+            &mut self,
+            _tag: &TLVTag,
+            _container_type: ContainerType,
+        ) -> ControlFlow<()> {
+            self.containers += 1;
+            self.depth += 1;
+            self.max_depth = self.max_depth.max(self.depth);
+            ControlFlow::Continue(())
+        }
+
+        fn exit_container(&mut self, _container_type: ContainerType) -> ControlFlow<()> {
+            self.depth -= 1;
+            ControlFlow::Continue(())
+        }
+    }
+
+    // Keeps the strings of members with the wanted context tag
+    struct FilteringVisitor {
+        tag: TLVTag,
+        strings: Vec<String>,
+    }
+
+    impl TLVVisitor for FilteringVisitor {
+        fn primitive(&mut self, tag: &TLVTag, value: TLVValueRef<'_>) -> ControlFlow<()> {
+            if let (true, TLVValueRef::Utf8String(string)) = (*tag == self.tag, value) {
+                self.strings.push(String::from(string));
+            }
+            ControlFlow::Continue(())
+        }
+    }
+
+    // Structure {0 = 42U, 1 = [true, -3], 2 = List [[Anonymous = "a", 3 = Null]]}, 1.5
+    const TEST_BYTES: &[u8] = &[
+        0x15, 0x24, 0x00, 0x2a, 0x36, 0x01, 0x09, 0x00, 0xfd, 0x18, 0x37, 0x02, 0x0c, 0x01, 0x61,
+        0x34, 0x03, 0x18, 0x18, 0x0a, 0x00, 0x00, 0xc0, 0x3f,
+    ];
+
+    #[test]
+    fn test_counting_visitor() {
+        let mut visitor = CountingVisitor::default();
+        assert_eq!(
+            walk(TEST_BYTES, &mut visitor).expect("Failed to walk"),
+            ControlFlow::Continue(())
+        );
+        assert_eq!(visitor.primitives, 6);
+        assert_eq!(visitor.containers, 3);
+        assert_eq!(visitor.depth, 0);
+        assert_eq!(visitor.max_depth, 2);
+
+        // Structure {0 = [], 1 = Null, 2 = []}
+        let mut visitor = CountingVisitor::default();
+        assert!(walk(
+            &[0x15, 0x36, 0x00, 0x18, 0x34, 0x01, 0x36, 0x02, 0x18, 0x18],
+            &mut visitor,
+        )
+        .expect("Failed to walk")
+        .is_continue());
+        assert_eq!((visitor.primitives, visitor.containers), (1, 3));
+        assert_eq!(visitor.depth, 0);
+
+        let mut visitor = CountingVisitor::default();
+        assert!(walk(&[], &mut visitor)
+            .expect("Failed to walk empty payload")
+            .is_continue());
+        assert_eq!((visitor.primitives, visitor.containers), (0, 0));
+    }
+
+    #[test]
+    fn test_filtering_visitor() {
+        // Structure {1 = "a", 2 = {1 = "b", 3 = "c"}, 1 = 1U}
+        let test_bytes = &[
+            0x15, 0x2c, 0x01, 0x01, 0x61, 0x35, 0x02, 0x2c, 0x01, 0x01, 0x62, 0x2c, 0x03, 0x01,
+            0x63, 0x18, 0x24, 0x01, 0x01, 0x18,
+        ];
+        let mut visitor = FilteringVisitor {
+            tag: TLVTag::ContextSpecific(1),
+            strings: Vec::new(),
+        };
+        assert!(walk(test_bytes, &mut visitor)
+            .expect("Failed to walk")
+            .is_continue());
+        assert_eq!(visitor.strings, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_walk_early_abort() {
+        let mut visitor = CountingVisitor {
+            stop_after: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            walk(TEST_BYTES, &mut visitor).expect("Failed to walk"),
+            ControlFlow::Break(())
+        );
+        assert_eq!(visitor.primitives, 2);
+        assert_eq!(visitor.depth, 2);
+    }
+
+    #[test]
+    fn test_walk_depth_limit() {
+        let mut visitor = CountingVisitor::default();
+        assert_eq!(
+            walk_with_max_depth(TEST_BYTES, 1, &mut visitor)
+                .expect_err("Depth limit exceeded is expected"),
+            TLVError::DepthLimitExceeded(1)
+        );
+        assert_eq!(visitor.containers, 1);
+        assert!(
+            walk_with_max_depth(TEST_BYTES, 2, &mut CountingVisitor::default())
+                .expect("Failed to walk")
+                .is_continue()
+        );
+    }
+
+    #[test]
+    fn test_walk_max_elements() {
+        // 12 control bytes, End of Container markers included
+        let config = TLVReaderConfig::default().max_elements(12);
+        let mut visitor = CountingVisitor::default();
+        assert!(walk_with_config(TEST_BYTES, &config, &mut visitor)
+            .expect("Failed to walk")
+            .is_continue());
+        assert_eq!(visitor.primitives + visitor.containers * 2, 12);
+
+        let config = TLVReaderConfig::default().max_elements(11);
+        let mut visitor = CountingVisitor::default();
+        assert_eq!(
+            walk_with_config(TEST_BYTES, &config, &mut visitor)
+                .expect_err("Too many elements is expected"),
+            TLVError::TooManyElements { limit: 11 }
+        );
+        assert_eq!(visitor.primitives, 5);
+    }
+
+    #[test]
+    fn test_walk_malformed() {
+        // Structure {0 = 42, 1 = -17} without its End of Container
+        assert_eq!(
+            walk(
+                &[0x15, 0x20, 0x00, 0x2a, 0x20, 0x01, 0xef],
+                &mut CountingVisitor::default()
+            )
+            .expect_err("Unterminated container is expected"),
+            TLVError::UnterminatedContainer { offset: 0 }
+        );
+        // Stray End of Container
+        assert_eq!(
+            walk(&[0x04, 0x01, 0x18], &mut CountingVisitor::default())
+                .expect_err("End of container is expected"),
+            TLVError::EndOfContainer
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Low level parsers of the Matter TLV wire format, for protocol layers working on the bytes
+//! themselves. Each returns the bytes following what it parsed. Input too short for a number or a
+//! length field fails with `TruncatedValue`, invalid UTF-8 with `ParseError`, nothing is logged.
+//!
+//! ```
+//! use tlv::errors::TLVError;
+//! use tlv::tags::ControlByte;
+//! use tlv::types::TLVFieldSize;
+//! use tlv::wire;
+//!
+//! // Context tag 1, UTF-8 String with a 1-octet length, "Hi"
+//! let bytes = [0x2c, 0x01, 0x02, 0x48, 0x69];
+//! let (rest, control_byte) = wire::parse_control_byte(&bytes)?;
+//! assert_eq!(control_byte, ControlByte { tag_control: 0x01, element_type: 0x0c });
+//! let (rest, tag_number) = wire::parse_u8(rest)?;
+//! assert_eq!(tag_number, 1);
+//! let (rest, len) = wire::parse_length_field(rest, TLVFieldSize::OneOctet)?;
+//! assert_eq!(wire::parse_str(&rest[..len])?, "Hi");
+//!
+//! // Integers and floats are little-endian
+//! assert_eq!(wire::parse_u16(&[0x01, 0x02, 0xff])?, (&[0xff][..], 0x0201));
+//! assert_eq!(wire::parse_i32(&[0xfe, 0xff, 0xff, 0xff])?.1, -2);
+//! assert_eq!(wire::parse_f32(&[0x00, 0x00, 0xc0, 0x3f])?.1, 1.5);
+//! assert_eq!(
+//!     wire::parse_u64(&[0x01, 0x02]),
+//!     Err(TLVError::TruncatedValue { expected: 8, available: 2 })
+//! );
+//! # Ok::<(), TLVError>(())
+//! ```
+
+use crate::errors::TLVError;
+use crate::tags::ControlByte;
+use crate::types::TLVFieldSize;
+use core::mem::size_of;
+use core::str::from_utf8;
+use nom::error::Error;
+use nom::number::complete::{
+    le_f32, le_f64, le_i16, le_i32, le_i64, le_i8, le_u16, le_u32, le_u64, le_u8,
+};
+
+// Fixed width values are checked first, so short input is not reported as malformed
+fn check_width<T>(bytes: &[u8]) -> Result<(), TLVError> {
+    let expected = size_of::<T>();
+    if bytes.len() < expected {
+        return Err(TLVError::TruncatedValue {
+            expected,
+            available: bytes.len(),
+        });
+    }
+    Ok(())
+}
+
+pub fn parse_u8(bytes: &[u8]) -> Result<(&[u8], u8), TLVError> {
+    check_width::<u8>(bytes)?;
+    le_u8::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_u16(bytes: &[u8]) -> Result<(&[u8], u16), TLVError> {
+    check_width::<u16>(bytes)?;
+    le_u16::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_u32(bytes: &[u8]) -> Result<(&[u8], u32), TLVError> {
+    check_width::<u32>(bytes)?;
+    le_u32::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_u64(bytes: &[u8]) -> Result<(&[u8], u64), TLVError> {
+    check_width::<u64>(bytes)?;
+    le_u64::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_i8(bytes: &[u8]) -> Result<(&[u8], i8), TLVError> {
+    check_width::<i8>(bytes)?;
+    le_i8::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_i16(bytes: &[u8]) -> Result<(&[u8], i16), TLVError> {
+    check_width::<i16>(bytes)?;
+    le_i16::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_i32(bytes: &[u8]) -> Result<(&[u8], i32), TLVError> {
+    check_width::<i32>(bytes)?;
+    le_i32::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_i64(bytes: &[u8]) -> Result<(&[u8], i64), TLVError> {
+    check_width::<i64>(bytes)?;
+    le_i64::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_f32(bytes: &[u8]) -> Result<(&[u8], f32), TLVError> {
+    check_width::<f32>(bytes)?;
+    le_f32::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_f64(bytes: &[u8]) -> Result<(&[u8], f64), TLVError> {
+    check_width::<f64>(bytes)?;
+    le_f64::<_, Error<&[u8]>>(bytes).map_err(|_| TLVError::ParseError)
+}
+
+pub fn parse_str(utf8_bytes: &[u8]) -> Result<&str, TLVError> {
+    from_utf8(utf8_bytes).map_err(|_| TLVError::ParseError)
+}
+
+// First byte of an element, nothing left is the end of the payload
+pub fn parse_control_byte(bytes: &[u8]) -> Result<(&[u8], ControlByte), TLVError> {
+    match bytes.split_first() {
+        Some((control_byte, remaining_bytes)) => {
+            Ok((remaining_bytes, ControlByte::from(*control_byte)))
+        }
+        None => Err(TLVError::EndOfTLV),
+    }
+}
+
+// Length of a string value, lengths the platform cannot address fail with ValueOutOfRange
+pub fn parse_length_field(
+    bytes: &[u8],
+    field_size: TLVFieldSize,
+) -> Result<(&[u8], usize), TLVError> {
+    if (field_size as usize) > bytes.len() {
+        return Err(TLVError::TruncatedValue {
+            expected: field_size as usize,
+            available: bytes.len(),
+        });
+    }
+    let (remaining_bytes, len) = match field_size {
+        TLVFieldSize::OneOctet => parse_u8(bytes).map(|(rest, len)| (rest, len as u64))?,
+        TLVFieldSize::TwoOctets => parse_u16(bytes).map(|(rest, len)| (rest, len as u64))?,
+        TLVFieldSize::FourOctets => parse_u32(bytes).map(|(rest, len)| (rest, len as u64))?,
+        TLVFieldSize::EightOctets => parse_u64(bytes)?,
+    };
+    let len = usize::try_from(len).map_err(|_| TLVError::ValueOutOfRange)?;
+    Ok((remaining_bytes, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_length_field() {
+        assert_eq!(
+            parse_length_field(&[0x02, 0x00, 0x48], TLVFieldSize::TwoOctets),
+            Ok((&[0x48][..], 2))
+        );
+        assert_eq!(
+            parse_length_field(&[0xff; 8], TLVFieldSize::EightOctets),
+            if usize::BITS < 64 {
+                Err(TLVError::ValueOutOfRange)
+            } else {
+                Ok((&[][..], usize::MAX))
+            }
+        );
+        assert_eq!(
+            parse_length_field(&[0x02, 0x00, 0x00], TLVFieldSize::FourOctets),
+            Err(TLVError::TruncatedValue {
+                expected: 4,
+                available: 3
+            })
+        );
+        assert_eq!(parse_control_byte(&[]), Err(TLVError::EndOfTLV));
+        assert_eq!(
+            parse_control_byte(&[0xd5, 0x01]),
+            Ok((
+                &[0x01][..],
+                ControlByte {
+                    tag_control: 0x06,
+                    element_type: 0x15
+                }
+            ))
+        );
+    }
+}
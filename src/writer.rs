@@ -1,202 +1,946 @@
 #![allow(dead_code)] // Until the Library is used
 
-use crate::tags::{tag_bytes, TLVTag, TagControl};
-use crate::types::ElementType;
-use bytes::Bytes;
+use crate::errors::TLVError;
+use crate::reader::{TLVReader, TLVReaderConfig};
+use crate::tags::{self, TLVTag, TagControl};
+use crate::types::{ContainerType, ElementType, SignedInteger, TLVFieldSize, UnsignedInteger};
+use crate::value::TLVValue;
+use crate::{canonical, hex};
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use bytes::{BufMut, Bytes};
+use core::mem::size_of;
+use num::ToPrimitive;
 
-trait TLVEncode {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8>;
+#[cfg(feature = "derive")]
+pub use tlv_derive::TLVEncode;
+
+pub trait TLVEncode {
+    fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8>;
     fn encode_tlv(&self) -> Vec<u8> {
-        self.encode_tlv_with_tag(TLVTag::Anonymous)
+        self.encode_tlv_with_tag(&TLVTag::Anonymous)
+    }
+    // Appends the element to the output, overridden where encoding to a new Vec would add a copy
+    fn encode_tlv_into(&self, tag: &TLVTag, output: &mut Vec<u8>) {
+        output.extend(self.encode_tlv_with_tag(tag));
+    }
+    // Bytes of the encoded element, to size buffers before writing. The default encodes the
+    // element, overridden where the size is known without encoding.
+    fn encoded_size(&self, tag: &TLVTag) -> usize {
+        self.encode_tlv_with_tag(tag).len()
     }
 }
 
+// None is written as Null, TLVWriter::put_optional leaves the element out instead
+impl<T: TLVEncode> TLVEncode for Option<T> {
+    fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+        match self {
+            Some(value) => value.encode_tlv_with_tag(tag),
+            None => encode_null_with_tag(tag),
+        }
+    }
+
+    fn encode_tlv_into(&self, tag: &TLVTag, output: &mut Vec<u8>) {
+        match self {
+            Some(value) => value.encode_tlv_into(tag, output),
+            None => encode_primitive_into(output, tag, ElementType::Null, &[], &[]),
+        }
+    }
+
+    fn encoded_size(&self, tag: &TLVTag) -> usize {
+        match self {
+            Some(value) => value.encoded_size(tag),
+            None => header_len(tag),
+        }
+    }
+}
+
+// Control byte and tag
+fn header_len(tag: &TLVTag) -> usize {
+    1 + TagControl::from(tag).octets_count() as usize
+}
+
 fn encode_primitive(
-    tag: TLVTag,
+    tag: &TLVTag,
     element_type: ElementType,
     len_bytes: &[u8],
     val_bytes: &[u8],
 ) -> Vec<u8> {
     let mut element = Vec::new();
-    let tag_control = TagControl::from(tag.clone()) as u8;
-    let tag_bytes = tag_bytes(tag);
-    let control_byte = tag_control | element_type as u8;
-    element.push(control_byte);
-    element.extend_from_slice(&tag_bytes);
-    element.extend_from_slice(len_bytes);
-    element.extend_from_slice(val_bytes);
+    encode_primitive_into(&mut element, tag, element_type, len_bytes, val_bytes);
     element
 }
 
-impl TLVEncode for i8 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(tag, ElementType::Int8, &[], val_bytes.as_ref())
-    }
+fn encode_primitive_into(
+    output: &mut Vec<u8>,
+    tag: &TLVTag,
+    element_type: ElementType,
+    len_bytes: &[u8],
+    val_bytes: &[u8],
+) {
+    let tag_control = TagControl::from(tag) as u8;
+    let control_byte = tag_control | element_type as u8;
+    output.reserve(header_len(tag) + len_bytes.len() + val_bytes.len());
+    output.push(control_byte);
+    tags::put_tag_bytes(tag, output);
+    output.extend_from_slice(len_bytes);
+    output.extend_from_slice(val_bytes);
 }
 
-impl TLVEncode for i16 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(tag, ElementType::Int16, &[], val_bytes.as_ref())
-    }
+// IPv6 minimum MTU, the largest Matter message sent over UDP without fragmentation
+pub const MATTER_IPV6_MTU: usize = 1280;
+
+// String element types per length field width
+const UTF8_STRING_TYPES: [ElementType; 4] = [
+    ElementType::UTF8String1ByteLength,
+    ElementType::UTF8String2ByteLength,
+    ElementType::UTF8String4ByteLength,
+    ElementType::UTF8String8ByteLength,
+];
+const BYTE_STRING_TYPES: [ElementType; 4] = [
+    ElementType::ByteString1ByteLength,
+    ElementType::ByteString2ByteLength,
+    ElementType::ByteString4ByteLength,
+    ElementType::ByteString8ByteLength,
+];
+
+// Length prefixed value with the narrowest length field
+fn encode_string_into(
+    output: &mut Vec<u8>,
+    tag: &TLVTag,
+    element_types: [ElementType; 4],
+    val_bytes: &[u8],
+) {
+    let field_size = TLVFieldSize::minimal_for(val_bytes.len());
+    encode_string_with_field_size_into(output, tag, element_types, field_size, val_bytes)
 }
 
-impl TLVEncode for i32 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(tag, ElementType::Int32, &[], val_bytes.as_ref())
-    }
+// The length field may be wider than needed, never narrower
+fn encode_string_with_field_size_into(
+    output: &mut Vec<u8>,
+    tag: &TLVTag,
+    element_types: [ElementType; 4],
+    field_size: TLVFieldSize,
+    val_bytes: &[u8],
+) {
+    // Reserved up front, so the buffer does not grow twice for the header then the value
+    output.reserve(header_len(tag) + field_size as usize + val_bytes.len());
+    encode_string_header_into(output, tag, element_types, field_size, val_bytes.len());
+    output.extend_from_slice(val_bytes);
+}
+
+fn string_size(tag: &TLVTag, val_len: usize) -> usize {
+    header_len(tag) + TLVFieldSize::minimal_for(val_len) as usize + val_len
 }
 
-impl TLVEncode for i64 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(tag, ElementType::Int64, &[], val_bytes.as_ref())
+// Control byte, tag and length field, the value octets follow
+fn encode_string_header_into(
+    output: &mut Vec<u8>,
+    tag: &TLVTag,
+    element_types: [ElementType; 4],
+    field_size: TLVFieldSize,
+    val_len: usize,
+) {
+    let [one_octet, two_octets, four_octets, eight_octets] = element_types;
+    let element_type = match field_size {
+        TLVFieldSize::OneOctet => one_octet,
+        TLVFieldSize::TwoOctets => two_octets,
+        TLVFieldSize::FourOctets => four_octets,
+        TLVFieldSize::EightOctets => eight_octets,
+    };
+    let len_bytes = (val_len as u64).to_le_bytes();
+    encode_primitive_into(
+        output,
+        tag,
+        element_type,
+        &len_bytes[..field_size as usize],
+        &[],
+    );
+}
+
+macro_rules! encode_number {
+    ($type:ty, $element_type:expr) => {
+        impl TLVEncode for $type {
+            fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+                encode_primitive(tag, $element_type, &[], &self.to_le_bytes())
+            }
+
+            fn encode_tlv_into(&self, tag: &TLVTag, output: &mut Vec<u8>) {
+                encode_primitive_into(output, tag, $element_type, &[], &self.to_le_bytes())
+            }
+
+            fn encoded_size(&self, tag: &TLVTag) -> usize {
+                header_len(tag) + size_of::<Self>()
+            }
+        }
+    };
+}
+
+encode_number!(i8, ElementType::Int8);
+encode_number!(i16, ElementType::Int16);
+encode_number!(i32, ElementType::Int32);
+encode_number!(i64, ElementType::Int64);
+encode_number!(u8, ElementType::UInt8);
+encode_number!(u16, ElementType::UInt16);
+encode_number!(u32, ElementType::UInt32);
+encode_number!(u64, ElementType::UInt64);
+encode_number!(f32, ElementType::FloatingPointNumber32);
+encode_number!(f64, ElementType::FloatingPointNumber64);
+
+impl TLVEncode for bool {
+    fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+        let mut element = Vec::new();
+        self.encode_tlv_into(tag, &mut element);
+        element
+    }
+
+    fn encode_tlv_into(&self, tag: &TLVTag, output: &mut Vec<u8>) {
+        let element_type = if *self {
+            ElementType::BooleanTrue
+        } else {
+            ElementType::BooleanFalse
+        };
+        encode_primitive_into(output, tag, element_type, &[], &[])
+    }
+
+    fn encoded_size(&self, tag: &TLVTag) -> usize {
+        header_len(tag)
     }
 }
-impl TLVEncode for u8 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(tag, ElementType::UInt8, &[], val_bytes.as_ref())
+
+impl TLVEncode for str {
+    fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+        let mut element = Vec::new();
+        self.encode_tlv_into(tag, &mut element);
+        element
+    }
+
+    fn encode_tlv_into(&self, tag: &TLVTag, output: &mut Vec<u8>) {
+        encode_string_into(output, tag, UTF8_STRING_TYPES, self.as_bytes())
+    }
+
+    fn encoded_size(&self, tag: &TLVTag) -> usize {
+        string_size(tag, self.len())
     }
 }
 
-impl TLVEncode for u16 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(tag, ElementType::UInt16, &[], val_bytes.as_ref())
+impl TLVEncode for [u8] {
+    fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+        let mut element = Vec::new();
+        self.encode_tlv_into(tag, &mut element);
+        element
+    }
+
+    fn encode_tlv_into(&self, tag: &TLVTag, output: &mut Vec<u8>) {
+        encode_string_into(output, tag, BYTE_STRING_TYPES, self)
+    }
+
+    fn encoded_size(&self, tag: &TLVTag) -> usize {
+        string_size(tag, self.len())
     }
 }
 
-impl TLVEncode for u32 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(tag, ElementType::UInt32, &[], val_bytes.as_ref())
+impl TLVEncode for String {
+    fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+        self.as_str().encode_tlv_with_tag(tag)
+    }
+
+    fn encode_tlv_into(&self, tag: &TLVTag, output: &mut Vec<u8>) {
+        self.as_str().encode_tlv_into(tag, output)
+    }
+
+    fn encoded_size(&self, tag: &TLVTag) -> usize {
+        self.as_str().encoded_size(tag)
     }
 }
 
-impl TLVEncode for u64 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(tag, ElementType::UInt64, &[], val_bytes.as_ref())
+impl TLVEncode for Bytes {
+    fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+        self.as_ref().encode_tlv_with_tag(tag)
+    }
+
+    fn encode_tlv_into(&self, tag: &TLVTag, output: &mut Vec<u8>) {
+        self.as_ref().encode_tlv_into(tag, output)
+    }
+
+    fn encoded_size(&self, tag: &TLVTag) -> usize {
+        self.as_ref().encoded_size(tag)
     }
 }
 
-impl TLVEncode for f32 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(
-            tag,
-            ElementType::FloatingPointNumber32,
-            &[],
-            val_bytes.as_ref(),
-        )
+// Numbers keep the width of their variant, container members their tags
+impl TLVEncode for TLVValue {
+    fn encode_tlv_with_tag(&self, tag: &TLVTag) -> Vec<u8> {
+        let mut element = Vec::with_capacity(self.encoded_size(tag));
+        self.encode_tlv_into(tag, &mut element);
+        element
+    }
+
+    fn encode_tlv_into(&self, tag: &TLVTag, output: &mut Vec<u8>) {
+        encode_value_into(output, tag, self, false)
+    }
+
+    fn encoded_size(&self, tag: &TLVTag) -> usize {
+        match self {
+            TLVValue::Int8(value) => value.encoded_size(tag),
+            TLVValue::Int16(value) => value.encoded_size(tag),
+            TLVValue::Int32(value) => value.encoded_size(tag),
+            TLVValue::Int64(value) => value.encoded_size(tag),
+            TLVValue::UInt8(value) => value.encoded_size(tag),
+            TLVValue::UInt16(value) => value.encoded_size(tag),
+            TLVValue::UInt32(value) => value.encoded_size(tag),
+            TLVValue::UInt64(value) => value.encoded_size(tag),
+            TLVValue::Bool(value) => value.encoded_size(tag),
+            TLVValue::Float32(value) => value.encoded_size(tag),
+            TLVValue::Float64(value) => value.encoded_size(tag),
+            TLVValue::Utf8String(value) => value.encoded_size(tag),
+            TLVValue::ByteString(value) => value.as_slice().encoded_size(tag),
+            TLVValue::Null => header_len(tag),
+            TLVValue::Structure(members) | TLVValue::List(members) => {
+                let members: usize = members
+                    .iter()
+                    .map(|(tag, value)| value.encoded_size(tag))
+                    .sum();
+                header_len(tag) + members + 1
+            }
+            TLVValue::Array(members) => {
+                let members: usize = members
+                    .iter()
+                    .map(|value| value.encoded_size(&TLVTag::Anonymous))
+                    .sum();
+                header_len(tag) + members + 1
+            }
+        }
     }
 }
 
-impl TLVEncode for f64 {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_le_bytes();
-        encode_primitive(
+fn encode_value_into(output: &mut Vec<u8>, tag: &TLVTag, value: &TLVValue, canonical_nans: bool) {
+    match value {
+        TLVValue::Int8(value) => value.encode_tlv_into(tag, output),
+        TLVValue::Int16(value) => value.encode_tlv_into(tag, output),
+        TLVValue::Int32(value) => value.encode_tlv_into(tag, output),
+        TLVValue::Int64(value) => value.encode_tlv_into(tag, output),
+        TLVValue::UInt8(value) => value.encode_tlv_into(tag, output),
+        TLVValue::UInt16(value) => value.encode_tlv_into(tag, output),
+        TLVValue::UInt32(value) => value.encode_tlv_into(tag, output),
+        TLVValue::UInt64(value) => value.encode_tlv_into(tag, output),
+        TLVValue::Bool(value) => value.encode_tlv_into(tag, output),
+        TLVValue::Float32(value) if canonical_nans => {
+            canonical::canonical_f32(*value).encode_tlv_into(tag, output)
+        }
+        TLVValue::Float64(value) if canonical_nans => {
+            canonical::canonical_f64(*value).encode_tlv_into(tag, output)
+        }
+        TLVValue::Float32(value) => value.encode_tlv_into(tag, output),
+        TLVValue::Float64(value) => value.encode_tlv_into(tag, output),
+        TLVValue::Utf8String(value) => value.encode_tlv_into(tag, output),
+        TLVValue::ByteString(value) => value.as_slice().encode_tlv_into(tag, output),
+        TLVValue::Null => encode_primitive_into(output, tag, ElementType::Null, &[], &[]),
+        TLVValue::Structure(members) => encode_members_into(
+            output,
+            tag,
+            ContainerType::Structure,
+            members.iter().map(|(tag, value)| (tag, value)),
+            canonical_nans,
+        ),
+        TLVValue::Array(members) => encode_members_into(
+            output,
+            tag,
+            ContainerType::Array,
+            members.iter().map(|value| (&TLVTag::Anonymous, value)),
+            canonical_nans,
+        ),
+        TLVValue::List(members) => encode_members_into(
+            output,
             tag,
-            ElementType::FloatingPointNumber64,
-            &[],
-            val_bytes.as_ref(),
-        )
+            ContainerType::List,
+            members.iter().map(|(tag, value)| (tag, value)),
+            canonical_nans,
+        ),
     }
 }
 
-impl TLVEncode for bool {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let element_type = if *self {
-            ElementType::BooleanTrue
-        } else {
-            ElementType::BooleanFalse
-        };
-        encode_primitive(tag, element_type, &[], &[])
+fn encode_members_into<'a>(
+    output: &mut Vec<u8>,
+    tag: &TLVTag,
+    container_type: ContainerType,
+    members: impl Iterator<Item = (&'a TLVTag, &'a TLVValue)>,
+    canonical_nans: bool,
+) {
+    encode_primitive_into(output, tag, container_type.into(), &[], &[]);
+    for (tag, value) in members {
+        encode_value_into(output, tag, value, canonical_nans);
     }
+    output.push(ElementType::EndOfContainer as u8);
 }
 
-impl TLVEncode for String {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.clone().into_bytes();
-        let val_len = val_bytes.len();
-        let (element_type, len_bytes) = if val_len <= u8::MAX as usize {
-            (
-                ElementType::UTF8String1ByteLength,
-                (val_len as u8).to_le_bytes().to_vec(),
-            )
-        } else if val_len <= u16::MAX as usize {
-            (
-                ElementType::UTF8String2ByteLength,
-                (val_len as u16).to_le_bytes().to_vec(),
-            )
-        } else if val_len <= u32::MAX as usize {
-            (
-                ElementType::UTF8String4ByteLength,
-                (val_len as u32).to_le_bytes().to_vec(),
-            )
-        } else {
-            (
-                ElementType::UTF8String8ByteLength,
-                (val_len as u64).to_le_bytes().to_vec(),
-            )
-        };
-        encode_primitive(
-            tag,
-            element_type,
-            len_bytes.as_slice(),
-            val_bytes.as_slice(),
-        )
-    }
+// Any member of the value at any depth
+fn holds_qualified_common_tag(value: &TLVValue) -> bool {
+    value.into_iter().any(|(tag, member)| {
+        tag.is_qualified_common_profile() || holds_qualified_common_tag(member)
+    })
 }
 
-impl TLVEncode for Bytes {
-    fn encode_tlv_with_tag(&self, tag: TLVTag) -> Vec<u8> {
-        let val_bytes = self.to_vec();
-        let val_len = val_bytes.len();
-        let (element_type, len_bytes) = if val_len <= u8::MAX as usize {
-            (
-                ElementType::ByteString1ByteLength,
-                (val_len as u8).to_le_bytes().to_vec(),
-            )
-        } else if val_len <= u16::MAX as usize {
-            (
-                ElementType::ByteString2ByteLength,
-                (val_len as u16).to_le_bytes().to_vec(),
-            )
-        } else if val_len <= u32::MAX as usize {
-            (
-                ElementType::ByteString4ByteLength,
-                (val_len as u32).to_le_bytes().to_vec(),
-            )
-        } else {
-            (
-                ElementType::ByteString8ByteLength,
-                (val_len as u64).to_le_bytes().to_vec(),
-            )
-        };
-        encode_primitive(
-            tag,
-            element_type,
-            len_bytes.as_slice(),
-            val_bytes.as_slice(),
-        )
+fn unqualify_member_tags(value: &mut TLVValue) {
+    match value {
+        TLVValue::Structure(members) | TLVValue::List(members) => {
+            for (tag, member) in members.iter_mut() {
+                *tag = tag.unqualified();
+                unqualify_member_tags(member);
+            }
+        }
+        TLVValue::Array(members) => members.iter_mut().for_each(unqualify_member_tags),
+        _ => {}
     }
 }
 
-pub fn encode_null_with_tag(tag: TLVTag) -> Vec<u8> {
+pub fn encode_null_with_tag(tag: &TLVTag) -> Vec<u8> {
     encode_primitive(tag, ElementType::Null, &[], &[])
 }
 
 pub fn encode_null() -> Vec<u8> {
-    encode_null_with_tag(TLVTag::Anonymous)
+    encode_null_with_tag(&TLVTag::Anonymous)
+}
+
+// Handling of fully qualified common profile tags, see TLVTag::is_qualified_common_profile
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum QualifiedCommonTags {
+    #[default]
+    Keep, // Written as given, e.g. to reproduce a captured payload
+    Unqualify, // Written in their common profile form
+    Reject,    // Failing with InvalidTag
+}
+
+#[derive(Debug, Default)]
+pub struct TLVWriter {
+    bytes: Vec<u8>,
+    containers: Vec<(ContainerType, usize)>, // Open containers and offsets of their control bytes
+    max_len: Option<usize>,
+    canonical_nans: bool,
+    qualified_common_tags: QualifiedCommonTags,
+    flushed: usize, // Bytes handed over to a TLVBufWriter's buffer, no longer in bytes
+}
+
+impl TLVWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Output buffer allocated once for capacity bytes, writing up to them does not reallocate
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bytes: Vec::with_capacity(capacity),
+            ..Self::default()
+        }
+    }
+
+    // Output buffer of exactly the size of the elements, see TLVEncode::encoded_size. Containers
+    // written around them need a byte each for their control byte and End of Container, plus tags.
+    pub fn sized_for(elements: &[(&TLVTag, &dyn TLVEncode)]) -> Self {
+        let capacity = elements
+            .iter()
+            .map(|(tag, value)| value.encoded_size(tag))
+            .sum();
+        Self::with_capacity(capacity)
+    }
+
+    // Grows up to max_len bytes, End of Container markers of open containers included so ending
+    // them always fits. A put going past it fails with MessageTooLarge and writes nothing.
+    pub fn with_max_len(max_len: usize) -> Self {
+        Self {
+            max_len: Some(max_len),
+            ..Self::default()
+        }
+    }
+
+    // Payload of a Matter message fitting the IPv6 MTU. The overhead is everything else in the
+    // packet: IPv6 and UDP headers, message and protocol headers, and the MIC.
+    pub fn with_matter_budget(header_overhead: usize) -> Self {
+        Self::with_max_len(MATTER_IPV6_MTU.saturating_sub(header_overhead))
+    }
+
+    // NaNs of floats written afterwards become the canonical quiet NaN of their width, see
+    // canonical::CANONICAL_NAN_F32. Covers puts of f32 and f64 values and floats in put_value.
+    pub fn canonicalize_nans(mut self, canonical_nans: bool) -> Self {
+        self.canonical_nans = canonical_nans;
+        self
+    }
+
+    // Applies to the tags of every put, members of the values of put_value and put_elements
+    // included. Nothing is written when a tag is rejected.
+    pub fn qualified_common_tags(mut self, qualified_common_tags: QualifiedCommonTags) -> Self {
+        self.qualified_common_tags = qualified_common_tags;
+        self
+    }
+
+    // Every element is written with a tag checked here, against the open container first
+    fn check_tag(&self, tag: TLVTag) -> Result<TLVTag, TLVError> {
+        self.check_member_tag(&tag)?;
+        if !tag.is_qualified_common_profile() {
+            return Ok(tag);
+        }
+        match self.qualified_common_tags {
+            QualifiedCommonTags::Keep => Ok(tag),
+            QualifiedCommonTags::Unqualify => Ok(tag.unqualified()),
+            QualifiedCommonTags::Reject => Err(TLVError::InvalidTag),
+        }
+    }
+
+    // Fails when len bytes, once every open container is ended, would go past max_len
+    fn check_len(&self, len: usize) -> Result<(), TLVError> {
+        match self.max_len {
+            Some(max_len)
+                if len
+                    .saturating_add(self.flushed)
+                    .saturating_add(self.containers.len())
+                    > max_len =>
+            {
+                Err(TLVError::MessageTooLarge { limit: max_len })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Keeps the bytes written past start if they fit
+    fn commit(&mut self, start: usize) -> Result<(), TLVError> {
+        let result = self.check_len(self.bytes.len());
+        if result.is_err() {
+            self.bytes.truncate(start);
+        }
+        result
+    }
+
+    pub fn put<T: TLVEncode + ?Sized>(&mut self, tag: TLVTag, value: &T) -> Result<(), TLVError> {
+        let tag = self.check_tag(tag)?;
+        let start = self.bytes.len();
+        value.encode_tlv_into(&tag, &mut self.bytes);
+        if self.canonical_nans {
+            self.canonicalize_nan(start);
+        }
+        self.commit(start)
+    }
+
+    // Floats have no length field, their value ends the element written from start
+    fn canonicalize_nan(&mut self, start: usize) {
+        let element_type = self.bytes[start] & tags::ELEMENT_TYPE_MASK;
+        let end = self.bytes.len();
+        if element_type == ElementType::FloatingPointNumber32 as u8 {
+            let value = &mut self.bytes[end - 4..];
+            let bits = u32::from_le_bytes([value[0], value[1], value[2], value[3]]);
+            let canonical = canonical::canonical_f32(f32::from_bits(bits));
+            value.copy_from_slice(&canonical.to_le_bytes());
+        } else if element_type == ElementType::FloatingPointNumber64 as u8 {
+            let value = &mut self.bytes[end - 8..];
+            let mut bits = [0; 8];
+            bits.copy_from_slice(value);
+            let canonical = canonical::canonical_f64(f64::from_le_bytes(bits));
+            value.copy_from_slice(&canonical.to_le_bytes());
+        }
+    }
+
+    // Unsigned integer using the narrowest width able to hold the value
+    pub fn put_uint(&mut self, tag: TLVTag, value: u64) -> Result<(), TLVError> {
+        match UnsignedInteger::minimal_for(value) {
+            UnsignedInteger::UInt8 => self.put(tag, &(value as u8)),
+            UnsignedInteger::UInt16 => self.put(tag, &(value as u16)),
+            UnsignedInteger::UInt32 => self.put(tag, &(value as u32)),
+            UnsignedInteger::UInt64 => self.put(tag, &value),
+        }
+    }
+
+    // Optional field of a structure, nothing is written for None. read_field and try_read_field
+    // see it as absent.
+    pub fn put_optional<T: TLVEncode>(
+        &mut self,
+        tag: TLVTag,
+        value: &Option<T>,
+    ) -> Result<(), TLVError> {
+        match value {
+            Some(value) => self.put(tag, value),
+            None => Ok(()),
+        }
+    }
+
+    // Signed integer using the narrowest width able to hold the value
+    pub fn put_int(&mut self, tag: TLVTag, value: i64) -> Result<(), TLVError> {
+        match SignedInteger::minimal_for(value) {
+            SignedInteger::Int8 => self.put(tag, &(value as i8)),
+            SignedInteger::Int16 => self.put(tag, &(value as i16)),
+            SignedInteger::Int32 => self.put(tag, &(value as i32)),
+            SignedInteger::Int64 => self.put(tag, &value),
+        }
+    }
+
+    // Enumeration of a cluster as an unsigned integer of the narrowest width, values without an
+    // unsigned representation fail with ValueOutOfRange
+    pub fn put_enum<E: ToPrimitive>(&mut self, tag: TLVTag, value: &E) -> Result<(), TLVError> {
+        let value = value.to_u64().ok_or(TLVError::ValueOutOfRange)?;
+        self.put_uint(tag, value)
+    }
+
+    // Bitmap of a cluster as an unsigned integer of the narrowest width
+    #[cfg(feature = "bitflags")]
+    pub fn put_flags<F: bitflags::Flags>(&mut self, tag: TLVTag, flags: &F) -> Result<(), TLVError>
+    where
+        F::Bits: Into<u64>,
+    {
+        self.put_uint(tag, flags.bits().into())
+    }
+
+    // Matter TLV integers are at most 8 octets, 128-bit values are written with the narrowest width
+    // when they fit in 64 bits and fail with ValueOutOfRange otherwise, never truncated
+    pub fn put_u128(&mut self, tag: TLVTag, value: u128) -> Result<(), TLVError> {
+        let value = u64::try_from(value).map_err(|_| TLVError::ValueOutOfRange)?;
+        self.put_uint(tag, value)
+    }
+
+    pub fn put_i128(&mut self, tag: TLVTag, value: i128) -> Result<(), TLVError> {
+        let value = i64::try_from(value).map_err(|_| TLVError::ValueOutOfRange)?;
+        self.put_int(tag, value)
+    }
+
+    // UTF-8 String with the given length field width rather than the narrowest one, fails with
+    // ValueOutOfRange when the length does not fit in it
+    pub fn put_str_with_len_width(
+        &mut self,
+        tag: TLVTag,
+        value: &str,
+        width: TLVFieldSize,
+    ) -> Result<(), TLVError> {
+        self.put_string_with_len_width(tag, UTF8_STRING_TYPES, value.as_bytes(), width)
+    }
+
+    // Octet String counterpart of put_str_with_len_width
+    pub fn put_bytes_with_len_width(
+        &mut self,
+        tag: TLVTag,
+        value: &[u8],
+        width: TLVFieldSize,
+    ) -> Result<(), TLVError> {
+        self.put_string_with_len_width(tag, BYTE_STRING_TYPES, value, width)
+    }
+
+    fn put_string_with_len_width(
+        &mut self,
+        tag: TLVTag,
+        element_types: [ElementType; 4],
+        value: &[u8],
+        width: TLVFieldSize,
+    ) -> Result<(), TLVError> {
+        if TLVFieldSize::minimal_for(value.len()) as usize > width as usize {
+            return Err(TLVError::ValueOutOfRange);
+        }
+        let tag = self.check_tag(tag)?;
+        let start = self.bytes.len();
+        encode_string_with_field_size_into(&mut self.bytes, &tag, element_types, width, value);
+        self.commit(start)
+    }
+
+    // Octet String of len octets copied from the source without holding the whole value, the source
+    // must yield exactly len octets. Nothing is written when it fails.
+    #[cfg(feature = "std")]
+    pub fn put_bytes_from_reader(
+        &mut self,
+        tag: TLVTag,
+        len: u64,
+        mut src: impl std::io::Read,
+    ) -> Result<(), TLVError> {
+        use std::io::Read;
+
+        let tag = self.check_tag(tag)?;
+        let start = self.bytes.len();
+        let val_len = usize::try_from(len).map_err(|_| TLVError::ValueOutOfRange)?;
+        let field_size = TLVFieldSize::minimal_for(val_len);
+        encode_string_header_into(
+            &mut self.bytes,
+            &tag,
+            BYTE_STRING_TYPES,
+            field_size,
+            val_len,
+        );
+        if let Err(e) = self.check_len(self.bytes.len().saturating_add(val_len)) {
+            self.bytes.truncate(start);
+            return Err(e);
+        }
+        let copied = src
+            .by_ref()
+            .take(len)
+            .read_to_end(&mut self.bytes)
+            .and_then(|copied| Ok((copied, src.read(&mut [0])?)));
+        match copied {
+            Ok((copied, 0)) if copied == val_len => Ok(()),
+            Ok(_) => {
+                self.bytes.truncate(start);
+                Err(TLVError::SourceLengthMismatch { declared: len })
+            }
+            Err(e) => {
+                self.bytes.truncate(start);
+                Err(TLVError::Io(e.to_string()))
+            }
+        }
+    }
+
+    pub fn put_null(&mut self, tag: TLVTag) -> Result<(), TLVError> {
+        let tag = self.check_tag(tag)?;
+        let start = self.bytes.len();
+        encode_primitive_into(&mut self.bytes, &tag, ElementType::Null, &[], &[]);
+        self.commit(start)
+    }
+
+    pub fn start_container(
+        &mut self,
+        tag: TLVTag,
+        container_type: ContainerType,
+    ) -> Result<(), TLVError> {
+        let tag = self.check_tag(tag)?;
+        let start = self.bytes.len();
+        encode_primitive_into(&mut self.bytes, &tag, container_type.into(), &[], &[]);
+        self.containers.push((container_type, self.flushed + start));
+        let result = self.commit(start);
+        if result.is_err() {
+            self.containers.pop();
+        }
+        result
+    }
+
+    pub fn end_container(&mut self) -> Result<(), TLVError> {
+        self.containers.pop().ok_or(TLVError::NotInContainer)?;
+        self.bytes.push(ElementType::EndOfContainer as u8);
+        Ok(())
+    }
+
+    // Numbers are written with the width of their variant
+    pub fn put_value(&mut self, tag: TLVTag, value: &TLVValue) -> Result<(), TLVError> {
+        let tag = self.check_tag(tag)?;
+        match self.qualified_common_tags {
+            QualifiedCommonTags::Keep => {}
+            _ if !holds_qualified_common_tag(value) => {}
+            QualifiedCommonTags::Reject => return Err(TLVError::InvalidTag),
+            QualifiedCommonTags::Unqualify => {
+                // Members rewritten in a copy, the value passed is left as it is
+                let mut value = value.clone();
+                unqualify_member_tags(&mut value);
+                return self.put_value(tag, &value);
+            }
+        }
+        let start = self.bytes.len();
+        encode_value_into(&mut self.bytes, &tag, value, self.canonical_nans);
+        self.commit(start)
+    }
+
+    // Members of the open container, or top level elements, from data rather than code.
+    // On failure nothing of the call is kept, the writer is as it was before it.
+    pub fn put_elements(
+        &mut self,
+        elements: impl IntoIterator<Item = (TLVTag, TLVValue)>,
+    ) -> Result<(), TLVError> {
+        let start = self.bytes.len();
+        for (tag, value) in elements {
+            let result = self.put_value(tag, &value);
+            if result.is_err() {
+                self.bytes.truncate(start);
+                return result;
+            }
+        }
+        Ok(())
+    }
+
+    // Array members are anonymous, Structure members tagged, List members either
+    fn check_member_tag(&self, tag: &TLVTag) -> Result<(), TLVError> {
+        match (self.current_container_type(), tag) {
+            (Some(ContainerType::Array), TLVTag::Anonymous) => Ok(()),
+            (Some(ContainerType::Array), _) => Err(TLVError::InvalidTag),
+            (Some(ContainerType::Structure), TLVTag::Anonymous) => Err(TLVError::InvalidTag),
+            (Some(ContainerType::Structure), _) => Ok(()),
+            // List members may be anonymous or tagged and share tags
+            (Some(ContainerType::List), _) | (None, _) => Ok(()),
+        }
+    }
+
+    // Whether putting the element would succeed, e.g. to start a new report chunk instead
+    pub fn would_fit<T: TLVEncode + ?Sized>(&self, tag: &TLVTag, value: &T) -> bool {
+        let Ok(tag) = self.check_tag(tag.clone()) else {
+            return false;
+        };
+        let len = value.encode_tlv_with_tag(&tag).len();
+        self.check_len(self.bytes.len().saturating_add(len)).is_ok()
+    }
+
+    // Bytes written so far
+    pub fn len(&self) -> usize {
+        self.flushed + self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Bytes left under max_len once the open containers are ended, None when unbounded
+    pub fn remaining_capacity(&self) -> Option<usize> {
+        self.max_len
+            .map(|max_len| max_len.saturating_sub(self.len() + self.containers.len()))
+    }
+
+    pub fn open_container_depth(&self) -> usize {
+        self.containers.len()
+    }
+
+    pub fn current_container_type(&self) -> Option<ContainerType> {
+        self.containers
+            .last()
+            .map(|(container_type, _)| *container_type)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn to_hex_string(&self) -> String {
+        hex::to_hex(&self.bytes)
+    }
+
+    // Reader over the payload written, to check it before sending it. The buffer is handed over
+    // without a copy. Fails with UnterminatedContainer when a container is still open.
+    pub fn into_reader(self) -> Result<TLVReader, TLVError> {
+        self.into_reader_with_config(TLVReaderConfig::default())
+    }
+
+    pub fn into_reader_with_config(self, config: TLVReaderConfig) -> Result<TLVReader, TLVError> {
+        if let Some((_, offset)) = self.containers.first() {
+            return Err(TLVError::UnterminatedContainer { offset: *offset });
+        }
+        Ok(TLVReader::from_bytes_with_config(
+            Bytes::from(self.bytes),
+            config,
+        ))
+    }
+
+    // Appends the bytes written since the last flush to the buffer
+    fn flush_into(&mut self, buf: &mut impl BufMut) {
+        buf.put_slice(&self.bytes);
+        self.flushed += self.bytes.len();
+        self.bytes.clear();
+    }
+}
+
+// Writer appending to a buffer it does not own, e.g. a BytesMut holding the headers of a frame.
+// Elements are encoded as TLVWriter does, then appended with put_slice. The room the buffer has
+// left bounds the payload as max_len does, End of Container markers of open containers included,
+// and a put going past it fails with BufferTooSmall and appends nothing.
+pub struct TLVBufWriter<'a, B: BufMut> {
+    buf: &'a mut B,
+    tlv_writer: TLVWriter,
+}
+
+impl<'a, B: BufMut> TLVBufWriter<'a, B> {
+    pub fn new(buf: &'a mut B) -> Self {
+        let tlv_writer = TLVWriter::with_max_len(buf.remaining_mut());
+        Self { buf, tlv_writer }
+    }
+
+    pub fn canonicalize_nans(mut self, canonical_nans: bool) -> Self {
+        self.tlv_writer = self.tlv_writer.canonicalize_nans(canonical_nans);
+        self
+    }
+
+    pub fn qualified_common_tags(mut self, qualified_common_tags: QualifiedCommonTags) -> Self {
+        self.tlv_writer = self.tlv_writer.qualified_common_tags(qualified_common_tags);
+        self
+    }
+
+    fn flush(&mut self, result: Result<(), TLVError>) -> Result<(), TLVError> {
+        match result {
+            Ok(()) => {
+                self.tlv_writer.flush_into(self.buf);
+                Ok(())
+            }
+            Err(TLVError::MessageTooLarge { limit }) => {
+                Err(TLVError::BufferTooSmall { capacity: limit })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn put<T: TLVEncode + ?Sized>(&mut self, tag: TLVTag, value: &T) -> Result<(), TLVError> {
+        let result = self.tlv_writer.put(tag, value);
+        self.flush(result)
+    }
+
+    pub fn put_uint(&mut self, tag: TLVTag, value: u64) -> Result<(), TLVError> {
+        let result = self.tlv_writer.put_uint(tag, value);
+        self.flush(result)
+    }
+
+    pub fn put_optional<T: TLVEncode>(
+        &mut self,
+        tag: TLVTag,
+        value: &Option<T>,
+    ) -> Result<(), TLVError> {
+        let result = self.tlv_writer.put_optional(tag, value);
+        self.flush(result)
+    }
+
+    pub fn put_int(&mut self, tag: TLVTag, value: i64) -> Result<(), TLVError> {
+        let result = self.tlv_writer.put_int(tag, value);
+        self.flush(result)
+    }
+
+    pub fn put_null(&mut self, tag: TLVTag) -> Result<(), TLVError> {
+        let result = self.tlv_writer.put_null(tag);
+        self.flush(result)
+    }
+
+    pub fn put_value(&mut self, tag: TLVTag, value: &TLVValue) -> Result<(), TLVError> {
+        let result = self.tlv_writer.put_value(tag, value);
+        self.flush(result)
+    }
+
+    pub fn put_elements(
+        &mut self,
+        elements: impl IntoIterator<Item = (TLVTag, TLVValue)>,
+    ) -> Result<(), TLVError> {
+        let result = self.tlv_writer.put_elements(elements);
+        self.flush(result)
+    }
+
+    pub fn start_container(
+        &mut self,
+        tag: TLVTag,
+        container_type: ContainerType,
+    ) -> Result<(), TLVError> {
+        let result = self.tlv_writer.start_container(tag, container_type);
+        self.flush(result)
+    }
+
+    pub fn end_container(&mut self) -> Result<(), TLVError> {
+        let result = self.tlv_writer.end_container();
+        self.flush(result)
+    }
+
+    pub fn would_fit<T: TLVEncode + ?Sized>(&self, tag: &TLVTag, value: &T) -> bool {
+        self.tlv_writer.would_fit(tag, value)
+    }
+
+    pub fn open_container_depth(&self) -> usize {
+        self.tlv_writer.open_container_depth()
+    }
+
+    // Bytes appended to the buffer, containers left open are not ended
+    pub fn finalize(self) -> usize {
+        self.tlv_writer.len()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::reader::{TLVReader, TLVReaderConfig};
     use crate::tags::{CommonProfileLength, FullyQualifiedProfileLength};
 
     #[test]
@@ -205,30 +949,30 @@ mod tests {
         let test_output = &[0x04, 0x2a];
         let test_input: u8 = 42;
         assert_eq!(
-            test_input.encode_tlv_with_tag(TLVTag::Anonymous),
+            test_input.encode_tlv_with_tag(&TLVTag::Anonymous),
             test_output
         );
 
         // Context tag 1, Unsigned Integer, 1-octet value, 1 = 42U
         let test_output = &[0x24, 0x01, 0x2a];
         assert_eq!(
-            test_input.encode_tlv_with_tag(TLVTag::ContextSpecific(1)),
+            test_input.encode_tlv_with_tag(&TLVTag::ContextSpecific(1)),
             test_output
         );
 
         // Common profile tag 1, Unsigned Integer, 1-octet value, CHIP::1 = 42U
         let test_output = &[0x44, 0x01, 0x00, 0x2a];
         assert_eq!(
-            test_input.encode_tlv_with_tag(TLVTag::CommonProfile(CommonProfileLength::TwoOctets {
-                tag_number: 1
-            })),
+            test_input.encode_tlv_with_tag(&TLVTag::CommonProfile(
+                CommonProfileLength::TwoOctets { tag_number: 1 }
+            )),
             test_output
         );
 
         // Common profile tag 100000, Unsigned Integer, 1-octet value, CHIP::100000 = 42U
         let test_output = &[0x64, 0xa0, 0x86, 0x01, 0x00, 0x2a];
         assert_eq!(
-            test_input.encode_tlv_with_tag(TLVTag::CommonProfile(
+            test_input.encode_tlv_with_tag(&TLVTag::CommonProfile(
                 CommonProfileLength::FourOctets { tag_number: 100000 }
             )),
             test_output
@@ -238,7 +982,7 @@ mod tests {
         // 2-octet tag 1, Unsigned Integer, 1-octet value 42, 65521::57069:1 = 42U
         let test_output = &[0xc4, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x2a];
         assert_eq!(
-            test_input.encode_tlv_with_tag(TLVTag::FullyQualifiedProfile(
+            test_input.encode_tlv_with_tag(&TLVTag::FullyQualifiedProfile(
                 FullyQualifiedProfileLength::SixOctets {
                     vendor_id: 65521,
                     profile_number: 57069,
@@ -253,7 +997,7 @@ mod tests {
         // Unsigned Integer, 1-octet value 42, 65521::57069:2857762541 = 42U
         let test_output = &[0xe4, 0xf1, 0xff, 0xed, 0xde, 0xed, 0xfe, 0x55, 0xaa, 0x2a];
         assert_eq!(
-            test_input.encode_tlv_with_tag(TLVTag::FullyQualifiedProfile(
+            test_input.encode_tlv_with_tag(&TLVTag::FullyQualifiedProfile(
                 FullyQualifiedProfileLength::EightOctets {
                     vendor_id: 65521,
                     profile_number: 57069,
@@ -421,6 +1165,628 @@ mod tests {
         assert_eq!(test_input.encode_tlv(), test_output);
     }
 
+    #[test]
+    fn test_write_long_strings() {
+        // Context tag 1, Octet String, 2-octet length specifying 300 octets
+        let mut test_output = vec![0x31, 0x01, 0x2c, 0x01];
+        test_output.extend([0xa5; 300]);
+        let test_input = Bytes::from(vec![0xa5; 300]);
+        assert_eq!(
+            test_input.encode_tlv_with_tag(&TLVTag::ContextSpecific(1)),
+            test_output
+        );
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put(TLVTag::ContextSpecific(1), &test_input)
+            .expect("Failed to write element");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(1), &[0xa5; 300][..])
+            .expect("Failed to write element");
+        assert_eq!(
+            tlv_writer.bytes(),
+            [&test_output[..], &test_output].concat()
+        );
+
+        // UTF-8 String, 4-octet length specifying 70000 octets
+        let mut test_output = vec![0x0e, 0x70, 0x11, 0x01, 0x00];
+        test_output.extend([b'a'; 70000]);
+        let test_input = "a".repeat(70000);
+        assert_eq!(test_input.encode_tlv(), test_output);
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put(TLVTag::Anonymous, test_input.as_str())
+            .expect("Failed to write element");
+        assert_eq!(tlv_writer.into_bytes(), test_output);
+    }
+
+    #[test]
+    fn test_write_strings_with_len_width() {
+        // UTF-8 String, 2-octet length, "Hello!"
+        let test_output = &[0x0d, 0x06, 0x00, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_str_with_len_width(TLVTag::Anonymous, "Hello!", TLVFieldSize::TwoOctets)
+            .expect("Failed to write string");
+        assert_eq!(tlv_writer.bytes(), test_output);
+        assert_eq!(
+            TLVReader::new(tlv_writer.bytes())
+                .read_char_str()
+                .expect("Failed to read character string"),
+            "Hello!"
+        );
+
+        // Context tag 1, Octet String, 8-octet length specifying 2 octets 00 01
+        let test_output = &[
+            0x33, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+        ];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_bytes_with_len_width(
+                TLVTag::ContextSpecific(1),
+                &[0x00, 0x01],
+                TLVFieldSize::EightOctets,
+            )
+            .expect("Failed to write byte string");
+        assert_eq!(tlv_writer.bytes(), test_output);
+        assert_eq!(
+            TLVReader::new(tlv_writer.bytes())
+                .read_byte_str()
+                .expect("Failed to read byte string"),
+            vec![0x00, 0x01]
+        );
+
+        // 300 octets do not fit in a 1-octet length
+        let mut tlv_writer = TLVWriter::new();
+        assert_eq!(
+            tlv_writer
+                .put_bytes_with_len_width(TLVTag::Anonymous, &[0xa5; 300], TLVFieldSize::OneOctet)
+                .expect_err("Value out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+        assert!(tlv_writer.bytes().is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_bytes_from_reader() {
+        use std::io::Read;
+
+        // Octet String, 4-octet length specifying 4 MiB of 0xa5
+        let len = 4 << 20;
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_null(TLVTag::Anonymous)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_bytes_from_reader(TLVTag::Anonymous, len, std::io::repeat(0xa5).take(len))
+            .expect("Failed to write byte string");
+        assert_eq!(tlv_writer.bytes()[1..6], [0x12, 0x00, 0x00, 0x40, 0x00]);
+        let config = TLVReaderConfig::default().max_element_len(len as usize);
+        let mut tlv_reader = TLVReader::with_config(tlv_writer.bytes(), config);
+        tlv_reader.next().expect("Failed to move to next element");
+        let value = tlv_reader
+            .read_byte_slice()
+            .expect("Failed to read byte string");
+        assert_eq!(value.len() as u64, len);
+        assert!(value.iter().all(|byte| *byte == 0xa5));
+
+        // Context tag 1, Octet String, 1-octet length specifying 3 octets 00 01 02
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_bytes_from_reader(TLVTag::ContextSpecific(1), 3, &[0x00, 0x01, 0x02][..])
+            .expect("Failed to write byte string");
+        assert_eq!(tlv_writer.bytes(), [0x30, 0x01, 0x03, 0x00, 0x01, 0x02]);
+
+        // Source shorter, then longer than declared, the writer keeps what it had
+        for src in [&[0x00, 0x01][..], &[0x00, 0x01, 0x02, 0x03]] {
+            assert_eq!(
+                tlv_writer
+                    .put_bytes_from_reader(TLVTag::Anonymous, 3, src)
+                    .expect_err("Source length mismatch is expected"),
+                TLVError::SourceLengthMismatch { declared: 3 }
+            );
+            assert_eq!(tlv_writer.bytes(), [0x30, 0x01, 0x03, 0x00, 0x01, 0x02]);
+        }
+
+        // Past the limit of the writer before any octet is copied
+        let mut tlv_writer = TLVWriter::with_max_len(4);
+        assert_eq!(
+            tlv_writer
+                .put_bytes_from_reader(TLVTag::Anonymous, 3, std::io::repeat(0xa5))
+                .expect_err("Message too large is expected"),
+            TLVError::MessageTooLarge { limit: 4 }
+        );
+        assert!(tlv_writer.bytes().is_empty());
+    }
+
+    #[test]
+    fn test_write_max_len() {
+        // Structure {0 = 42U, 1 = Null, 2 = true}, 9 bytes with its End of Container
+        let test_output = &[0x15, 0x24, 0x00, 0x2a, 0x34, 0x01, 0x29, 0x02, 0x18];
+        let too_large = TLVError::MessageTooLarge { limit: 9 };
+        let mut tlv_writer = TLVWriter::with_max_len(9);
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(0), &42u8)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_null(TLVTag::ContextSpecific(1))
+            .expect("Failed to write element");
+        // One byte over once the End of Container is counted
+        assert_eq!(
+            tlv_writer
+                .put(TLVTag::ContextSpecific(2), &300u16)
+                .expect_err("Message too large is expected"),
+            too_large
+        );
+        assert_eq!(
+            tlv_writer
+                .start_container(TLVTag::ContextSpecific(2), ContainerType::Array)
+                .expect_err("Message too large is expected"),
+            too_large
+        );
+        assert_eq!(tlv_writer.bytes(), &test_output[..6]);
+
+        // Exactly the limit
+        tlv_writer
+            .put(TLVTag::ContextSpecific(2), &true)
+            .expect("Failed to write element");
+        assert_eq!(
+            tlv_writer
+                .put_value(TLVTag::ContextSpecific(3), &TLVValue::Null)
+                .expect_err("Message too large is expected"),
+            too_large
+        );
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.bytes(), test_output);
+        assert_eq!(
+            tlv_writer
+                .end_container()
+                .expect_err("Not in container is expected"),
+            TLVError::NotInContainer
+        );
+
+        // A value going past the limit halfway is not written at all
+        let mut tlv_writer = TLVWriter::with_max_len(4);
+        let value = TLVValue::Array(vec![TLVValue::UInt8(1), TLVValue::UInt8(2)]);
+        assert_eq!(
+            tlv_writer
+                .put_value(TLVTag::Anonymous, &value)
+                .expect_err("Message too large is expected"),
+            TLVError::MessageTooLarge { limit: 4 }
+        );
+        assert!(tlv_writer.bytes().is_empty());
+        tlv_writer
+            .put_bytes_with_len_width(TLVTag::Anonymous, &[0x01], TLVFieldSize::TwoOctets)
+            .expect("Failed to write byte string");
+        assert_eq!(tlv_writer.bytes(), [0x11, 0x01, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_writer_introspection() {
+        let mut tlv_writer = TLVWriter::with_max_len(11);
+        assert!(tlv_writer.is_empty());
+        assert_eq!(tlv_writer.remaining_capacity(), Some(11));
+        assert_eq!(tlv_writer.open_container_depth(), 0);
+        assert_eq!(tlv_writer.current_container_type(), None);
+
+        // Structure {0 = [1U,
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .start_container(TLVTag::ContextSpecific(0), ContainerType::Array)
+            .expect("Failed to start container");
+        tlv_writer
+            .put(TLVTag::Anonymous, &1u8)
+            .expect("Failed to write element");
+        assert_eq!(tlv_writer.len(), 5);
+        assert_eq!(tlv_writer.remaining_capacity(), Some(4));
+        assert_eq!(tlv_writer.open_container_depth(), 2);
+        assert_eq!(
+            tlv_writer.current_container_type(),
+            Some(ContainerType::Array)
+        );
+
+        // Failed put, nothing changes
+        tlv_writer
+            .put(TLVTag::Anonymous, &70000u32)
+            .expect_err("Message too large is expected");
+        assert_eq!(tlv_writer.len(), 5);
+        assert_eq!(tlv_writer.remaining_capacity(), Some(4));
+        assert_eq!(tlv_writer.open_container_depth(), 2);
+
+        // Ending a container uses the byte already counted for it
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.len(), 6);
+        assert_eq!(tlv_writer.remaining_capacity(), Some(4));
+        assert_eq!(
+            tlv_writer.current_container_type(),
+            Some(ContainerType::Structure)
+        );
+
+        // ], 1 = 300U}
+        tlv_writer
+            .put_uint(TLVTag::ContextSpecific(1), 300)
+            .expect("Failed to write element");
+        assert_eq!(tlv_writer.remaining_capacity(), Some(0));
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.len(), 11);
+        assert_eq!(tlv_writer.remaining_capacity(), Some(0));
+        assert_eq!(tlv_writer.open_container_depth(), 0);
+        assert_eq!(tlv_writer.current_container_type(), None);
+
+        let tlv_writer = TLVWriter::new();
+        assert_eq!(tlv_writer.remaining_capacity(), None);
+    }
+
+    #[test]
+    fn test_encoded_size() {
+        let short_string = String::from("Hi");
+        let long_string = "a".repeat(300);
+        let structure = TLVValue::Structure(vec![
+            (TLVTag::ContextSpecific(0), TLVValue::UInt16(7)),
+            (
+                TLVTag::ContextSpecific(1),
+                TLVValue::Array(vec![TLVValue::Null, TLVValue::Float64(1.5)]),
+            ),
+            (
+                TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                    vendor_id: 0xfff1,
+                    profile_number: 1,
+                    tag_number: 2,
+                }),
+                TLVValue::ByteString(vec![0x01; 20]),
+            ),
+        ]);
+        let test_vectors: &[(TLVTag, &dyn TLVEncode)] = &[
+            (TLVTag::Anonymous, &-3i8),
+            (TLVTag::ContextSpecific(1), &70000u32),
+            (
+                TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number: 100000 }),
+                &1.5f32,
+            ),
+            (TLVTag::ContextSpecific(2), &true),
+            (TLVTag::ContextSpecific(3), &short_string),
+            (TLVTag::ContextSpecific(4), &long_string),
+            (
+                TLVTag::ContextSpecific(5),
+                &Bytes::from_static(&[0x01, 0x02]),
+            ),
+            (TLVTag::ContextSpecific(6), &None::<u8>),
+            (TLVTag::ContextSpecific(7), &Some(-1i64)),
+            (TLVTag::Anonymous, &structure),
+        ];
+        for (tag, value) in test_vectors {
+            assert_eq!(
+                value.encoded_size(tag),
+                value.encode_tlv_with_tag(tag).len(),
+                "{:?}",
+                tag
+            );
+        }
+
+        // Writing the elements fills the buffer without growing it
+        let elements: Vec<(&TLVTag, &dyn TLVEncode)> = test_vectors
+            .iter()
+            .map(|(tag, value)| (tag, *value))
+            .collect();
+        let mut tlv_writer = TLVWriter::sized_for(&elements);
+        let capacity = tlv_writer.bytes.capacity();
+        for (tag, value) in test_vectors {
+            tlv_writer
+                .put(tag.clone(), *value)
+                .expect("Failed to write element");
+        }
+        assert_eq!(tlv_writer.len(), capacity);
+        assert_eq!(tlv_writer.bytes.capacity(), capacity);
+
+        let tlv_writer = TLVWriter::with_capacity(64);
+        assert!(tlv_writer.is_empty());
+        assert!(tlv_writer.bytes.capacity() >= 64);
+    }
+
+    #[test]
+    fn test_write_matter_budget() {
+        // Structure {1 = [hex:00... x 100, ...]} in 1200 bytes
+        let mut tlv_writer = TLVWriter::with_matter_budget(80);
+        assert_eq!(tlv_writer.remaining_capacity(), Some(1200));
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .start_container(TLVTag::ContextSpecific(1), ContainerType::Array)
+            .expect("Failed to start container");
+        let event = [0u8; 100];
+        let mut events = 0;
+        while tlv_writer.would_fit(&TLVTag::Anonymous, &event[..]) {
+            tlv_writer
+                .put(TLVTag::Anonymous, &event[..])
+                .expect("Failed to write element");
+            events += 1;
+        }
+        // 1 + 2 + 11 * 102 bytes and both End of Container
+        assert_eq!(events, 11);
+        assert_eq!(tlv_writer.remaining_capacity(), Some(73));
+        let last_event = [0u8; 71];
+        assert!(tlv_writer.would_fit(&TLVTag::Anonymous, &last_event[..]));
+        tlv_writer
+            .put(TLVTag::Anonymous, &last_event[..])
+            .expect("Failed to write element");
+
+        // One element too many
+        assert!(!tlv_writer.would_fit(&TLVTag::Anonymous, &true));
+        assert_eq!(
+            tlv_writer
+                .put(TLVTag::Anonymous, &true)
+                .expect_err("Message too large is expected"),
+            TLVError::MessageTooLarge { limit: 1200 }
+        );
+        tlv_writer.end_container().expect("Failed to end container");
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.len(), 1200);
+
+        assert_eq!(
+            TLVWriter::with_matter_budget(2000).remaining_capacity(),
+            Some(0)
+        );
+        assert!(TLVWriter::new().would_fit(&TLVTag::Anonymous, &[0u8; 2000][..]));
+    }
+
+    #[test]
+    fn test_buf_writer() {
+        use bytes::BytesMut;
+
+        // Structure {0 = 300U, 1 = [Null, -2], 2 = "Hi"} after a 4-byte header
+        let write = |tlv_writer: &mut TLVWriter| {
+            tlv_writer.start_container(TLVTag::Anonymous, ContainerType::Structure)?;
+            tlv_writer.put_uint(TLVTag::ContextSpecific(0), 300)?;
+            tlv_writer.start_container(TLVTag::ContextSpecific(1), ContainerType::Array)?;
+            tlv_writer.put_null(TLVTag::Anonymous)?;
+            tlv_writer.put_int(TLVTag::Anonymous, -2)?;
+            tlv_writer.end_container()?;
+            tlv_writer.put(TLVTag::ContextSpecific(2), "Hi")?;
+            tlv_writer.end_container()
+        };
+        let mut expected = TLVWriter::new();
+        write(&mut expected).expect("Failed to write payload");
+
+        let mut frame = BytesMut::from(&[0xde, 0xad, 0xbe, 0xef][..]);
+        let mut tlv_writer = TLVBufWriter::new(&mut frame);
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_uint(TLVTag::ContextSpecific(0), 300)
+            .expect("Failed to write element");
+        tlv_writer
+            .start_container(TLVTag::ContextSpecific(1), ContainerType::Array)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_elements([
+                (TLVTag::Anonymous, TLVValue::Null),
+                (TLVTag::Anonymous, TLVValue::Int8(-2)),
+            ])
+            .expect("Failed to write elements");
+        tlv_writer.end_container().expect("Failed to end container");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(2), "Hi")
+            .expect("Failed to write element");
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.finalize(), expected.len());
+        assert_eq!(frame[..4], [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(frame[4..], *expected.bytes());
+
+        // A fixed buffer, room for Structure {0 = 300U and the End of Container
+        let mut storage = [0u8; 6];
+        let mut buf = &mut storage[..];
+        let mut tlv_writer = TLVBufWriter::new(&mut buf);
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_uint(TLVTag::ContextSpecific(0), 300)
+            .expect("Failed to write element");
+        assert!(!tlv_writer.would_fit(&TLVTag::Anonymous, &true));
+        assert_eq!(
+            tlv_writer
+                .put_null(TLVTag::ContextSpecific(1))
+                .expect_err("Buffer too small is expected"),
+            TLVError::BufferTooSmall { capacity: 6 }
+        );
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.finalize(), 6);
+        assert_eq!(storage, [0x15, 0x25, 0x00, 0x2c, 0x01, 0x18]);
+    }
+
+    #[test]
+    fn test_write_128_bit_integers() {
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_u128(TLVTag::Anonymous, 42)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_u128(TLVTag::Anonymous, u64::MAX as u128)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_i128(TLVTag::Anonymous, i64::MIN as i128)
+            .expect("Failed to write element");
+        // Unsigned Integer 1-octet 42, 8-octet 2^64 - 1, Signed Integer 8-octet -2^63
+        assert_eq!(
+            tlv_writer.bytes(),
+            [
+                0x04, 0x2a, 0x07, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x03, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x80
+            ]
+        );
+
+        for result in [
+            tlv_writer.put_u128(TLVTag::Anonymous, u64::MAX as u128 + 1),
+            tlv_writer.put_i128(TLVTag::Anonymous, i64::MAX as i128 + 1),
+            tlv_writer.put_i128(TLVTag::Anonymous, i64::MIN as i128 - 1),
+        ] {
+            assert_eq!(
+                result.expect_err("Value out of range is expected"),
+                TLVError::ValueOutOfRange
+            );
+        }
+        assert_eq!(tlv_writer.len(), 20);
+    }
+
+    #[test]
+    fn test_write_borrowed_tag() {
+        // Fully qualified tag 65521::57069:1 on 1U, 2U and "a"
+        let tag = TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+            vendor_id: 65521,
+            profile_number: 57069,
+            tag_number: 1,
+        });
+        let encoded = [
+            1u8.encode_tlv_with_tag(&tag),
+            2u8.encode_tlv_with_tag(&tag),
+            "a".encode_tlv_with_tag(&tag),
+        ]
+        .concat();
+        assert_eq!(
+            encoded,
+            [
+                0xc4, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x01, 0xc4, 0xf1, 0xff, 0xed, 0xde, 0x01,
+                0x00, 0x02, 0xcc, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00, 0x01, 0x61
+            ]
+        );
+        assert_eq!(
+            TagControl::from(&tag) as u8,
+            TagControl::from(tag.clone()) as u8
+        );
+    }
+
+    #[test]
+    fn test_write_canonical_nans() {
+        let mut tlv_writer = TLVWriter::new().canonicalize_nans(true);
+        for value in [
+            f32::from_bits(0x7fa0_0000), // Signaling
+            f32::from_bits(0xffc0_0001), // Negative with a payload
+            f32::INFINITY,
+            -0.0,
+        ] {
+            tlv_writer
+                .put(TLVTag::Anonymous, &value)
+                .expect("Failed to write element");
+        }
+        tlv_writer
+            .put(TLVTag::Anonymous, &f64::from_bits(0x7ff0_0000_0000_0001))
+            .expect("Failed to write element");
+        tlv_writer
+            .put_value(
+                TLVTag::Anonymous,
+                &TLVValue::Array(vec![TLVValue::Float32(f32::from_bits(0x7fa0_0000))]),
+            )
+            .expect("Failed to write element");
+        // Single precision NaN, NaN, +inf, -0.0, double precision NaN, [single precision NaN]
+        assert_eq!(
+            tlv_writer.bytes(),
+            [
+                0x0a, 0x00, 0x00, 0xc0, 0x7f, 0x0a, 0x00, 0x00, 0xc0, 0x7f, 0x0a, 0x00, 0x00, 0x80,
+                0x7f, 0x0a, 0x00, 0x00, 0x00, 0x80, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8,
+                0x7f, 0x16, 0x0a, 0x00, 0x00, 0xc0, 0x7f, 0x18
+            ]
+        );
+
+        // Bit patterns are kept by default
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put(TLVTag::Anonymous, &f32::from_bits(0x7fa0_0000))
+            .expect("Failed to write element");
+        assert_eq!(tlv_writer.bytes(), [0x0a, 0x00, 0x00, 0xa0, 0x7f]);
+    }
+
+    fn qualified_common(tag_number: u16) -> TLVTag {
+        TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+            vendor_id: 0,
+            profile_number: 0,
+            tag_number,
+        })
+    }
+
+    #[test]
+    fn test_write_qualified_common_tags() {
+        // 0::0:1 = 42U, tags are written as given by default
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put(qualified_common(1), &42u8)
+            .expect("Failed to write element");
+        assert_eq!(
+            tlv_writer.bytes(),
+            [0xc4, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x2a]
+        );
+
+        let mut tlv_writer = TLVWriter::new().qualified_common_tags(QualifiedCommonTags::Unqualify);
+        tlv_writer
+            .put(qualified_common(1), &42u8)
+            .expect("Failed to write element");
+        let tag = TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+            vendor_id: 0,
+            profile_number: 0,
+            tag_number: 70000,
+        });
+        tlv_writer
+            .start_container(tag, ContainerType::Structure)
+            .expect("Failed to start container");
+        let value = TLVValue::List(vec![(qualified_common(3), TLVValue::Null)]);
+        tlv_writer
+            .put_value(qualified_common(2), &value)
+            .expect("Failed to write element");
+        tlv_writer.end_container().expect("Failed to end container");
+        let tag = TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+            vendor_id: 0,
+            profile_number: 1,
+            tag_number: 1,
+        });
+        tlv_writer.put(tag, &true).expect("Failed to write element");
+        // CHIP::1 = 42U, CHIP::70000 = {CHIP::2 = List [CHIP::3 = Null]} with a 4-octet tag,
+        // 0::1:1 = true kept fully qualified
+        assert_eq!(
+            tlv_writer.bytes(),
+            [
+                0x44, 0x01, 0x00, 0x2a, 0x75, 0x70, 0x11, 0x01, 0x00, 0x57, 0x02, 0x00, 0x54, 0x03,
+                0x00, 0x18, 0x18, 0xc9, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00
+            ]
+        );
+
+        let mut tlv_writer = TLVWriter::new().qualified_common_tags(QualifiedCommonTags::Reject);
+        assert_eq!(
+            tlv_writer
+                .put(qualified_common(1), &42u8)
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        assert_eq!(
+            tlv_writer
+                .start_container(qualified_common(1), ContainerType::Array)
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        let value = TLVValue::Structure(vec![(
+            TLVTag::ContextSpecific(0),
+            TLVValue::Structure(vec![(qualified_common(1), TLVValue::Null)]),
+        )]);
+        assert_eq!(
+            tlv_writer
+                .put_value(TLVTag::Anonymous, &value)
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        assert!(!tlv_writer.would_fit(&qualified_common(1), &42u8));
+        assert!(tlv_writer.is_empty());
+        assert_eq!(tlv_writer.open_container_depth(), 0);
+        tlv_writer
+            .put(qualified_common(1).unqualified(), &42u8)
+            .expect("Failed to write element");
+        assert_eq!(tlv_writer.bytes(), [0x44, 0x01, 0x00, 0x2a]);
+    }
+
     #[test]
     fn test_write_bool() {
         // Boolean false
@@ -439,19 +1805,19 @@ mod tests {
         // Anonymous tag, Null
         let test_output = &[0x14];
         assert_eq!(encode_null(), test_output);
-        assert_eq!(encode_null_with_tag(TLVTag::Anonymous), test_output);
+        assert_eq!(encode_null_with_tag(&TLVTag::Anonymous), test_output);
 
         // Context tag 1 = Null
         let test_output = &[0x34, 0x01];
         assert_eq!(
-            encode_null_with_tag(TLVTag::ContextSpecific(1)),
+            encode_null_with_tag(&TLVTag::ContextSpecific(1)),
             test_output
         );
 
         // Common profile tag 1, CHIP::1 = Null
         let test_output = &[0x54, 0x01, 0x00];
         assert_eq!(
-            encode_null_with_tag(TLVTag::CommonProfile(CommonProfileLength::TwoOctets {
+            encode_null_with_tag(&TLVTag::CommonProfile(CommonProfileLength::TwoOctets {
                 tag_number: 1
             })),
             test_output
@@ -460,7 +1826,7 @@ mod tests {
         // Common profile tag 100000, CHIP::100000 = Null
         let test_output = &[0x74, 0xa0, 0x86, 0x01, 0x00];
         assert_eq!(
-            encode_null_with_tag(TLVTag::CommonProfile(CommonProfileLength::FourOctets {
+            encode_null_with_tag(&TLVTag::CommonProfile(CommonProfileLength::FourOctets {
                 tag_number: 100000
             })),
             test_output
@@ -470,7 +1836,7 @@ mod tests {
         // 2-octet tag 1, 65521::57069:1 = Null
         let test_output = &[0xd4, 0xf1, 0xff, 0xed, 0xde, 0x01, 0x00];
         assert_eq!(
-            encode_null_with_tag(TLVTag::FullyQualifiedProfile(
+            encode_null_with_tag(&TLVTag::FullyQualifiedProfile(
                 FullyQualifiedProfileLength::SixOctets {
                     vendor_id: 65521,
                     profile_number: 57069,
@@ -484,7 +1850,7 @@ mod tests {
         // 4-octet tag 0xAA55FEED/2857762541, 65521::57069:2857762541 = Null
         let test_output = &[0xf4, 0xf1, 0xff, 0xed, 0xde, 0xed, 0xfe, 0x55, 0xaa];
         assert_eq!(
-            encode_null_with_tag(TLVTag::FullyQualifiedProfile(
+            encode_null_with_tag(&TLVTag::FullyQualifiedProfile(
                 FullyQualifiedProfileLength::EightOctets {
                     vendor_id: 65521,
                     profile_number: 57069,
@@ -494,4 +1860,574 @@ mod tests {
             test_output
         );
     }
+
+    #[test]
+    fn test_writer_sequence() {
+        // Context tag 1 = 42U + Context tag 2 = Null + Anonymous "Hi"
+        let test_output = &[0x24, 0x01, 0x2a, 0x34, 0x02, 0x0c, 0x02, 0x48, 0x69];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put(TLVTag::ContextSpecific(1), &42u8)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_null(TLVTag::ContextSpecific(2))
+            .expect("Failed to write element");
+        tlv_writer
+            .put(TLVTag::Anonymous, &String::from("Hi"))
+            .expect("Failed to write element");
+        assert_eq!(tlv_writer.bytes(), test_output);
+        assert_eq!(tlv_writer.to_hex_string(), "24012a34020c024869");
+        assert_eq!(tlv_writer.into_bytes(), test_output);
+    }
+
+    #[test]
+    fn test_put_optional() {
+        // Structure {0 = 1U, 2 = Null}
+        let test_output = &[0x15, 0x24, 0x00, 0x01, 0x34, 0x02, 0x18];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_optional(TLVTag::ContextSpecific(0), &Some(1u8))
+            .expect("Failed to write optional");
+        tlv_writer
+            .put_optional::<u8>(TLVTag::ContextSpecific(1), &None)
+            .expect("Failed to write optional");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(2), &None::<u8>)
+            .expect("Failed to write null");
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.bytes(), test_output);
+
+        let mut tlv_reader = TLVReader::new(test_output);
+        tlv_reader
+            .enter_container()
+            .expect("Failed to enter container");
+        assert_eq!(tlv_reader.try_read_field::<u8>(0), Ok(Some(1)));
+        assert_eq!(tlv_reader.try_read_field::<u8>(1), Ok(None));
+        assert_eq!(tlv_reader.try_read_field::<u8>(2), Ok(None));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_encode() {
+        use crate::reader::TLVDecode;
+        use crate::testing::assert_roundtrip;
+
+        #[derive(TLVEncode, TLVDecode, Debug, PartialEq)]
+        struct BasicInformation {
+            #[tlv(tag = 0)]
+            vendor_id: u16,
+            #[tlv(tag = 1, skip_if_none)]
+            serial_number: Option<String>,
+            #[tlv(tag = 2)]
+            location: Option<String>,
+        }
+
+        // Structure {0 = 0xfff1U, 2 = Null}
+        let value = BasicInformation {
+            vendor_id: 0xfff1,
+            serial_number: None,
+            location: None,
+        };
+        assert_roundtrip(
+            &value,
+            Some(&[0x15, 0x25, 0x00, 0xf1, 0xff, 0x34, 0x02, 0x18]),
+        );
+
+        // Structure {0 = 0xfff1U, 1 = "1", 2 = "XX"}
+        let value = BasicInformation {
+            vendor_id: 0xfff1,
+            serial_number: Some(String::from("1")),
+            location: Some(String::from("XX")),
+        };
+        assert_roundtrip(
+            &value,
+            Some(&[
+                0x15, 0x25, 0x00, 0xf1, 0xff, 0x2c, 0x01, 0x01, 0x31, 0x2c, 0x02, 0x02, 0x58, 0x58,
+                0x18,
+            ]),
+        );
+    }
+
+    #[derive(num_derive::ToPrimitive)]
+    enum Direction {
+        Up = 1,
+        Wide = 0x0194,
+    }
+
+    // Derived conversions cast negative discriminants, without a range check
+    struct Negative;
+
+    impl ToPrimitive for Negative {
+        fn to_i64(&self) -> Option<i64> {
+            Some(-1)
+        }
+
+        fn to_u64(&self) -> Option<u64> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_put_enum() {
+        // Context tag 1 = 1U, Context tag 2 = 404U on 2 octets
+        let test_output = &[0x24, 0x01, 0x01, 0x25, 0x02, 0x94, 0x01];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_enum(TLVTag::ContextSpecific(1), &Direction::Up)
+            .expect("Failed to write enum");
+        tlv_writer
+            .put_enum(TLVTag::ContextSpecific(2), &Direction::Wide)
+            .expect("Failed to write enum");
+        assert_eq!(tlv_writer.bytes(), test_output);
+        assert_eq!(
+            tlv_writer
+                .put_enum(TLVTag::ContextSpecific(3), &Negative)
+                .expect_err("Value out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+        assert_eq!(tlv_writer.bytes(), test_output);
+    }
+
+    #[cfg(feature = "bitflags")]
+    #[test]
+    fn test_put_flags() {
+        bitflags::bitflags! {
+            #[derive(Debug, PartialEq)]
+            struct Map8: u8 {
+                const ON = 0x01;
+                const BLINK = 0x80;
+            }
+
+            #[derive(Debug, PartialEq)]
+            struct Map16: u16 {
+                const LOW = 0x0001;
+                const HIGH = 0x8000;
+            }
+
+            #[derive(Debug, PartialEq)]
+            struct Map32: u32 {
+                const HIGH = 0x8000_0000;
+            }
+
+            #[derive(Debug, PartialEq)]
+            struct Map64: u64 {
+                const HIGH = 0x8000_0000_0000_0000;
+            }
+        }
+
+        // Context tag 1 = 0x81U, 2 = 0x8001U, 3 = 0x80000000U, 4 = 0x8000000000000000U
+        let test_output = &[
+            0x24, 0x01, 0x81, 0x25, 0x02, 0x01, 0x80, 0x26, 0x03, 0x00, 0x00, 0x00, 0x80, 0x27,
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+        ];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_flags(TLVTag::ContextSpecific(1), &(Map8::ON | Map8::BLINK))
+            .expect("Failed to write flags");
+        tlv_writer
+            .put_flags(TLVTag::ContextSpecific(2), &(Map16::LOW | Map16::HIGH))
+            .expect("Failed to write flags");
+        tlv_writer
+            .put_flags(TLVTag::ContextSpecific(3), &Map32::HIGH)
+            .expect("Failed to write flags");
+        tlv_writer
+            .put_flags(TLVTag::ContextSpecific(4), &Map64::HIGH)
+            .expect("Failed to write flags");
+        assert_eq!(tlv_writer.bytes(), test_output);
+
+        let mut tlv_reader = TLVReader::new(test_output);
+        assert_eq!(tlv_reader.read_flags(), Ok(Map8::ON | Map8::BLINK));
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(tlv_reader.read_flags(), Ok(Map16::LOW | Map16::HIGH));
+        assert_eq!(
+            tlv_reader
+                .read_flags::<Map8>()
+                .expect_err("Value out of range is expected"),
+            TLVError::ValueOutOfRange
+        );
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(tlv_reader.read_flags(), Ok(Map32::HIGH));
+        tlv_reader.next().expect("Failed to move to next element");
+        assert_eq!(tlv_reader.read_flags(), Ok(Map64::HIGH));
+
+        // Unsigned Integer, 1-octet, value 0x83
+        let tlv_reader = TLVReader::new(&[0x04, 0x83]);
+        assert_eq!(
+            tlv_reader
+                .read_flags::<Map8>()
+                .expect_err("Unknown flags are expected"),
+            TLVError::UnknownFlags { value: 0x83 }
+        );
+        assert_eq!(tlv_reader.read_flags_truncate(), Ok(Map8::ON | Map8::BLINK));
+        assert_eq!(
+            tlv_reader.read_flags_truncate(),
+            Ok(Map16::LOW),
+            "narrow encodings of wider bitmaps are accepted"
+        );
+    }
+
+    #[test]
+    fn test_put_elements() {
+        // Context tag 1 = 42U, Context tag 2 = [true, "Hi"]
+        let elements = vec![
+            (TLVTag::ContextSpecific(1), TLVValue::UInt8(42)),
+            (
+                TLVTag::ContextSpecific(2),
+                TLVValue::Array(vec![
+                    TLVValue::Bool(true),
+                    TLVValue::Utf8String(String::from("Hi")),
+                ]),
+            ),
+        ];
+        let test_output = &[
+            0x24, 0x01, 0x2a, 0x36, 0x02, 0x09, 0x0c, 0x02, 0x48, 0x69, 0x18,
+        ];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_elements(elements.clone())
+            .expect("Failed to write elements");
+        assert_eq!(&tlv_writer.bytes()[1..], test_output);
+
+        // Failures leave the writer as before the call
+        let before = tlv_writer.bytes().to_vec();
+        let anonymous = (TLVTag::Anonymous, TLVValue::Null);
+        assert_eq!(
+            tlv_writer
+                .put_elements(elements.iter().cloned().chain([anonymous.clone()]))
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        assert_eq!(tlv_writer.bytes(), before);
+        tlv_writer
+            .start_container(TLVTag::ContextSpecific(3), ContainerType::Array)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_elements([anonymous.clone(), anonymous])
+            .expect("Failed to write elements");
+        assert_eq!(
+            tlv_writer
+                .put_elements([(TLVTag::ContextSpecific(0), TLVValue::Null)])
+                .expect_err("Invalid tag is expected"),
+            TLVError::InvalidTag
+        );
+        tlv_writer.end_container().expect("Failed to end container");
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(
+            tlv_writer.bytes()[12..],
+            [0x36, 0x03, 0x14, 0x14, 0x18, 0x18]
+        );
+
+        let mut tlv_writer = TLVWriter::with_max_len(5);
+        tlv_writer
+            .put(TLVTag::Anonymous, &true)
+            .expect("Failed to write element");
+        assert_eq!(
+            tlv_writer
+                .put_elements(elements)
+                .expect_err("Message too large is expected"),
+            TLVError::MessageTooLarge { limit: 5 }
+        );
+        assert_eq!(tlv_writer.bytes(), [0x09]);
+    }
+
+    #[test]
+    fn test_write_minimal_width() {
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_uint(TLVTag::Anonymous, 255)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_uint(TLVTag::Anonymous, 256)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_uint(TLVTag::Anonymous, 65536)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_uint(TLVTag::Anonymous, 40000000000)
+            .expect("Failed to write element");
+        assert_eq!(
+            tlv_writer.bytes(),
+            &[
+                0x04, 0xff, 0x05, 0x00, 0x01, 0x06, 0x00, 0x00, 0x01, 0x00, 0x07, 0x00, 0x90, 0x2f,
+                0x50, 0x09, 0x00, 0x00, 0x00
+            ]
+        );
+
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_int(TLVTag::Anonymous, -128)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_int(TLVTag::Anonymous, -129)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_int(TLVTag::Anonymous, -170000)
+            .expect("Failed to write element");
+        tlv_writer
+            .put_int(TLVTag::Anonymous, -40000000000)
+            .expect("Failed to write element");
+        assert_eq!(
+            tlv_writer.bytes(),
+            &[
+                0x00, 0x80, 0x01, 0x7f, 0xff, 0x02, 0xf0, 0x67, 0xfd, 0xff, 0x03, 0x00, 0x70, 0xd0,
+                0xaf, 0xf6, 0xff, 0xff, 0xff
+            ]
+        );
+    }
+
+    #[test]
+    fn test_into_reader() {
+        // Structure {0 = [1U, "a"], 1 = List [[2 = Null]]} written under a cap, then checked
+        let mut tlv_writer = TLVWriter::with_max_len(32);
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .and_then(|()| {
+                tlv_writer.start_container(TLVTag::ContextSpecific(0), ContainerType::Array)
+            })
+            .and_then(|()| tlv_writer.put(TLVTag::Anonymous, &1u8))
+            .and_then(|()| tlv_writer.put(TLVTag::Anonymous, "a"))
+            .and_then(|()| tlv_writer.end_container())
+            .and_then(|()| {
+                tlv_writer.start_container(TLVTag::ContextSpecific(1), ContainerType::List)
+            })
+            .and_then(|()| tlv_writer.put_null(TLVTag::ContextSpecific(2)))
+            .and_then(|()| tlv_writer.end_container())
+            .and_then(|()| tlv_writer.end_container())
+            .expect("Failed to write payload");
+        let tlv_reader = tlv_writer.into_reader().expect("Failed to convert writer");
+        assert_eq!(
+            tlv_reader.read_value(),
+            Ok(TLVValue::Structure(vec![
+                (
+                    TLVTag::ContextSpecific(0),
+                    TLVValue::Array(vec![
+                        TLVValue::UInt8(1),
+                        TLVValue::Utf8String(String::from("a"))
+                    ])
+                ),
+                (
+                    TLVTag::ContextSpecific(1),
+                    TLVValue::List(vec![(TLVTag::ContextSpecific(2), TLVValue::Null)])
+                ),
+            ]))
+        );
+
+        let config = TLVReaderConfig::default().max_depth(1);
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put_value(
+                TLVTag::Anonymous,
+                &TLVValue::Array(vec![TLVValue::Array(vec![])]),
+            )
+            .expect("Failed to write payload");
+        let tlv_reader = tlv_writer
+            .into_reader_with_config(config)
+            .expect("Failed to convert writer");
+        assert_eq!(
+            tlv_reader.read_value(),
+            Err(TLVError::DepthLimitExceeded(1))
+        );
+
+        // Open containers are not ended for the reader
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .put(TLVTag::Anonymous, &true)
+            .and_then(|()| tlv_writer.start_container(TLVTag::Anonymous, ContainerType::List))
+            .and_then(|()| {
+                tlv_writer.start_container(TLVTag::ContextSpecific(0), ContainerType::Structure)
+            })
+            .expect("Failed to write payload");
+        assert_eq!(
+            tlv_writer
+                .into_reader()
+                .expect_err("Unterminated container is expected"),
+            TLVError::UnterminatedContainer { offset: 1 }
+        );
+    }
+
+    #[test]
+    fn test_put_elements_list_members() {
+        // List [[0 = 1U, 0 = 2U, Anonymous = 3U, 1 = List [[true, 2 = Null, 2 = Null]]]]
+        let test_output = &[
+            0x17, 0x24, 0x00, 0x01, 0x24, 0x00, 0x02, 0x04, 0x03, 0x37, 0x01, 0x09, 0x34, 0x02,
+            0x34, 0x02, 0x18, 0x18,
+        ];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::List)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_elements([
+                (TLVTag::ContextSpecific(0), TLVValue::UInt8(1)),
+                (TLVTag::ContextSpecific(0), TLVValue::UInt8(2)),
+                (TLVTag::Anonymous, TLVValue::UInt8(3)),
+            ])
+            .expect("Failed to write elements");
+        tlv_writer
+            .start_container(TLVTag::ContextSpecific(1), ContainerType::List)
+            .expect("Failed to start container");
+        tlv_writer
+            .put_elements([
+                (TLVTag::Anonymous, TLVValue::Bool(true)),
+                (TLVTag::ContextSpecific(2), TLVValue::Null),
+                (TLVTag::ContextSpecific(2), TLVValue::Null),
+            ])
+            .expect("Failed to write elements");
+        tlv_writer.end_container().expect("Failed to end container");
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.bytes(), test_output);
+
+        // The anonymous member is rejected in a Structure, the tagged ones in an Array
+        let members = [
+            (TLVTag::ContextSpecific(0), TLVValue::UInt8(1)),
+            (TLVTag::Anonymous, TLVValue::UInt8(3)),
+        ];
+        for (container_type, valid) in [(ContainerType::Structure, 0), (ContainerType::Array, 1)] {
+            let mut tlv_writer = TLVWriter::new();
+            tlv_writer
+                .start_container(TLVTag::Anonymous, container_type)
+                .expect("Failed to start container");
+            assert_eq!(
+                tlv_writer.put_elements(members.clone()),
+                Err(TLVError::InvalidTag)
+            );
+            assert_eq!(tlv_writer.len(), 1, "{:?}", container_type);
+            tlv_writer
+                .put_elements([members[valid].clone()])
+                .expect("Failed to write element");
+        }
+    }
+
+    #[test]
+    fn test_put_member_tags() {
+        // Structure {0 = true, 1 = []}, then Array [1U, {}]
+        let test_output = &[
+            0x15, 0x29, 0x00, 0x36, 0x01, 0x18, 0x18, 0x16, 0x04, 0x01, 0x15, 0x18, 0x18,
+        ];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        assert_eq!(
+            tlv_writer.put(TLVTag::Anonymous, &true),
+            Err(TLVError::InvalidTag)
+        );
+        assert_eq!(
+            tlv_writer.put_null(TLVTag::Anonymous),
+            Err(TLVError::InvalidTag)
+        );
+        assert_eq!(
+            tlv_writer.start_container(TLVTag::Anonymous, ContainerType::Array),
+            Err(TLVError::InvalidTag)
+        );
+        assert_eq!(tlv_writer.len(), 1);
+        assert!(!tlv_writer.would_fit(&TLVTag::Anonymous, &true));
+        tlv_writer
+            .put(TLVTag::ContextSpecific(0), &true)
+            .expect("Failed to write element");
+        tlv_writer
+            .start_container(TLVTag::ContextSpecific(1), ContainerType::Array)
+            .expect("Failed to start container");
+        assert_eq!(
+            tlv_writer.put_uint(TLVTag::ContextSpecific(2), 1),
+            Err(TLVError::InvalidTag)
+        );
+        tlv_writer.end_container().expect("Failed to end container");
+        tlv_writer.end_container().expect("Failed to end container");
+
+        let mut buf = Vec::new();
+        let mut buf_writer = TLVBufWriter::new(&mut buf);
+        buf_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Array)
+            .expect("Failed to start container");
+        assert_eq!(
+            buf_writer.put(TLVTag::ContextSpecific(0), &1u8),
+            Err(TLVError::InvalidTag)
+        );
+        assert_eq!(
+            buf_writer.put_value(TLVTag::ContextSpecific(0), &TLVValue::Null),
+            Err(TLVError::InvalidTag)
+        );
+        assert_eq!(
+            buf_writer.start_container(TLVTag::ContextSpecific(1), ContainerType::Structure),
+            Err(TLVError::InvalidTag)
+        );
+        buf_writer
+            .put(TLVTag::Anonymous, &1u8)
+            .expect("Failed to write element");
+        buf_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        buf_writer.end_container().expect("Failed to end container");
+        buf_writer.end_container().expect("Failed to end container");
+        buf_writer.finalize();
+        assert_eq!(tlv_writer.bytes(), &test_output[..7]);
+        assert_eq!(buf, test_output[7..]);
+    }
+
+    #[test]
+    fn test_write_containers() {
+        // Structure, two context specific tags, Signed Integer, 1 octet values, {0 = 42, 1 = -17}
+        let test_output = &[0x15, 0x20, 0x00, 0x2a, 0x20, 0x01, 0xef, 0x18];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(0), &42i8)
+            .expect("Failed to write element");
+        tlv_writer
+            .put(TLVTag::ContextSpecific(1), &-17i8)
+            .expect("Failed to write element");
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.bytes(), test_output);
+
+        // Array, mix of element types, [42, -170000, {}, 17.9, "Hello!"]
+        let test_output = &[
+            0x16, 0x00, 0x2a, 0x02, 0xf0, 0x67, 0xfd, 0xff, 0x15, 0x18, 0x0a, 0x33, 0x33, 0x8f,
+            0x41, 0x0c, 0x06, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x21, 0x18,
+        ];
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Array)
+            .expect("Failed to start container");
+        tlv_writer
+            .put(TLVTag::Anonymous, &42i8)
+            .expect("Failed to write element");
+        tlv_writer
+            .put(TLVTag::Anonymous, &-170000i32)
+            .expect("Failed to write element");
+        tlv_writer
+            .start_container(TLVTag::Anonymous, ContainerType::Structure)
+            .expect("Failed to start container");
+        tlv_writer.end_container().expect("Failed to end container");
+        tlv_writer
+            .put(TLVTag::Anonymous, &17.9f32)
+            .expect("Failed to write element");
+        tlv_writer
+            .put(TLVTag::Anonymous, &String::from("Hello!"))
+            .expect("Failed to write element");
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.bytes(), test_output);
+
+        // Context tag 1, List, empty
+        let mut tlv_writer = TLVWriter::new();
+        tlv_writer
+            .start_container(TLVTag::ContextSpecific(1), ContainerType::List)
+            .expect("Failed to start container");
+        tlv_writer.end_container().expect("Failed to end container");
+        assert_eq!(tlv_writer.bytes(), &[0x37, 0x01, 0x18]);
+        assert_eq!(
+            tlv_writer
+                .end_container()
+                .expect_err("Not in container is expected"),
+            TLVError::NotInContainer
+        );
+    }
 }
@@ -0,0 +1,359 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitInt};
+
+// Decodes a Structure into a struct with named fields, each carrying #[tlv(tag = N)] with its
+// context tag. #[tlv(deny_unknown)] on the struct rejects members without a matching field.
+#[proc_macro_derive(TLVDecode, attributes(tlv))]
+pub fn derive_tlv_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_tlv_decode(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// Encodes a struct with named fields as a Structure, with the same #[tlv(tag = N)] attributes.
+// Option fields are written as Null when None, or left out with #[tlv(skip_if_none)]. Decoding
+// reads both as None.
+#[proc_macro_derive(TLVEncode, attributes(tlv))]
+pub fn derive_tlv_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_tlv_encode(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct TaggedField<'a> {
+    ident: &'a syn::Ident,
+    ty: &'a syn::Type,
+    tag: u8,
+    skip_if_none: bool,
+}
+
+fn container_deny_unknown(input: &DeriveInput) -> syn::Result<bool> {
+    let mut deny_unknown = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("tlv"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("deny_unknown") {
+                deny_unknown = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported tlv attribute, expected `deny_unknown`"))
+            }
+        })?;
+    }
+    Ok(deny_unknown)
+}
+
+// Context tag of the field and whether it has skip_if_none
+fn field_attributes(field: &syn::Field) -> syn::Result<(u8, bool)> {
+    let mut tag = None;
+    let mut skip_if_none = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("tlv"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                let value: LitInt = meta.value()?.parse()?;
+                let tag_number = value.base10_parse::<u32>()?;
+                tag = Some(u8::try_from(tag_number).map_err(|_| {
+                    syn::Error::new_spanned(
+                        &value,
+                        format!(
+                            "context tag {} is out of range, expected 0 to 255",
+                            tag_number
+                        ),
+                    )
+                })?);
+                Ok(())
+            } else if meta.path.is_ident("skip_if_none") {
+                skip_if_none = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported tlv attribute, expected `tag = N` or `skip_if_none`"))
+            }
+        })?;
+    }
+    let ident = field.ident.as_ref().expect("named field");
+    let tag = tag.ok_or_else(|| {
+        syn::Error::new_spanned(
+            field,
+            format!("missing #[tlv(tag = N)] attribute on field `{}`", ident),
+        )
+    })?;
+    Ok((tag, skip_if_none))
+}
+
+fn tagged_fields<'a>(
+    input: &'a DeriveInput,
+    trait_name: &str,
+) -> syn::Result<Vec<TaggedField<'a>>> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!(
+                        "{} can only be derived for structs with named fields",
+                        trait_name
+                    ),
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!("{} can only be derived for structs", trait_name),
+            ))
+        }
+    };
+    let mut tagged_fields: Vec<TaggedField> = Vec::new();
+    for field in fields {
+        let (tag, skip_if_none) = field_attributes(field)?;
+        if let Some(other) = tagged_fields.iter().find(|other| other.tag == tag) {
+            return Err(syn::Error::new_spanned(
+                field,
+                format!("tag {} is already used by field `{}`", tag, other.ident),
+            ));
+        }
+        tagged_fields.push(TaggedField {
+            ident: field.ident.as_ref().expect("named field"),
+            ty: &field.ty,
+            tag,
+            skip_if_none,
+        });
+    }
+    Ok(tagged_fields)
+}
+
+fn expand_tlv_decode(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let deny_unknown = container_deny_unknown(input)?;
+    let fields = tagged_fields(input, "TLVDecode")?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let slots: Vec<_> = fields
+        .iter()
+        .map(|field| format_ident!("tlv_{}", field.ident))
+        .collect();
+    let idents = fields.iter().map(|field| field.ident);
+    let types = fields.iter().map(|field| field.ty);
+    let tags: Vec<_> = fields.iter().map(|field| field.tag).collect();
+    let field_types = fields.iter().map(|field| field.ty);
+    let unknown_member = if deny_unknown {
+        quote!(return Err(::tlv::errors::TLVError::UnknownMember(tag)))
+    } else {
+        quote!({})
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::tlv::reader::TLVDecode for #name #ty_generics #where_clause {
+            fn decode_tlv(
+                tlv_reader: &mut ::tlv::reader::TLVReader,
+            ) -> Result<Self, ::tlv::errors::TLVError> {
+                use ::tlv::errors::TLVError;
+                use ::tlv::reader::TLVDecode;
+                use ::tlv::tags::TLVTag;
+
+                if tlv_reader.read_type()?
+                    != ::tlv::types::TLVType::Container(::tlv::types::ContainerType::Structure)
+                {
+                    return Err(TLVError::InvalidType);
+                }
+                #(let mut #slots: Option<#types> = None;)*
+                tlv_reader.enter_container()?;
+                loop {
+                    let tag = match tlv_reader.read_tag() {
+                        Err(TLVError::EndOfContainer) => break,
+                        result => result?,
+                    };
+                    match tag {
+                        #(TLVTag::ContextSpecific(#tags) => {
+                            if #slots.is_some() {
+                                return Err(TLVError::DuplicateMember(tag));
+                            }
+                            #slots = Some(TLVDecode::decode_tlv(tlv_reader)?);
+                        })*
+                        tag => #unknown_member,
+                    }
+                    match tlv_reader.next() {
+                        Err(TLVError::EndOfContainer) => break,
+                        result => result?,
+                    }
+                }
+                tlv_reader.exit_container()?;
+                Ok(Self {
+                    #(#idents: match #slots.or_else(<#field_types as TLVDecode>::decode_absent) {
+                        Some(value) => value,
+                        None => {
+                            return Err(TLVError::MissingMember(TLVTag::ContextSpecific(#tags)))
+                        }
+                    },)*
+                })
+            }
+        }
+    })
+}
+
+fn expand_tlv_encode(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    // Accepted for symmetry with TLVDecode, it has no effect on encoding
+    container_deny_unknown(input)?;
+    let fields = tagged_fields(input, "TLVEncode")?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let puts = fields.iter().map(|field| {
+        let ident = field.ident;
+        let tag = field.tag;
+        if field.skip_if_none {
+            quote!(tlv_writer.put_optional(TLVTag::ContextSpecific(#tag), &self.#ident)?;)
+        } else {
+            quote!(tlv_writer.put(TLVTag::ContextSpecific(#tag), &self.#ident)?;)
+        }
+    });
+
+    // alloc is declared in a scope of its own, so Vec resolves in no_std crates with alloc and in
+    // std crates, which have no alloc in their extern prelude
+    Ok(quote! {
+        const _: () = {
+            extern crate alloc;
+
+            impl #impl_generics ::tlv::writer::TLVEncode for #name #ty_generics #where_clause {
+                fn encode_tlv_with_tag(&self, tag: &::tlv::tags::TLVTag) -> alloc::vec::Vec<u8> {
+                    use ::tlv::errors::TLVError;
+                    use ::tlv::tags::TLVTag;
+
+                    let mut tlv_writer = ::tlv::writer::TLVWriter::new();
+                    // Writers without a length limit do not fail
+                    let _ = (|| -> Result<(), TLVError> {
+                        tlv_writer.start_container(
+                            tag.clone(),
+                            ::tlv::types::ContainerType::Structure,
+                        )?;
+                        #(#puts)*
+                        tlv_writer.end_container()
+                    })();
+                    tlv_writer.into_bytes()
+                }
+            }
+        };
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn expansion_error(input: DeriveInput) -> String {
+        expand_tlv_decode(&input)
+            .expect_err("Compile error is expected")
+            .to_string()
+    }
+
+    #[test]
+    fn test_expand_tlv_decode() {
+        let input: DeriveInput = parse_quote! {
+            #[tlv(deny_unknown)]
+            struct Attribute {
+                #[tlv(tag = 0)]
+                id: u32,
+                #[tlv(tag = 1)]
+                label: Option<String>,
+            }
+        };
+        let expansion = expand_tlv_decode(&input)
+            .expect("Failed to expand")
+            .to_string();
+        assert!(expansion.contains("UnknownMember"));
+        assert!(expansion.contains("TLVTag :: ContextSpecific (1u8)"));
+    }
+
+    #[test]
+    fn test_expand_tlv_encode() {
+        let input: DeriveInput = parse_quote! {
+            struct Attribute {
+                #[tlv(tag = 0)]
+                id: u32,
+                #[tlv(tag = 1, skip_if_none)]
+                label: Option<String>,
+            }
+        };
+        let expansion = expand_tlv_encode(&input)
+            .expect("Failed to expand")
+            .to_string();
+        assert!(expansion.contains("put (TLVTag :: ContextSpecific (0u8) , & self . id)"));
+        assert!(
+            expansion.contains("put_optional (TLVTag :: ContextSpecific (1u8) , & self . label)")
+        );
+        assert_eq!(
+            expand_tlv_encode(&parse_quote! {
+                enum Attribute {}
+            })
+            .expect_err("Compile error is expected")
+            .to_string(),
+            "TLVEncode can only be derived for structs"
+        );
+    }
+
+    #[test]
+    fn test_missing_tag_attribute() {
+        assert_eq!(
+            expansion_error(parse_quote! {
+                struct Attribute {
+                    #[tlv(tag = 0)]
+                    id: u32,
+                    label: String,
+                }
+            }),
+            "missing #[tlv(tag = N)] attribute on field `label`"
+        );
+    }
+
+    #[test]
+    fn test_invalid_attributes() {
+        assert_eq!(
+            expansion_error(parse_quote! {
+                struct Attribute {
+                    #[tlv(tag = 256)]
+                    id: u32,
+                }
+            }),
+            "context tag 256 is out of range, expected 0 to 255"
+        );
+        assert_eq!(
+            expansion_error(parse_quote! {
+                struct Attribute {
+                    #[tlv(tag = 0)]
+                    id: u32,
+                    #[tlv(tag = 0)]
+                    label: String,
+                }
+            }),
+            "tag 0 is already used by field `id`"
+        );
+        assert_eq!(
+            expansion_error(parse_quote! {
+                struct Attribute {
+                    #[tlv(name = 0)]
+                    id: u32,
+                }
+            }),
+            "unsupported tlv attribute, expected `tag = N` or `skip_if_none`"
+        );
+        assert_eq!(
+            expansion_error(parse_quote! {
+                struct Attribute(#[tlv(tag = 0)] u32);
+            }),
+            "TLVDecode can only be derived for structs with named fields"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+#ifndef TLV_H
+#define TLV_H
+
+/* Generated with cbindgen, do not edit */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+ * Functions return TLV_OK or one of the negative error codes below, outputs are written through
+ * the pointers only on success. tlv_pretty_print is the exception: when the output is too small
+ * it still writes the length of the text to written, so callers can retry with a large enough one.
+ */
+
+#define TLV_OK 0
+
+#define TLV_END_OF_TLV -1
+
+#define TLV_END_OF_CONTAINER -2
+
+#define TLV_NOT_IN_CONTAINER -3
+
+#define TLV_UNDER_RUN -4
+
+#define TLV_INVALID_TYPE -5
+
+#define TLV_INVALID_TAG -6
+
+#define TLV_VALUE_OUT_OF_RANGE -7
+
+#define TLV_BUFFER_TOO_SMALL -8
+
+#define TLV_NULL_POINTER -9
+
+#define TLV_INVALID_ENCODING -10
+
+typedef struct TLVReader TLVReader;
+
+typedef struct TLVTagInfo {
+  uint8_t tag_control;
+  uint16_t vendor_id;
+  uint16_t profile_number;
+  uint32_t tag_number;
+} TLVTagInfo;
+
+TLVReader *tlv_reader_new(const uint8_t *bytes, size_t len);
+
+void tlv_reader_free(TLVReader *reader);
+
+int32_t tlv_reader_next(TLVReader *reader);
+
+int32_t tlv_reader_enter_container(TLVReader *reader);
+
+int32_t tlv_reader_exit_container(TLVReader *reader);
+
+int32_t tlv_reader_tag(const TLVReader *reader, TLVTagInfo *tag);
+
+int32_t tlv_reader_element_type(const TLVReader *reader, uint8_t *element_type);
+
+int32_t tlv_reader_get_uint(const TLVReader *reader, uint64_t *value);
+
+int32_t tlv_reader_get_int(const TLVReader *reader, int64_t *value);
+
+int32_t tlv_reader_get_bool(const TLVReader *reader, bool *value);
+
+int32_t tlv_reader_get_double(const TLVReader *reader, double *value);
+
+int32_t tlv_reader_get_str(const TLVReader *reader, const uint8_t **value, size_t *len);
+
+int32_t tlv_reader_get_bytes(const TLVReader *reader, const uint8_t **value, size_t *len);
+
+int32_t tlv_pretty_print(const uint8_t *bytes,
+                         size_t len,
+                         char *output,
+                         size_t output_len,
+                         size_t *written);
+
+#endif /* TLV_H */
@@ -0,0 +1,411 @@
+// C ABI over the reader, for embedded C code and test harnesses. A reader copies the buffer it is
+// given, strings it returns point into that copy and stay valid until the reader is freed.
+// Functions return TLV_OK or one of the negative error codes below, outputs are written through
+// the pointers only on success, tlv_pretty_print's written excepted. Pointers must be valid for the given lengths, readers must come
+// from tlv_reader_new and be freed once with tlv_reader_free.
+#![allow(clippy::missing_safety_doc)]
+
+use core::ffi::c_char;
+use core::ptr;
+use core::slice;
+use tlv::errors::TLVError;
+use tlv::reader::TLVReader;
+use tlv::tags::{
+    CommonProfileLength, FullyQualifiedProfileLength, ImplicitProfileLength, TLVTag, TagControl,
+    ELEMENT_TYPE_MASK,
+};
+use tlv::value::TLVDocument;
+
+pub const TLV_OK: i32 = 0;
+pub const TLV_END_OF_TLV: i32 = -1;
+pub const TLV_END_OF_CONTAINER: i32 = -2;
+pub const TLV_NOT_IN_CONTAINER: i32 = -3;
+pub const TLV_UNDER_RUN: i32 = -4;
+pub const TLV_INVALID_TYPE: i32 = -5;
+pub const TLV_INVALID_TAG: i32 = -6;
+pub const TLV_VALUE_OUT_OF_RANGE: i32 = -7;
+pub const TLV_BUFFER_TOO_SMALL: i32 = -8;
+pub const TLV_NULL_POINTER: i32 = -9;
+// Malformed payloads and exceeded reader limits
+pub const TLV_INVALID_ENCODING: i32 = -10;
+
+fn error_code(error: &TLVError) -> i32 {
+    match error {
+        TLVError::EndOfTLV => TLV_END_OF_TLV,
+        TLVError::EndOfContainer => TLV_END_OF_CONTAINER,
+        TLVError::NotInContainer => TLV_NOT_IN_CONTAINER,
+        TLVError::UnderRun | TLVError::TruncatedTag { .. } | TLVError::TruncatedValue { .. } => {
+            TLV_UNDER_RUN
+        }
+        TLVError::InvalidType | TLVError::UnknownElementType { .. } => TLV_INVALID_TYPE,
+        TLVError::InvalidTag => TLV_INVALID_TAG,
+        TLVError::ValueOutOfRange => TLV_VALUE_OUT_OF_RANGE,
+        TLVError::BufferTooSmall { .. } => TLV_BUFFER_TOO_SMALL,
+        _ => TLV_INVALID_ENCODING,
+    }
+}
+
+fn status(result: Result<(), TLVError>) -> i32 {
+    match result {
+        Ok(()) => TLV_OK,
+        Err(e) => error_code(&e),
+    }
+}
+
+// Tag of an element, fields the tag control does not use are zero
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct TLVTagInfo {
+    // Upper 3 bits of the control byte, e.g. 0x20 for a context tag
+    pub tag_control: u8,
+    pub vendor_id: u16,
+    pub profile_number: u16,
+    pub tag_number: u32,
+}
+
+impl From<&TLVTag> for TLVTagInfo {
+    fn from(tag: &TLVTag) -> Self {
+        let mut info = TLVTagInfo {
+            tag_control: TagControl::from(tag) as u8,
+            ..Default::default()
+        };
+        match *tag {
+            TLVTag::Anonymous => {}
+            TLVTag::ContextSpecific(tag_number) => info.tag_number = tag_number.into(),
+            TLVTag::CommonProfile(CommonProfileLength::TwoOctets { tag_number })
+            | TLVTag::ImplicitProfile(ImplicitProfileLength::TwoOctets { tag_number }) => {
+                info.tag_number = tag_number.into()
+            }
+            TLVTag::CommonProfile(CommonProfileLength::FourOctets { tag_number })
+            | TLVTag::ImplicitProfile(ImplicitProfileLength::FourOctets { tag_number }) => {
+                info.tag_number = tag_number
+            }
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::SixOctets {
+                vendor_id,
+                profile_number,
+                tag_number,
+            }) => {
+                info.vendor_id = vendor_id;
+                info.profile_number = profile_number;
+                info.tag_number = tag_number.into();
+            }
+            TLVTag::FullyQualifiedProfile(FullyQualifiedProfileLength::EightOctets {
+                vendor_id,
+                profile_number,
+                tag_number,
+            }) => {
+                info.vendor_id = vendor_id;
+                info.profile_number = profile_number;
+                info.tag_number = tag_number;
+            }
+        }
+        info
+    }
+}
+
+unsafe fn input<'a>(bytes: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (bytes.is_null(), len) {
+        (_, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(slice::from_raw_parts(bytes, len)),
+    }
+}
+
+// Reads the element under the cursor and writes it to the output
+unsafe fn read<T>(
+    reader: *const TLVReader,
+    output: *mut T,
+    read: impl FnOnce(&TLVReader) -> Result<T, TLVError>,
+) -> i32 {
+    let Some(reader) = reader.as_ref() else {
+        return TLV_NULL_POINTER;
+    };
+    if output.is_null() {
+        return TLV_NULL_POINTER;
+    }
+    match read(reader) {
+        Ok(value) => {
+            output.write(value);
+            TLV_OK
+        }
+        Err(e) => error_code(&e),
+    }
+}
+
+// NULL when the buffer is NULL with a non zero length
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_new(bytes: *const u8, len: usize) -> *mut TLVReader {
+    match input(bytes, len) {
+        Some(bytes) => Box::into_raw(Box::new(TLVReader::new(bytes))),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_free(reader: *mut TLVReader) {
+    if !reader.is_null() {
+        drop(Box::from_raw(reader));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_next(reader: *mut TLVReader) -> i32 {
+    match reader.as_mut() {
+        Some(reader) => status(reader.next()),
+        None => TLV_NULL_POINTER,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_enter_container(reader: *mut TLVReader) -> i32 {
+    match reader.as_mut() {
+        Some(reader) => status(reader.enter_container().map(|_| ())),
+        None => TLV_NULL_POINTER,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_exit_container(reader: *mut TLVReader) -> i32 {
+    match reader.as_mut() {
+        Some(reader) => status(reader.exit_container()),
+        None => TLV_NULL_POINTER,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_tag(reader: *const TLVReader, tag: *mut TLVTagInfo) -> i32 {
+    read(reader, tag, |reader| {
+        Ok(TLVTagInfo::from(&reader.read_tag()?))
+    })
+}
+
+// Element type of the control byte, 0x00 to 0x17 as in the specification
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_element_type(
+    reader: *const TLVReader,
+    element_type: *mut u8,
+) -> i32 {
+    read(reader, element_type, |reader| {
+        reader.read_type()?;
+        Ok(reader.remaining()[0] & ELEMENT_TYPE_MASK)
+    })
+}
+
+// Unsigned integer of any width
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_get_uint(reader: *const TLVReader, value: *mut u64) -> i32 {
+    read(reader, value, TLVReader::read_uint)
+}
+
+// Signed integer of any width
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_get_int(reader: *const TLVReader, value: *mut i64) -> i32 {
+    read(reader, value, TLVReader::read_int)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_get_bool(reader: *const TLVReader, value: *mut bool) -> i32 {
+    read(reader, value, TLVReader::read_bool)
+}
+
+// Floats of either width
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_get_double(reader: *const TLVReader, value: *mut f64) -> i32 {
+    read(reader, value, |reader| {
+        reader
+            .read_f64()
+            .or_else(|_| reader.read_f32().map(f64::from))
+    })
+}
+
+// UTF-8 string, not NUL terminated
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_get_str(
+    reader: *const TLVReader,
+    value: *mut *const u8,
+    len: *mut usize,
+) -> i32 {
+    if len.is_null() {
+        return TLV_NULL_POINTER;
+    }
+    read(reader, value, |reader| {
+        let string = reader.read_str()?;
+        len.write(string.len());
+        Ok(string.as_ptr())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tlv_reader_get_bytes(
+    reader: *const TLVReader,
+    value: *mut *const u8,
+    len: *mut usize,
+) -> i32 {
+    if len.is_null() {
+        return TLV_NULL_POINTER;
+    }
+    read(reader, value, |reader| {
+        let bytes = reader.read_byte_slice()?;
+        len.write(bytes.len());
+        Ok(bytes.as_ptr())
+    })
+}
+
+// Writes the payload in the notation of TLVValue's Display, one top level element per line, NUL
+// terminated. Unlike the other outputs, written is also written on failure with
+// TLV_BUFFER_TOO_SMALL: it holds the length of the text without the NUL, so callers can retry
+// with a large enough output. It is left alone on other failures.
+#[no_mangle]
+pub unsafe extern "C" fn tlv_pretty_print(
+    bytes: *const u8,
+    len: usize,
+    output: *mut c_char,
+    output_len: usize,
+    written: *mut usize,
+) -> i32 {
+    let Some(bytes) = input(bytes, len) else {
+        return TLV_NULL_POINTER;
+    };
+    if output.is_null() || written.is_null() {
+        return TLV_NULL_POINTER;
+    }
+    let document = match TLVDocument::parse(bytes) {
+        Ok(document) => document,
+        Err(e) => return error_code(&e),
+    };
+    let lines: Vec<String> = document
+        .elements
+        .iter()
+        .map(|element| element.to_string())
+        .collect();
+    let text = lines.join("\n");
+    written.write(text.len());
+    if text.len() >= output_len {
+        return TLV_BUFFER_TOO_SMALL;
+    }
+    ptr::copy_nonoverlapping(text.as_ptr(), output.cast::<u8>(), text.len());
+    output.add(text.len()).write(0);
+    TLV_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ffi::CStr;
+
+    #[test]
+    fn test_reader() {
+        // Structure {1 = 42U, 2 = "Hi", 3 = -2, 4 = true}
+        let test_bytes = [
+            0x15, 0x24, 0x01, 0x2a, 0x2c, 0x02, 0x02, 0x48, 0x69, 0x20, 0x03, 0xfe, 0x29, 0x04,
+            0x18,
+        ];
+        unsafe {
+            let reader = tlv_reader_new(test_bytes.as_ptr(), test_bytes.len());
+            assert!(!reader.is_null());
+            let mut element_type = 0;
+            assert_eq!(tlv_reader_element_type(reader, &mut element_type), TLV_OK);
+            assert_eq!(element_type, 0x15);
+            assert_eq!(tlv_reader_enter_container(reader), TLV_OK);
+
+            let mut tag = TLVTagInfo::default();
+            assert_eq!(tlv_reader_tag(reader, &mut tag), TLV_OK);
+            assert_eq!(
+                tag,
+                TLVTagInfo {
+                    tag_control: 0x20,
+                    tag_number: 1,
+                    ..Default::default()
+                }
+            );
+            let mut uint = 0;
+            assert_eq!(tlv_reader_get_uint(reader, &mut uint), TLV_OK);
+            assert_eq!(uint, 42);
+            let mut int = 0;
+            assert_eq!(tlv_reader_get_int(reader, &mut int), TLV_INVALID_TYPE);
+
+            assert_eq!(tlv_reader_next(reader), TLV_OK);
+            let (mut string, mut len) = (ptr::null(), 0);
+            assert_eq!(tlv_reader_get_str(reader, &mut string, &mut len), TLV_OK);
+            assert_eq!(slice::from_raw_parts(string, len), b"Hi");
+            assert_eq!(
+                tlv_reader_get_bytes(reader, &mut string, &mut len),
+                TLV_INVALID_TYPE
+            );
+
+            assert_eq!(tlv_reader_next(reader), TLV_OK);
+            assert_eq!(tlv_reader_get_int(reader, &mut int), TLV_OK);
+            assert_eq!(int, -2);
+            assert_eq!(tlv_reader_next(reader), TLV_OK);
+            let mut boolean = false;
+            assert_eq!(tlv_reader_get_bool(reader, &mut boolean), TLV_OK);
+            assert!(boolean);
+            assert_eq!(tlv_reader_next(reader), TLV_END_OF_CONTAINER);
+            assert_eq!(tlv_reader_exit_container(reader), TLV_OK);
+            assert_eq!(tlv_reader_exit_container(reader), TLV_NOT_IN_CONTAINER);
+            assert_eq!(tlv_reader_next(reader), TLV_END_OF_TLV);
+            tlv_reader_free(reader);
+
+            assert!(tlv_reader_new(ptr::null(), 1).is_null());
+            assert_eq!(tlv_reader_next(ptr::null_mut()), TLV_NULL_POINTER);
+            assert_eq!(
+                tlv_reader_get_uint(ptr::null(), &mut uint),
+                TLV_NULL_POINTER
+            );
+        }
+    }
+
+    #[test]
+    fn test_pretty_print() {
+        // Context tag 1, Unsigned Integer 42, then anonymous Array [1.5]
+        let test_bytes = [0x24, 0x01, 0x2a, 0x16, 0x0a, 0x00, 0x00, 0xc0, 0x3f, 0x18];
+        let mut output = [0 as c_char; 32];
+        let mut written = 0;
+        unsafe {
+            assert_eq!(
+                tlv_pretty_print(
+                    test_bytes.as_ptr(),
+                    test_bytes.len(),
+                    output.as_mut_ptr(),
+                    output.len(),
+                    &mut written
+                ),
+                TLV_OK
+            );
+            let text = CStr::from_ptr(output.as_ptr());
+            assert_eq!(text.to_str(), Ok("1 = 42U\n[1.5]"));
+            assert_eq!(written, 13);
+
+            // Too small for the NUL, then for most of the text, the output is left as it was
+            for output_len in [13, 4] {
+                let mut output = [0x7f as c_char; 32];
+                let mut written = 0;
+                assert_eq!(
+                    tlv_pretty_print(
+                        test_bytes.as_ptr(),
+                        test_bytes.len(),
+                        output.as_mut_ptr(),
+                        output_len,
+                        &mut written
+                    ),
+                    TLV_BUFFER_TOO_SMALL
+                );
+                assert_eq!(written, 13);
+                assert_eq!(output, [0x7f as c_char; 32]);
+            }
+
+            // UTF-8 String of 5 octets, 1 present
+            assert_eq!(
+                tlv_pretty_print(
+                    [0x0c, 0x05, 0x48].as_ptr(),
+                    3,
+                    output.as_mut_ptr(),
+                    output.len(),
+                    &mut written
+                ),
+                TLV_UNDER_RUN
+            );
+            assert_eq!(written, 13);
+        }
+    }
+}